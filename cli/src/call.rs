@@ -1,6 +1,9 @@
+use std::io;
+
 use anyhow::bail;
 use clap::{self, Args, Subcommand};
 use dtu::app::server::AppServer;
+use dtu::app_server::{method_parcel_string, BinderReply, DecodedReply, ParamType};
 use dtu::db;
 use dtu::db::device::models::{self, Apk, SystemServiceMethod};
 use dtu::db::{DeviceDatabase, MetaDatabase, MetaSqliteDatabase};
@@ -59,6 +62,11 @@ pub struct AppService {
     #[arg(short, long)]
     action: Option<String>,
 
+    /// The smali return type to decode the reply as, such as
+    /// `Ljava/lang/String;`
+    #[arg(short = 'R', long)]
+    returns: Option<String>,
+
     /// The Parcel string for defining the Parcel
     #[arg(last = true)]
     parcel: Vec<String>,
@@ -81,8 +89,16 @@ impl AppService {
             self.action.as_ref().map(|it| it.as_str()),
             qa.as_ref(),
         )?;
-        println!("{}", res);
-        Ok(())
+        if self.txn.is_none() {
+            // The interface transaction replies with the descriptor
+            let decoded = DecodedReply {
+                value: Some(res.response.into()),
+                ..Default::default()
+            };
+            serde_json::to_writer(io::stdout(), &decoded)?;
+            return Ok(());
+        }
+        print_reply(&res, self.returns.as_deref())
     }
 }
 
@@ -97,6 +113,13 @@ pub struct SystemService {
     interface: Option<ClassName>,
 
     /// The system service method name, if None, --txn must be supplied
+    ///
+    /// The arguments are then converted to a Parcel using the method's
+    /// signature, one argument per parameter. `null` is accepted for objects,
+    /// arrays are comma separated, byte arrays are hex, Bundles are
+    /// `key=value` strings separated by commas, ComponentNames are
+    /// `package/class` and ParcelFileDescriptors are paths, optionally
+    /// prefixed with `rw:`
     #[arg(short, long)]
     method: Option<String>,
 
@@ -104,7 +127,16 @@ pub struct SystemService {
     #[arg(short, long)]
     txn: Option<u32>,
 
-    /// The Parcel string for defining the Parcel
+    /// Always treat the arguments as a Parcel string, even with -m/--method
+    #[arg(short, long)]
+    raw: bool,
+
+    /// The smali return type to decode the reply as, defaults to the
+    /// method's return type
+    #[arg(short = 'R', long)]
+    returns: Option<String>,
+
+    /// The Parcel string for defining the Parcel or the method arguments
     #[arg(last = true)]
     parcel: Vec<String>,
 }
@@ -142,35 +174,52 @@ impl SystemService {
             },
         };
 
-        let txn_number = self.get_transaction_id(&db, &service)?;
-
         if self.method.is_none() && self.txn.is_none() {
             bail!("need either -m/--method or -t/--txn");
         }
 
-        let qa = if self.parcel.len() > 0 {
-            Some(parse_parcel_string(self.parcel.as_slice())?)
+        let (txn_number, qa, return_type) = if self.txn.is_none() && !self.raw {
+            let method = self.get_method(&db, &service)?;
+            let Some(sig) = method.signature.as_ref() else {
+                bail!(
+                    "the signature of {} is unknown, use -r/--raw and a Parcel string",
+                    method.name
+                );
+            };
+            let qa = method_parcel_string(sig, &self.parcel)?;
+            let qa = if self.parcel.is_empty() {
+                None
+            } else {
+                Some(qa)
+            };
+            (method.transaction_id as u32, qa, method.return_type)
         } else {
-            None
+            let (txn_number, return_type) = match self.txn {
+                Some(id) if id < 1 => bail!("invalid transaction id {}", id),
+                Some(id) => (id, None),
+                None => {
+                    let method = self.get_method(&db, &service)?;
+                    (method.transaction_id as u32, method.return_type)
+                }
+            };
+            let qa = if self.parcel.len() > 0 {
+                Some(parse_parcel_string(self.parcel.as_slice())?)
+            } else {
+                None
+            };
+            (txn_number, qa, return_type)
         };
         let mut srv = get_app_server(&ctx)?;
         let res = srv.call_system_service(&self.service, txn_number, Some(iface), qa.as_ref())?;
-        println!("{}", res);
-
-        Ok(())
+        let return_type = self.returns.as_deref().or(return_type.as_deref());
+        print_reply(&res, return_type)
     }
-    fn get_transaction_id(
+
+    fn get_method(
         &self,
         db: &DeviceDatabase,
         service: &Option<models::SystemService>,
-    ) -> anyhow::Result<u32> {
-        if let Some(id) = self.txn {
-            if id < 1 {
-                bail!("invalid transaction id {}", id);
-            }
-            return Ok(id);
-        }
-
+    ) -> anyhow::Result<SystemServiceMethod> {
         let svc = match service.as_ref() {
             None => bail!(
                 "service {} not found in database and --txn not set",
@@ -182,15 +231,26 @@ impl SystemService {
         let name = self.method.as_ref().unwrap().as_str();
 
         let method = db.get_system_service_methods_by_service_id(svc.id)?;
-        let methods = method
-            .iter()
+        let mut methods = method
+            .into_iter()
             .filter(|it| it.name == name)
-            .collect::<Vec<&SystemServiceMethod>>();
+            .collect::<Vec<SystemServiceMethod>>();
+        // Narrow overloads down by the number of arguments if possible
+        if !self.raw && methods.len() > 1 {
+            let matching = methods
+                .iter()
+                .filter(|it| self.takes_arg_count(it))
+                .cloned()
+                .collect::<Vec<SystemServiceMethod>>();
+            if !matching.is_empty() {
+                methods = matching;
+            }
+        }
         let count = methods.len();
         if count == 0 {
             bail!("no method named {} found on service {}", name, self.service);
         } else if count == 1 {
-            return Ok(methods.get(0).unwrap().transaction_id as u32);
+            return Ok(methods.remove(0));
         }
 
         Ok(prompt_choice(
@@ -198,6 +258,20 @@ impl SystemService {
             &format!("Multiple methods named {} found for {}", name, self.service),
             "Choice: ",
         )?
-        .transaction_id as u32)
+        .clone())
+    }
+
+    fn takes_arg_count(&self, method: &SystemServiceMethod) -> bool {
+        method
+            .signature
+            .as_ref()
+            .and_then(|it| ParamType::parse_signature(it).ok())
+            .is_some_and(|it| it.len() == self.parcel.len())
     }
 }
+
+fn print_reply(reply: &BinderReply, return_type: Option<&str>) -> anyhow::Result<()> {
+    let decoded = reply.decode(return_type)?;
+    serde_json::to_writer(io::stdout(), &decoded)?;
+    Ok(())
+}
@@ -143,6 +143,9 @@ impl AddApk {
         let opts = DiffOptions::new(source);
         let mut task = DiffTask::new(opts, db, &diff_db, check, &mon);
         task.do_system_services = false;
+        task.do_native_services = false;
+        task.do_fs_survey = false;
+        task.do_properties = false;
         let res = task.run();
         drop(mon);
         res?;
@@ -0,0 +1,138 @@
+use std::io;
+use std::str::FromStr;
+
+use anyhow::bail;
+use clap::{self, Args};
+
+use dtu::db::device::models::DiffSource;
+use dtu::db::{DeviceDatabase, MetaDatabase, MetaSqliteDatabase};
+use dtu::prereqs::Prereq;
+use dtu::prop_audit::{
+    audit_device_properties, compare_property_findings, ComparedPropertyFinding, PropertyRules,
+    Severity,
+};
+use dtu::utils::ensure_prereq;
+use dtu::{Context, DefaultContext, UnknownBool};
+
+use crate::diff::get_diff_source;
+use crate::parsers::DiffSourceValueParser;
+use crate::printer::{color, Printer};
+
+/// Audit the device properties for insecure values
+///
+/// The rules shipped with dtu can be disabled or extended in the
+/// `[property-audit]` section of dtu.toml. Findings are stored in the device
+/// database, replacing any previous results, and compared to the values in
+/// the diff source. Without `-S/--diff-source` the comparison is skipped if
+/// the emulator hasn't been diffed.
+#[derive(Args)]
+pub struct AuditProps {
+    /// Set the diff source, defaults to the emulator
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// Only show findings that the diff source doesn't share
+    #[arg(short = 'n', long)]
+    only_new: bool,
+
+    /// Only show findings with at least this severity
+    #[arg(short, long, default_value_t = Severity::Info)]
+    min_severity: Severity,
+
+    /// Print the findings as JSON
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl AuditProps {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::SQLDatabaseSetup)?;
+        let meta = MetaSqliteDatabase::new(&ctx)?;
+        let db = DeviceDatabase::new(&ctx)?;
+
+        // Resolve the diff source before replacing the stored findings, the
+        // audit itself doesn't need one
+        let diff_source = if self.diff_source.is_none()
+            && ctx.maybe_get_env("DTU_DIFF_SOURCE").is_none()
+            && !meta.prereq_done(Prereq::EmulatorDiff)?
+        {
+            None
+        } else {
+            Some(get_diff_source(&ctx, &meta, &db, &self.diff_source)?)
+        };
+        if self.only_new && diff_source.is_none() {
+            bail!("-n/--only-new needs a diff source, use -S or diff the emulator");
+        }
+
+        let rules = PropertyRules::for_project(&ctx)?;
+        audit_device_properties(&db, &rules)?;
+
+        let mut findings =
+            compare_property_findings(&db, &rules, diff_source.as_ref().map(|it| it.id))?;
+        findings.retain(|it| self.should_show(it));
+        findings.sort_by(|lhs, rhs| {
+            severity(rhs)
+                .cmp(&severity(lhs))
+                .then_with(|| lhs.property.name.cmp(&rhs.property.name))
+        });
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &findings)?;
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        if findings.is_empty() {
+            printer.println("No findings");
+            return Ok(());
+        }
+        let diff_name = diff_source.as_ref().map(|it| it.name.as_str());
+        for it in findings.iter() {
+            print_finding(&printer, diff_name, it);
+        }
+        Ok(())
+    }
+
+    fn should_show(&self, it: &ComparedPropertyFinding) -> bool {
+        if self.only_new && it.in_diff == UnknownBool::True {
+            return false;
+        }
+        severity(it) >= self.min_severity
+    }
+}
+
+fn severity(it: &ComparedPropertyFinding) -> Severity {
+    Severity::from_str(&it.finding.severity).unwrap_or_default()
+}
+
+fn print_finding(printer: &Printer, diff_source: Option<&str>, it: &ComparedPropertyFinding) {
+    let sev = severity(it);
+    let color = match sev {
+        Severity::High => color::RED,
+        Severity::Medium => color::YELLOW,
+        Severity::Low => color::CYAN,
+        Severity::Info => color::GREY,
+    };
+    printer.print_colored(format!("[{}] ", sev), color);
+    let line = format!(
+        "{} {}={}",
+        it.finding.rule, it.property.name, it.property.value
+    );
+    let Some(diff_source) = diff_source else {
+        printer.println(line);
+        printer.println(format!("    {}", it.finding.description));
+        return;
+    };
+    printer.print(line);
+    let diff_value = match &it.diff {
+        None => String::from("not diffed"),
+        Some(_) => String::from(it.diff_value().unwrap_or("unset")),
+    };
+    let diff_color = match it.in_diff {
+        UnknownBool::True => color::GREY,
+        _ => color::INTERESTING,
+    };
+    printer.println_colored(format!(" ({}: {})", diff_source, diff_value), diff_color);
+    printer.println(format!("    {}", it.finding.description));
+}
@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::bail;
 use clap::{self, Args, Subcommand};
@@ -44,6 +44,10 @@ enum Commands {
 
     #[command()]
     List(List),
+
+    /// Set or clear the smali directory for a diff source
+    #[command()]
+    SetSmaliDir(SetSmaliDir),
 }
 
 #[derive(Args)]
@@ -55,6 +59,11 @@ struct Add {
     /// Name for the diff source
     #[arg(short, long)]
     name: String,
+
+    /// Directory holding the source's smali, laid out like the project smali
+    /// directory. Used to diff method bodies.
+    #[arg(short, long)]
+    smali_dir: Option<PathBuf>,
 }
 
 impl Add {
@@ -62,7 +71,11 @@ impl Add {
         let other_db_path = path_must_str(&self.path);
         let other_db = DeviceDatabase::new_from_path(other_db_path)?;
         let name = self.name.clone();
-        let ins = models::InsertDiffSource { name: &name };
+        let smali_dir = match &self.smali_dir {
+            Some(dir) => Some(canonical_smali_dir(dir)?),
+            None => None,
+        };
+        let ins = models::InsertDiffSource::new(&name).set_smali_dir(smali_dir.as_deref());
 
         let id = db.with_connection(|c| {
             insert_into(diff_sources::table)
@@ -71,7 +84,11 @@ impl Add {
                 .get_result(c)
         })?;
 
-        let new_source = models::DiffSource { id, name };
+        let new_source = models::DiffSource {
+            id,
+            name,
+            smali_dir,
+        };
         let res = self.add_source(new_source, &db, &other_db);
 
         if res.is_err() {
@@ -144,6 +161,36 @@ impl List {
     }
 }
 
+#[derive(Args)]
+struct SetSmaliDir {
+    /// Diff source to update
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    source: models::DiffSource,
+
+    /// Directory holding the source's smali, the current value is cleared
+    /// if this isn't set
+    #[arg(short, long)]
+    smali_dir: Option<PathBuf>,
+}
+
+impl SetSmaliDir {
+    fn run(&self, db: DeviceDatabase) -> anyhow::Result<()> {
+        let smali_dir = match &self.smali_dir {
+            Some(dir) => Some(canonical_smali_dir(dir)?),
+            None => None,
+        };
+        db.set_diff_source_smali_dir(self.source.id, smali_dir.as_deref())?;
+        Ok(())
+    }
+}
+
+fn canonical_smali_dir(dir: &Path) -> anyhow::Result<String> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", path_must_str(dir));
+    }
+    Ok(path_must_str(&dir.canonicalize()?).into())
+}
+
 impl DiffSource {
     pub fn run(&self) -> anyhow::Result<()> {
         let ctx = DefaultContext::new();
@@ -153,6 +200,7 @@ impl DiffSource {
             Commands::Add(c) => c.run(db),
             Commands::Remove(c) => c.run(db),
             Commands::List(c) => c.run(db),
+            Commands::SetSmaliDir(c) => c.run(db),
         }
     }
 }
@@ -14,8 +14,8 @@ use dtu::{Context, DefaultContext};
 
 use crate::utils::task_canceller;
 
-use super::get_aosp_database;
 use super::monitor::PrintMonitor;
+use super::{get_aosp_database, get_aosp_smali_dir_path};
 
 #[derive(Args)]
 pub struct EmulatorDiff {
@@ -37,6 +37,12 @@ pub struct EmulatorDiff {
     /// applicable if -p/--path is set.
     #[arg(short = 'A', long)]
     api_level: Option<u32>,
+
+    /// Directory holding the emulator's smali, laid out like the project
+    /// smali directory. Used to diff method bodies, defaults to the AOSP
+    /// smali for the API level if it exists.
+    #[arg(short, long)]
+    smali_dir: Option<PathBuf>,
 }
 
 impl EmulatorDiff {
@@ -59,11 +65,14 @@ impl EmulatorDiff {
         let other_db = self.get_diff_db(&ctx)?;
 
         let source = db.get_diff_source_by_name(EMULATOR_DIFF_SOURCE)?;
-        let res = self.add_source(source, &db, &other_db);
+        let res = self.add_source(source.clone(), &db, &other_db);
 
         if res.is_ok() {
             prereq.completed = true;
             meta.update_progress(&prereq)?;
+            if let Some(dir) = self.get_smali_dir(&ctx)? {
+                db.set_diff_source_smali_dir(source.id, Some(path_must_str(&dir)))?;
+            }
         }
 
         res
@@ -79,10 +88,25 @@ impl EmulatorDiff {
         get_aosp_database(ctx, api_level)
     }
 
+    fn get_smali_dir(&self, ctx: &dyn Context) -> anyhow::Result<Option<PathBuf>> {
+        if let Some(dir) = &self.smali_dir {
+            if !dir.is_dir() {
+                bail!("{} is not a directory", path_must_str(dir));
+            }
+            return Ok(Some(dir.canonicalize()?));
+        }
+        if self.path.is_some() {
+            return Ok(None);
+        }
+        let api_level = self.api_level.unwrap_or_else(|| ctx.get_target_api_level());
+        let dir = get_aosp_smali_dir_path(ctx, api_level)?;
+        Ok(if dir.is_dir() { Some(dir) } else { None })
+    }
+
     fn wipe_emulator_diff(&self, db: &DeviceDatabase) -> anyhow::Result<()> {
         let ds = db.get_diff_source_by_name(EMULATOR_DIFF_SOURCE)?;
         db.delete_diff_source_by_id(ds.id)?;
-        let ins = models::InsertDiffSource { name: &ds.name };
+        let ins = models::InsertDiffSource::new(&ds.name).set_smali_dir(ds.smali_dir.as_deref());
         db.with_connection(|c| insert_into(diff_sources::table).values(&ins).execute(c))?;
         Ok(())
     }
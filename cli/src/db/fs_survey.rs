@@ -0,0 +1,73 @@
+use clap::{self, Args};
+
+use dtu::db::device::{FsSurveyEvent, FsSurveyTask};
+use dtu::db::DeviceDatabase;
+use dtu::prereqs::Prereq;
+use dtu::tasks::EventMonitor;
+use dtu::utils::ensure_prereq;
+use dtu::DefaultContext;
+
+use crate::printer::Printer;
+use crate::utils::{get_app_server, task_canceller};
+
+struct PrintMonitor {
+    quiet: bool,
+}
+
+impl EventMonitor<FsSurveyEvent> for PrintMonitor {
+    fn on_event(&self, evt: FsSurveyEvent) {
+        if self.quiet {
+            return;
+        }
+        let printer = Printer::new();
+        match evt {
+            FsSurveyEvent::RootStarted { root, count } => {
+                printer.println(format!("surveying {} ({} directories)", root, count));
+            }
+            FsSurveyEvent::Directory { path, entries } => {
+                printer.println(format!("    {}: {} entries", path, entries));
+            }
+            FsSurveyEvent::RootEnded { root, entries } => {
+                printer.println(format!("{}: {} entries total", root, entries));
+            }
+        }
+    }
+}
+
+/// Record the mode, owner, group, and SELinux label of everything the test
+/// application can stat
+///
+/// The search runs from the application server so the results reflect what
+/// an untrusted app can see. This replaces any previous results. Run this
+/// before `db emulator-diff` or adding a diff source to have the results
+/// diffed.
+#[derive(Args)]
+pub struct FsSurvey {
+    /// Directory to survey, can be given multiple times. Defaults to /data,
+    /// /system, and /vendor
+    #[arg(short, long = "root")]
+    roots: Vec<String>,
+
+    /// Don't print per directory results
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+impl FsSurvey {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::SQLDatabaseSetup)?;
+        ensure_prereq(&ctx, Prereq::AppSetup)?;
+
+        let db = DeviceDatabase::new(&ctx)?;
+        let mut app_server = get_app_server(&ctx)?;
+        let (_cancel, check) = task_canceller()?;
+        let monitor = PrintMonitor { quiet: self.quiet };
+
+        let mut task = FsSurveyTask::new(&db, &mut app_server, check, &monitor);
+        if !self.roots.is_empty() {
+            task.set_roots(self.roots.clone());
+        }
+        Ok(task.run()?)
+    }
+}
@@ -1,8 +1,11 @@
 mod add_apk;
 mod add_service_impl;
+mod audit_props;
 mod diff_source;
 mod emulator_diff;
-mod monitor;
+mod fs_survey;
+pub(crate) mod monitor;
+mod permission_checks;
 mod setup;
 mod update_binder_status;
 
@@ -19,8 +22,11 @@ use dtu::utils::ensure_dir_exists;
 use dtu::{Context, DefaultContext};
 
 use add_service_impl::AddServiceImpl;
+use audit_props::AuditProps;
 use diff_source::DiffSource;
 use emulator_diff::EmulatorDiff;
+use fs_survey::FsSurvey;
+use permission_checks::PermissionChecks;
 use setup::Setup;
 
 use crate::db::update_binder_status::UpdateBinderAvailability;
@@ -60,6 +66,21 @@ enum Commands {
     /// and update the database
     UpdateBinderAvailability(UpdateBinderAvailability),
 
+    /// Use the graph database to find the permission checks reached by every
+    /// system service method
+    #[command()]
+    PermissionChecks(PermissionChecks),
+
+    /// Use the application server application to record the permissions and
+    /// SELinux labels of files the application can stat
+    #[command()]
+    FsSurvey(FsSurvey),
+
+    /// Audit the device properties for insecure values, such as a debuggable
+    /// build or disabled verified boot
+    #[command()]
+    AuditProps(AuditProps),
+
     /// Wipes the whole database
     #[command()]
     Wipe,
@@ -74,6 +95,9 @@ impl DB {
             Commands::EmulatorDiff(c) => c.run(),
             Commands::AddApk(c) => c.run(),
             Commands::UpdateBinderAvailability(c) => c.run(),
+            Commands::PermissionChecks(c) => c.run(),
+            Commands::FsSurvey(c) => c.run(),
+            Commands::AuditProps(c) => c.run(),
             Commands::Wipe => self.wipe_database(),
         }
     }
@@ -97,6 +121,19 @@ pub(crate) fn get_aosp_database_path(ctx: &dyn Context, api_level: u32) -> anyho
         .join("device.db"))
 }
 
+/// Directory holding the AOSP smali for the given API level, laid out like
+/// the project smali directory
+pub(crate) fn get_aosp_smali_dir_path(
+    ctx: &dyn Context,
+    api_level: u32,
+) -> anyhow::Result<PathBuf> {
+    Ok(ctx
+        .get_user_local_dir()?
+        .join("aosp")
+        .join(api_level.to_string())
+        .join("smali"))
+}
+
 pub(crate) fn get_aosp_database(
     ctx: &dyn Context,
     api_level: u32,
@@ -176,6 +176,38 @@ impl PrintMonitor {
                 self.inc();
             }
             DiffEvent::ActivitiesEnded => {}
+
+            DiffEvent::NativeServicesStarted { count } => {
+                self.on_started("Native Services", count);
+            }
+            DiffEvent::NativeService { .. } => {
+                self.inc();
+            }
+            DiffEvent::NativeServicesEnded => {}
+
+            DiffEvent::HalServicesStarted { count } => {
+                self.on_started("HAL Services", count);
+            }
+            DiffEvent::HalService { .. } => {
+                self.inc();
+            }
+            DiffEvent::HalServicesEnded => {}
+
+            DiffEvent::FsSurveyStarted { count } => {
+                self.on_started("Filesystem Survey", count);
+            }
+            DiffEvent::FsSurveyEntry { .. } => {
+                self.inc();
+            }
+            DiffEvent::FsSurveyEnded => {}
+
+            DiffEvent::PropertiesStarted { count } => {
+                self.on_started("Device Properties", count);
+            }
+            DiffEvent::Property { .. } => {
+                self.inc();
+            }
+            DiffEvent::PropertiesEnded => {}
         }
     }
 }
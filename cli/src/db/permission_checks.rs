@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use clap::{self, Args};
+
+use dtu::db::device::{PermissionCheckEvent, PermissionCheckTask, DEFAULT_PERMISSION_CHECK_DEPTH};
+use dtu::db::graph::get_default_graphdb;
+use dtu::db::DeviceDatabase;
+use dtu::prereqs::Prereq;
+use dtu::tasks::EventMonitor;
+use dtu::utils::ensure_prereq;
+use dtu::DefaultContext;
+
+use crate::printer::{color, Printer};
+use crate::utils::task_canceller;
+
+struct PrintMonitor {
+    quiet: bool,
+}
+
+impl EventMonitor<PermissionCheckEvent> for PrintMonitor {
+    fn on_event(&self, evt: PermissionCheckEvent) {
+        if self.quiet {
+            return;
+        }
+        match evt {
+            PermissionCheckEvent::SystemService {
+                name,
+                methods,
+                unchecked,
+                ..
+            } => {
+                let printer = Printer::new();
+                let line = format!("{}: {}/{} methods unchecked", name, unchecked, methods);
+                if unchecked > 0 {
+                    printer.println_colored(line, color::YELLOW);
+                } else {
+                    printer.println(line);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Statically find the permission checks reached by every system service
+/// method using the graph database
+///
+/// This replaces any previous results. Use `-u/--unchecked` to list the
+/// methods that didn't reach any permission check in the previous run.
+#[derive(Args)]
+pub struct PermissionChecks {
+    /// The call depth to search for permission checks
+    #[arg(short, long, default_value_t = DEFAULT_PERMISSION_CHECK_DEPTH)]
+    depth: usize,
+
+    /// Don't print per service results
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// List the system service methods that didn't reach any permission
+    /// check in the previous run instead of running the analysis
+    #[arg(short, long)]
+    unchecked: bool,
+}
+
+impl PermissionChecks {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::SQLDatabaseSetup)?;
+        let db = DeviceDatabase::new(&ctx)?;
+        if self.unchecked {
+            return self.list_unchecked(&db);
+        }
+
+        ensure_prereq(&ctx, Prereq::GraphDatabaseSetup)?;
+        let graph = get_default_graphdb(&ctx)?;
+        let (_cancel, check) = task_canceller()?;
+        let monitor = PrintMonitor { quiet: self.quiet };
+
+        let mut task = PermissionCheckTask::new(&db, &graph, check, &monitor);
+        task.set_depth(self.depth);
+        Ok(task.run()?)
+    }
+
+    fn list_unchecked(&self, db: &DeviceDatabase) -> anyhow::Result<()> {
+        let services = db
+            .get_system_services()?
+            .into_iter()
+            .map(|it| (it.id, it.name))
+            .collect::<HashMap<i32, String>>();
+        let mut methods = db.get_unchecked_system_service_methods()?;
+        methods.sort_by_key(|it| (it.system_service_id, it.transaction_id));
+
+        let printer = Printer::new();
+        for m in methods {
+            let service = services
+                .get(&m.system_service_id)
+                .map_or("<unknown>", |it| it.as_str());
+            printer.println(format!("{} {} {}", service, m.transaction_id, m));
+        }
+        Ok(())
+    }
+}
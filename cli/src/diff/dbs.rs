@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+use anyhow::bail;
+use clap::{self, Args};
+use dtu::db::device::{DatabaseDiffReport, DatabaseDiffTask};
+use dtu::tasks::NoopMonitor;
+use dtu::utils::path_must_str;
+
+use crate::db::monitor::PrintMonitor;
+use crate::printer::Printer;
+use crate::utils::task_canceller;
+
+#[derive(Args)]
+pub struct Dbs {
+    /// The old device database, for example from before an OTA
+    old: PathBuf,
+
+    /// The new device database
+    new: PathBuf,
+
+    /// Print the report as JSON
+    #[arg(short, long)]
+    json: bool,
+
+    /// Also write the JSON report to the given file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl Dbs {
+    pub fn run(&self) -> anyhow::Result<()> {
+        for path in [&self.old, &self.new] {
+            if !path.is_file() {
+                bail!("{} is not a file", path_must_str(path));
+            }
+        }
+
+        let report = self.diff()?;
+
+        if let Some(output) = &self.output {
+            let writer = BufWriter::new(File::create(output)?);
+            serde_json::to_writer_pretty(writer, &report)?;
+        }
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &report)?;
+            return Ok(());
+        }
+
+        Printer::new().print(&report);
+        Ok(())
+    }
+
+    fn diff(&self) -> anyhow::Result<DatabaseDiffReport> {
+        let (_cancel, check) = task_canceller()?;
+        // Progress output would corrupt the JSON on stdout
+        if self.json {
+            let mon = NoopMonitor::new();
+            let task = DatabaseDiffTask::new(&self.old, &self.new, check, &mon);
+            return Ok(task.run()?);
+        }
+        let (mon, _handle) = PrintMonitor::start()?;
+        let task = DatabaseDiffTask::new(&self.old, &self.new, check, &mon);
+        let res = task.run();
+        drop(mon);
+        Ok(res?)
+    }
+}
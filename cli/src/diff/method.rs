@@ -0,0 +1,118 @@
+use clap::{self, Args};
+use dtu::db::device::models::{DiffSource, SystemService};
+use dtu::db::device::{diff_system_service_method_smali, MethodSmaliDiff};
+use dtu::db::{DeviceDatabase, MetaDatabase};
+use dtu::Context;
+
+use crate::diff::{get_diff_source, with_default_smali_dir};
+use crate::parsers::{DiffSourceValueParser, SystemServiceValueParser};
+use crate::printer::{color, Printer};
+
+#[derive(Args)]
+pub struct Method {
+    /// The system service the method belongs to
+    #[arg(short, long, value_parser = SystemServiceValueParser)]
+    service: SystemService,
+
+    /// The method name
+    #[arg(short, long)]
+    method: String,
+
+    /// Set the diff source, defaults to the emulator
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// Show the diff side by side instead of in unified format
+    #[arg(short = 'y', long)]
+    side_by_side: bool,
+
+    /// Number of lines of context to show in unified format
+    #[arg(short = 'C', long, default_value_t = 3)]
+    context: usize,
+
+    /// Width of each column when showing the diff side by side
+    #[arg(short = 'W', long, default_value_t = 60)]
+    width: usize,
+}
+
+impl Method {
+    pub fn run(&self, ctx: &dyn Context, meta: &dyn MetaDatabase) -> anyhow::Result<()> {
+        let db = DeviceDatabase::new(ctx)?;
+        let diff_source = get_diff_source(ctx, meta, &db, &self.diff_source)?;
+        let diff_source = with_default_smali_dir(ctx, diff_source);
+
+        let method = db
+            .get_system_service_methods_by_service_id(self.service.id)?
+            .into_iter()
+            .find(|it| it.name == self.method)
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "no method {} for service {}",
+                    self.method, self.service.name
+                ))
+            })?;
+
+        let diff = diff_system_service_method_smali(ctx, &db, &diff_source, &method)?;
+
+        if !diff.in_diff_source {
+            eprintln!(
+                "{} not found in diff source {}",
+                method.name, diff_source.name
+            );
+        }
+        if !diff.diff.has_changes() {
+            println!("no changes");
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        if self.side_by_side {
+            print_side_by_side(&printer, &diff, self.width);
+        } else {
+            print_unified(&printer, &diff, self.context);
+        }
+        Ok(())
+    }
+}
+
+fn print_unified(printer: &Printer, diff: &MethodSmaliDiff, context: usize) {
+    for line in diff.unified(context).lines() {
+        let color = match line.chars().next() {
+            Some('@') => color::CYAN,
+            Some('+') => color::GREEN,
+            Some('-') => color::RED,
+            _ => {
+                printer.println(line);
+                continue;
+            }
+        };
+        printer.println_colored(line, color);
+    }
+}
+
+fn print_side_by_side(printer: &Printer, diff: &MethodSmaliDiff, width: usize) {
+    let old = diff
+        .diff_file
+        .as_ref()
+        .map_or(String::from("/dev/null"), |it| {
+            it.to_string_lossy().into_owned()
+        });
+    printer.println(format!(
+        "{:<width$}   {}",
+        old,
+        diff.device_file.to_string_lossy(),
+        width = width
+    ));
+    for line in diff.diff.side_by_side(width).lines() {
+        let color = match line.chars().nth(width + 1) {
+            Some('|') => color::CYAN,
+            Some('>') => color::GREEN,
+            Some('<') => color::RED,
+            _ => {
+                printer.println(line);
+                continue;
+            }
+        };
+        printer.println_colored(line, color);
+    }
+}
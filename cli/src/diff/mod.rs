@@ -5,9 +5,17 @@ use dtu::{Context, DefaultContext};
 
 use dtu::db::device::models::DiffSource;
 use dtu::db::device::EMULATOR_DIFF_SOURCE;
-use dtu::utils::{ClassName, SmaliMethodSignatureIterator};
+use dtu::utils::{path_must_str, ClassName, SmaliMethodSignatureIterator};
 
+use crate::db::get_aosp_smali_dir_path;
+
+mod dbs;
+mod method;
+mod report;
 mod ui;
+use dbs::Dbs;
+use method::Method;
+use report::Report;
 use ui::UI;
 
 #[derive(Args)]
@@ -20,14 +28,33 @@ pub struct Diff {
 enum Command {
     #[command()]
     UI(UI),
+
+    /// Diff the smali of a system service method against the diff source
+    #[command()]
+    Method(Method),
+
+    /// Compare two device databases, for example before and after an OTA
+    #[command()]
+    Dbs(Dbs),
+
+    /// Write the diff results as a Markdown, HTML, or SARIF report
+    #[command()]
+    Report(Report),
 }
 
 impl Diff {
     pub fn run(&self) -> anyhow::Result<()> {
+        // Diffing two databases doesn't need a project
+        if let Command::Dbs(c) = &self.command {
+            return c.run();
+        }
         let ctx = DefaultContext::new();
         let meta = MetaSqliteDatabase::new(&ctx)?;
         match &self.command {
             Command::UI(c) => c.run(&ctx, &meta),
+            Command::Method(c) => c.run(&ctx, &meta),
+            Command::Report(c) => c.run(&ctx, &meta),
+            Command::Dbs(_) => unreachable!(),
         }
     }
 }
@@ -65,6 +92,20 @@ pub fn get_diff_source(
     Ok(diff_source)
 }
 
+/// Fill in the smali directory for the emulator diff source if it wasn't set
+/// when the diff was created but the AOSP smali is available
+pub fn with_default_smali_dir(ctx: &dyn Context, mut diff_source: DiffSource) -> DiffSource {
+    if diff_source.smali_dir.is_some() || diff_source.name != EMULATOR_DIFF_SOURCE {
+        return diff_source;
+    }
+    if let Ok(dir) = get_aosp_smali_dir_path(ctx, ctx.get_target_api_level()) {
+        if dir.is_dir() {
+            diff_source.smali_dir = Some(path_must_str(&dir).into());
+        }
+    }
+    diff_source
+}
+
 pub fn smali_sig_contains_class(vals: &str) -> bool {
     if vals == "?" {
         return false;
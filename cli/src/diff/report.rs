@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::{self, Args};
+use dtu::db::device::models::DiffSource;
+use dtu::db::device::{DiffReport, DiffReportFilter, DiffReportFormat};
+use dtu::db::{DeviceDatabase, MetaDatabase};
+use dtu::Context;
+
+use crate::diff::get_diff_source;
+use crate::diff::ui::state::State;
+use crate::parsers::DiffSourceValueParser;
+
+#[derive(Args)]
+pub struct Report {
+    /// Set the diff source, defaults to the emulator
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// The output format: markdown, html, or sarif
+    #[arg(short, long, default_value_t = DiffReportFormat::Markdown)]
+    format: DiffReportFormat,
+
+    /// Write the report to the given file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Include items hidden in `diff ui`
+    #[arg(short = 'A', long)]
+    all: bool,
+}
+
+impl Report {
+    pub fn run(&self, ctx: &dyn Context, meta: &dyn MetaDatabase) -> anyhow::Result<()> {
+        let db = DeviceDatabase::new(ctx)?;
+        let diff_source = get_diff_source(ctx, meta, &db, &self.diff_source)?;
+        let filter = if self.all {
+            DiffReportFilter::default()
+        } else {
+            State::load(ctx)?.report_filter()
+        };
+
+        let report = DiffReport::from_db(&db, &diff_source, &filter)?;
+
+        let mut writer: Box<dyn Write> = match &self.output {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        report.write(self.format, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -25,6 +25,7 @@ use crate::diff::ui::diffed_method::DiffedSystemServiceMethodData;
 use crate::diff::ui::filter_boxes::{ApkIPCFilterBox, SystemServiceMethodFilterBox};
 use crate::diff::ui::tabs::{Tab, TabContainer};
 use crate::diff::ui::ui::{ActiveSection, ActiveTab};
+use crate::diff::with_default_smali_dir;
 use crate::ui::widgets::{BlockBuilder, ClosureWidget, BG_COLOR, FG_COLOR};
 
 use super::state::State;
@@ -40,6 +41,8 @@ pub struct Applet<'a> {
     search_string: Option<String>,
     editing_search_string: bool,
     showing_popup: bool,
+    showing_diff: bool,
+    side_by_side_diff: bool,
     showing_help: bool,
     show_hidden: bool,
     should_quit: bool,
@@ -69,6 +72,8 @@ impl<'a> Applet<'a> {
             showing_help: false,
             show_hidden: true,
             showing_popup: false,
+            showing_diff: false,
+            side_by_side_diff: false,
         })
     }
 
@@ -146,6 +151,7 @@ impl<'a> Applet<'a> {
             KeyCode::Char('G') => self.sel_end(),
             KeyCode::Char('O') => self.open_requested(),
             KeyCode::Char('L') => self.logcat_requested(),
+            KeyCode::Char('D') => self.toggle_side_by_side_diff(),
             _ => return false,
         }
         true
@@ -225,11 +231,17 @@ impl<'a> Applet<'a> {
             'k' => self.dec_sel_idx(),
             'g' => self.sel_start(),
             'c' => self.clipboard_requested(),
+            'd' => self.showing_diff = !self.showing_diff,
             _ => return false,
         }
         true
     }
 
+    fn toggle_side_by_side_diff(&mut self) {
+        self.side_by_side_diff = !self.side_by_side_diff;
+        self.showing_diff = true;
+    }
+
     fn toggle_show_hidden(&mut self) {
         self.show_hidden = !self.show_hidden;
         self.active_tab.set_show_hidden(self.show_hidden);
@@ -368,6 +380,7 @@ impl<'a> Applet<'a> {
             Some(Box::new(SystemServiceMethodCustomizer::new(
                 self.db.clone(),
                 hidden_services,
+                with_default_smali_dir(self.ctx, self.diff_source.clone()),
             ))),
             Some(Box::new(SystemServiceMethodFilterBox::new(services))),
         );
@@ -558,6 +571,9 @@ impl<'a> Applet<'a> {
     pub fn get_popup(&self) -> Option<ClosureWidget> {
         if self.showing_help {
             Some(self.get_help_popup())
+        } else if self.showing_diff {
+            self.active_tab
+                .get_diff_popup(self.ctx, self.side_by_side_diff)
         } else if self.showing_popup {
             self.active_tab.get_info_popup()
         } else {
@@ -635,6 +651,9 @@ c               Invoke the clipboard action with $DTU_CLIPBOARD_EXECUTABLE
                 or dtu-clipboard
 L               Attempt to send a logcat string for the currently highlighted
                 selection to dtu-clipboard
+d               Show/hide the smali diff against the diff source for the
+                highlighted system service method
+D               Switch the smali diff between unified and side by side
 J/K             Change diff type down/up
 j/k             Move selection down/up
 g               Move selection to top
@@ -7,15 +7,18 @@ use std::ops::Deref;
 use anyhow::bail;
 use dtu::adb::{Adb, ExecAdb};
 use ratatui::style::Style;
+use ratatui::text::{Line, Text};
 use ratatui::widgets::{Paragraph, Widget};
 
-use dtu::db::device::models::DiffedProvider;
+use dtu::db::device::models::{DiffSource, DiffedProvider};
+use dtu::db::device::{diff_system_service_method_smali, MethodSmaliDiff};
 use dtu::db::{ApkIPC, ApkIPCKind, DeviceDatabase};
 
 use crate::diff::ui::diffed_method::DiffedSystemServiceMethodData;
 use crate::diff::{smali_sig_contains_class, smali_sig_looks_like_binder};
 use crate::ui::widgets::{
-    BlockBuilder, ClosureWidget, BG_COLOR, FG_COLOR, INTERESTING_COLOR, PURPLE,
+    BlockBuilder, ClosureWidget, ACTIVE_COLOR, BG_COLOR, ERR_COLOR, FG_COLOR, INTERESTING_COLOR,
+    OK_COLOR, PURPLE,
 };
 use crate::utils::{find_fully_qualified_apk, invoke_dtu_clipboard, invoke_dtu_open_file, ostr};
 use dtu::utils::{find_smali_file_for_class, path_must_str, ClassName};
@@ -39,6 +42,19 @@ pub trait Customizer<E> {
         None
     }
 
+    /// Get a popup showing the diff of the item against the diff source
+    fn get_diff_popup(
+        &self,
+        ctx: &dyn Context,
+        item: &E,
+        side_by_side: bool,
+    ) -> Option<ClosureWidget> {
+        let _ = item;
+        let _ = ctx;
+        let _ = side_by_side;
+        None
+    }
+
     fn open_selection(&self, ctx: &dyn Context, item: &E) -> anyhow::Result<()> {
         let _ = item;
         let _ = ctx;
@@ -61,15 +77,67 @@ pub trait Customizer<E> {
 pub struct SystemServiceMethodCustomizer {
     db: DeviceDatabase,
     hidden_services: HashSet<i32>,
+    diff_source: DiffSource,
 }
 
 impl SystemServiceMethodCustomizer {
-    pub fn new(db: DeviceDatabase, hidden_services: HashSet<i32>) -> Self {
+    pub fn new(db: DeviceDatabase, hidden_services: HashSet<i32>, diff_source: DiffSource) -> Self {
         Self {
             db,
             hidden_services,
+            diff_source,
+        }
+    }
+
+    fn get_method_diff(
+        &self,
+        ctx: &dyn Context,
+        item: &DiffedSystemServiceMethodData,
+    ) -> anyhow::Result<MethodSmaliDiff> {
+        let method = self.db.get_system_service_method_by_id(item.id)?;
+        Ok(diff_system_service_method_smali(
+            ctx,
+            &self.db,
+            &self.diff_source,
+            &method,
+        )?)
+    }
+}
+
+fn smali_diff_text(diff: &MethodSmaliDiff, side_by_side: bool, width: usize) -> Text<'static> {
+    let mut lines = Vec::new();
+    if !diff.in_diff_source {
+        lines.push(Line::from("Method not found in the diff source"));
+    }
+    if !diff.diff.has_changes() {
+        lines.push(Line::from("No changes"));
+        return Text::from(lines);
+    }
+
+    if side_by_side {
+        // Account for the borders and the ` | ` separator
+        let width = width.saturating_sub(5) / 2;
+        for line in diff.diff.side_by_side(width).lines() {
+            let style = match line.chars().nth(width + 1) {
+                Some('|') => Style::default().fg(ACTIVE_COLOR),
+                Some('>') => Style::default().fg(OK_COLOR),
+                Some('<') => Style::default().fg(ERR_COLOR),
+                _ => Style::default(),
+            };
+            lines.push(Line::styled(String::from(line), style));
+        }
+    } else {
+        for line in diff.unified(3).lines() {
+            let style = match line.chars().next() {
+                Some('@') => Style::default().fg(ACTIVE_COLOR),
+                Some('+') => Style::default().fg(OK_COLOR),
+                Some('-') => Style::default().fg(ERR_COLOR),
+                _ => Style::default(),
+            };
+            lines.push(Line::styled(String::from(line), style));
         }
     }
+    Text::from(lines)
 }
 
 impl Customizer<DiffedSystemServiceMethodData> for SystemServiceMethodCustomizer {
@@ -157,6 +225,27 @@ impl Customizer<DiffedSystemServiceMethodData> for SystemServiceMethodCustomizer
             para.render(area, buf);
         })))
     }
+
+    fn get_diff_popup(
+        &self,
+        ctx: &dyn Context,
+        item: &DiffedSystemServiceMethodData,
+        side_by_side: bool,
+    ) -> Option<ClosureWidget> {
+        let diff = self.get_method_diff(ctx, item).map_err(|e| e.to_string());
+        let title = format!("Smali diff against {}", self.diff_source.name);
+        Some(ClosureWidget::new(Box::new(move |area, buf| {
+            let text = match &diff {
+                Ok(diff) => smali_diff_text(diff, side_by_side, area.width as usize),
+                Err(e) => Text::from(format!("Failed to diff method: {}", e)),
+            };
+            let block = BlockBuilder::default()
+                .with_style(Style::default().bg(FG_COLOR).fg(BG_COLOR))
+                .with_text(title.as_str())
+                .build();
+            Paragraph::new(text).block(block).render(area, buf);
+        })))
+    }
 }
 
 pub struct ApkIPCCustomizer<U> {
@@ -444,6 +533,8 @@ impl Customizer<DiffedProvider> for ProviderCustomizer {
                 .map(|it| it.as_str())
                 .unwrap_or("None")
         );
+        let mut text = text;
+        text.push_str(&provider_paths_text(&self.db, item));
         let block = BlockBuilder::default()
             .with_style(Style::default().bg(FG_COLOR).fg(BG_COLOR))
             .with_text("Details")
@@ -454,3 +545,41 @@ impl Customizer<DiffedProvider> for ProviderCustomizer {
         })))
     }
 }
+
+/// Describe the `<path-permission>` and `<grant-uri-permission>` entries of the
+/// provider for the details popup
+fn provider_paths_text(db: &DeviceDatabase, item: &DiffedProvider) -> String {
+    let differs = |matches: bool| {
+        if matches {
+            ""
+        } else {
+            " (differs from diff source)"
+        }
+    };
+
+    let mut text = format!(
+        "Grant URI Permissions: {}{}\n",
+        item.grant_uri_permissions,
+        differs(item.grant_uri_permissions_match_diff)
+    );
+    for grant in db
+        .get_grant_uri_permissions_by_provider_id(item.provider.id)
+        .unwrap_or_default()
+    {
+        text.push_str(&format!("   {}\n", grant));
+    }
+
+    let paths = db
+        .get_path_permissions_by_provider_id(item.provider.id)
+        .unwrap_or_default();
+    if !paths.is_empty() || !item.path_permissions_match_diff {
+        text.push_str(&format!(
+            "Path Permissions:{}\n",
+            differs(item.path_permissions_match_diff)
+        ));
+    }
+    for pp in paths {
+        text.push_str(&format!("   {}\n", pp));
+    }
+    text
+}
@@ -4,7 +4,7 @@ use crossterm::event;
 use dtu::Context;
 
 mod filter_boxes;
-mod state;
+pub(crate) mod state;
 mod ui;
 
 mod diffed_method;
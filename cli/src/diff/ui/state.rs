@@ -5,6 +5,7 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use dtu::db::device::DiffReportFilter;
 use dtu::utils::ensure_dir_exists;
 use dtu::Context;
 
@@ -43,6 +44,19 @@ impl State {
         file.write_all(json.as_bytes())?;
         Ok(())
     }
+
+    /// Get a [DiffReportFilter] that hides the same items as the UI
+    pub fn report_filter(&self) -> DiffReportFilter {
+        DiffReportFilter {
+            hidden_system_services: self.hidden_system_services.clone(),
+            hidden_system_service_methods: self.hidden_system_service_methods.clone(),
+            hidden_apks: self.hidden_apks.clone(),
+            hidden_providers: self.hidden_providers.clone(),
+            hidden_activities: self.hidden_activities.clone(),
+            hidden_receivers: self.hidden_receivers.clone(),
+            hidden_services: self.hidden_services.clone(),
+        }
+    }
 }
 
 fn get_state_file(ctx: &dyn Context) -> anyhow::Result<PathBuf> {
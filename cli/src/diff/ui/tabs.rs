@@ -40,6 +40,8 @@ pub trait Tab {
 
     fn get_info_popup(&self) -> Option<ClosureWidget>;
 
+    fn get_diff_popup(&self, ctx: &dyn Context, side_by_side: bool) -> Option<ClosureWidget>;
+
     fn set_search_string(&mut self, search: Option<String>);
 }
 
@@ -352,6 +354,16 @@ where
         }
     }
 
+    fn get_diff_popup(&self, ctx: &dyn Context, side_by_side: bool) -> Option<ClosureWidget> {
+        match &self.customizer {
+            None => None,
+            Some(it) => {
+                let item = self.items.get(self.sel)?;
+                it.get_diff_popup(ctx, item, side_by_side)
+            }
+        }
+    }
+
     fn set_search_string(&mut self, search: Option<String>) {
         match search {
             None => self.filter = None,
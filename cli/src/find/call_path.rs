@@ -0,0 +1,143 @@
+use std::io;
+
+use clap::{self, Args};
+use dtu::Context;
+use sha2::{Digest, Sha256};
+
+use crate::find::utils::get_method_search;
+use crate::parsers::GraphSourceValueParser;
+use crate::printer::{color, Printer};
+use crate::utils::{oshash, ostr, project_cacheable, shash};
+use dtu::db::graph::{GraphDatabase, MethodCallPath};
+use dtu::utils::{hex, ClassName};
+
+#[derive(Args)]
+pub struct FindCallPath {
+    /// The source of the method the path starts at
+    #[arg(short = 'F', long, value_parser = GraphSourceValueParser)]
+    from_source: Option<String>,
+
+    /// The class of the method the path starts at
+    #[arg(short = 'c', long)]
+    from_class: Option<ClassName>,
+
+    /// The name of the method the path starts at
+    #[arg(short = 'n', long)]
+    from_name: Option<String>,
+
+    /// The signature of the method the path starts at
+    #[arg(short = 's', long)]
+    from_signature: Option<String>,
+
+    /// The source of the method the path ends at
+    #[arg(short = 'T', long, value_parser = GraphSourceValueParser)]
+    to_source: Option<String>,
+
+    /// The class of the method the path ends at
+    #[arg(short = 'C', long)]
+    to_class: Option<ClassName>,
+
+    /// The name of the method the path ends at
+    #[arg(short = 'N', long)]
+    to_name: Option<String>,
+
+    /// The signature of the method the path ends at
+    #[arg(short = 'S', long)]
+    to_signature: Option<String>,
+
+    /// Show up to this many paths ordered by length instead of only the
+    /// shortest paths
+    #[arg(short = 'k', long)]
+    count: Option<usize>,
+
+    /// Maximum depth to search
+    #[arg(short, long, default_value_t = 5)]
+    depth: usize,
+
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+
+    /// Ignore the cached results
+    #[arg(long, default_value_t = false)]
+    no_cache: bool,
+}
+
+impl FindCallPath {
+    pub fn run(&self, ctx: &dyn Context, db: &dyn GraphDatabase) -> anyhow::Result<()> {
+        let mut hasher = Sha256::new();
+        oshash(&mut hasher, &self.from_source);
+        oshash(&mut hasher, &self.from_class);
+        oshash(&mut hasher, &self.from_name);
+        oshash(&mut hasher, &self.from_signature);
+        // Keep `-n foo` and `-N foo` from hashing the same
+        shash(&mut hasher, "->");
+        oshash(&mut hasher, &self.to_source);
+        oshash(&mut hasher, &self.to_class);
+        oshash(&mut hasher, &self.to_name);
+        oshash(&mut hasher, &self.to_signature);
+        let digest = hasher.finalize();
+        let cache = match self.count {
+            Some(k) => format!(
+                "find-call-path-{}-{}-k{}",
+                hex::bytes_to_hex(&digest),
+                self.depth,
+                k
+            ),
+            None => format!(
+                "find-call-path-{}-{}",
+                hex::bytes_to_hex(&digest),
+                self.depth
+            ),
+        };
+        let mpaths = project_cacheable(&ctx, &cache, self.no_cache, || self.go(db))?;
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &mpaths)?;
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        for (i, p) in mpaths.iter().enumerate() {
+            if i > 0 {
+                printer.println("");
+            }
+
+            let mut iter = p.path.iter();
+            let first = match iter.next() {
+                Some(v) => v,
+                None => continue,
+            };
+
+            printer.println_colored(
+                format!("{} in {}", first.as_smali(), first.source),
+                color::YELLOW,
+            );
+
+            for c in iter {
+                printer.print("   ");
+                printer.println_colored(format!("{} in {}", c.as_smali(), c.source), color::GREY);
+            }
+        }
+        Ok(())
+    }
+
+    fn go(&self, db: &dyn GraphDatabase) -> anyhow::Result<Vec<MethodCallPath>> {
+        let from = get_method_search(
+            ostr(&self.from_name),
+            self.from_class.as_ref(),
+            ostr(&self.from_signature),
+            ostr(&self.from_source),
+        )?;
+        let to = get_method_search(
+            ostr(&self.to_name),
+            self.to_class.as_ref(),
+            ostr(&self.to_signature),
+            ostr(&self.to_source),
+        )?;
+        Ok(match self.count {
+            Some(k) => db.find_k_shortest_call_paths(&from, &to, self.depth, k)?,
+            None => db.find_call_paths(&from, &to, self.depth)?,
+        })
+    }
+}
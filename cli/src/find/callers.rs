@@ -8,9 +8,21 @@ use crate::find::utils::get_method_search;
 use crate::parsers::GraphSourceValueParser;
 use crate::printer::{color, Printer};
 use crate::utils::{oshash, ostr, project_cacheable};
-use dtu::db::graph::{GraphDatabase, MethodCallPath};
+use dtu::db::graph::{DispatchMode, GraphDatabase, MethodCallPath};
 use dtu::utils::{hex, ClassName};
 
+/// Keeps the cache names for direct dispatch unchanged
+fn cache_suffix(dispatch: DispatchMode) -> String {
+    let mut suffix = String::new();
+    if dispatch.is_class_hierarchy() {
+        suffix.push_str("-cha");
+    }
+    if dispatch.is_binder() {
+        suffix.push_str("-binder");
+    }
+    suffix
+}
+
 #[derive(Args)]
 pub struct FindCallers {
     /// The source of the method to find callers to
@@ -41,21 +53,39 @@ pub struct FindCallers {
     #[arg(short, long, default_value_t = 3)]
     depth: usize,
 
+    /// Use class hierarchy analysis to also find callers of overridden methods
+    #[arg(short = 'H', long, default_value_t = false)]
+    class_hierarchy: bool,
+
+    /// Also find callers on the other side of binder calls into system services, requires
+    /// `graph link-binder`
+    #[arg(short = 'B', long, default_value_t = false)]
+    binder: bool,
+
     /// Ignore the cached results
     #[arg(long, default_value_t = false)]
     no_cache: bool,
 }
 
 impl FindCallers {
+    fn dispatch(&self) -> DispatchMode {
+        DispatchMode::new(self.class_hierarchy).with_binder(self.binder)
+    }
+
     pub fn run(&self, ctx: &dyn Context, db: &dyn GraphDatabase) -> anyhow::Result<()> {
         let mut hasher = Sha256::new();
         oshash(&mut hasher, &self.method_source);
-        oshash(&mut hasher, &self.method_source);
+        oshash(&mut hasher, &self.call_source);
         oshash(&mut hasher, &self.name);
         oshash(&mut hasher, &self.signature);
         oshash(&mut hasher, &self.class);
         let digest = hasher.finalize();
-        let cache = format!("find-callers-{}-{}", hex::bytes_to_hex(&digest), self.depth);
+        let cache = format!(
+            "find-callers-{}-{}{}",
+            hex::bytes_to_hex(&digest),
+            self.depth,
+            cache_suffix(self.dispatch())
+        );
         let mpaths = project_cacheable(&ctx, &cache, self.no_cache, || self.go(db))?;
 
         if self.json {
@@ -101,7 +131,12 @@ impl FindCallers {
             ostr(&self.signature),
             ostr(&self.method_source),
         )?;
-        Ok(db.find_callers(&search, ostr(&self.call_source), self.depth)?)
+        Ok(db.find_callers_with_dispatch(
+            &search,
+            ostr(&self.call_source),
+            self.depth,
+            self.dispatch(),
+        )?)
     }
 }
 
@@ -135,12 +170,25 @@ pub struct FindOutgoingCalls {
     #[arg(short, long, default_value_t = 3)]
     depth: usize,
 
+    /// Use class hierarchy analysis to also follow calls into overriding methods
+    #[arg(short = 'H', long, default_value_t = false)]
+    class_hierarchy: bool,
+
+    /// Follow binder calls from Stub.Proxy methods into system services, requires
+    /// `graph link-binder`
+    #[arg(short = 'B', long, default_value_t = false)]
+    binder: bool,
+
     /// Ignore the cached results
     #[arg(short, long, default_value_t = false)]
     no_cache: bool,
 }
 
 impl FindOutgoingCalls {
+    fn dispatch(&self) -> DispatchMode {
+        DispatchMode::new(self.class_hierarchy).with_binder(self.binder)
+    }
+
     pub fn run(&self, ctx: &dyn Context, db: &dyn GraphDatabase) -> anyhow::Result<()> {
         let mut hasher = Sha256::new();
         // We deliberately leave the `into_source` out of this cache string because the
@@ -152,9 +200,10 @@ impl FindOutgoingCalls {
         oshash(&mut hasher, &self.class);
         let digest = hasher.finalize();
         let cache = format!(
-            "find-outgoing-call-{}-{}",
+            "find-outgoing-call-{}-{}{}",
             hex::bytes_to_hex(&digest),
-            self.depth
+            self.depth,
+            cache_suffix(self.dispatch())
         );
         let mpaths = project_cacheable(&ctx, &cache, self.no_cache, || self.go(db))?;
 
@@ -181,7 +230,7 @@ impl FindOutgoingCalls {
             ostr(&self.signature),
             ostr(&self.leaving_source),
         )?;
-        Ok(db.find_outgoing_calls(&search, self.depth)?)
+        Ok(db.find_outgoing_calls_with_dispatch(&search, self.depth, self.dispatch())?)
     }
 
     fn show_into(
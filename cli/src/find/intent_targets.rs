@@ -0,0 +1,91 @@
+use std::io;
+
+use clap::{self, Args};
+use dtu::db::graph::intents::{
+    find_intent_targets, load_intent_components, IntentTargetKind, IntentTargetSearch,
+};
+use dtu::db::graph::GraphDatabase;
+use dtu::db::DeviceDatabase;
+use dtu::prereqs::Prereq;
+use dtu::utils::{ensure_prereq, ClassName};
+use dtu::Context;
+
+use crate::parsers::GraphSourceValueParser;
+use crate::printer::{color, Printer};
+use crate::utils::ostr;
+
+#[derive(Args)]
+pub struct FindIntentTargets {
+    /// Only consider intents sent from this source
+    #[arg(short = 'S', long, value_parser = GraphSourceValueParser)]
+    source: Option<String>,
+
+    /// Only consider intents with the given action
+    #[arg(short, long)]
+    action: Option<String>,
+
+    /// Only consider intents sent to the given kind of component: activity,
+    /// service, or receiver
+    #[arg(short, long)]
+    kind: Option<IntentTargetKind>,
+
+    /// Only consider intents sent to the given component
+    #[arg(short, long)]
+    target: Option<ClassName>,
+
+    /// Only show exported and enabled components
+    #[arg(short = 'X', long)]
+    exported: bool,
+
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl FindIntentTargets {
+    pub fn run(&self, ctx: &dyn Context, db: &dyn GraphDatabase) -> anyhow::Result<()> {
+        ensure_prereq(ctx, Prereq::SQLDatabaseSetup)?;
+        let device = DeviceDatabase::new(ctx)?;
+        let components = load_intent_components(&device)?;
+
+        let search = IntentTargetSearch {
+            source: ostr(&self.source),
+            action: ostr(&self.action),
+            kind: self.kind,
+            target: self.target.as_ref(),
+            only_exported: self.exported,
+        };
+
+        let edges = find_intent_targets(db, &components, &search)?;
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &edges)?;
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        let mut last = None;
+        for edge in &edges {
+            if last != Some(&edge.sender) {
+                let m = &edge.sender;
+                printer.println_colored(format!("{} in {}", m.as_smali(), m.source), color::YELLOW);
+                last = Some(&edge.sender);
+            }
+            let t = &edge.target;
+            let how = match &edge.action {
+                Some(action) => action.as_str(),
+                None => "explicit",
+            };
+            let line = format!(
+                "   -> {} {} in {} ({}, {})",
+                t.kind, t.class_name, t.apk, edge.via, how
+            );
+            if t.exported && t.enabled {
+                printer.println(line);
+            } else {
+                printer.println_colored(line, color::GREY);
+            }
+        }
+        Ok(())
+    }
+}
@@ -26,6 +26,15 @@ use manifest::FindManifest;
 mod callers;
 use callers::{FindCallers, FindOutgoingCalls};
 
+mod call_path;
+use call_path::FindCallPath;
+
+mod native_methods;
+use native_methods::FindNativeMethods;
+
+mod intent_targets;
+use intent_targets::FindIntentTargets;
+
 mod class;
 use class::FindClass;
 
@@ -41,6 +50,9 @@ use methods::Methods;
 mod fields;
 use fields::Fields;
 
+mod weak_permissions;
+use weak_permissions::FindWeakPermissions;
+
 mod apk_graph;
 use apk_graph::{ApkIPCCallsGeneric, FindIPCCalls, FindIntentActivities, FindParseUri};
 #[derive(Args)]
@@ -75,6 +87,13 @@ enum Command {
     #[command()]
     Permission(Permission),
 
+    /// Find permissions that don't protect the components requiring them:
+    /// undefined permissions, custom normal or dangerous permissions guarding
+    /// privileged components, and permissions defined differently than in
+    /// the diff source
+    #[command()]
+    WeakPermissions(FindWeakPermissions),
+
     /// Find a smali file
     #[command()]
     SmaliFile(SmaliFile),
@@ -87,6 +106,18 @@ enum Command {
     #[command()]
     OutgoingCalls(FindOutgoingCalls),
 
+    /// Find the shortest call paths between two methods
+    #[command()]
+    CallPath(FindCallPath),
+
+    /// Find native methods and the libraries implementing them
+    #[command()]
+    NativeMethods(FindNativeMethods),
+
+    /// Find components that intents sent from app and framework code can reach
+    #[command()]
+    IntentTargets(FindIntentTargets),
+
     /// Find Activitys that call `getIntent`
     #[command()]
     IntentActivities(FindIntentActivities),
@@ -126,6 +157,7 @@ impl Find {
             Command::Methods(c) => c.run(&ctx),
             Command::Fields(c) => c.run(&ctx),
             Command::Permission(c) => c.run(&ctx),
+            Command::WeakPermissions(c) => c.run(&ctx),
             Command::ProtectedBroadcast(c) => c.run(&ctx),
             Command::SmaliFile(c) => c.run(&ctx),
             Command::Manifest(c) => c.run(&ctx),
@@ -141,6 +173,18 @@ impl Find {
                 let db = graph_db(&ctx)?;
                 c.run(&ctx, &db)
             }
+            Command::CallPath(c) => {
+                let db = graph_db(&ctx)?;
+                c.run(&ctx, &db)
+            }
+            Command::NativeMethods(c) => {
+                let db = graph_db(&ctx)?;
+                c.run(&ctx, &db)
+            }
+            Command::IntentTargets(c) => {
+                let db = graph_db(&ctx)?;
+                c.run(&ctx, &db)
+            }
             Command::IntentActivities(c) => {
                 let db = graph_db(&ctx)?;
                 c.run(&ctx, &db)
@@ -0,0 +1,88 @@
+use std::io;
+
+use clap::{self, Args};
+use dtu::db::graph::{GraphDatabase, MethodSearch};
+use dtu::utils::ClassName;
+use dtu::Context;
+
+use crate::parsers::GraphSourceValueParser;
+use crate::printer::{color, Printer};
+use crate::utils::ostr;
+
+#[derive(Args)]
+pub struct FindNativeMethods {
+    /// Method class
+    #[arg(short, long)]
+    class: Option<ClassName>,
+
+    /// Method name
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// Method signature
+    #[arg(short, long)]
+    signature: Option<String>,
+
+    /// The source of the methods
+    #[arg(short = 'S', long, value_parser = GraphSourceValueParser)]
+    source: Option<String>,
+
+    /// Only show methods that aren't linked to a library
+    #[arg(short, long)]
+    unlinked: bool,
+
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl FindNativeMethods {
+    pub fn run(&self, _ctx: &dyn Context, db: &dyn GraphDatabase) -> anyhow::Result<()> {
+        let search = if self.class.is_some() || self.name.is_some() || self.signature.is_some() {
+            Some(
+                MethodSearch::new_from_opts(
+                    self.class.as_ref(),
+                    ostr(&self.name),
+                    ostr(&self.signature),
+                    ostr(&self.source),
+                )
+                .map_err(anyhow::Error::msg)?,
+            )
+        } else {
+            None
+        };
+
+        let mut natives = db.get_native_methods(search.as_ref())?;
+        if search.is_none() {
+            if let Some(src) = &self.source {
+                natives.retain(|it| it.method.source == *src);
+            }
+        }
+        if self.unlinked {
+            natives.retain(|it| it.implementations.is_empty());
+        }
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &natives)?;
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        for native in natives {
+            let m = &native.method;
+            printer.println_colored(format!("{} in {}", m.as_smali(), m.source), color::YELLOW);
+            if native.implementations.is_empty() {
+                printer.println_colored("   no library found", color::GREY);
+            }
+            for imp in native.implementations {
+                let how = if imp.registered {
+                    "RegisterNatives"
+                } else {
+                    "export"
+                };
+                printer.println(format!("   {} {} ({})", imp.library, imp.symbol, how));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::io;
+
+use clap::{self, Args};
+use dtu::db::device::models::DiffSource;
+use dtu::db::device::{find_weak_permissions, WeakPermission, WeakPermissionKind};
+use dtu::db::{DeviceDatabase, MetaSqliteDatabase};
+use dtu::prereqs::Prereq;
+use dtu::utils::ensure_prereq;
+use dtu::Context;
+
+use crate::diff::get_diff_source;
+use crate::parsers::DiffSourceValueParser;
+use crate::printer::{color, Printer};
+
+#[derive(Args)]
+pub struct FindWeakPermissions {
+    /// Set the diff source to compare permission definitions against,
+    /// defaults to the emulator
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// Don't compare permission definitions against a diff source
+    #[arg(long, conflicts_with = "diff_source")]
+    no_diff: bool,
+
+    /// Only show permissions reported for the given reason: undefined, weak,
+    /// or changed-from-diff
+    #[arg(short, long)]
+    kind: Option<WeakPermissionKind>,
+
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl FindWeakPermissions {
+    pub fn run(&self, ctx: &dyn Context) -> anyhow::Result<()> {
+        ensure_prereq(ctx, Prereq::SQLDatabaseSetup)?;
+        let db = DeviceDatabase::new(ctx)?;
+
+        let diff_id = if self.no_diff {
+            None
+        } else {
+            let meta = MetaSqliteDatabase::new(ctx)?;
+            Some(get_diff_source(ctx, &meta, &db, &self.diff_source)?.id)
+        };
+
+        let mut found = find_weak_permissions(&db, diff_id)?;
+        if let Some(kind) = self.kind {
+            found.retain(|it| it.kind == kind);
+        }
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &found)?;
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        for it in found.iter() {
+            print_weak_permission(&printer, it);
+        }
+        Ok(())
+    }
+}
+
+fn print_weak_permission(printer: &Printer, it: &WeakPermission) {
+    let color = match it.kind {
+        WeakPermissionKind::Undefined | WeakPermissionKind::Weak => color::RED,
+        WeakPermissionKind::ChangedFromDiff => color::YELLOW,
+    };
+    printer.print_colored(format!("[{}] ", it.kind), color);
+    printer.print(&it.name);
+    match &it.protection_level {
+        Some(level) => printer.print(format!(" - {}", level)),
+        None => printer.print(" - not defined"),
+    }
+    if let Some(diff) = &it.diff_protection_level {
+        printer.print_colored(format!(" (diff: {})", diff), color::YELLOW);
+    }
+    printer.println("");
+
+    if !it.defined_by.is_empty() {
+        printer.println(format!("    defined by {}", it.defined_by.join(", ")));
+    }
+    if !it.requested_by.is_empty() {
+        printer.println(format!("    requested by {}", it.requested_by.join(", ")));
+    }
+    for comp in it.components.iter() {
+        let line = format!(
+            "    {} {} in {} ({})",
+            comp.kind.as_str(),
+            comp.name,
+            comp.apk,
+            comp.attribute
+        );
+        if comp.privileged {
+            printer.println_colored(line, color::INTERESTING);
+        } else {
+            printer.println(line);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use clap::{self, Args};
+
+use dtu::db::device::models::{SystemService, SystemServiceMethod};
+use dtu::db::DeviceDatabase;
+use dtu::prereqs::Prereq;
+use dtu::tasks::binder_fuzz::{BinderFuzzEvent, BinderFuzzTask, FuzzOutcome};
+use dtu::tasks::EventMonitor;
+use dtu::utils::ensure_prereq;
+use dtu::DefaultContext;
+
+use crate::printer::{color, Printer};
+use crate::utils::{get_app_server, task_canceller};
+
+struct PrintMonitor {
+    quiet: bool,
+}
+
+impl EventMonitor<BinderFuzzEvent> for PrintMonitor {
+    fn on_event(&self, evt: BinderFuzzEvent) {
+        let printer = Printer::new();
+        match evt {
+            BinderFuzzEvent::MethodStarted {
+                service,
+                method,
+                cases,
+            } => {
+                printer.println(format!("fuzzing {} {} ({} inputs)", service, method, cases));
+            }
+            BinderFuzzEvent::MethodSkipped {
+                service,
+                method,
+                reason,
+            } => {
+                if !self.quiet {
+                    printer.println_colored(
+                        format!("skipping {} {}: {}", service, method, reason),
+                        color::GREY,
+                    );
+                }
+            }
+            BinderFuzzEvent::CaseDone {
+                description,
+                outcome,
+                exception,
+            } => {
+                let color = match outcome {
+                    FuzzOutcome::Ok => color::GREEN,
+                    FuzzOutcome::Exception | FuzzOutcome::Timeout => color::YELLOW,
+                    FuzzOutcome::SecurityException => color::GREY,
+                    FuzzOutcome::Crash | FuzzOutcome::Error => color::RED,
+                };
+                // Always show anything that might be a bug
+                if self.quiet && !matches!(outcome, FuzzOutcome::Crash | FuzzOutcome::Timeout) {
+                    return;
+                }
+                printer.print_colored(format!("    [{}] ", outcome), color);
+                match exception {
+                    Some(ex) => printer.println(format!("{} - {}", description, ex)),
+                    None => printer.println(description),
+                }
+            }
+            BinderFuzzEvent::MethodEnded { .. } => {}
+        }
+    }
+}
+
+/// Fuzz system service methods with inputs generated from their signatures
+///
+/// Each input is sent through the application server and the outcome of
+/// every call is stored in the database, replacing any previous results for
+/// the method. Exceptions are also summarized for `fuzz unprotected`. Fuzzing
+/// stops if a call appears to crash the device.
+#[derive(Args)]
+pub struct Binder {
+    /// Only fuzz the given system service, defaults to every service the
+    /// application can get a binder for
+    #[arg(short, long)]
+    service: Option<String>,
+
+    /// Only fuzz the given method, requires --service
+    #[arg(short, long, requires = "service")]
+    method: Option<String>,
+
+    /// Milliseconds to wait for each call before giving up
+    #[arg(short, long, default_value_t = 5000)]
+    timeout: u64,
+
+    /// Maximum number of inputs to send to each method
+    #[arg(short = 'c', long, default_value_t = 64)]
+    max_cases: usize,
+
+    /// Only print crashes and timeouts
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+impl Binder {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::SQLDatabaseSetup)?;
+        ensure_prereq(&ctx, Prereq::AppSetup)?;
+
+        let db = DeviceDatabase::new(&ctx)?;
+        let methods = self.get_methods(&db)?;
+        if methods.is_empty() {
+            bail!("no methods to fuzz");
+        }
+
+        // Make sure the server is reachable before sending anything, this
+        // also forwards the port if needed
+        get_app_server(&ctx)?;
+
+        let (_cancel, check) = task_canceller()?;
+        let monitor = PrintMonitor { quiet: self.quiet };
+
+        let mut task = BinderFuzzTask::new(&ctx, &db, check, &monitor);
+        task.set_timeout(Duration::from_millis(self.timeout))
+            .set_max_cases(self.max_cases);
+        Ok(task.run(&methods)?)
+    }
+
+    fn get_methods(
+        &self,
+        db: &DeviceDatabase,
+    ) -> anyhow::Result<Vec<(SystemService, Vec<SystemServiceMethod>)>> {
+        let services = match &self.service {
+            Some(name) => vec![db.get_system_service_by_name(name)?],
+            None => db
+                .get_system_services()?
+                .into_iter()
+                .filter(|it| !it.can_get_binder.is_false())
+                .collect(),
+        };
+
+        let mut methods = Vec::with_capacity(services.len());
+        for service in services {
+            let mut service_methods = db.get_system_service_methods_by_service_id(service.id)?;
+            if let Some(name) = &self.method {
+                service_methods.retain(|it| &it.name == name);
+            }
+            if !service_methods.is_empty() {
+                methods.push((service, service_methods));
+            }
+        }
+        Ok(methods)
+    }
+}
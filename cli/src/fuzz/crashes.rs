@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::io;
+
+use clap::{self, Args};
+use serde::Serialize;
+
+use dtu::db::meta::models::{Crash, CrashOccurrence};
+use dtu::db::{MetaDatabase, MetaSqliteDatabase};
+use dtu::tasks::crash_triage::CrashKind;
+use dtu::DefaultContext;
+
+use crate::printer::{color, Printer};
+
+/// List the crashes found by `fuzz triage`
+#[derive(Args)]
+pub struct Crashes {
+    /// Only show crashes of the given kind: java, system-server, anr, or
+    /// native
+    #[arg(short, long)]
+    kind: Option<CrashKind>,
+
+    /// Show the normalized stack and every occurrence
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Remove all stored crashes instead of listing them
+    #[arg(long)]
+    clear: bool,
+
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct CrashWithOccurrences {
+    #[serde(flatten)]
+    crash: Crash,
+    occurrences: Vec<CrashOccurrence>,
+}
+
+impl Crashes {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let meta = MetaSqliteDatabase::new(&ctx)?;
+
+        if self.clear {
+            meta.wipe_crashes()?;
+            return Ok(());
+        }
+
+        let mut occurrences: HashMap<i32, Vec<CrashOccurrence>> = HashMap::new();
+        for occ in meta.get_crash_occurrences()? {
+            occurrences.entry(occ.crash).or_default().push(occ);
+        }
+
+        let mut crashes = meta
+            .get_crashes()?
+            .into_iter()
+            .filter(|it| self.kind.map_or(true, |kind| it.kind == kind.as_str()))
+            .map(|crash| CrashWithOccurrences {
+                occurrences: occurrences.remove(&crash.id).unwrap_or_default(),
+                crash,
+            })
+            .collect::<Vec<_>>();
+        // Most common first
+        crashes.sort_by(|a, b| b.occurrences.len().cmp(&a.occurrences.len()));
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &crashes)?;
+            return Ok(());
+        }
+
+        let printer = Printer::new();
+        for it in crashes.iter() {
+            self.print_crash(&printer, it);
+        }
+        Ok(())
+    }
+
+    fn print_crash(&self, printer: &Printer, it: &CrashWithOccurrences) {
+        let crash = &it.crash;
+        printer.print_colored(format!("[{}] ", crash.kind), color::RED);
+        printer.print(format!("{} in {}", crash.signature, crash.process));
+        printer.println_colored(
+            format!(" ({}x, {})", it.occurrences.len(), &crash.stack_hash[..12]),
+            color::GREY,
+        );
+
+        // The most recent command is the most likely to reproduce it
+        let last_command = it.occurrences.iter().rev().find_map(|o| o.command.as_ref());
+        if !self.verbose {
+            if let Some(cmd) = last_command {
+                printer.println(format!("    after {}", cmd));
+            }
+            return;
+        }
+
+        for frame in crash.stack.lines() {
+            printer.println(format!("    {}", frame));
+        }
+        for occ in it.occurrences.iter() {
+            let when = occ.time.as_deref().unwrap_or("");
+            printer.println_colored(
+                format!("  {}:{} {}", occ.source, occ.line, when),
+                color::CYAN,
+            );
+            if let Some(cmd) = &occ.command {
+                printer.println(format!("    after {}", cmd));
+            }
+        }
+    }
+}
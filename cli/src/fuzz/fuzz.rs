@@ -1,7 +1,10 @@
 use clap::{self, Args, Subcommand};
 
+use super::binder::Binder;
+use super::crashes::Crashes;
 use super::import::Import;
 use super::logcat::Logcat;
+use super::triage::Triage;
 use super::unprotected::Unprotected;
 
 #[derive(Args)]
@@ -23,6 +26,18 @@ enum Commands {
     /// Setup logcat listeners
     #[command()]
     Logcat(Logcat),
+
+    /// Fuzz system services with inputs generated from method signatures
+    #[command()]
+    Binder(Binder),
+
+    /// Find crashes in logcat output
+    #[command()]
+    Triage(Triage),
+
+    /// List crashes found by triage
+    #[command()]
+    Crashes(Crashes),
 }
 
 impl Fuzz {
@@ -31,6 +46,9 @@ impl Fuzz {
             Commands::Import(c) => c.run(),
             Commands::Unprotected(c) => c.run(),
             Commands::Logcat(c) => c.run(),
+            Commands::Binder(c) => c.run(),
+            Commands::Triage(c) => c.run(),
+            Commands::Crashes(c) => c.run(),
         }
     }
 }
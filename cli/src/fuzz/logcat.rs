@@ -8,6 +8,7 @@ use crossbeam::channel::{bounded, Receiver, Sender};
 
 use crate::utils::get_adb;
 use dtu::adb::Adb;
+use dtu::tasks::crash_triage::APP_LOG_TAG;
 use dtu::utils::ensure_dir_exists;
 use dtu::{run_cmd, Context, DefaultContext};
 
@@ -111,8 +112,13 @@ impl Logcat {
         let system_server_pid = self.get_system_server_pid(&adb);
 
         // First logcat will be all errors and verbose ssfuzz. This one is
-        // mostly for development purposes, but it may help generally.
-        let args = format!("logcat {} -b all '*:E' 'ssfuzz:V'", common_args);
+        // mostly for development purposes, but it may help generally. The
+        // test app's info logs include the app server commands `fuzz triage`
+        // ties crashes to.
+        let args = format!(
+            "logcat {} -b all '*:E' 'ssfuzz:V' '{}:I'",
+            common_args, APP_LOG_TAG
+        );
         handles.push(start_logcat_proc(
             adb.clone(),
             args,
@@ -123,7 +129,10 @@ impl Logcat {
         let (tx, rx) = bounded(1);
         senders.push(tx);
         // Second is all errors and info ssfuzz
-        let args = format!("logcat {} -b all '*:E' 'ssfuzz:I'", common_args);
+        let args = format!(
+            "logcat {} -b all '*:E' 'ssfuzz:I' '{}:I'",
+            common_args, APP_LOG_TAG
+        );
         handles.push(start_logcat_proc(
             adb.clone(),
             args,
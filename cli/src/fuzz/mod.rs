@@ -1,8 +1,14 @@
 pub mod fuzz;
 pub use fuzz::Fuzz;
 
+mod binder;
+
+mod crashes;
+
 mod import;
 
 mod unprotected;
 
 mod logcat;
+
+mod triage;
@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::bail;
+use clap::{self, Args, ValueHint};
+
+use dtu::adb::Adb;
+use dtu::db::{MetaDatabase, MetaSqliteDatabase};
+use dtu::tasks::crash_triage::{
+    get_logcat_captures, store_crash, triage_logcat, CrashTriage, TriagedCrash, APP_LOG_TAG,
+};
+use dtu::{Context, DefaultContext};
+
+use crate::printer::{color, Printer};
+use crate::utils::{get_adb, task_canceller};
+
+/// Find crashes in logcat output and store them in the meta database
+///
+/// Java crashes, system_server deaths, ANRs and native tombstones are
+/// deduplicated by a hash of their normalized stack and tied to the last app
+/// server command logged before them. Triaging the same file again doesn't
+/// add duplicates. Use `fuzz crashes` to see the results.
+#[derive(Args)]
+pub struct Triage {
+    /// Logcat files to triage, defaults to everything saved by `fuzz logcat`
+    #[arg(value_hint = ValueHint::FilePath)]
+    files: Vec<PathBuf>,
+
+    /// Triage a live logcat stream from the device until interrupted
+    #[arg(short, long, conflicts_with = "files")]
+    live: bool,
+}
+
+impl Triage {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let meta = MetaSqliteDatabase::new(&ctx)?;
+        let printer = Printer::new();
+
+        if self.live {
+            return self.run_live(&ctx, &meta, &printer);
+        }
+
+        let files = if self.files.is_empty() {
+            get_logcat_captures(&ctx.get_output_dir_child("fuzz_logcats")?)
+        } else {
+            self.files.clone()
+        };
+        if files.is_empty() {
+            bail!("no logcat files to triage, use `fuzz logcat` or pass files");
+        }
+
+        for path in files.iter() {
+            let crashes = triage_logcat(BufReader::new(File::open(path)?))?;
+            let source = path.to_string_lossy();
+            let mut new = 0;
+            for crash in crashes.iter() {
+                if store_crash(&meta, &source, crash)? {
+                    new += 1;
+                }
+            }
+            printer.println(format!(
+                "{}: {} crashes, {} new",
+                source,
+                crashes.len(),
+                new
+            ));
+        }
+        Ok(())
+    }
+
+    fn run_live(
+        &self,
+        ctx: &dyn Context,
+        meta: &dyn MetaDatabase,
+        printer: &Printer,
+    ) -> anyhow::Result<()> {
+        let adb = get_adb(ctx, true)?;
+        // Keep running to store crashes still being read when interrupted,
+        // adb gets the signal too and ends the stream
+        let (_cancel, _check) = task_canceller()?;
+
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let source = format!("live-{}", secs);
+        let cmd = format!(
+            "logcat -T 1 -b all -v threadtime '*:E' 'Watchdog:W' '{}:I'",
+            APP_LOG_TAG
+        );
+
+        let mut triage = CrashTriage::new();
+        let store = |crash: &TriagedCrash| -> anyhow::Result<()> {
+            store_crash(meta, &source, crash)?;
+            print_triaged(printer, crash);
+            Ok(())
+        };
+
+        adb.shell_split_streamed(
+            &cmd,
+            b'\n',
+            &mut |line| {
+                for crash in triage.push_line(line) {
+                    store(&crash)?;
+                }
+                Ok(())
+            },
+            &mut |_| Ok(()),
+        )?;
+
+        for crash in triage.finish() {
+            store(&crash)?;
+        }
+        Ok(())
+    }
+}
+
+fn print_triaged(printer: &Printer, crash: &TriagedCrash) {
+    printer.print_colored(format!("[{}] ", crash.kind), color::RED);
+    printer.println(format!("{} in {}", crash.signature, crash.process));
+    if let Some(cmd) = &crash.command {
+        printer.println_colored(format!("    after {}", cmd), color::GREY);
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+use clap::{self, Args};
+use dtu::db::graph::export::{
+    export_graph, export_graph_to_path, DotWriter, ExportFormat, GraphExportFilter, GraphMLWriter,
+    DEFAULT_EXPORT_DEPTH,
+};
+use dtu::db::graph::{get_default_graphdb, MethodSearch};
+use dtu::prereqs::Prereq;
+use dtu::utils::{ensure_prereq, ClassName};
+use dtu::DefaultContext;
+
+use crate::parsers::GraphSourceValueParser;
+use crate::utils::ostr;
+
+#[derive(Args)]
+pub struct Export {
+    /// The output format: graphml, dot, or neo4j
+    #[arg(short, long, default_value_t = ExportFormat::GraphML)]
+    format: ExportFormat,
+
+    /// The output file, or directory for neo4j CSVs. graphml and dot are
+    /// written to stdout if this isn't given
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Only export items from the given sources, can be passed multiple times
+    #[arg(short = 'S', long = "source", value_parser = GraphSourceValueParser)]
+    sources: Vec<String>,
+
+    /// Only export the subgraph reachable from methods in this class
+    #[arg(short, long)]
+    class: Option<ClassName>,
+
+    /// Only export the subgraph reachable from methods with this name
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// Only export the subgraph reachable from methods with this signature
+    #[arg(short = 's', long)]
+    signature: Option<String>,
+
+    /// The source of the methods the subgraph is rooted at
+    #[arg(short = 'R', long, value_parser = GraphSourceValueParser)]
+    root_source: Option<String>,
+
+    /// The call depth of the subgraph
+    #[arg(short, long, default_value_t = DEFAULT_EXPORT_DEPTH)]
+    depth: usize,
+}
+
+impl Export {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::GraphDatabaseSetup)?;
+        let db = get_default_graphdb(&ctx)?;
+
+        let root = if self.class.is_some() || self.name.is_some() || self.signature.is_some() {
+            Some(
+                MethodSearch::new_from_opts(
+                    self.class.as_ref(),
+                    ostr(&self.name),
+                    ostr(&self.signature),
+                    ostr(&self.root_source),
+                )
+                .map_err(anyhow::Error::msg)?,
+            )
+        } else if self.root_source.is_some() {
+            anyhow::bail!("--root-source requires a class, name, or signature");
+        } else {
+            None
+        };
+
+        let filter = GraphExportFilter {
+            sources: self.sources.iter().map(|it| it.as_str()).collect(),
+            root,
+            depth: self.depth,
+        };
+
+        match (&self.output, self.format) {
+            (Some(path), fmt) => export_graph_to_path(&db, &filter, fmt, path)?,
+            (None, ExportFormat::GraphML) => {
+                let mut sink = GraphMLWriter::new(BufWriter::new(io::stdout()));
+                export_graph(&db, &filter, &mut sink)?;
+            }
+            (None, ExportFormat::Dot) => {
+                let mut sink = DotWriter::new(BufWriter::new(io::stdout()));
+                export_graph(&db, &filter, &mut sink)?;
+            }
+            (None, ExportFormat::Neo4jCsv) => {
+                anyhow::bail!("an output directory is required for neo4j exports")
+            }
+        }
+
+        Ok(())
+    }
+}
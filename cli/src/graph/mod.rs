@@ -1,12 +1,17 @@
 use clap::{self, Args, Subcommand};
 use dtu::db::meta::get_default_metadb;
-use dtu::db::MetaDatabase;
+use dtu::db::{DeviceDatabase, MetaDatabase};
 use dtu::prereqs::Prereq;
+use dtu::utils::ensure_prereq;
 use dtu::DefaultContext;
 
 use crate::parsers::GraphSourceValueParser;
+use crate::utils::task_canceller;
+use dtu::db::graph::binder::link_binder_calls;
+use dtu::db::graph::native::link_native_methods;
 use dtu::db::graph::{get_default_graphdb, GraphDatabase};
 
+mod export;
 mod monitor;
 mod setup;
 
@@ -22,6 +27,10 @@ enum Command {
     #[command(alias = "full-setup")]
     Setup(setup::Setup),
 
+    /// Import the sources whose smalisa output changed since they were imported
+    #[command()]
+    Update(setup::Update),
+
     /// Wipe the graph database
     #[command()]
     Wipe,
@@ -29,17 +38,84 @@ enum Command {
     /// Remove a graph database source
     #[command()]
     RemoveSource(RemoveSource),
+
+    /// Recompute the method overrides used for class hierarchy analysis
+    #[command()]
+    UpdateOverrides,
+
+    /// Recompute the calls made through reflection with constant class and method names
+    #[command()]
+    UpdateReflection,
+
+    /// Link native methods to the pulled native libraries implementing them
+    #[command()]
+    LinkNative,
+
+    /// Link Stub.Proxy methods to the system service methods they call over binder
+    #[command()]
+    LinkBinder,
+
+    /// Export the graph database to GraphML, DOT, or Neo4j CSV files
+    #[command()]
+    Export(export::Export),
 }
 
 impl Graph {
     pub fn run(self) -> anyhow::Result<()> {
         match self.command {
             Command::Setup(c) => c.run(),
+            Command::Update(c) => c.run(),
             Command::RemoveSource(c) => c.run(),
             Command::Wipe => self.wipe(),
+            Command::UpdateOverrides => self.update_overrides(),
+            Command::UpdateReflection => self.update_reflection(),
+            Command::LinkNative => self.link_native(),
+            Command::LinkBinder => self.link_binder(),
+            Command::Export(c) => c.run(),
         }
     }
 
+    fn update_overrides(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let db = get_default_graphdb(&ctx)?;
+        db.update_method_overrides()?;
+        Ok(())
+    }
+
+    fn update_reflection(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let db = get_default_graphdb(&ctx)?;
+        db.update_reflective_calls()?;
+        Ok(())
+    }
+
+    fn link_native(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::GraphDatabaseSetup)?;
+        let db = get_default_graphdb(&ctx)?;
+        let (_cancel, check) = task_canceller()?;
+        let summary = link_native_methods(&db, &ctx, &check)?;
+        println!(
+            "Linked {}/{} native methods using {} libraries",
+            summary.linked, summary.native_methods, summary.libraries
+        );
+        Ok(())
+    }
+
+    fn link_binder(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::SQLDatabaseSetup)?;
+        ensure_prereq(&ctx, Prereq::GraphDatabaseSetup)?;
+        let device = DeviceDatabase::new(&ctx)?;
+        let db = get_default_graphdb(&ctx)?;
+        let summary = link_binder_calls(&db, &device, &ctx)?;
+        println!(
+            "Linked {} proxy methods across {} system services",
+            summary.linked, summary.services
+        );
+        Ok(())
+    }
+
     fn wipe(&self) -> anyhow::Result<()> {
         let ctx = DefaultContext::new();
         let meta = get_default_metadb(&ctx)?;
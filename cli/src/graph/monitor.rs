@@ -186,6 +186,17 @@ impl ImportPrintMonitor {
             SetupEvent::ImportDone { .. } => {
                 self.printer.println("done");
             }
+            SetupEvent::SourceUnchanged { .. } => {
+                self.sources_done += 1;
+                self.show_status();
+            }
+            SetupEvent::FixingEdges { source } => {
+                self.printer.println(format!(
+                    "Restoring edges from other sources into {}",
+                    source
+                ));
+                self.show_status();
+            }
             SetupEvent::Finalizing => {
                 self.printer.update_status_line_styled(
                     "Finalizing database",
@@ -2,6 +2,7 @@ use std::fs;
 use std::path::PathBuf;
 
 use crate::graph::monitor::{start_import_print_thread, start_smalisa_print_thread};
+use crate::printer::Printer;
 use crate::utils::task_canceller;
 
 use clap::{self, Args};
@@ -11,7 +12,7 @@ use dtu::db::graph::{get_default_graphdb, GraphDatabaseSetup, InitialImportOptio
 use dtu::db::{MetaDatabase, MetaSqliteDatabase};
 use dtu::prereqs::Prereq;
 use dtu::tasks::{smalisa, ChannelEventMonitor, EventMonitor, TaskCancelCheck};
-use dtu::utils::{opt_deny, path_must_name, Denylist, DevicePath, OptDenylist};
+use dtu::utils::{ensure_prereq, opt_deny, path_must_name, Denylist, DevicePath, OptDenylist};
 use dtu::{Context, DefaultContext};
 
 #[derive(Args)]
@@ -50,14 +51,14 @@ impl Setup {
             anyhow::bail!("graph database already setup");
         }
 
-        let deny = self.get_denylist()?;
+        let deny = get_denylist(self.apk_denylist_file.as_ref())?;
 
-        let apk_paths = self.get_apk_paths(&ctx, &deny)?;
+        let apk_paths = get_apk_paths(&ctx, &deny)?;
         // +1 for framework
         let num_sources = apk_paths.len() + 1;
 
         if !meta.prereq_done(Prereq::Smalisa)? {
-            self.run_smalisa(&ctx, num_sources, &apk_paths)?;
+            run_smalisa(&ctx, num_sources, &apk_paths)?;
             meta.update_prereq(Prereq::Smalisa, true)?;
         }
 
@@ -70,106 +71,173 @@ impl Setup {
         Ok(())
     }
 
-    fn run_smalisa(
+    fn run_import(
         &self,
         ctx: &dyn Context,
         num_sources: usize,
-        apk_paths: &Vec<PathBuf>,
+        deny: OptDenylist<DevicePath>,
     ) -> anyhow::Result<()> {
-        let source_dir = ctx.get_smali_dir()?.join("framework");
+        let db = get_default_graphdb(&ctx)?;
+
         let (mon, chan) = ChannelEventMonitor::create();
-        let (source_tx, source_rx) = unbounded();
         let (cancel, check) = task_canceller()?;
-        let _handle =
-            start_smalisa_print_thread(String::from("framework"), source_rx, chan, num_sources);
-        let opts = smalisa::AddDirectoryOptions::new(String::from(FRAMEWORK_SOURCE), source_dir);
-
-        smalisa::AddDirTask::new(ctx, &mon, opts, &check).run()?;
-
-        for path in apk_paths {
-            if check.was_cancelled() {
-                break;
-            }
-            self.smalisa_apk_dir(&ctx, &mon, &path, &check, &source_tx)?;
-        }
 
+        let _handle = start_import_print_thread(String::from("framework"), chan, num_sources);
+        let opts = InitialImportOptions::new(deny);
+        db.run_initial_import(ctx, opts, &mon, &check)?;
         drop(mon);
         drop(cancel);
-
         Ok(())
     }
+}
 
-    fn run_import(
-        &self,
-        ctx: &dyn Context,
-        num_sources: usize,
-        deny: OptDenylist<DevicePath>,
-    ) -> anyhow::Result<()> {
+/// Bring the graph database up to date after the smali has changed
+///
+/// Smalisa is run for any source that is missing its CSVs, and then every source whose CSVs
+/// differ from when it was last imported is imported again. New sources are added.
+#[derive(Args)]
+pub struct Update {
+    /// Denylist of APKs, see `dtu graph setup --help`
+    #[arg(short = 'l', long)]
+    apk_denylist_file: Option<PathBuf>,
+
+    /// Remove all existing smalisa output and rerun smalisa for every source
+    ///
+    /// This is required if the smali was regenerated, since smalisa is skipped for sources that
+    /// already have output.
+    #[arg(short, long, action = clap::ArgAction::SetTrue, default_value_t = false)]
+    rerun_smalisa: bool,
+
+    /// Only record the hashes of the current smalisa output for all sources in the database
+    ///
+    /// Use this once on databases that were set up before updates were supported, otherwise the
+    /// first update will import every source again.
+    #[arg(long, action = clap::ArgAction::SetTrue, default_value_t = false)]
+    baseline: bool,
+}
+
+impl Update {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::GraphDatabaseSetup)?;
         let db = get_default_graphdb(&ctx)?;
 
+        if self.baseline {
+            db.record_import_hashes(&ctx)?;
+            return Ok(());
+        }
+
+        let deny = get_denylist(self.apk_denylist_file.as_ref())?;
+        let apk_paths = get_apk_paths(&ctx, &deny)?;
+        let num_sources = apk_paths.len() + 1;
+
+        if self.rerun_smalisa {
+            let import_dir = ctx.get_graph_import_dir()?;
+            if import_dir.exists() {
+                fs::remove_dir_all(&import_dir)?;
+            }
+        }
+
+        run_smalisa(&ctx, num_sources, &apk_paths)?;
+
         let (mon, chan) = ChannelEventMonitor::create();
         let (cancel, check) = task_canceller()?;
-
-        let _handle = start_import_print_thread(String::from("framework"), chan, num_sources);
+        let handle = start_import_print_thread(String::from("framework"), chan, num_sources);
         let opts = InitialImportOptions::new(deny);
-        db.run_initial_import(ctx, opts, &mon, &check)?;
+        let updated = db.run_incremental_update(&ctx, opts, &mon, &check)?;
         drop(mon);
         drop(cancel);
+        drop(handle);
+
+        let printer = Printer::new();
+        if updated.is_empty() {
+            printer.println("Graph database is up to date");
+        } else {
+            for src in updated {
+                printer.println(format!("Updated {}", src));
+            }
+        }
         Ok(())
     }
+}
 
-    fn get_denylist(&self) -> anyhow::Result<OptDenylist<DevicePath>> {
-        // The denylist file will just be a list of device paths to the APK
-        Ok(match self.apk_denylist_file.as_ref() {
-            None => None,
-            Some(pb) => Some(Denylist::from_path(pb, |s| DevicePath::new(s))?),
-        })
+pub(super) fn run_smalisa(
+    ctx: &dyn Context,
+    num_sources: usize,
+    apk_paths: &Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let source_dir = ctx.get_smali_dir()?.join("framework");
+    let (mon, chan) = ChannelEventMonitor::create();
+    let (source_tx, source_rx) = unbounded();
+    let (cancel, check) = task_canceller()?;
+    let _handle =
+        start_smalisa_print_thread(String::from("framework"), source_rx, chan, num_sources);
+    let opts = smalisa::AddDirectoryOptions::new(String::from(FRAMEWORK_SOURCE), source_dir);
+
+    smalisa::AddDirTask::new(ctx, &mon, opts, &check).run()?;
+
+    for path in apk_paths {
+        if check.was_cancelled() {
+            break;
+        }
+        smalisa_apk_dir(&ctx, &mon, &path, &check, &source_tx)?;
     }
 
-    fn get_apk_paths(
-        &self,
-        ctx: &dyn Context,
-        deny: &OptDenylist<DevicePath>,
-    ) -> anyhow::Result<Vec<PathBuf>> {
-        let smali_apks = ctx.get_smali_dir()?.join("apks");
-
-        let rd = fs::read_dir(&smali_apks)?;
-
-        let dirs = rd
-            .filter_map(|it| {
-                let ent = it.ok()?;
-                let path = ent.path();
-                // Only directories matter
-                if !path.is_dir() {
-                    return None;
-                }
-                // Check the squashed path against the denylist
-                let squashed = DevicePath::from_squashed(path_must_name(&path));
-                if opt_deny(deny, &squashed) {
-                    return None;
-                }
-                Some(path)
-            })
-            .collect::<Vec<PathBuf>>();
-
-        Ok(dirs)
-    }
+    drop(mon);
+    drop(cancel);
 
-    fn smalisa_apk_dir(
-        &self,
-        ctx: &dyn Context,
-        mon: &dyn EventMonitor<smalisa::Event>,
-        pb: &PathBuf,
-        check: &TaskCancelCheck,
-        source_tx: &Sender<String>,
-    ) -> anyhow::Result<()> {
-        let path = DevicePath::from_path(pb)?;
-        source_tx
-            .send(String::from(path.device_file_name()))
-            .unwrap();
-        log::info!("Running Smalisa on APK {}", path);
-        let opts = smalisa::AddDirectoryOptions::new(path.get_squashed_string(), pb.clone());
-        smalisa::AddDirTask::new(ctx, mon, opts, check).run()?;
-        Ok(())
-    }
+    Ok(())
+}
+
+pub(super) fn get_denylist(file: Option<&PathBuf>) -> anyhow::Result<OptDenylist<DevicePath>> {
+    // The denylist file will just be a list of device paths to the APK
+    Ok(match file {
+        None => None,
+        Some(pb) => Some(Denylist::from_path(pb, |s| DevicePath::new(s))?),
+    })
+}
+
+pub(super) fn get_apk_paths(
+    ctx: &dyn Context,
+    deny: &OptDenylist<DevicePath>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let smali_apks = ctx.get_smali_dir()?.join("apks");
+
+    let rd = fs::read_dir(&smali_apks)?;
+
+    let dirs = rd
+        .filter_map(|it| {
+            let ent = it.ok()?;
+            let path = ent.path();
+            // Only directories matter
+            if !path.is_dir() {
+                return None;
+            }
+            // Check the squashed path against the denylist
+            let squashed = DevicePath::from_squashed(path_must_name(&path));
+            if opt_deny(deny, &squashed) {
+                return None;
+            }
+            Some(path)
+        })
+        .collect::<Vec<PathBuf>>();
+
+    Ok(dirs)
+}
+
+fn smalisa_apk_dir(
+    ctx: &dyn Context,
+    mon: &dyn EventMonitor<smalisa::Event>,
+    pb: &PathBuf,
+    check: &TaskCancelCheck,
+    source_tx: &Sender<String>,
+) -> anyhow::Result<()> {
+    let path = DevicePath::from_path(pb)?;
+    source_tx
+        .send(String::from(path.device_file_name()))
+        .unwrap();
+    log::info!("Running Smalisa on APK {}", path);
+    let opts = smalisa::AddDirectoryOptions::new(path.get_squashed_string(), pb.clone());
+    smalisa::AddDirTask::new(ctx, mon, opts, check).run()?;
+    Ok(())
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::io;
 
 use clap::Args;
@@ -25,6 +26,18 @@ pub struct Apks {
     #[arg(short, long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     only_priv: bool,
 
+    /// Only show APKs signed with the platform key
+    #[arg(short = 'P', long)]
+    platform_signed: bool,
+
+    /// Only show APKs signed with the certificate with the given SHA-256 digest
+    #[arg(short, long)]
+    cert: Option<String>,
+
+    /// Only show APKs with the given sharedUserId (ie android.uid.system)
+    #[arg(short = 'u', long)]
+    shared_uid: Option<String>,
+
     /// Include the path to the APK on the device
     #[arg(short, long, default_value_t = false, action = clap::ArgAction::SetTrue)]
     show_path: bool,
@@ -40,11 +53,36 @@ impl Apks {
         let meta = MetaSqliteDatabase::new(&ctx)?;
         meta.ensure_prereq(Prereq::SQLDatabaseSetup)?;
         let db = DeviceDatabase::new(&ctx)?;
+        let allowed = self.get_allowed(&db)?;
         if self.only_new {
-            self.show_new(&ctx, &meta, &db)
+            self.show_new(&ctx, &meta, &db, allowed.as_ref())
         } else {
-            self.show_all(&ctx, &db)
+            self.show_all(&ctx, &db, allowed.as_ref())
+        }
+    }
+
+    /// Get the ids of the APKs matching the signing filters, if any were given
+    fn get_allowed(&self, db: &DeviceDatabase) -> anyhow::Result<Option<HashSet<i32>>> {
+        let mut allowed: Option<HashSet<i32>> = None;
+        let mut retain = |apks: Vec<Apk>| {
+            let ids = apks.into_iter().map(|it| it.id).collect::<HashSet<i32>>();
+            allowed = Some(match allowed.take() {
+                Some(prev) => prev.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        };
+
+        if self.platform_signed {
+            retain(db.get_platform_signed_apks()?);
         }
+        if let Some(cert) = &self.cert {
+            let digest = cert.replace(':', "").to_ascii_lowercase();
+            retain(db.get_apks_signed_with(&[digest.as_str()])?);
+        }
+        if let Some(uid) = &self.shared_uid {
+            retain(db.get_apks_by_shared_user_id(uid)?);
+        }
+        Ok(allowed)
     }
 
     fn show_new(
@@ -52,6 +90,7 @@ impl Apks {
         ctx: &dyn Context,
         meta: &dyn MetaDatabase,
         db: &DeviceDatabase,
+        allowed: Option<&HashSet<i32>>,
     ) -> anyhow::Result<()> {
         let diff_source = get_diff_source(ctx, meta, db, &self.diff_source)?;
         let apks = db.get_apk_diffs_by_diff_id(diff_source.id)?;
@@ -59,18 +98,29 @@ impl Apks {
             .iter()
             .filter(|it| !it.exists_in_diff)
             .map(|it| &it.apk);
-        self.show_apks(&mut filt)?;
+        self.show_apks(&mut filt, allowed)?;
         Ok(())
     }
 
-    fn show_all(&self, _ctx: &dyn Context, db: &DeviceDatabase) -> anyhow::Result<()> {
+    fn show_all(
+        &self,
+        _ctx: &dyn Context,
+        db: &DeviceDatabase,
+        allowed: Option<&HashSet<i32>>,
+    ) -> anyhow::Result<()> {
         let apks = db.get_apks()?;
         let mut it = apks.iter();
-        self.show_apks(&mut it)?;
+        self.show_apks(&mut it, allowed)?;
         Ok(())
     }
 
-    fn show_apks<'a>(&self, apks: &mut dyn Iterator<Item = &'a Apk>) -> anyhow::Result<()> {
+    fn show_apks<'a>(
+        &self,
+        apks: &mut dyn Iterator<Item = &'a Apk>,
+        allowed: Option<&HashSet<i32>>,
+    ) -> anyhow::Result<()> {
+        let mut apks = apks.filter(|apk| allowed.map_or(true, |it| it.contains(&apk.id)));
+
         if self.json {
             serde_json::to_writer(io::stdout(), &apks.collect::<Vec<&Apk>>())?;
             return Ok(());
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::io;
+
+use clap::Args;
+use dtu::db::device::models::{fmt_mode, DiffSource, DiffedFsSurveyEntry, FsSurveyEntry};
+use dtu::db::{DeviceDatabase, MetaSqliteDatabase};
+use dtu::DefaultContext;
+
+use crate::diff::get_diff_source;
+use crate::parsers::DiffSourceValueParser;
+
+#[derive(Args)]
+pub struct FsSurvey {
+    /// Only show entries that don't exist in the given diff source (or emulator by default)
+    #[arg(short = 'n', long)]
+    only_new: bool,
+
+    /// Only show entries that exist in the given diff source (or emulator
+    /// by default) but differ from it
+    #[arg(short = 'c', long)]
+    only_changed: bool,
+
+    /// Set the diff source (only valid with -n/--only-new or -c/--only-changed)
+    /// otherwise the emulator is the default
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// Only show world writable entries
+    #[arg(short = 'w', long)]
+    world_writable: bool,
+
+    /// Only show world readable entries
+    #[arg(short = 'r', long)]
+    world_readable: bool,
+
+    /// Only show setuid or setgid entries
+    #[arg(short = 's', long)]
+    setid: bool,
+
+    /// Only show entries under the given path
+    #[arg(short, long)]
+    prefix: Option<String>,
+
+    /// JSON output
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl FsSurvey {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let db = DeviceDatabase::new(&ctx)?;
+
+        let diffs = if self.only_new || self.only_changed {
+            let meta = MetaSqliteDatabase::new(&ctx)?;
+            let diff_source = get_diff_source(&ctx, &meta, &db, &self.diff_source)?;
+            let diffs = db.get_fs_survey_entry_diffs_by_diff_id(diff_source.id)?;
+            Some(HashMap::from_iter(
+                diffs.into_iter().map(|it| (it.entry.id, it)),
+            ))
+        } else {
+            None
+        };
+
+        let entries = db
+            .get_fs_survey_entries()?
+            .into_iter()
+            .filter(|it| self.include_entry(it, diffs.as_ref()))
+            .collect::<Vec<FsSurveyEntry>>();
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &entries)?;
+            return Ok(());
+        }
+
+        for e in entries.iter() {
+            println!("{}", e);
+            if let Some(diff) = diffs.as_ref().and_then(|it| it.get(&e.id)) {
+                if diff.exists_in_diff {
+                    show_changes(diff);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn include_entry(
+        &self,
+        e: &FsSurveyEntry,
+        diffs: Option<&HashMap<i32, DiffedFsSurveyEntry>>,
+    ) -> bool {
+        if self.world_writable && !e.is_world_writable() {
+            return false;
+        }
+        if self.world_readable && !e.is_world_readable() {
+            return false;
+        }
+        if self.setid && !(e.is_setuid() || e.is_setgid()) {
+            return false;
+        }
+        if let Some(prefix) = &self.prefix {
+            if !e.path.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+
+        let diff = match diffs {
+            None => return true,
+            Some(diffs) => match diffs.get(&e.id) {
+                Some(v) => v,
+                None => return false,
+            },
+        };
+
+        if self.only_new && diff.exists_in_diff {
+            return false;
+        }
+
+        !self.only_changed || (diff.exists_in_diff && has_changes(diff))
+    }
+}
+
+fn has_changes(diff: &DiffedFsSurveyEntry) -> bool {
+    !(diff.mode_matches_diff
+        && diff.owner_matches_diff
+        && diff.group_matches_diff
+        && diff.label_matches_diff)
+}
+
+fn show_changes(diff: &DiffedFsSurveyEntry) {
+    let show = |name: &str, matches: bool, value: Option<&str>| {
+        if !matches {
+            println!("    {} differs, diff: {}", name, value.unwrap_or("<none>"));
+        }
+    };
+    let diff_mode = diff.diff_mode.map(fmt_mode);
+    show("mode", diff.mode_matches_diff, diff_mode.as_deref());
+    show("owner", diff.owner_matches_diff, diff.diff_owner.as_deref());
+    show("group", diff.group_matches_diff, diff.diff_group.as_deref());
+    show("label", diff.label_matches_diff, diff.diff_label.as_deref());
+}
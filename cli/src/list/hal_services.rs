@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::io;
+
+use clap::Args;
+use dtu::db::device::models::{DiffSource, DiffedHalService, HalService};
+use dtu::db::{DeviceDatabase, MetaSqliteDatabase};
+use dtu::{DefaultContext, UnknownBool};
+
+use crate::diff::get_diff_source;
+use crate::parsers::DiffSourceValueParser;
+
+#[derive(Args)]
+pub struct HalServices {
+    /// Only show HALs that don't exist in the given diff source (or emulator by default)
+    #[arg(short = 'n', long)]
+    only_new: bool,
+
+    /// Set the diff source (only valid with -n/--only-new) otherwise the
+    /// emulator is the default
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// Only show HALs that an untrusted app is allowed to find
+    #[arg(short = 'R', long)]
+    only_reachable: bool,
+
+    /// Only show HIDL HALs
+    #[arg(short = 'H', long)]
+    only_hidl: bool,
+
+    /// Only show AIDL HALs
+    #[arg(short = 'A', long)]
+    only_aidl: bool,
+
+    /// Only show vendor HALs
+    #[arg(short = 'V', long)]
+    only_vendor: bool,
+
+    /// JSON output
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl HalServices {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let db = DeviceDatabase::new(&ctx)?;
+
+        let diffs = if self.only_new {
+            let meta = MetaSqliteDatabase::new(&ctx)?;
+            let diff_source = get_diff_source(&ctx, &meta, &db, &self.diff_source)?;
+            let diffs = db.get_hal_service_diffs_by_diff_id(diff_source.id)?;
+            Some(HashMap::from_iter(
+                diffs.into_iter().map(|it| (it.service.id, it)),
+            ))
+        } else {
+            None
+        };
+
+        let hals = db
+            .get_hal_services()?
+            .into_iter()
+            .filter(|it| self.include_hal(it, diffs.as_ref()))
+            .collect::<Vec<HalService>>();
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &hals)?;
+            return Ok(());
+        }
+
+        for h in hals.iter() {
+            println!("{}", h);
+            if let Some(ty) = &h.selinux_type {
+                println!("    type: {} reachable: {}", ty, h.reachable);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn include_hal(&self, h: &HalService, diffs: Option<&HashMap<i32, DiffedHalService>>) -> bool {
+        if self.only_reachable && h.reachable != UnknownBool::True {
+            return false;
+        }
+        if self.only_vendor && !h.is_vendor() {
+            return false;
+        }
+        if (self.only_hidl && !h.is_hidl()) || (self.only_aidl && !h.is_aidl()) {
+            return false;
+        }
+
+        match diffs {
+            None => true,
+            Some(diffs) => diffs.get(&h.id).map_or(false, |it| !it.exists_in_diff),
+        }
+    }
+}
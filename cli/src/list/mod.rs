@@ -1,19 +1,23 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::io;
 
 use clap::{self, Args, Subcommand};
 
-use dtu::db::device::models::{self, Activity, Apk, DiffSource, Provider, Receiver, Service};
+use dtu::db::device::models::{
+    self, Activity, Apk, DiffSource, IntentFilterSearch, Provider, ProviderWithPaths, Receiver,
+    Service, UriPathPattern,
+};
 use dtu::db::meta::get_default_metadb;
-use dtu::db::{ApkIPC, DeviceDatabase, Diffable, PermissionMode, PermissionProtected};
+use dtu::db::{ApkIPC, DeviceDatabase, Diffable, Idable, PermissionMode, PermissionProtected};
 use dtu::prereqs::Prereq;
 use dtu::utils::{ensure_prereq, ClassName};
 use dtu::DefaultContext;
 
 use crate::diff::get_diff_source;
 use crate::parsers::DiffSourceValueParser;
+use crate::utils::ostr;
 
 mod apks;
 use apks::Apks;
@@ -24,6 +28,15 @@ use system_services::SystemServices;
 mod system_service_methods;
 use system_service_methods::SystemServiceMethods;
 
+mod native_services;
+use native_services::NativeServices;
+
+mod hal_services;
+use hal_services::HalServices;
+
+mod fs_survey;
+use fs_survey::FsSurvey;
+
 mod classes;
 use classes::{Children, InterfaceImpl, Parents};
 
@@ -55,6 +68,92 @@ struct CommonParams {
     json: bool,
 }
 
+/// Filters on the intent filters of activities, receivers, and services
+#[derive(Args, Clone)]
+struct IntentFilterParams {
+    /// Only show entries with an intent filter handling the given action
+    #[arg(short = 'a', long)]
+    action: Option<String>,
+
+    /// Only show entries with an intent filter containing the given category
+    #[arg(short = 'c', long)]
+    category: Option<String>,
+
+    /// Only show entries with an intent filter handling the given data scheme
+    #[arg(short = 's', long)]
+    scheme: Option<String>,
+
+    /// Only show entries with an intent filter handling the given data host
+    #[arg(short = 'H', long)]
+    host: Option<String>,
+
+    /// Only show entries with an intent filter handling the given MIME type
+    #[arg(short = 'm', long)]
+    mime_type: Option<String>,
+}
+
+impl IntentFilterParams {
+    fn search(&self) -> IntentFilterSearch<'_> {
+        IntentFilterSearch {
+            action: ostr(&self.action),
+            category: ostr(&self.category),
+            scheme: ostr(&self.scheme),
+            host: ostr(&self.host),
+            mime_type: ostr(&self.mime_type),
+        }
+    }
+
+    /// Remove any items that don't have an intent filter matching the search
+    /// parameters, `handling` gets the matching items from the database
+    fn retain<T, F>(&self, items: Vec<T>, handling: F) -> anyhow::Result<Vec<T>>
+    where
+        T: Idable,
+        F: FnOnce(&IntentFilterSearch) -> dtu::db::Result<Vec<T>>,
+    {
+        let search = self.search();
+        if search.is_empty() {
+            return Ok(items);
+        }
+        let ids = handling(&search)?
+            .iter()
+            .map(|it| it.get_id())
+            .collect::<HashSet<i32>>();
+        Ok(items
+            .into_iter()
+            .filter(|it| ids.contains(&it.get_id()))
+            .collect())
+    }
+}
+
+#[derive(Args)]
+struct ProviderPathParams {
+    /// Show the path-permission and grant-uri-permission entries of each provider
+    #[arg(short = 'p', long)]
+    show_paths: bool,
+
+    /// Only show providers that can grant URI permissions
+    #[arg(short = 'G', long)]
+    only_grant_uri: bool,
+}
+
+#[derive(Args)]
+struct ProviderParams {
+    #[command(flatten)]
+    common: CommonParams,
+
+    #[command(flatten)]
+    paths: ProviderPathParams,
+}
+
+#[derive(Args)]
+struct IntentParams {
+    #[command(flatten)]
+    common: CommonParams,
+
+    #[command(flatten)]
+    filter: IntentFilterParams,
+}
+
 impl CommonParams {
     fn get_diff_id(&self, ctx: &DefaultContext, db: &DeviceDatabase) -> anyhow::Result<i32> {
         let meta = get_default_metadb(ctx)?;
@@ -184,10 +283,10 @@ impl CommonParams {
         Ok(())
     }
 
-    fn list_receivers(self) -> anyhow::Result<()> {
+    fn list_receivers(self, filter: &IntentFilterParams) -> anyhow::Result<()> {
         self.do_list(
             |p, ctx, db| {
-                Ok(if p.only_new {
+                let receivers = if p.only_new {
                     db.get_receiver_diffs_by_diff_id(p.get_diff_id(ctx, db)?)?
                         .into_iter()
                         .filter(|it| p.filter_allow(it))
@@ -195,16 +294,17 @@ impl CommonParams {
                         .collect::<Vec<Receiver>>()
                 } else {
                     db.get_receivers()?
-                })
+                };
+                filter.retain(receivers, |s| db.get_receivers_handling(s))
             },
             None::<&dyn for<'a> Fn(&'a Receiver) -> String>,
         )
     }
 
-    fn list_activities(self) -> anyhow::Result<()> {
+    fn list_activities(self, filter: &IntentFilterParams) -> anyhow::Result<()> {
         self.do_list(
             |p, ctx, db| {
-                Ok(if p.only_new {
+                let activities = if p.only_new {
                     db.get_activity_diffs_by_diff_id(p.get_diff_id(ctx, db)?)?
                         .into_iter()
                         .filter(|it| p.filter_allow(it))
@@ -212,32 +312,95 @@ impl CommonParams {
                         .collect::<Vec<Activity>>()
                 } else {
                     db.get_activities()?
-                })
+                };
+                filter.retain(activities, |s| db.get_activities_handling(s))
             },
             None::<&dyn for<'a> Fn(&'a Activity) -> String>,
         )
     }
 
-    fn list_providers(self) -> anyhow::Result<()> {
-        self.do_list(
-            |p, ctx, db| {
-                Ok(if p.only_new {
-                    db.get_provider_diffs_by_diff_id(p.get_diff_id(ctx, db)?)?
-                        .into_iter()
-                        .filter(|it| p.filter_allow(it))
-                        .map(|it| it.provider)
-                        .collect::<Vec<Provider>>()
-                } else {
-                    db.get_providers()?
-                })
-            },
+    fn get_providers(
+        &self,
+        ctx: &DefaultContext,
+        db: &DeviceDatabase,
+    ) -> anyhow::Result<Vec<Provider>> {
+        Ok(if self.only_new {
+            db.get_provider_diffs_by_diff_id(self.get_diff_id(ctx, db)?)?
+                .into_iter()
+                .filter(|it| self.filter_allow(it))
+                .map(|it| it.provider)
+                .collect::<Vec<Provider>>()
+        } else {
+            db.get_providers()?
+        })
+    }
+
+    fn list_providers(self, params: &ProviderPathParams) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        ensure_prereq(&ctx, Prereq::SQLDatabaseSetup)?;
+        let db = DeviceDatabase::new(&ctx)?;
+
+        let paths = db
+            .get_provider_paths(db.get_providers()?)?
+            .into_iter()
+            .map(|it| (it.provider.id, it))
+            .collect::<HashMap<i32, ProviderWithPaths>>();
+
+        let get = |p: &Self,
+                   ctx: &DefaultContext,
+                   db: &DeviceDatabase|
+         -> anyhow::Result<Vec<Provider>> {
+            let providers = p.get_providers(ctx, db)?;
+            if !params.only_grant_uri {
+                return Ok(providers);
+            }
+            Ok(providers
+                .into_iter()
+                .filter(|it| paths.get(&it.id).map_or(false, grants_uri_permissions))
+                .collect())
+        };
+
+        if !self.json {
+            for prov in get(&self, &ctx, &db)? {
+                println!("{}", prov);
+                if !params.show_paths {
+                    continue;
+                }
+                let with_paths = match paths.get(&prov.id) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                for grant in &with_paths.grant_uri_patterns {
+                    println!("   grant-uri-permission {}", grant);
+                }
+                for pp in &with_paths.path_permissions {
+                    println!("   path-permission {}", pp);
+                }
+            }
+            return Ok(());
+        }
+
+        self.do_list_json(
+            &ctx,
+            &db,
+            get,
             Some(&|prov: &Provider| {
                 #[derive(serde::Serialize)]
-                struct MetaData {
+                struct PathPermission<'a> {
+                    path: Option<UriPathPattern<'a>>,
+                    read_permission: Option<&'a str>,
+                    write_permission: Option<&'a str>,
+                }
+
+                #[derive(serde::Serialize)]
+                struct MetaData<'a> {
                     authorities: Vec<String>,
                     permission: Option<String>,
                     read_permission: Option<String>,
                     write_permission: Option<String>,
+                    grant_uri_permissions: bool,
+                    grant_uri_patterns: Vec<Option<UriPathPattern<'a>>>,
+                    path_permissions: Vec<PathPermission<'a>>,
                 }
 
                 let authorities = prov
@@ -252,17 +415,43 @@ impl CommonParams {
                     .get_permission_for_mode(PermissionMode::Write)
                     .map(String::from);
 
+                let (grant_uri_patterns, path_permissions) = match paths.get(&prov.id) {
+                    None => (Vec::new(), Vec::new()),
+                    Some(v) => (
+                        v.grant_uri_patterns
+                            .iter()
+                            .map(|it| it.uri_pattern())
+                            .collect(),
+                        v.path_permissions
+                            .iter()
+                            .map(|it| PathPermission {
+                                path: it.uri_pattern(),
+                                read_permission: it.read_permission.as_deref(),
+                                write_permission: it.write_permission.as_deref(),
+                            })
+                            .collect(),
+                    ),
+                };
+
                 MetaData {
                     authorities,
                     permission,
                     read_permission,
                     write_permission,
+                    grant_uri_permissions: prov.grant_uri_permissions,
+                    grant_uri_patterns,
+                    path_permissions,
                 }
             }),
         )
     }
 }
 
+/// Whether the provider can grant URI permissions on at least some of its paths
+fn grants_uri_permissions(prov: &ProviderWithPaths) -> bool {
+    prov.provider.grant_uri_permissions || !prov.grant_uri_patterns.is_empty()
+}
+
 #[derive(Args)]
 struct ServiceParams {
     /// Only show entries that don't exist in the given diff source (or emulator by default)
@@ -286,6 +475,9 @@ struct ServiceParams {
 
     #[arg(short = 'j', long = "json")]
     json: bool,
+
+    #[command(flatten)]
+    filter: IntentFilterParams,
 }
 
 #[derive(Subcommand)]
@@ -302,17 +494,29 @@ enum Command {
     #[command()]
     SystemServiceMethods(SystemServiceMethods),
 
+    /// Show all native services defined in init .rc files
+    #[command()]
+    NativeServices(NativeServices),
+
+    /// Show all HIDL and AIDL HAL services
+    #[command()]
+    HalServices(HalServices),
+
+    /// Show the results of `db fs-survey`
+    #[command()]
+    FsSurvey(FsSurvey),
+
     /// List all known ContentProviders
     #[command()]
-    Providers(CommonParams),
+    Providers(ProviderParams),
 
     /// List all known BroadcastReceivers
     #[command()]
-    Receivers(CommonParams),
+    Receivers(IntentParams),
 
     /// List all known Activities
     #[command()]
-    Activities(CommonParams),
+    Activities(IntentParams),
 
     /// List all known Services
     #[command()]
@@ -341,9 +545,12 @@ impl List {
             Command::Apks(c) => c.run(),
             Command::SystemServices(c) => c.run(),
             Command::SystemServiceMethods(c) => c.run(),
-            Command::Providers(p) => p.list_providers(),
-            Command::Receivers(p) => p.list_receivers(),
-            Command::Activities(p) => p.list_activities(),
+            Command::NativeServices(c) => c.run(),
+            Command::HalServices(c) => c.run(),
+            Command::FsSurvey(c) => c.run(),
+            Command::Providers(p) => p.common.list_providers(&p.paths),
+            Command::Receivers(p) => p.common.list_receivers(&p.filter),
+            Command::Activities(p) => p.common.list_activities(&p.filter),
             Command::Services(p) => p.list_services(),
             Command::Permissions => self.list_permissions(),
             Command::InterfaceImpl(c) => c.run(),
@@ -367,6 +574,7 @@ impl List {
 impl ServiceParams {
     fn list_services(self) -> anyhow::Result<()> {
         let only_returns_binder = self.only_returns_binder;
+        let filter = &self.filter;
         let c = CommonParams {
             only_public: self.only_public,
             only_enabled: self.only_enabled,
@@ -385,6 +593,7 @@ impl ServiceParams {
                 } else {
                     db.get_services()?
                 };
+                let services = filter.retain(services, |s| db.get_services_handling(s))?;
 
                 if !only_returns_binder {
                     return Ok(services);
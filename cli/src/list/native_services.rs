@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::io;
+
+use clap::Args;
+use dtu::db::device::models::{
+    DiffSource, DiffedNativeService, NativeService, NativeServiceWithDetails,
+};
+use dtu::db::{DeviceDatabase, MetaSqliteDatabase};
+use dtu::DefaultContext;
+
+use crate::diff::get_diff_source;
+use crate::parsers::DiffSourceValueParser;
+
+#[derive(Args)]
+pub struct NativeServices {
+    /// Only show services that don't exist in the given diff source (or emulator by default)
+    #[arg(short = 'n', long)]
+    only_new: bool,
+
+    /// Only show services that exist in the given diff source (or emulator
+    /// by default) but differ from it
+    #[arg(short = 'c', long)]
+    only_changed: bool,
+
+    /// Set the diff source (only valid with -n/--only-new or -c/--only-changed)
+    /// otherwise the emulator is the default
+    #[arg(short = 'S', long, value_parser = DiffSourceValueParser)]
+    diff_source: Option<DiffSource>,
+
+    /// Only show services running as root
+    #[arg(short = 'r', long)]
+    only_root: bool,
+
+    /// Only show services that create sockets
+    #[arg(short = 's', long)]
+    only_sockets: bool,
+
+    /// Only show services defined in the vendor or odm partitions
+    #[arg(short = 'V', long)]
+    only_vendor: bool,
+
+    /// Show the groups, capabilities, seclabel, sockets, and triggers of each service
+    #[arg(short, long)]
+    details: bool,
+
+    /// JSON output
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl NativeServices {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let db = DeviceDatabase::new(&ctx)?;
+
+        let diffs = if self.only_new || self.only_changed {
+            let meta = MetaSqliteDatabase::new(&ctx)?;
+            let diff_source = get_diff_source(&ctx, &meta, &db, &self.diff_source)?;
+            let diffs = db.get_native_service_diffs_by_diff_id(diff_source.id)?;
+            Some(HashMap::from_iter(
+                diffs.into_iter().map(|it| (it.service.id, it)),
+            ))
+        } else {
+            None
+        };
+
+        let services = db
+            .get_native_services()?
+            .into_iter()
+            .filter(|it| self.include_service(it, diffs.as_ref()))
+            .collect::<Vec<NativeService>>();
+
+        let services = db
+            .get_native_service_details(services)?
+            .into_iter()
+            .filter(|it| !self.only_sockets || !it.sockets.is_empty())
+            .collect::<Vec<NativeServiceWithDetails>>();
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &services)?;
+            return Ok(());
+        }
+
+        for s in services.iter() {
+            println!("{}", s.service);
+            if let Some(diff) = diffs.as_ref().and_then(|it| it.get(&s.id)) {
+                if diff.exists_in_diff {
+                    show_changes(diff);
+                }
+            }
+            if self.details {
+                show_details(s);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn include_service(
+        &self,
+        s: &NativeService,
+        diffs: Option<&HashMap<i32, DiffedNativeService>>,
+    ) -> bool {
+        if self.only_root && !s.runs_as_root() {
+            return false;
+        }
+        if self.only_vendor && !s.is_vendor() {
+            return false;
+        }
+
+        let diff = match diffs {
+            None => return true,
+            Some(diffs) => match diffs.get(&s.id) {
+                Some(v) => v,
+                None => return false,
+            },
+        };
+
+        if self.only_new && diff.exists_in_diff {
+            return false;
+        }
+
+        !self.only_changed || (diff.exists_in_diff && has_changes(diff))
+    }
+}
+
+fn has_changes(diff: &DiffedNativeService) -> bool {
+    !(diff.path_matches_diff
+        && diff.user_matches_diff
+        && diff.capabilities_matches_diff
+        && diff.seclabel_matches_diff
+        && diff.sockets_match_diff)
+}
+
+fn show_changes(diff: &DiffedNativeService) {
+    let show = |name: &str, matches: bool, value: Option<&str>| {
+        if !matches {
+            println!("    {} differs, diff: {}", name, value.unwrap_or("<none>"));
+        }
+    };
+    show("path", diff.path_matches_diff, diff.diff_path.as_deref());
+    show("user", diff.user_matches_diff, diff.diff_user.as_deref());
+    show(
+        "capabilities",
+        diff.capabilities_matches_diff,
+        diff.diff_capabilities.as_deref(),
+    );
+    show(
+        "seclabel",
+        diff.seclabel_matches_diff,
+        diff.diff_seclabel.as_deref(),
+    );
+    if !diff.sockets_match_diff {
+        println!("    sockets differ");
+    }
+}
+
+fn show_details(s: &NativeServiceWithDetails) {
+    if !s.args.is_empty() {
+        println!("    args: {}", s.args);
+    }
+    println!("    groups: {}", s.groups);
+    let caps = match s.capabilities.as_deref() {
+        None => "<unrestricted>",
+        Some("") => "<none>",
+        Some(v) => v,
+    };
+    println!("    capabilities: {}", caps);
+    if let Some(label) = &s.seclabel {
+        println!("    seclabel: {}", label);
+    }
+    println!("    rc file: {}", s.rc_file);
+    for sock in s.sockets.iter() {
+        println!("    socket {}", sock);
+    }
+    for trigger in s.triggers.iter() {
+        println!("    {}", trigger);
+    }
+}
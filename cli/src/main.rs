@@ -75,6 +75,9 @@ use check::RunCheck;
 mod selinux;
 use selinux::Selinux;
 
+mod session;
+use session::Session;
+
 mod scripting;
 use scripting::Scripting;
 
@@ -268,6 +271,10 @@ enum Commands {
     #[command()]
     Selinux(Selinux),
 
+    /// Record and replay application server sessions
+    #[command()]
+    Session(Session),
+
     #[command(name = "_scripting")]
     #[command(alias = "_s")]
     #[command(hide = true)]
@@ -359,6 +366,7 @@ fn main() -> anyhow::Result<()> {
         Commands::Call(c) => c.run(),
         Commands::RunCheck(c) => c.run(),
         Commands::Selinux(c) => c.run(),
+        Commands::Session(c) => c.run(),
 
         Commands::Version => panic!("unreachable"),
     };
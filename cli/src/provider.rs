@@ -1,3 +1,4 @@
+use anyhow::bail;
 use base64::Engine;
 use clap::{self, Args};
 use dtu::app::IntentString;
@@ -8,7 +9,7 @@ use std::path::PathBuf;
 use crate::parsers::{parse_intent_string, parse_parcel_string};
 use crate::utils::get_app_server;
 use dtu::app::server::{AppServer, ProviderUriBuilder};
-use dtu::db::{MetaDatabase, MetaSqliteDatabase};
+use dtu::db::{DeviceDatabase, MetaDatabase, MetaSqliteDatabase};
 use dtu::prereqs::Prereq;
 use dtu::DefaultContext;
 
@@ -47,13 +48,23 @@ enum Subcommand {
     /// Write a file on a `ContentProvider`
     #[command()]
     WriteFile(WriteFile),
+
+    /// Show the permissions and path rules of a `ContentProvider` from the
+    /// device database
+    #[command()]
+    Info(Info),
 }
 
 impl Provider {
     pub fn run(&self) -> anyhow::Result<()> {
         let ctx = DefaultContext::new();
         let meta = MetaSqliteDatabase::new(&ctx)?;
-        meta.ensure_prereq(Prereq::AppSetup)?;
+        // Info only reads the device database, everything else needs the app
+        let prereq = match &self.command {
+            Subcommand::Info(_) => Prereq::SQLDatabaseSetup,
+            _ => Prereq::AppSetup,
+        };
+        meta.ensure_prereq(prereq)?;
         match &self.command {
             Subcommand::Call(c) => c.run(&self.authority)?,
             Subcommand::Query(c) => c.run(&self.authority)?,
@@ -61,6 +72,57 @@ impl Provider {
             Subcommand::Delete(c) => c.run(&self.authority)?,
             Subcommand::ReadFile(c) => c.run(&self.authority)?,
             Subcommand::WriteFile(c) => c.run(&self.authority)?,
+            Subcommand::Info(c) => c.run(&ctx, &self.authority)?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct Info {
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+}
+
+impl Info {
+    fn run(&self, ctx: &DefaultContext, authority: &str) -> anyhow::Result<()> {
+        let db = DeviceDatabase::new(ctx)?;
+        let provider = db.get_provider_containing_authority(authority)?;
+        let info = match db.get_provider_paths(vec![provider])?.pop() {
+            Some(v) => v,
+            None => bail!("no provider info found for {}", authority),
+        };
+
+        if self.json {
+            serde_json::to_writer(std::io::stdout(), &info)?;
+            return Ok(());
+        }
+
+        let or_none = |it: &Option<String>| it.clone().unwrap_or_else(|| String::from("None"));
+
+        println!("Name: {}", info.name);
+        println!("Authorities: {}", info.authorities);
+        println!("Exported: {}", info.exported);
+        println!("Enabled: {}", info.enabled);
+        println!("Permission: {}", or_none(&info.permission));
+        println!("Read Permission: {}", or_none(&info.read_permission));
+        println!("Write Permission: {}", or_none(&info.write_permission));
+        println!(
+            "Grant URI Permissions: {}",
+            info.provider.grant_uri_permissions
+        );
+        if !info.grant_uri_patterns.is_empty() {
+            println!("Grant URI Patterns:");
+            for grant in &info.grant_uri_patterns {
+                println!("   {}", grant);
+            }
+        }
+        if !info.path_permissions.is_empty() {
+            println!("Path Permissions:");
+            for pp in &info.path_permissions {
+                println!("   {}", pp);
+            }
         }
         Ok(())
     }
@@ -127,6 +127,10 @@ impl Applet {
             Event::FrameworkEnded => {}
             Event::ApksStarted => {}
             Event::ApksEnded => {}
+            Event::NativeLibsStarted => {}
+            Event::NativeLibsEnded => {}
+            Event::InitRcStarted => {}
+            Event::InitRcEnded => {}
             Event::FindingDirectories => {}
             Event::DirectoryFound { .. } => {}
             Event::DirectoryDone { .. } => {}
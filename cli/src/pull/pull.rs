@@ -73,6 +73,22 @@ pub struct Pull {
     )]
     force_vdex: bool,
 
+    #[arg(
+        long,
+        help = "Don't pull native libraries or extract them from APKs",
+        action = clap::ArgAction::SetTrue,
+        default_value_t = false,
+    )]
+    no_native_libs: bool,
+
+    #[arg(
+        long,
+        help = "Don't pull the init .rc files defining native services",
+        action = clap::ArgAction::SetTrue,
+        default_value_t = false,
+    )]
+    no_init_rc: bool,
+
     /// The number of threads to use, 2 is the minimum
     #[arg(short = 'T', long, default_value_t = 4)]
     num_threads: usize,
@@ -95,6 +111,8 @@ impl Pull {
             opts.try_vdex = true;
         }
         opts.worker_threads = self.num_threads;
+        opts.native_libs = !self.no_native_libs;
+        opts.init_rc = !self.no_init_rc;
         opts
     }
 
@@ -0,0 +1,138 @@
+use std::io;
+use std::path::PathBuf;
+
+use clap::{self, Args, ValueHint};
+use serde::Serialize;
+
+use dtu::app::server::TcpAppServer;
+use dtu::app_server::{read_session, SessionEntry};
+use dtu::DefaultContext;
+
+use crate::printer::{color, Printer};
+use crate::utils::get_app_server;
+
+#[derive(Args)]
+pub struct Session {
+    #[command(subcommand)]
+    command: Subcommand,
+}
+
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Send every command in a session file again and compare the responses
+    ///
+    /// Sessions are recorded by setting DTU_SESSION_FILE before running any
+    /// commands that use the application server. The replay itself is not
+    /// recorded.
+    #[command()]
+    Replay(Replay),
+}
+
+impl Session {
+    pub fn run(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Subcommand::Replay(c) => c.run(),
+        }
+    }
+}
+
+#[derive(Args)]
+struct Replay {
+    /// The session file to replay
+    #[arg(value_hint = ValueHint::FilePath)]
+    file: PathBuf,
+
+    /// Response keys to ignore when comparing, can be given multiple times
+    #[arg(short, long)]
+    ignore: Vec<String>,
+
+    /// Only show responses that differ
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Show json
+    #[arg(short, long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct ReplayResult<'a> {
+    recorded: &'a SessionEntry,
+    replayed: SessionEntry,
+    differences: Vec<String>,
+}
+
+impl Replay {
+    fn run(&self) -> anyhow::Result<()> {
+        let ctx = DefaultContext::new();
+        let entries = read_session(&self.file)?;
+
+        // Make sure the server is reachable before sending anything, this
+        // also forwards the port if needed
+        get_app_server(&ctx)?;
+
+        let printer = Printer::new();
+        let mut results = Vec::with_capacity(entries.len());
+        let mut differing = 0;
+
+        for (i, entry) in entries.iter().enumerate() {
+            // The server only handles one command per connection, this also
+            // keeps the replay out of any session being recorded
+            let mut srv = TcpAppServer::from_ctx(&ctx)?;
+            let replayed = entry.replay(&mut srv);
+            let differences = entry.differences(&replayed, &self.ignore);
+            if !differences.is_empty() {
+                differing += 1;
+            }
+            let res = ReplayResult {
+                recorded: entry,
+                replayed,
+                differences,
+            };
+            if !self.json {
+                self.print_result(&printer, i + 1, &res);
+            }
+            results.push(res);
+        }
+
+        if self.json {
+            serde_json::to_writer(io::stdout(), &results)?;
+            return Ok(());
+        }
+
+        printer.println(format!(
+            "{} of {} responses differ",
+            differing,
+            results.len()
+        ));
+        Ok(())
+    }
+
+    fn print_result(&self, printer: &Printer, idx: usize, res: &ReplayResult) {
+        if res.differences.is_empty() {
+            if !self.quiet {
+                printer.print_colored("[same] ", color::GREEN);
+                printer.println(format!("{} {}", idx, res.recorded.command));
+            }
+            return;
+        }
+
+        printer.print_colored("[diff] ", color::RED);
+        printer.println(format!("{} {}", idx, res.recorded.command));
+        printer.println_colored(format!("    {}", res.recorded.payload), color::GREY);
+        for path in res.differences.iter() {
+            let path = if path.is_empty() { "response" } else { path };
+            printer.println_colored(format!("    {}", path), color::YELLOW);
+        }
+        printer.println(format!("    was: {}", describe(&res.recorded)));
+        printer.println(format!("    now: {}", describe(&res.replayed)));
+    }
+}
+
+fn describe(entry: &SessionEntry) -> String {
+    match (&entry.response, &entry.error) {
+        (Some(response), _) => response.to_string(),
+        (None, Some(err)) => format!("error: {}", err),
+        (None, None) => String::new(),
+    }
+}
@@ -27,6 +27,7 @@ use signal_hook::iterator::Signals;
 
 use dtu::adb::{Adb, ExecAdb};
 use dtu::app::server::{ConnectError, TcpAppServer};
+use dtu::app_server::SessionRecorder;
 use dtu::db::DeviceDatabase;
 use dtu::tasks::{TaskCancelCheck, TaskCanceller};
 use dtu::utils::{find_file_for_class, find_smali_file_for_class, ClassName, DevicePath};
@@ -160,7 +161,11 @@ pub fn oshash<T: AsRef<str>>(sha: &mut Sha256, opt: &Option<T>) {
 /// Convenience function to get an [AppServer] implementation and give a user
 /// friendly error.
 pub fn get_app_server(ctx: &dyn Context) -> anyhow::Result<TcpAppServer> {
-    get_app_server_recur(ctx, false)
+    let mut srv = get_app_server_recur(ctx, false)?;
+    if let Some(recorder) = SessionRecorder::from_ctx(ctx)? {
+        srv.set_recorder(recorder);
+    }
+    Ok(srv)
 }
 
 fn get_app_server_recur(ctx: &dyn Context, called: bool) -> anyhow::Result<TcpAppServer> {
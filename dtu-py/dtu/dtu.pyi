@@ -319,6 +319,8 @@ class GraphDB:
         method_source: Optional[str] = ...,
         call_source: Optional[str] = ...,
         depth: int = ...,
+        class_hierarchy: bool = ...,
+        binder: bool = ...,
     ) -> list[MethodCallPath]: ...
 
     def find_outgoing_calls(
@@ -329,8 +331,36 @@ class GraphDB:
         signature: Optional[str] = ...,
         source: Optional[str] = ...,
         depth: int = ...,
+        class_hierarchy: bool = ...,
+        binder: bool = ...,
     ) -> list[MethodCallPath]: ...
 
+    def find_call_paths(
+        self,
+        *,
+        from_class: Optional[str] = ...,
+        from_name: Optional[str] = ...,
+        from_signature: Optional[str] = ...,
+        from_source: Optional[str] = ...,
+        to_class: Optional[str] = ...,
+        to_name: Optional[str] = ...,
+        to_signature: Optional[str] = ...,
+        to_source: Optional[str] = ...,
+        depth: int = ...,
+        k: Optional[int] = ...,
+    ) -> list[MethodCallPath]: ...
+
+    def find_intent_targets(
+        self,
+        *,
+        ctx: Optional[Context] = ...,
+        source: Optional[str] = ...,
+        action: Optional[str] = ...,
+        kind: Optional[str] = ...,
+        target: Optional[str] = ...,
+        only_exported: bool = ...,
+    ) -> list[IntentEdge]: ...
+
     def get_classes_for(self, src: str) -> list[ClassName]: ...
     def get_methods_for(self, source: str) -> list[MethodSpec]: ...
 
@@ -401,6 +431,29 @@ class MethodCallPath:
     def initial(self) -> MethodSpec: ...
 
 
+class IntentEdge:
+    @property
+    def sender(self) -> MethodSpec: ...
+    @property
+    def via(self) -> str: ...
+    @property
+    def action(self) -> Optional[str]: ...
+    @property
+    def kind(self) -> str: ...
+    @property
+    def apk(self) -> DevicePath: ...
+    @property
+    def class_(self) -> ClassName: ...
+    @property
+    def exported(self) -> bool: ...
+    @property
+    def enabled(self) -> bool: ...
+    @property
+    def permission(self) -> Optional[str]: ...
+
+    def is_explicit(self) -> bool: ...
+
+
 class DeviceDB:
     def __new__(cls, ctx: Optional[Context] = ...) -> DeviceDB: ...
 
@@ -599,6 +652,8 @@ class Apk:
     def is_priv(self) -> bool: ...
     @property
     def device_path(self) -> DevicePath: ...
+    @property
+    def shared_user_id(self) -> Optional[str]: ...
 
 
 class ApkWithPermissions:
@@ -845,6 +900,10 @@ class DiffedProvider:
     def diff_write_permission(self) -> Optional[str]: ...
     @property
     def diff_read_permission(self) -> Optional[str]: ...
+    @property
+    def path_permissions_match_diff(self) -> bool: ...
+    @property
+    def grant_uri_permissions_match_diff(self) -> bool: ...
 
 
 class SystemServiceImpl:
@@ -937,6 +996,8 @@ class DiffSource:
     def id(self) -> int: ...
     @property
     def name(self) -> str: ...
+    @property
+    def smali_dir(self) -> Optional[str]: ...
 
 
 class FuzzResult:
@@ -1048,18 +1109,18 @@ class AppServer:
         *,
         iface: Optional[str] = ...,
         parcel_data: Optional[list[ParcelValue]] = ...,
-    ) -> str: ...
+    ) -> BinderReply: ...
 
     def call_app_service(
         self,
-        txn: int,
         package: str,
         class_: str,
         *,
+        txn: Optional[int] = ...,
         iface: Optional[str] = ...,
         action: Optional[str] = ...,
         parcel_data: Optional[list[ParcelValue]] = ...,
-    ) -> str: ...
+    ) -> BinderReply: ...
 
     def broadcast(
         self,
@@ -1092,6 +1153,17 @@ class AppServer:
     ) -> str: ...
 
 
+class BinderReply:
+    @property
+    def response(self) -> str: ...
+    @property
+    def reply(self) -> bytes: ...
+
+    def decode(self, return_type: Optional[str] = ...) -> str: ...
+
+    def __repr__(self) -> str: ...
+
+
 class CommandResult:
     @property
     def exit(self) -> int: ...
@@ -2,9 +2,8 @@ use std::{borrow::Cow, collections::HashMap};
 
 use dtu::{
     app_server::{
-        extract_string_from_json, maybe_extract_string_from_json, AppServer, CallAppService,
-        CallSystemService, Command, CommandResult, IntentData, ProviderCommand, ProviderSubcommand,
-        TcpAppServer,
+        maybe_extract_string_from_json, AppServer, BinderReply, CallAppService, CallSystemService,
+        Command, CommandResult, IntentData, ProviderCommand, ProviderSubcommand, TcpAppServer,
     },
     utils::ClassName,
 };
@@ -149,7 +148,8 @@ impl PyAppServer {
 
     /// Call a method on the given system service.
     ///
-    /// The parcel_data must be provided as a list of `ParcelValue` instances
+    /// The parcel_data must be provided as a list of `ParcelValue` instances. A DtuError is
+    /// raised if the service wrote an exception to the reply.
     #[pyo3(signature = (name, txn, *, iface = None, parcel_data = None))]
     fn call_system_service(
         &mut self,
@@ -157,7 +157,7 @@ impl PyAppServer {
         txn: u32,
         iface: Option<&str>,
         parcel_data: Option<Vec<ParcelValue>>,
-    ) -> Result<String> {
+    ) -> PyResult<PyBinderReply> {
         let iface = iface.map(ClassName::from);
         let parcel_data = parcel_data.as_ref().map(build_parcel_string);
         let payload = CallSystemService {
@@ -166,13 +166,18 @@ impl PyAppServer {
             iface: iface.as_ref(),
             parcel_data: parcel_data.as_ref().map(String::as_str),
         };
-        let res = self.0.send_command(Command::SystemService, &payload)?;
-        Ok(extract_string_from_json("response", &res)?)
+        let reply = self
+            .0
+            .transact(Command::SystemService, &payload)
+            .map_err(AppServerError::from)?;
+        PyBinderReply::checked(reply, false)
     }
 
     /// Call a method on the given application service.
     ///
-    /// The parcel_data must be provided as a list of `ParcelValue` instances
+    /// The parcel_data must be provided as a list of `ParcelValue` instances. A DtuError is
+    /// raised if the service wrote an exception to the reply. Without a txn the interface
+    /// descriptor is returned as the reply's `response`.
     #[pyo3(signature = (package, class, *, txn = None, iface = None, action = None, parcel_data = None))]
     fn call_app_service(
         &mut self,
@@ -182,7 +187,7 @@ impl PyAppServer {
         iface: Option<&str>,
         action: Option<&str>,
         parcel_data: Option<Vec<ParcelValue>>,
-    ) -> Result<String> {
+    ) -> PyResult<PyBinderReply> {
         let class = ClassName::from(class);
         let iface = iface.map(ClassName::from);
         let parcel_data = parcel_data.as_ref().map(build_parcel_string);
@@ -194,8 +199,12 @@ impl PyAppServer {
             iface: iface.as_ref(),
             parcel_data: parcel_data.as_ref().map(String::as_str),
         };
-        let res = self.0.send_command(Command::AppService, &payload)?;
-        Ok(extract_string_from_json("response", &res)?)
+        let reply = self
+            .0
+            .transact(Command::AppService, &payload)
+            .map_err(AppServerError::from)?;
+        // Without a txn the interface transaction is sent, which replies with just the descriptor
+        PyBinderReply::checked(reply, txn.is_none())
     }
 
     /// Send a broadcast from the context of the test application
@@ -325,3 +334,57 @@ impl PyCommandResult {
         )
     }
 }
+
+/// The reply to a binder transaction made by the application server
+#[pyclass(module = "dtu", frozen, name = "BinderReply")]
+pub struct PyBinderReply(BinderReply);
+
+impl PyBinderReply {
+    /// Wrap the reply, raising if it contains an exception
+    fn checked(reply: BinderReply, interface_transaction: bool) -> PyResult<Self> {
+        let exception = reply
+            .exception(interface_transaction)
+            .map_err(|e| DtuError::new_err(e.to_string()))?;
+        if let Some(ex) = exception {
+            let msg = match ex.message {
+                Some(msg) => format!("{}: {}", ex.name, msg),
+                None => String::from(ex.name),
+            };
+            return Err(DtuError::new_err(msg));
+        }
+        Ok(Self(reply))
+    }
+}
+
+#[pymethods]
+impl PyBinderReply {
+    /// A hex dump of the reply parcel, or the interface descriptor for `INTERFACE_TRANSACTION`
+    #[getter]
+    fn response(&self) -> &str {
+        &self.0.response
+    }
+
+    /// The raw reply parcel, including the exception header
+    #[getter]
+    fn reply(&self) -> &[u8] {
+        &self.0.reply
+    }
+
+    /// Decode the reply to JSON using the method's smali return type, such as
+    /// `Ljava/lang/String;`
+    ///
+    /// Without a return type only the exception header is decoded and the rest of the reply is
+    /// left as hex.
+    #[pyo3(signature = (return_type = None))]
+    fn decode(&self, return_type: Option<&str>) -> PyResult<String> {
+        let decoded = self
+            .0
+            .decode(return_type)
+            .map_err(|e| DtuError::new_err(e.to_string()))?;
+        serde_json::to_string(&decoded).map_err(|e| DtuError::new_err(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("BinderReply(reply_len={})", self.0.reply.len())
+    }
+}
@@ -6,20 +6,26 @@ use std::{
 use dtu::{
     db::graph::{
         get_default_graphdb,
+        intents::{
+            find_intent_targets, load_intent_components, IntentEdge, IntentTargetKind,
+            IntentTargetSearch,
+        },
         models::{
             ClassSearch, FieldAccessOp, FieldRef, FieldSearch, FieldSpec, MethodCallPath,
             MethodSearch, MethodSpec,
         },
-        ClassSpec, DefaultGraphDatabase, GraphDatabase, StringSearch,
+        ClassSpec, DefaultGraphDatabase, DispatchMode, GraphDatabase, StringSearch,
     },
+    db::DeviceDatabase,
     utils::ClassName,
+    Context,
 };
 use pyo3::{prelude::*, types::PyTuple};
 
 use crate::{
     context::PyContext,
     exception::DtuError,
-    types::{PyAccessFlag, PyClassName},
+    types::{PyAccessFlag, PyClassName, PyDevicePath},
     utils::{reduce, unpickle},
 };
 
@@ -175,8 +181,10 @@ impl GraphDB {
     /// Find all callers of the given class up to a certain depth.
     ///
     /// At least one of `class_` or `name` is required for this search. High depth values may
-    /// negatively impact performance.
-    #[pyo3(signature = (*, class_ = None, name = None, signature = None, method_source = None, call_source = None, depth = 5))]
+    /// negatively impact performance. If `class_hierarchy` is set, callers of methods that the
+    /// searched method overrides are also included. If `binder` is set, callers of `Stub.Proxy`
+    /// methods linked to the searched system service method are also included.
+    #[pyo3(signature = (*, class_ = None, name = None, signature = None, method_source = None, call_source = None, depth = 5, class_hierarchy = false, binder = false))]
     fn find_callers(
         &self,
         class_: Option<&str>,
@@ -185,6 +193,8 @@ impl GraphDB {
         method_source: Option<&str>,
         call_source: Option<&str>,
         depth: usize,
+        class_hierarchy: bool,
+        binder: bool,
     ) -> PyResult<Vec<PyMethodCallPath>> {
         let cn = class_.map(ClassName::from);
         let search = MethodSearch::new_from_opts(cn.as_ref(), name, signature, method_source)
@@ -192,7 +202,12 @@ impl GraphDB {
 
         Ok(self
             .0
-            .find_callers(&search, call_source, depth)
+            .find_callers_with_dispatch(
+                &search,
+                call_source,
+                depth,
+                DispatchMode::new(class_hierarchy).with_binder(binder),
+            )
             .map_err(GraphError)?
             .into_iter()
             .map(PyMethodCallPath::from)
@@ -201,7 +216,10 @@ impl GraphDB {
     }
 
     /// Find all calls leaving the given method up to a given depth.
-    #[pyo3(signature = (*, class_ = None, name = None, signature = None, source = None, depth = 5))]
+    ///
+    /// If `class_hierarchy` is set, calls are also followed into methods overriding the callee. If
+    /// `binder` is set, calls to `Stub.Proxy` methods are followed into the system service.
+    #[pyo3(signature = (*, class_ = None, name = None, signature = None, source = None, depth = 5, class_hierarchy = false, binder = false))]
     fn find_outgoing_calls(
         &self,
         class_: Option<&str>,
@@ -209,6 +227,8 @@ impl GraphDB {
         signature: Option<&str>,
         source: Option<&str>,
         depth: usize,
+        class_hierarchy: bool,
+        binder: bool,
     ) -> PyResult<Vec<PyMethodCallPath>> {
         let cn = class_.map(ClassName::from);
         let search = MethodSearch::new_from_opts(cn.as_ref(), name, signature, source)
@@ -216,13 +236,102 @@ impl GraphDB {
 
         Ok(self
             .0
-            .find_outgoing_calls(&search, depth)
+            .find_outgoing_calls_with_dispatch(
+                &search,
+                depth,
+                DispatchMode::new(class_hierarchy).with_binder(binder),
+            )
             .map_err(GraphError)?
             .into_iter()
             .map(PyMethodCallPath::from)
             .collect())
     }
 
+    /// Find the shortest call paths between two methods.
+    ///
+    /// At least one of the class or name is required for both the `from_` and `to_` methods. If
+    /// `k` is given, up to `k` paths are returned ordered by length instead of only the shortest.
+    #[pyo3(signature = (*, from_class = None, from_name = None, from_signature = None, from_source = None, to_class = None, to_name = None, to_signature = None, to_source = None, depth = 5, k = None))]
+    fn find_call_paths(
+        &self,
+        from_class: Option<&str>,
+        from_name: Option<&str>,
+        from_signature: Option<&str>,
+        from_source: Option<&str>,
+        to_class: Option<&str>,
+        to_name: Option<&str>,
+        to_signature: Option<&str>,
+        to_source: Option<&str>,
+        depth: usize,
+        k: Option<usize>,
+    ) -> PyResult<Vec<PyMethodCallPath>> {
+        let from_cn = from_class.map(ClassName::from);
+        let from =
+            MethodSearch::new_from_opts(from_cn.as_ref(), from_name, from_signature, from_source)
+                .map_err(|_| {
+                DtuError::new_err("at least one of `from_class` or `from_name` required")
+            })?;
+        let to_cn = to_class.map(ClassName::from);
+        let to = MethodSearch::new_from_opts(to_cn.as_ref(), to_name, to_signature, to_source)
+            .map_err(|_| DtuError::new_err("at least one of `to_class` or `to_name` required"))?;
+
+        let paths = match k {
+            Some(k) => self.0.find_k_shortest_call_paths(&from, &to, depth, k),
+            None => self.0.find_call_paths(&from, &to, depth),
+        }
+        .map_err(GraphError)?;
+
+        Ok(paths.into_iter().map(PyMethodCallPath::from).collect())
+    }
+
+    /// Find the activities, services, and receivers that intents sent from methods in the graph
+    /// can reach.
+    ///
+    /// Intent actions and component names are taken from the constant strings in the methods
+    /// calling `startActivity`, `startService`, `sendBroadcast` and similar, and matched against
+    /// the intent filters in the device's manifests. `kind` is one of `activity`, `service`, or
+    /// `receiver`.
+    #[pyo3(signature = (*, ctx = None, source = None, action = None, kind = None, target = None, only_exported = false))]
+    fn find_intent_targets(
+        &self,
+        ctx: Option<&PyContext>,
+        source: Option<&str>,
+        action: Option<&str>,
+        kind: Option<&str>,
+        target: Option<&str>,
+        only_exported: bool,
+    ) -> PyResult<Vec<PyIntentEdge>> {
+        let default_ctx;
+        let ctx: &dyn Context = match ctx {
+            Some(v) => v,
+            None => {
+                default_ctx = dtu::DefaultContext::new();
+                &default_ctx
+            }
+        };
+        let kind = kind
+            .map(str::parse::<IntentTargetKind>)
+            .transpose()
+            .map_err(DtuError::mapper)?;
+        let target = target.map(ClassName::from);
+
+        let device = DeviceDatabase::new(ctx).map_err(DtuError::mapper)?;
+        let components = load_intent_components(&device).map_err(GraphError)?;
+        let search = IntentTargetSearch {
+            source,
+            action,
+            kind,
+            target: target.as_ref(),
+            only_exported,
+        };
+
+        Ok(find_intent_targets(&self.0, &components, &search)
+            .map_err(GraphError)?
+            .into_iter()
+            .map(PyIntentEdge::from)
+            .collect())
+    }
+
     /// Get all classes defined by the given source
     fn get_classes_for(&self, src: &str) -> Result<Vec<PyClassName>> {
         Ok(self
@@ -600,3 +709,89 @@ impl From<PyMethodCallPath> for MethodCallPath {
         v.0
     }
 }
+
+#[pyclass(module = "dtu", frozen, name = "IntentEdge")]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PyIntentEdge(pub(crate) IntentEdge);
+
+impl AsRef<IntentEdge> for PyIntentEdge {
+    fn as_ref(&self) -> &IntentEdge {
+        &self.0
+    }
+}
+
+#[pymethods]
+impl PyIntentEdge {
+    #[staticmethod]
+    fn __unpickle(value: &[u8]) -> PyResult<Self> {
+        unpickle::<IntentEdge, _>(value)
+    }
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyTuple>> {
+        reduce::<_, IntentEdge>(self, py)
+    }
+
+    /// The method sending the intent
+    #[getter]
+    fn sender(&self) -> PyMethodSpec {
+        self.0.sender.clone().into()
+    }
+
+    /// The name of the method used to send the intent
+    #[getter]
+    fn via(&self) -> &str {
+        &self.0.via
+    }
+
+    /// The matched action, or None for explicit intents
+    #[getter]
+    fn action(&self) -> Option<&str> {
+        self.0.action.as_ref().map(|it| it.as_str())
+    }
+
+    /// One of `activity`, `service`, or `receiver`
+    #[getter]
+    fn kind(&self) -> String {
+        self.0.target.kind.to_string()
+    }
+
+    #[getter]
+    fn apk(&self) -> PyDevicePath {
+        self.0.target.apk.clone().into()
+    }
+
+    #[getter]
+    fn class_(&self) -> PyClassName {
+        self.0.target.class_name.clone().into()
+    }
+
+    #[getter]
+    fn exported(&self) -> bool {
+        self.0.target.exported
+    }
+
+    #[getter]
+    fn enabled(&self) -> bool {
+        self.0.target.enabled
+    }
+
+    #[getter]
+    fn permission(&self) -> Option<&str> {
+        self.0.target.permission.as_ref().map(|it| it.as_str())
+    }
+
+    fn is_explicit(&self) -> bool {
+        self.0.is_explicit()
+    }
+}
+
+impl From<IntentEdge> for PyIntentEdge {
+    fn from(v: IntentEdge) -> Self {
+        Self(v)
+    }
+}
+
+impl From<PyIntentEdge> for IntentEdge {
+    fn from(v: PyIntentEdge) -> Self {
+        v.0
+    }
+}
@@ -37,7 +37,7 @@ mod pydtu {
 
     #[pymodule_export]
     use super::graph::{
-        GraphDB, PyClassSpec, PyFieldRef, PyFieldSpec, PyMethodCallPath, PyMethodSpec,
+        GraphDB, PyClassSpec, PyFieldRef, PyFieldSpec, PyIntentEdge, PyMethodCallPath, PyMethodSpec,
     };
 
     #[pymodule_export]
@@ -56,7 +56,7 @@ mod pydtu {
     };
 
     #[pymodule_export]
-    use super::app_server::{PyAppServer, PyCommandResult};
+    use super::app_server::{PyAppServer, PyBinderReply, PyCommandResult};
 
     #[pymodule_export]
     use super::filestore::PyFileStore;
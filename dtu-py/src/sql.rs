@@ -875,6 +875,10 @@ impl PyApk {
     fn device_path(&self) -> PyDevicePath {
         self.0.device_path.clone().into()
     }
+    #[getter]
+    fn shared_user_id(&self) -> Option<&str> {
+        self.0.shared_user_id.as_deref()
+    }
 }
 
 #[pyclass(module = "dtu", frozen, name = "ApkWithPermissions")]
@@ -1691,6 +1695,14 @@ impl PyDiffedProvider {
     fn diff_read_permission(&self) -> Option<&str> {
         self.0.diff_read_permission.as_ref().map(String::as_str)
     }
+    #[getter]
+    fn path_permissions_match_diff(&self) -> bool {
+        self.0.path_permissions_match_diff
+    }
+    #[getter]
+    fn grant_uri_permissions_match_diff(&self) -> bool {
+        self.0.grant_uri_permissions_match_diff
+    }
 }
 
 #[pyclass(module = "dtu", frozen, name = "SystemServiceImpl")]
@@ -2049,6 +2061,10 @@ impl PyDiffSource {
     fn name(&self) -> &str {
         &self.0.name
     }
+    #[getter]
+    fn smali_dir(&self) -> Option<&str> {
+        self.0.smali_dir.as_deref()
+    }
 }
 
 #[pyclass(module = "dtu", frozen, name = "FuzzResult")]
@@ -0,0 +1,18 @@
+-- Results of statically analyzing which permission checks a system service
+-- method reaches in the graph database. A method with an implementation but
+-- no rows here didn't reach any known permission check.
+CREATE TABLE permission_checks
+(
+    id                  INTEGER      NOT NULL,
+    method              INTEGER      NOT NULL,
+    system_service_impl INTEGER      NOT NULL,
+    check_class         VARCHAR(511) NOT NULL,
+    check_method        VARCHAR(255) NOT NULL,
+    permission          VARCHAR(255),
+    depth               INTEGER      NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (method) REFERENCES system_service_methods (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (system_service_impl) REFERENCES system_service_impls (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX permission_checks_method ON permission_checks(method);
@@ -0,0 +1,4 @@
+DROP TABLE intent_filter_data;
+DROP TABLE intent_filter_categories;
+DROP TABLE intent_filter_actions;
+DROP TABLE intent_filters;
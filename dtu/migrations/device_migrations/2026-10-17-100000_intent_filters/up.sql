@@ -0,0 +1,63 @@
+-- Intent filters declared on activities, receivers, and services. Each filter
+-- belongs to exactly one component.
+CREATE TABLE intent_filters
+(
+    id          INTEGER NOT NULL,
+    activity_id INTEGER,
+    receiver_id INTEGER,
+    service_id  INTEGER,
+    priority    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    FOREIGN KEY (activity_id) REFERENCES activities (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (receiver_id) REFERENCES receivers (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (service_id) REFERENCES services (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    CHECK ((activity_id IS NOT NULL) + (receiver_id IS NOT NULL) + (service_id IS NOT NULL) = 1)
+);
+
+CREATE INDEX intent_filters_activity ON intent_filters(activity_id);
+CREATE INDEX intent_filters_receiver ON intent_filters(receiver_id);
+CREATE INDEX intent_filters_service ON intent_filters(service_id);
+
+CREATE TABLE intent_filter_actions
+(
+    id        INTEGER      NOT NULL,
+    filter_id INTEGER      NOT NULL,
+    name      VARCHAR(255) NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (filter_id) REFERENCES intent_filters (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX intent_filter_actions_filter ON intent_filter_actions(filter_id);
+CREATE INDEX intent_filter_actions_name ON intent_filter_actions(name);
+
+CREATE TABLE intent_filter_categories
+(
+    id        INTEGER      NOT NULL,
+    filter_id INTEGER      NOT NULL,
+    name      VARCHAR(255) NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (filter_id) REFERENCES intent_filters (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX intent_filter_categories_filter ON intent_filter_categories(filter_id);
+
+-- One row per <data> element, the attributes are kept as they appear in the
+-- manifest since Android combines them across all <data> elements in a filter
+CREATE TABLE intent_filter_data
+(
+    id                    INTEGER NOT NULL,
+    filter_id             INTEGER NOT NULL,
+    scheme                VARCHAR(255),
+    host                  VARCHAR(255),
+    port                  VARCHAR(255),
+    path                  VARCHAR(255),
+    path_pattern          VARCHAR(255),
+    path_prefix           VARCHAR(255),
+    path_suffix           VARCHAR(255),
+    path_advanced_pattern VARCHAR(255),
+    mime_type             VARCHAR(255),
+    PRIMARY KEY (id),
+    FOREIGN KEY (filter_id) REFERENCES intent_filters (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX intent_filter_data_filter ON intent_filter_data(filter_id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS apk_certificates;
+ALTER TABLE apks DROP COLUMN shared_user_id;
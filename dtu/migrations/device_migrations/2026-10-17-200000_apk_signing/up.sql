@@ -0,0 +1,18 @@
+ALTER TABLE apks ADD COLUMN shared_user_id VARCHAR(255);
+
+-- Certificates each APK was signed with, keyed by the SHA-256 digest of the
+-- DER encoded certificate
+CREATE TABLE apk_certificates
+(
+    id      INTEGER      NOT NULL,
+    apk_id  INTEGER      NOT NULL,
+    scheme  VARCHAR(8)   NOT NULL,
+    sha256  VARCHAR(64)  NOT NULL,
+    subject TEXT         NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (apk_id) REFERENCES apks (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (apk_id, scheme, sha256)
+);
+
+CREATE INDEX apk_certificates_apk ON apk_certificates(apk_id);
+CREATE INDEX apk_certificates_sha256 ON apk_certificates(sha256);
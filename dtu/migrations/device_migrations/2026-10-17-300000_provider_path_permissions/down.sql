@@ -0,0 +1,4 @@
+ALTER TABLE provider_diffs DROP COLUMN grant_uri_permissions_match_diff;
+ALTER TABLE provider_diffs DROP COLUMN path_permissions_match_diff;
+DROP TABLE IF EXISTS provider_grant_uri_permissions;
+DROP TABLE IF EXISTS provider_path_permissions;
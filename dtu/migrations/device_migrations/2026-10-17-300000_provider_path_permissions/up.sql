@@ -0,0 +1,38 @@
+-- <path-permission> entries on providers. Only one of the path columns is
+-- expected to be set.
+CREATE TABLE provider_path_permissions
+(
+    id                    INTEGER NOT NULL,
+    provider_id           INTEGER NOT NULL,
+    path                  TEXT,
+    path_prefix           TEXT,
+    path_pattern          TEXT,
+    path_advanced_pattern TEXT,
+    path_suffix           TEXT,
+    permission            VARCHAR(255),
+    read_permission       VARCHAR(255),
+    write_permission      VARCHAR(255),
+    PRIMARY KEY (id),
+    FOREIGN KEY (provider_id) REFERENCES providers (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX provider_path_permissions_provider ON provider_path_permissions(provider_id);
+
+-- <grant-uri-permission> entries on providers
+CREATE TABLE provider_grant_uri_permissions
+(
+    id                    INTEGER NOT NULL,
+    provider_id           INTEGER NOT NULL,
+    path                  TEXT,
+    path_prefix           TEXT,
+    path_pattern          TEXT,
+    path_advanced_pattern TEXT,
+    path_suffix           TEXT,
+    PRIMARY KEY (id),
+    FOREIGN KEY (provider_id) REFERENCES providers (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX provider_grant_uri_permissions_provider ON provider_grant_uri_permissions(provider_id);
+
+ALTER TABLE provider_diffs ADD COLUMN path_permissions_match_diff BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE provider_diffs ADD COLUMN grant_uri_permissions_match_diff BOOLEAN NOT NULL DEFAULT 1;
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS native_service_diffs;
+DROP TABLE IF EXISTS native_service_triggers;
+DROP TABLE IF EXISTS native_service_sockets;
+DROP TABLE IF EXISTS native_services;
@@ -0,0 +1,70 @@
+-- `service` blocks from the init .rc files on the device. Lists are stored
+-- space separated.
+CREATE TABLE native_services
+(
+    id           INTEGER      NOT NULL,
+    name         VARCHAR(255) NOT NULL UNIQUE,
+    path         TEXT         NOT NULL,
+    args         TEXT         NOT NULL,
+    user         VARCHAR(255) NOT NULL,
+    groups       TEXT         NOT NULL,
+    -- NULL if no capabilities option was given, empty if all capabilities are
+    -- dropped
+    capabilities TEXT,
+    seclabel     VARCHAR(255),
+    classes      TEXT         NOT NULL,
+    disabled     BOOLEAN      NOT NULL,
+    oneshot      BOOLEAN      NOT NULL,
+    rc_file      TEXT         NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE native_service_sockets
+(
+    id          INTEGER      NOT NULL,
+    service_id  INTEGER      NOT NULL,
+    name        VARCHAR(255) NOT NULL,
+    socket_type VARCHAR(255) NOT NULL,
+    perm        VARCHAR(255) NOT NULL,
+    user        VARCHAR(255),
+    group_name  VARCHAR(255),
+    seclabel    VARCHAR(255),
+    PRIMARY KEY (id),
+    FOREIGN KEY (service_id) REFERENCES native_services (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX native_service_sockets_service ON native_service_sockets(service_id);
+
+-- The `on` triggers of the actions that start a service
+CREATE TABLE native_service_triggers
+(
+    id         INTEGER NOT NULL,
+    service_id INTEGER NOT NULL,
+    on_trigger TEXT    NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (service_id) REFERENCES native_services (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (service_id, on_trigger)
+);
+
+CREATE INDEX native_service_triggers_service ON native_service_triggers(service_id);
+
+CREATE TABLE native_service_diffs
+(
+    id                        INTEGER NOT NULL,
+    native_service            INTEGER NOT NULL,
+    diff_source               INTEGER NOT NULL,
+    exists_in_diff            BOOLEAN NOT NULL,
+    path_matches_diff         BOOLEAN NOT NULL,
+    diff_path                 TEXT,
+    user_matches_diff         BOOLEAN NOT NULL,
+    diff_user                 VARCHAR(255),
+    capabilities_matches_diff BOOLEAN NOT NULL,
+    diff_capabilities         TEXT,
+    seclabel_matches_diff     BOOLEAN NOT NULL,
+    diff_seclabel             VARCHAR(255),
+    sockets_match_diff        BOOLEAN NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (native_service) REFERENCES native_services (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (diff_source) REFERENCES diff_sources (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (native_service, diff_source)
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS hal_service_diffs;
+DROP TABLE IF EXISTS hal_services;
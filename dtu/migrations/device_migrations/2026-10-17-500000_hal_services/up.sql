@@ -0,0 +1,28 @@
+CREATE TABLE hal_services
+(
+    id            INTEGER      NOT NULL,
+    interface     VARCHAR(255) NOT NULL,
+    instance      VARCHAR(255) NOT NULL,
+    kind          VARCHAR(16)  NOT NULL,
+    transport     VARCHAR(32)  NOT NULL,
+    server_pid    INTEGER,
+    server_binary TEXT,
+    selinux_type  VARCHAR(255),
+    reachable     INTEGER      NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    UNIQUE (interface, instance, transport)
+);
+
+CREATE TABLE hal_service_diffs
+(
+    id                     INTEGER NOT NULL,
+    hal_service            INTEGER NOT NULL,
+    diff_source            INTEGER NOT NULL,
+    exists_in_diff         BOOLEAN NOT NULL,
+    transport_matches_diff BOOLEAN NOT NULL,
+    diff_transport         VARCHAR(32),
+    PRIMARY KEY (id),
+    FOREIGN KEY (hal_service) REFERENCES hal_services (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (diff_source) REFERENCES diff_sources (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (hal_service, diff_source)
+);
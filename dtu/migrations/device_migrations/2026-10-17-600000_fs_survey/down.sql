@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS fs_survey_entry_diffs;
+DROP TABLE IF EXISTS fs_survey_entries;
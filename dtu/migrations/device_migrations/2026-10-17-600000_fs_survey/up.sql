@@ -0,0 +1,30 @@
+CREATE TABLE fs_survey_entries
+(
+    id            INTEGER      NOT NULL,
+    path          TEXT         NOT NULL UNIQUE,
+    mode          INTEGER      NOT NULL,
+    owner         VARCHAR(255) NOT NULL,
+    group_name    VARCHAR(255) NOT NULL,
+    selinux_label VARCHAR(255),
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE fs_survey_entry_diffs
+(
+    id                 INTEGER NOT NULL,
+    fs_survey_entry    INTEGER NOT NULL,
+    diff_source        INTEGER NOT NULL,
+    exists_in_diff     BOOLEAN NOT NULL,
+    mode_matches_diff  BOOLEAN NOT NULL,
+    diff_mode          INTEGER,
+    owner_matches_diff BOOLEAN NOT NULL,
+    diff_owner         VARCHAR(255),
+    group_matches_diff BOOLEAN NOT NULL,
+    diff_group         VARCHAR(255),
+    label_matches_diff BOOLEAN NOT NULL,
+    diff_label         VARCHAR(255),
+    PRIMARY KEY (id),
+    FOREIGN KEY (fs_survey_entry) REFERENCES fs_survey_entries (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (diff_source) REFERENCES diff_sources (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (fs_survey_entry, diff_source)
+);
@@ -0,0 +1 @@
+ALTER TABLE diff_sources DROP COLUMN smali_dir;
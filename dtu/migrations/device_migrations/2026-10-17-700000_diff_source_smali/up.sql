@@ -0,0 +1,3 @@
+-- Directory holding the smali for a diff source, laid out like the project's
+-- smali directory, used to diff method bodies
+ALTER TABLE diff_sources ADD COLUMN smali_dir TEXT;
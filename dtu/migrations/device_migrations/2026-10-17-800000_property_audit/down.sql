@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS property_findings;
+DROP TABLE IF EXISTS device_property_diffs;
@@ -0,0 +1,25 @@
+CREATE TABLE device_property_diffs
+(
+    id                 INTEGER NOT NULL,
+    device_property    INTEGER NOT NULL,
+    diff_source        INTEGER NOT NULL,
+    exists_in_diff     BOOLEAN NOT NULL,
+    value_matches_diff BOOLEAN NOT NULL,
+    diff_value         VARCHAR(255),
+    PRIMARY KEY (id),
+    FOREIGN KEY (device_property) REFERENCES device_properties (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (diff_source) REFERENCES diff_sources (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (device_property, diff_source)
+);
+
+CREATE TABLE property_findings
+(
+    id              INTEGER      NOT NULL,
+    rule            VARCHAR(255) NOT NULL,
+    device_property INTEGER      NOT NULL,
+    severity        VARCHAR(16)  NOT NULL,
+    description     TEXT         NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (device_property) REFERENCES device_properties (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (rule, device_property)
+);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS binder_fuzz_results;
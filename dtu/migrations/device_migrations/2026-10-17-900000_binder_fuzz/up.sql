@@ -0,0 +1,12 @@
+CREATE TABLE binder_fuzz_results
+(
+    id          INTEGER      NOT NULL,
+    method      INTEGER      NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    parcel      TEXT         NOT NULL,
+    outcome     VARCHAR(32)  NOT NULL,
+    exception   VARCHAR(255),
+    message     TEXT,
+    PRIMARY KEY (id),
+    FOREIGN KEY (method) REFERENCES system_service_methods (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,17 @@
+-- Records which methods override (or implement) which other methods. This is
+-- derived from the `supers` and `interfaces` tables after a source is loaded
+-- and is used to expand virtual and interface calls when walking the call
+-- graph with class hierarchy analysis.
+CREATE TABLE method_overrides
+(
+    -- The overriding method
+    method  INTEGER NOT NULL,
+    -- The method in a parent class or interface that is overridden
+    parent  INTEGER NOT NULL,
+
+    PRIMARY KEY (method, parent),
+    FOREIGN KEY (method) REFERENCES methods (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (parent) REFERENCES methods (id) ON DELETE CASCADE ON UPDATE CASCADE
+) WITHOUT ROWID;
+
+CREATE INDEX method_overrides_parent ON method_overrides(parent);
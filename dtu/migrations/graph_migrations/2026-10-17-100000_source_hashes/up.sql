@@ -0,0 +1,10 @@
+-- Records a hash of the smalisa CSVs each source was imported from so that
+-- sources can be re-imported only when their CSVs change.
+CREATE TABLE source_hashes
+(
+    source  INTEGER NOT NULL,
+    hash    TEXT    NOT NULL,
+
+    PRIMARY KEY (source),
+    FOREIGN KEY (source) REFERENCES sources (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
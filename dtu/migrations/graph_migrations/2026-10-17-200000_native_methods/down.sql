@@ -0,0 +1 @@
+DROP TABLE native_methods;
@@ -0,0 +1,12 @@
+-- Maps native methods to the shared library symbols implementing them, either
+-- through `Java_...` exports or tables passed to `RegisterNatives`.
+CREATE TABLE native_methods
+(
+    method      INTEGER NOT NULL,
+    library     TEXT    NOT NULL,
+    symbol      TEXT    NOT NULL,
+    registered  BOOLEAN NOT NULL DEFAULT FALSE,
+
+    PRIMARY KEY (method, library, symbol),
+    FOREIGN KEY (method) REFERENCES methods (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,5 @@
+-- Marks the synthetic `calls` edges added for reflective lookups. These are
+-- found after a source is loaded by matching the constant strings in
+-- `method_strings` against class and method names in methods that use
+-- `Class.getDeclaredMethod` and friends.
+ALTER TABLE calls ADD COLUMN reflective BOOLEAN NOT NULL DEFAULT FALSE;
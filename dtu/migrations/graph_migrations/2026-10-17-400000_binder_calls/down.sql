@@ -0,0 +1 @@
+DROP TABLE binder_calls;
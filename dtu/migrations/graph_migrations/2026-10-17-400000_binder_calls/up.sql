@@ -0,0 +1,19 @@
+-- Links `Stub.Proxy` methods to the system service methods they reach over
+-- binder. This is built from the `system_services`, `system_service_impls`,
+-- and `system_service_methods` tables in the device database and is used to
+-- walk the call graph across process boundaries.
+CREATE TABLE binder_calls
+(
+    -- The `Stub.Proxy` method making the transaction
+    proxy           INTEGER NOT NULL,
+    -- The method in the system service implementation handling it
+    service_impl    INTEGER NOT NULL,
+    -- The transaction code from the `Stub`
+    transaction_id  INTEGER NOT NULL,
+
+    PRIMARY KEY (proxy, service_impl),
+    FOREIGN KEY (proxy) REFERENCES methods (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (service_impl) REFERENCES methods (id) ON DELETE CASCADE ON UPDATE CASCADE
+) WITHOUT ROWID;
+
+CREATE INDEX binder_calls_service_impl ON binder_calls(service_impl);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS crash_occurrences;
+DROP TABLE IF EXISTS crashes;
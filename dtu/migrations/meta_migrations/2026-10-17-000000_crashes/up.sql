@@ -0,0 +1,23 @@
+CREATE TABLE crashes (
+    id INTEGER NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    process VARCHAR(255) NOT NULL,
+    signature TEXT NOT NULL,
+    stack_hash VARCHAR(64) NOT NULL,
+    stack TEXT NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE (stack_hash)
+);
+
+CREATE TABLE crash_occurrences (
+    id INTEGER NOT NULL,
+    crash INTEGER NOT NULL,
+    source VARCHAR(255) NOT NULL,
+    line INTEGER NOT NULL,
+    time VARCHAR(32),
+    command TEXT,
+    raw TEXT NOT NULL,
+    PRIMARY KEY (id),
+    FOREIGN KEY (crash) REFERENCES crashes(id) ON DELETE CASCADE,
+    UNIQUE (source, line)
+);
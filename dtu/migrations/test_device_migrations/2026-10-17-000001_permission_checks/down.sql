@@ -0,0 +1,3 @@
+DELETE FROM permission_checks WHERE id IN (0);
+DELETE FROM system_service_methods WHERE id IN (0, 1, 2);
+DELETE FROM system_service_impls WHERE id IN (0);
@@ -0,0 +1,15 @@
+INSERT INTO system_service_impls (id, system_service_id, source, class_name)
+VALUES (0, 0, 'framework', 'Lcom/android/server/TestCanService;');
+
+INSERT INTO system_service_methods (id, system_service_id, transaction_id, name, signature, return_type)
+VALUES (0, 0, 1, 'checked', 'I', 'V');
+
+INSERT INTO system_service_methods (id, system_service_id, transaction_id, name, signature, return_type)
+VALUES (1, 0, 2, 'unchecked', '', 'V');
+
+-- test_cant has no implementation, so it was never analyzed
+INSERT INTO system_service_methods (id, system_service_id, transaction_id, name, signature, return_type)
+VALUES (2, 1, 1, 'noImpl', '', 'V');
+
+INSERT INTO permission_checks (id, method, system_service_impl, check_class, check_method, permission, depth)
+VALUES (0, 0, 0, 'Landroid/content/Context;', 'enforceCallingOrSelfPermission', 'android.permission.DUMP', 1);
@@ -0,0 +1,4 @@
+DELETE FROM intent_filters WHERE id IN (0, 1, 2, 3, 4);
+DELETE FROM services WHERE id IN (0);
+DELETE FROM receivers WHERE id IN (0);
+DELETE FROM activities WHERE id IN (0, 1, 2);
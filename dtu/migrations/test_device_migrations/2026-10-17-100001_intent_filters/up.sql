@@ -0,0 +1,39 @@
+INSERT INTO activities (id, class_name, permission, exported, enabled, pkg, apk_id)
+VALUES (0, 'just.an.app.BrowserActivity', NULL, true, true, 'just.an.app', 0);
+
+INSERT INTO activities (id, class_name, permission, exported, enabled, pkg, apk_id)
+VALUES (1, 'just.an.app.PrivateViewerActivity', NULL, false, true, 'just.an.app', 0);
+
+INSERT INTO activities (id, class_name, permission, exported, enabled, pkg, apk_id)
+VALUES (2, 'just.an.app.MainActivity', NULL, true, true, 'just.an.app', 0);
+
+INSERT INTO receivers (id, class_name, permission, exported, enabled, pkg, apk_id)
+VALUES (0, 'is.debuggable.BootReceiver', NULL, true, true, 'is.debuggable', 1);
+
+INSERT INTO services (id, class_name, permission, exported, enabled, pkg, apk_id)
+VALUES (0, 'is.debuggable.ShareService', NULL, true, true, 'is.debuggable', 1);
+
+-- BrowserActivity handles http and https links to example.com
+INSERT INTO intent_filters (id, activity_id, priority) VALUES (0, 0, 0);
+INSERT INTO intent_filter_actions (id, filter_id, name) VALUES (0, 0, 'android.intent.action.VIEW');
+INSERT INTO intent_filter_categories (id, filter_id, name) VALUES (0, 0, 'android.intent.category.DEFAULT');
+INSERT INTO intent_filter_categories (id, filter_id, name) VALUES (1, 0, 'android.intent.category.BROWSABLE');
+INSERT INTO intent_filter_data (id, filter_id, scheme, host) VALUES (0, 0, 'https', 'example.com');
+INSERT INTO intent_filter_data (id, filter_id, scheme) VALUES (1, 0, 'http');
+
+-- PrivateViewerActivity also handles https, but isn't exported
+INSERT INTO intent_filters (id, activity_id, priority) VALUES (1, 1, 0);
+INSERT INTO intent_filter_actions (id, filter_id, name) VALUES (1, 1, 'android.intent.action.VIEW');
+INSERT INTO intent_filter_categories (id, filter_id, name) VALUES (2, 1, 'android.intent.category.DEFAULT');
+INSERT INTO intent_filter_data (id, filter_id, scheme) VALUES (2, 1, 'https');
+
+INSERT INTO intent_filters (id, activity_id, priority) VALUES (2, 2, 0);
+INSERT INTO intent_filter_actions (id, filter_id, name) VALUES (2, 2, 'android.intent.action.MAIN');
+INSERT INTO intent_filter_categories (id, filter_id, name) VALUES (3, 2, 'android.intent.category.LAUNCHER');
+
+INSERT INTO intent_filters (id, receiver_id, priority) VALUES (3, 0, 100);
+INSERT INTO intent_filter_actions (id, filter_id, name) VALUES (3, 3, 'android.intent.action.BOOT_COMPLETED');
+
+INSERT INTO intent_filters (id, service_id, priority) VALUES (4, 0, 0);
+INSERT INTO intent_filter_actions (id, filter_id, name) VALUES (4, 4, 'android.intent.action.SEND');
+INSERT INTO intent_filter_data (id, filter_id, mime_type) VALUES (3, 4, 'text/plain');
@@ -0,0 +1,3 @@
+DELETE FROM apk_certificates WHERE id IN (0, 1, 2, 3);
+UPDATE apks SET shared_user_id = NULL WHERE id = 0;
+DELETE FROM apks WHERE id IN (2);
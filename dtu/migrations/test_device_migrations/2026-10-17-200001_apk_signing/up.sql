@@ -0,0 +1,18 @@
+INSERT INTO apks (id, app_name, name, is_debuggable, is_priv, device_path, shared_user_id)
+VALUES (2, 'android', 'framework-res.apk', false, true, '/system/framework/framework-res.apk', 'android.uid.system');
+
+UPDATE apks SET shared_user_id = 'android.uid.system' WHERE id = 0;
+
+-- The platform key
+INSERT INTO apk_certificates (id, apk_id, scheme, sha256, subject)
+VALUES (0, 2, 'v1', 'c8a2e9bccf597c2fb6dc66bee293fc13f2fc47ec77bc6b2b0d52c11f51192ab8', 'CN=Android, OU=Android, O=Android, L=Mountain View, ST=California, C=US');
+
+INSERT INTO apk_certificates (id, apk_id, scheme, sha256, subject)
+VALUES (1, 2, 'v2', 'c8a2e9bccf597c2fb6dc66bee293fc13f2fc47ec77bc6b2b0d52c11f51192ab8', 'CN=Android, OU=Android, O=Android, L=Mountain View, ST=California, C=US');
+
+INSERT INTO apk_certificates (id, apk_id, scheme, sha256, subject)
+VALUES (2, 0, 'v2', 'c8a2e9bccf597c2fb6dc66bee293fc13f2fc47ec77bc6b2b0d52c11f51192ab8', 'CN=Android, OU=Android, O=Android, L=Mountain View, ST=California, C=US');
+
+-- Some other key
+INSERT INTO apk_certificates (id, apk_id, scheme, sha256, subject)
+VALUES (3, 1, 'v2', '1111111111111111111111111111111111111111111111111111111111111111', 'CN=Debuggable');
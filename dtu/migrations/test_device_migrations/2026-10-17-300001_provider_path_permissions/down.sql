@@ -0,0 +1,2 @@
+DELETE FROM provider_grant_uri_permissions WHERE id IN (0);
+DELETE FROM provider_path_permissions WHERE id IN (0, 1);
@@ -0,0 +1,8 @@
+INSERT INTO provider_path_permissions (id, provider_id, path_prefix, read_permission, write_permission)
+VALUES (0, 1, '/public', NULL, 'exact.WRITE');
+
+INSERT INTO provider_path_permissions (id, provider_id, path, permission, read_permission, write_permission)
+VALUES (1, 1, '/secret', 'exact.SECRET', 'exact.SECRET', 'exact.SECRET');
+
+INSERT INTO provider_grant_uri_permissions (id, provider_id, path_pattern)
+VALUES (0, 2, '/shared/.*');
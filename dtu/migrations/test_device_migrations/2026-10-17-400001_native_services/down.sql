@@ -0,0 +1,3 @@
+DELETE FROM native_service_triggers WHERE id IN (0, 1);
+DELETE FROM native_service_sockets WHERE id IN (0, 1);
+DELETE FROM native_services WHERE id IN (0, 1, 2);
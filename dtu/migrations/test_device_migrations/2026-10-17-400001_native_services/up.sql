@@ -0,0 +1,20 @@
+INSERT INTO native_services (id, name, path, args, user, groups, capabilities, seclabel, classes, disabled, oneshot, rc_file)
+VALUES (0, 'vendor.rootd', '/vendor/bin/rootd', '--daemon', 'root', 'root system', NULL, 'u:r:vendor_rootd:s0', 'hal', 0, 0, '/vendor/etc/init/rootd.rc');
+
+INSERT INTO native_services (id, name, path, args, user, groups, capabilities, seclabel, classes, disabled, oneshot, rc_file)
+VALUES (1, 'netd', '/system/bin/netd', '', 'root', 'root net_admin', 'NET_ADMIN NET_RAW', NULL, 'main', 0, 0, '/system/etc/init/netd.rc');
+
+INSERT INTO native_services (id, name, path, args, user, groups, capabilities, seclabel, classes, disabled, oneshot, rc_file)
+VALUES (2, 'vendor.lazy', '/vendor/bin/lazyd', '', 'nobody', '', '', NULL, 'hal', 1, 1, '/vendor/etc/init/lazy.rc');
+
+INSERT INTO native_service_sockets (id, service_id, name, socket_type, perm, user, group_name, seclabel)
+VALUES (0, 0, 'rootd', 'stream', '0666', 'root', 'system', NULL);
+
+INSERT INTO native_service_sockets (id, service_id, name, socket_type, perm, user, group_name, seclabel)
+VALUES (1, 1, 'netd', 'stream', '0660', 'root', 'system', NULL);
+
+INSERT INTO native_service_triggers (id, service_id, on_trigger)
+VALUES (0, 0, 'late-init');
+
+INSERT INTO native_service_triggers (id, service_id, on_trigger)
+VALUES (1, 2, 'property:vendor.lazy.enable=1');
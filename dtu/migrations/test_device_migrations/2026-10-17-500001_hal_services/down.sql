@@ -0,0 +1 @@
+DELETE FROM hal_services WHERE id IN (0, 1, 2);
@@ -0,0 +1,8 @@
+INSERT INTO hal_services (id, interface, instance, kind, transport, server_pid, server_binary, selinux_type, reachable)
+VALUES (0, 'android.hardware.audio@6.0::IDevicesFactory', 'default', 'hidl', 'hwbinder', 512, '/vendor/bin/hw/android.hardware.audio.service', 'hal_audio_hwservice', -1);
+
+INSERT INTO hal_services (id, interface, instance, kind, transport, server_pid, server_binary, selinux_type, reachable)
+VALUES (1, 'android.hardware.power.IPower', 'default', 'aidl', 'binder', 600, '/vendor/bin/hw/android.hardware.power-service', 'hal_power_service', 1);
+
+INSERT INTO hal_services (id, interface, instance, kind, transport, server_pid, server_binary, selinux_type, reachable)
+VALUES (2, 'vendor.foo.IFoo', 'vendor.foo', 'aidl', 'vndbinder', NULL, NULL, NULL, 0);
@@ -0,0 +1 @@
+DELETE FROM fs_survey_entries WHERE id IN (0, 1, 2);
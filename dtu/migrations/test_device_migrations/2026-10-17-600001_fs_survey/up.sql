@@ -0,0 +1,8 @@
+INSERT INTO fs_survey_entries (id, path, mode, owner, group_name, selinux_label)
+VALUES (0, '/data/local/tmp', 16889, 'shell', 'shell', 'u:object_r:shell_data_file:s0');
+
+INSERT INTO fs_survey_entries (id, path, mode, owner, group_name, selinux_label)
+VALUES (1, '/vendor/etc/widevine.cfg', 33206, 'root', 'root', 'u:object_r:vendor_configs_file:s0');
+
+INSERT INTO fs_survey_entries (id, path, mode, owner, group_name, selinux_label)
+VALUES (2, '/system/bin/run-as', 35309, 'root', 'shell', NULL);
@@ -0,0 +1 @@
+DELETE FROM device_properties WHERE id IN (0, 1, 2, 3, 4);
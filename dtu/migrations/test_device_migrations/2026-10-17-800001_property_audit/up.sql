@@ -0,0 +1,14 @@
+INSERT INTO device_properties (id, name, value)
+VALUES (0, 'ro.debuggable', '1');
+
+INSERT INTO device_properties (id, name, value)
+VALUES (1, 'ro.secure', '1');
+
+INSERT INTO device_properties (id, name, value)
+VALUES (2, 'ro.build.tags', 'test-keys');
+
+INSERT INTO device_properties (id, name, value)
+VALUES (3, 'ro.build.version.security_patch', '2019-01-05');
+
+INSERT INTO device_properties (id, name, value)
+VALUES (4, 'ro.boot.verifiedbootstate', 'green');
@@ -0,0 +1,37 @@
+-- A small hierarchy for testing class hierarchy analysis:
+--
+--  Lcha/Iface;  <- implemented by Lcha/Base; <- extended by Lcha/Impl;
+--
+-- Lcha/Caller; only ever calls through Lcha/Iface; and Lcha/Base;
+INSERT INTO sources(id,name) VALUES
+    (6,'F');
+
+INSERT INTO classes(id,name,access_flags,source) VALUES
+    (200,'Lcha/Iface;',32770,6),
+    (201,'Lcha/Base;',2,6),
+    (202,'Lcha/Impl;',2,6),
+    (203,'Lcha/Caller;',2,6);
+
+INSERT INTO interfaces(interface,class,source) VALUES
+    (200,201,6);
+
+INSERT INTO supers(parent,child,source) VALUES
+    (201,202,6);
+
+INSERT INTO methods(id,class,name,args,ret,access_flags,source) VALUES
+    (1000,200,'run','I','V',2,6),
+    (1001,201,'run','I','V',2,6),
+    (1002,202,'run','I','V',2,6),
+    (1003,203,'viaIface','','V',2,6),
+    (1004,203,'viaBase','','V',2,6),
+    (1005,202,'helper','','V',2,6);
+
+INSERT INTO calls(caller,callee,source) VALUES
+    (1003,1000,6),
+    (1004,1001,6),
+    (1002,1005,6);
+
+INSERT INTO method_overrides(method,parent) VALUES
+    (1001,1000),
+    (1002,1000),
+    (1002,1001);
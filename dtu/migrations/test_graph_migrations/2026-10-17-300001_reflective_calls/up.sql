@@ -0,0 +1,45 @@
+-- Reflective lookups for testing the synthetic calls edges:
+--
+-- Lrefl/Caller;->callHidden looks up `refl.Target.hidden` by name
+-- Lrefl/Caller;->makeTarget looks up the constructor of `refl.Target`
+-- Lrefl/Caller;->noConstants uses reflection without any constant strings
+INSERT INTO sources(id,name) VALUES
+    (7,'R');
+
+INSERT INTO classes(id,name,access_flags,source) VALUES
+    (300,'Ljava/lang/Class;',2,1),
+    (301,'Ljava/lang/reflect/Method;',2,1),
+    (302,'Lrefl/Target;',2,7),
+    (303,'Lrefl/Caller;',2,7);
+
+INSERT INTO methods(id,class,name,args,ret,access_flags,source) VALUES
+    (1100,300,'forName','Ljava/lang/String;','Ljava/lang/Class;',10,1),
+    (1101,300,'getDeclaredMethod','Ljava/lang/String;[Ljava/lang/Class;','Ljava/lang/reflect/Method;',2,1),
+    (1102,300,'getDeclaredConstructor','[Ljava/lang/Class;','Ljava/lang/reflect/Constructor;',2,1),
+    (1103,301,'invoke','Ljava/lang/Object;[Ljava/lang/Object;','Ljava/lang/Object;',2,1),
+    (1104,302,'hidden','','V',2,7),
+    (1105,302,'hidden','I','V',2,7),
+    (1106,302,'other','','V',2,7),
+    (1107,302,'<init>','','V',65538,7),
+    (1108,303,'callHidden','','V',2,7),
+    (1109,303,'makeTarget','','V',2,7),
+    (1110,303,'noConstants','Ljava/lang/String;','V',2,7);
+
+INSERT INTO calls(caller,callee,source) VALUES
+    (1108,1100,7),
+    (1108,1101,7),
+    (1108,1103,7),
+    (1109,1100,7),
+    (1109,1102,7),
+    (1110,1100,7),
+    (1110,1101,7),
+    (1110,1103,7);
+
+INSERT INTO strings(id,string,source) VALUES
+    (100,'refl.Target',7),
+    (101,'hidden',7);
+
+INSERT INTO method_strings(string,method) VALUES
+    (100,1108),
+    (101,1108),
+    (100,1109);
@@ -0,0 +1,21 @@
+-- A binder call for testing IPC aware traversal:
+--
+-- Lbinder/App;->onCreate calls the proxy, which is linked to the service
+-- implementation, which calls a helper
+INSERT INTO classes(id,name,access_flags,source) VALUES
+    (310,'Lbinder/IFoo$Stub$Proxy;',2,6),
+    (311,'Lbinder/FooService;',2,6),
+    (312,'Lbinder/App;',2,6);
+
+INSERT INTO methods(id,class,name,args,ret,access_flags,source) VALUES
+    (1200,310,'doThing','I','V',2,6),
+    (1201,311,'doThing','I','V',2,6),
+    (1202,311,'helper','','V',2,6),
+    (1203,312,'onCreate','','V',2,6);
+
+INSERT INTO calls(caller,callee,source) VALUES
+    (1203,1200,6),
+    (1201,1202,6);
+
+INSERT INTO binder_calls(proxy,service_impl,transaction_id) VALUES
+    (1200,1201,1);
@@ -135,6 +135,7 @@ class Server : Service() {
                 val rawCmd = pack(into[0], into[1], into[2], into[3])
                 val cmd = getCommand(rawCmd)
                 readRawRequest().mapCatching {
+                    logCommand(cmd, it)
                     Request.forCommand(cmd, it).run(context)
                 }.fold({ json ->
                     respondSuccess(json)
@@ -149,6 +150,16 @@ class Server : Service() {
             }
         }
 
+        /**
+         * Log every command before running it so crashes found in logcat can
+         * be tied to the command that caused them. The prefix is matched by
+         * `fuzz triage`.
+         */
+        private fun logCommand(cmd: Command, raw: ByteArray) {
+            val payload = String(raw, StandardCharsets.UTF_8)
+            alogi("server command: ${cmd.name} ${payload.take(MAX_LOGGED_PAYLOAD)}")
+        }
+
         private fun respond(status: Int, data: String) {
             val bytes = byteArrayOf(
                 status.shr(24).and(0xFF).toByte(),
@@ -230,6 +241,8 @@ class Server : Service() {
     }
 
     companion object {
+        private const val MAX_LOGGED_PAYLOAD = 1024
+
         fun getCommand(code: Int): Command {
             return Command.values().find {
                 it.code == code
@@ -765,18 +778,21 @@ sealed class Request {
 
         override fun run(ctx: Context): String {
             val bind = binderForSystemService(name) ?: throw NoSystemServiceBinderException(name)
-            val res = doRawBinder(bind, txnId, interfaceToken, {
+            // The exception header is left in the reply so the client can decode
+            // it, including any SecurityException message
+            val (res, reply) = doRawBinder(bind, txnId, interfaceToken, {
                 parcelData?.writeToParcel(it, 0)
             }) {
+                val raw = it.marshall()
                 if (txnId != IBinder.INTERFACE_TRANSACTION) {
-                    it.readException()
-                    it.marshall().toHexDump()
+                    Pair(raw.toHexDump(), raw)
                 } else {
-                    it.readString()
+                    Pair(it.readString(), raw)
                 }
             }
             return JSONObject().apply {
                 put("response", res)
+                put("reply", reply.toBase64())
             }.toString()
         }
 
@@ -838,19 +854,22 @@ sealed class Request {
             }
             val service = binder ?: throw RequestException("null binder")
 
-            val res = doRawBinder(service, txnId, interfaceToken, { data ->
+            // The exception header is left in the reply so the client can decode
+            // it, including any SecurityException message
+            val (res, reply) = doRawBinder(service, txnId, interfaceToken, { data ->
                 parcelData?.writeToParcel(data, 0)
             }) {
+                val raw = it.marshall()
                 if (txnId != IBinder.INTERFACE_TRANSACTION) {
-                    it.readException()
-                    it.marshall().toHexDump()
+                    Pair(raw.toHexDump(), raw)
                 } else {
-                    it.readString()
+                    Pair(it.readString(), raw)
                 }
             }
 
             return JSONObject().apply {
                 put("response", res)
+                put("reply", reply.toBase64())
             }.toString()
         }
 
@@ -0,0 +1,427 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use super::parcel_string::{ParcelString, ParcelStringElem};
+
+pub type MethodArgResult<T> = std::result::Result<T, MethodArgError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MethodArgError {
+    #[error("invalid method signature `{0}`")]
+    InvalidSignature(String),
+    #[error("method takes {expected} arguments ({signature}) but {got} were given")]
+    ArgCount {
+        expected: usize,
+        got: usize,
+        signature: String,
+    },
+    #[error("argument {index} ({ty}): {msg}")]
+    InvalidArg {
+        index: usize,
+        ty: String,
+        msg: String,
+    },
+}
+
+/// A parameter type from a smali method signature, as far as writing it to
+/// a Parcel is concerned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Boolean,
+    Byte,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    CharSequence,
+    /// An `IBinder` or AIDL interface
+    Binder(String),
+    Bundle,
+    ParcelFileDescriptor,
+    ComponentName,
+    /// A `List` or `Map`, only null can be written
+    Collection(String),
+    /// An array of the given element type
+    Array(Box<ParamType>),
+    /// Any other object, assumed to be a Parcelable that can only be null
+    Object(String),
+}
+
+impl ParamType {
+    /// Split the arguments of a smali method signature, such as
+    /// `ILjava/lang/String;[B`, into their types
+    pub fn parse_signature(sig: &str) -> MethodArgResult<Vec<ParamType>> {
+        let mut types = Vec::new();
+        let mut rest = sig.trim_start_matches('(');
+        if let Some((args, _)) = rest.split_once(')') {
+            rest = args;
+        }
+        while !rest.is_empty() {
+            let len =
+                smali_type_len(rest).ok_or_else(|| MethodArgError::InvalidSignature(sig.into()))?;
+            let (ty, tail) = rest.split_at(len);
+            types.push(Self::from_smali(ty));
+            rest = tail;
+        }
+        Ok(types)
+    }
+
+    /// Get the type for a single smali type descriptor
+    pub fn from_smali(ty: &str) -> Self {
+        match ty {
+            "Z" => Self::Boolean,
+            "B" => Self::Byte,
+            "C" => Self::Char,
+            "S" => Self::Short,
+            "I" => Self::Int,
+            "J" => Self::Long,
+            "F" => Self::Float,
+            "D" => Self::Double,
+            "Ljava/lang/String;" => Self::String,
+            "Ljava/lang/CharSequence;" => Self::CharSequence,
+            "Landroid/os/IBinder;" => Self::Binder(ty.into()),
+            "Landroid/os/Bundle;" => Self::Bundle,
+            "Landroid/os/ParcelFileDescriptor;" => Self::ParcelFileDescriptor,
+            "Landroid/content/ComponentName;" => Self::ComponentName,
+            "Ljava/util/List;" | "Ljava/util/ArrayList;" | "Ljava/util/Map;" => {
+                Self::Collection(ty.into())
+            }
+            _ => match ty.strip_prefix('[') {
+                Some(elem) => Self::Array(Box::new(Self::from_smali(elem))),
+                None if is_aidl_interface(ty) => Self::Binder(ty.into()),
+                None => Self::Object(ty.into()),
+            },
+        }
+    }
+
+    /// Convert a command line argument for this type into the values that
+    /// have to be written to the Parcel
+    ///
+    /// Objects are written the way AIDL writes them: `null` is accepted for
+    /// any nullable type and Parcelables are preceded by the non-null flag.
+    pub fn to_elems<'a>(&self, arg: &'a str) -> Result<Vec<ParcelStringElem<'a>>, String> {
+        let is_null = arg == "null";
+        let elem = match self {
+            Self::Boolean => ParcelStringElem::Bool(parse_bool(arg)?),
+            // Parcel.writeByte writes an int anyway
+            Self::Byte => ParcelStringElem::Int(i32::from(parse_num::<i8>(arg)?)),
+            Self::Char => ParcelStringElem::Int(parse_char(arg)?),
+            Self::Short => ParcelStringElem::Short(parse_num(arg)?),
+            Self::Int => ParcelStringElem::Int(parse_num(arg)?),
+            Self::Long => ParcelStringElem::Long(parse_num(arg)?),
+            Self::Float => ParcelStringElem::Float(parse_num(arg)?),
+            Self::Double => ParcelStringElem::Double(parse_num(arg)?),
+            Self::String if is_null => ParcelStringElem::Null,
+            Self::String => ParcelStringElem::String(Cow::Borrowed(arg)),
+            Self::Binder(_) if is_null => {
+                return Err(String::from("null binders aren't supported"))
+            }
+            Self::Binder(_) => ParcelStringElem::Binder,
+            _ if is_null => return Ok(vec![self.null_elem()]),
+            // The non-null flag and then TextUtils.writeToParcel, where 1 is a
+            // plain String
+            Self::CharSequence => {
+                return Ok(vec![
+                    ParcelStringElem::Int(1),
+                    ParcelStringElem::Int(1),
+                    ParcelStringElem::String(Cow::Borrowed(arg)),
+                ])
+            }
+            Self::Bundle => return Ok(vec![ParcelStringElem::Int(1), parse_bundle(arg)?]),
+            Self::ParcelFileDescriptor => {
+                let fd = match arg.strip_prefix("rw:") {
+                    Some(path) => ParcelStringElem::WriteFd(Cow::Borrowed(path)),
+                    None => ParcelStringElem::ReadFd(Cow::Borrowed(arg)),
+                };
+                return Ok(vec![ParcelStringElem::Int(1), fd]);
+            }
+            Self::ComponentName => {
+                let Some((pkg, cls)) = arg.split_once('/') else {
+                    return Err(String::from("expected package/class"));
+                };
+                let cls = match cls.strip_prefix('.') {
+                    Some(_) => Cow::Owned(format!("{}{}", pkg, cls)),
+                    None => Cow::Borrowed(cls),
+                };
+                return Ok(vec![
+                    ParcelStringElem::Int(1),
+                    ParcelStringElem::String(Cow::Borrowed(pkg)),
+                    ParcelStringElem::String(cls),
+                ]);
+            }
+            Self::Array(elem) => return array_elems(elem, arg),
+            Self::Collection(_) | Self::Object(_) => {
+                return Err(String::from(
+                    "only null is supported, use -r/--raw to write it by hand",
+                ))
+            }
+        };
+        Ok(vec![elem])
+    }
+
+    /// What AIDL writes for a null value of this type
+    fn null_elem(&self) -> ParcelStringElem<'static> {
+        match self {
+            // Length prefixed values use -1, which is also what a null
+            // String looks like
+            Self::Array(_) | Self::Collection(_) => ParcelStringElem::Null,
+            // Everything else is a Parcelable behind the non-null flag
+            _ => ParcelStringElem::Int(0),
+        }
+    }
+}
+
+impl Display for ParamType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boolean => write!(f, "boolean"),
+            Self::Byte => write!(f, "byte"),
+            Self::Char => write!(f, "char"),
+            Self::Short => write!(f, "short"),
+            Self::Int => write!(f, "int"),
+            Self::Long => write!(f, "long"),
+            Self::Float => write!(f, "float"),
+            Self::Double => write!(f, "double"),
+            Self::String => write!(f, "String"),
+            Self::CharSequence => write!(f, "CharSequence"),
+            Self::Bundle => write!(f, "Bundle"),
+            Self::ParcelFileDescriptor => write!(f, "ParcelFileDescriptor"),
+            Self::ComponentName => write!(f, "ComponentName"),
+            Self::Array(elem) => write!(f, "{}[]", elem),
+            Self::Binder(ty) | Self::Collection(ty) | Self::Object(ty) => {
+                write!(f, "{}", smali_to_java(ty))
+            }
+        }
+    }
+}
+
+/// Build the Parcel for a call to a method with the given smali signature
+///
+/// The arguments are checked against the signature before anything is sent,
+/// see [ParamType::to_elems] for how each one is converted.
+pub fn method_parcel_string<'a, S: AsRef<str>>(
+    signature: &str,
+    args: &'a [S],
+) -> MethodArgResult<ParcelString<'a>> {
+    let types = ParamType::parse_signature(signature)?;
+    if types.len() != args.len() {
+        return Err(MethodArgError::ArgCount {
+            expected: types.len(),
+            got: args.len(),
+            signature: types
+                .iter()
+                .map(|it| it.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        });
+    }
+    let mut parcel = ParcelString::default();
+    for (index, (ty, arg)) in types.iter().zip(args).enumerate() {
+        let elems = ty
+            .to_elems(arg.as_ref())
+            .map_err(|msg| MethodArgError::InvalidArg {
+                index,
+                ty: ty.to_string(),
+                msg,
+            })?;
+        for e in elems {
+            parcel.push(e);
+        }
+    }
+    Ok(parcel)
+}
+
+/// Length of the first smali type in the string
+fn smali_type_len(s: &str) -> Option<usize> {
+    let dims = s.bytes().take_while(|it| *it == b'[').count();
+    let len = match s.as_bytes().get(dims)? {
+        b'Z' | b'B' | b'C' | b'S' | b'I' | b'J' | b'F' | b'D' => 1,
+        b'L' => s[dims..].find(';')? + 1,
+        _ => return None,
+    };
+    Some(dims + len)
+}
+
+/// AIDL interfaces are conventionally named `IName`
+fn is_aidl_interface(ty: &str) -> bool {
+    let Some(name) = ty.strip_prefix('L').and_then(|it| it.strip_suffix(';')) else {
+        return false;
+    };
+    let simple = name.rsplit(['/', '$']).next().unwrap_or(name);
+    let mut chars = simple.chars();
+    chars.next() == Some('I') && chars.next().is_some_and(|it| it.is_ascii_uppercase())
+}
+
+pub(super) fn smali_to_java(ty: &str) -> String {
+    ty.strip_prefix('L')
+        .and_then(|it| it.strip_suffix(';'))
+        .unwrap_or(ty)
+        .replace('/', ".")
+}
+
+fn parse_num<T>(arg: &str) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    arg.parse::<T>()
+        .map_err(|e| format!("invalid value `{}`: {}", arg, e))
+}
+
+fn parse_bool(arg: &str) -> Result<bool, String> {
+    match arg {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(format!("invalid boolean `{}`", arg)),
+    }
+}
+
+fn parse_char(arg: &str) -> Result<i32, String> {
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c as i32),
+        _ => parse_num::<i32>(arg),
+    }
+}
+
+/// Bundles are given as `key=value` pairs separated by commas, all values are
+/// strings. An empty argument is an empty Bundle.
+fn parse_bundle(arg: &str) -> Result<ParcelStringElem<'_>, String> {
+    let mut map = HashMap::new();
+    for kv in arg.split(',').filter(|it| !it.is_empty()) {
+        let Some((key, value)) = kv.split_once('=') else {
+            return Err(format!("expected key=value in bundle, got `{}`", kv));
+        };
+        map.insert(
+            String::from(key),
+            ParcelStringElem::String(Cow::Borrowed(value)),
+        );
+    }
+    Ok(ParcelStringElem::Bundle(map))
+}
+
+/// Arrays are given as comma separated values, byte arrays as hex
+fn array_elems<'a>(elem: &ParamType, arg: &'a str) -> Result<Vec<ParcelStringElem<'a>>, String> {
+    if *elem == ParamType::Byte {
+        if arg.len() % 2 != 0 || !arg.bytes().all(|it| it.is_ascii_hexdigit()) {
+            return Err(format!("invalid hex string `{}`", arg));
+        }
+        return Ok(vec![ParcelStringElem::HexByteArray(Cow::Borrowed(arg))]);
+    }
+    match elem {
+        ParamType::Boolean
+        | ParamType::Char
+        | ParamType::Int
+        | ParamType::Long
+        | ParamType::Float
+        | ParamType::Double
+        | ParamType::String => {}
+        _ => {
+            return Err(String::from(
+                "only null is supported, use -r/--raw to write it by hand",
+            ))
+        }
+    }
+    let values = if arg.is_empty() {
+        Vec::new()
+    } else {
+        arg.split(',').collect::<Vec<&str>>()
+    };
+    let mut elems = vec![ParcelStringElem::Int(values.len() as i32)];
+    for it in values {
+        elems.extend(elem.to_elems(it)?);
+    }
+    Ok(elems)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_signature() {
+        let types = ParamType::parse_signature(
+            "IZLjava/lang/String;[BLandroid/os/Bundle;Landroid/app/IActivityController;Landroid/content/Intent;[[J",
+        )
+        .unwrap();
+        assert_eq!(
+            types,
+            vec![
+                ParamType::Int,
+                ParamType::Boolean,
+                ParamType::String,
+                ParamType::Array(Box::new(ParamType::Byte)),
+                ParamType::Bundle,
+                ParamType::Binder("Landroid/app/IActivityController;".into()),
+                ParamType::Object("Landroid/content/Intent;".into()),
+                ParamType::Array(Box::new(ParamType::Array(Box::new(ParamType::Long)))),
+            ]
+        );
+        assert!(ParamType::parse_signature("").unwrap().is_empty());
+        assert!(ParamType::parse_signature("Ljava/lang/String").is_err());
+        assert!(ParamType::parse_signature("Q").is_err());
+    }
+
+    #[test]
+    fn test_method_parcel_string() {
+        let args = [
+            "10",
+            "true",
+            "hello",
+            "null",
+            "k=v",
+            "null",
+            "com.android.settings/.Settings",
+            "1,2",
+        ];
+        let parcel = method_parcel_string(
+            "IZLjava/lang/String;Ljava/lang/String;Landroid/os/Bundle;Landroid/content/Intent;Landroid/content/ComponentName;[I",
+            &args,
+        )
+        .unwrap();
+        assert_eq!(
+            parcel.build(),
+            "_INT10,BOOLtrue,_STRhello,NULL,_INT1,BUNDk=_STRv,_INT0,_INT1,_STRcom.android.settings,_STRcom.android.settings.Settings,_INT2,_INT1,_INT2"
+        );
+    }
+
+    #[test]
+    fn test_method_parcel_string_errors() {
+        assert!(matches!(
+            method_parcel_string("IJ", &["1"]),
+            Err(MethodArgError::ArgCount {
+                expected: 2,
+                got: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            method_parcel_string("IJ", &["1", "nope"]),
+            Err(MethodArgError::InvalidArg { index: 1, .. })
+        ));
+        assert!(method_parcel_string("Landroid/content/Intent;", &["foo"]).is_err());
+        assert!(method_parcel_string("Landroid/os/IBinder;", &["null"]).is_err());
+        assert!(method_parcel_string("[B", &["abc"]).is_err());
+    }
+
+    fn elems_string(ty: &ParamType, arg: &str) -> String {
+        let mut parcel = ParcelString::default();
+        for e in ty.to_elems(arg).unwrap() {
+            parcel.push(e);
+        }
+        parcel.build()
+    }
+
+    #[test]
+    fn test_to_elems_char_sequence() {
+        assert_eq!(
+            elems_string(&ParamType::CharSequence, "hello"),
+            "_INT1,_INT1,_STRhello"
+        );
+        assert_eq!(elems_string(&ParamType::CharSequence, "null"), "_INT0");
+    }
+}
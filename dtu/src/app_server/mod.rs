@@ -1,8 +1,17 @@
 pub mod intent_string;
 pub use intent_string::*;
 
+pub mod method_args;
+pub use method_args::*;
+
 pub mod parcel_string;
 pub use parcel_string::*;
 
+pub mod reply;
+pub use reply::*;
+
+pub mod session;
+pub use session::*;
+
 pub mod server;
 pub use server::*;
@@ -102,6 +102,9 @@ impl<'a> ParcelString<'a> {
     }
 
     fn build_to(&self, into: &mut String) {
+        if self.elems.is_empty() {
+            return;
+        }
         let last = self.elems.len() - 1;
         for (i, e) in self.elems.iter().enumerate() {
             let as_str = e.to_string();
@@ -194,6 +197,15 @@ pub(crate) fn escape_string(s: &str) -> String {
         .replace("%", "\\%")
 }
 
+/// A Bundle inside of a Bundle needs its separators escaped to survive the
+/// outer Bundle being split, this only works one level deep
+fn escape_bundle_value(value: &ParcelStringElem) -> String {
+    match value {
+        ParcelStringElem::Bundle(_) => value.to_string().replace('=', "\\=").replace(':', "\\:"),
+        _ => value.to_string(),
+    }
+}
+
 impl<'a> Eq for ParcelStringElem<'a> {}
 
 impl<'a> PartialEq for ParcelStringElem<'a> {
@@ -245,7 +257,7 @@ impl<'a> Display for ParcelStringElem<'a> {
                 if v.len() > 0 {
                     let last = v.len() - 1;
                     for (i, (key, value)) in v.iter().enumerate() {
-                        write!(f, "{}={}", escape_string(key), value)?;
+                        write!(f, "{}={}", escape_string(key), escape_bundle_value(value))?;
                         if i < last {
                             write!(f, ":")?;
                         }
@@ -329,4 +341,16 @@ mod test {
         simple_test!(write_bool, true, "BOOLtrue");
         simple_test!(write_bool, false, "BOOLfalse");
     }
+
+    #[test]
+    fn test_nested_bundle() {
+        let mut inner = HashMap::new();
+        inner.insert(String::from("b"), ParcelStringElem::Int(1));
+        let mut outer = HashMap::new();
+        outer.insert(String::from("a"), ParcelStringElem::Bundle(inner));
+        let mut builder = ParcelString::default();
+        builder.write_bundle(outer);
+        assert_eq!(builder.build(), "BUNDa=BUNDb\\=_INT1");
+        assert_eq!(ParcelString::default().build(), "");
+    }
 }
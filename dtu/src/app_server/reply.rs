@@ -0,0 +1,694 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::method_args::{smali_to_java, ParamType};
+use crate::utils::bytes_to_hex;
+
+pub type ReplyResult<T> = std::result::Result<T, ReplyError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplyError {
+    #[error("reply truncated reading {wanted} bytes at offset {offset}")]
+    Truncated { offset: usize, wanted: usize },
+    #[error("invalid reply at offset {offset}: {msg}")]
+    Invalid { offset: usize, msg: String },
+}
+
+// Exception codes from android.os.Parcel
+const EX_SECURITY: i32 = -1;
+const EX_BAD_PARCELABLE: i32 = -2;
+const EX_ILLEGAL_ARGUMENT: i32 = -3;
+const EX_NULL_POINTER: i32 = -4;
+const EX_ILLEGAL_STATE: i32 = -5;
+const EX_NETWORK_MAIN_THREAD: i32 = -6;
+const EX_UNSUPPORTED_OPERATION: i32 = -7;
+const EX_SERVICE_SPECIFIC: i32 = -8;
+const EX_PARCELABLE: i32 = -9;
+const EX_HAS_NOTED_APPOPS_REPLY_HEADER: i32 = -127;
+const EX_HAS_STRICTMODE_REPLY_HEADER: i32 = -128;
+
+// Value types from Parcel.writeValue
+const VAL_NULL: i32 = -1;
+const VAL_STRING: i32 = 0;
+const VAL_INTEGER: i32 = 1;
+const VAL_BUNDLE: i32 = 3;
+const VAL_SHORT: i32 = 5;
+const VAL_LONG: i32 = 6;
+const VAL_FLOAT: i32 = 7;
+const VAL_DOUBLE: i32 = 8;
+const VAL_BOOLEAN: i32 = 9;
+const VAL_CHARSEQUENCE: i32 = 10;
+const VAL_BYTEARRAY: i32 = 13;
+const VAL_STRINGARRAY: i32 = 14;
+const VAL_INTARRAY: i32 = 18;
+const VAL_LONGARRAY: i32 = 19;
+const VAL_BYTE: i32 = 20;
+const VAL_BOOLEANARRAY: i32 = 23;
+const VAL_PERSISTABLEBUNDLE: i32 = 25;
+const VAL_DOUBLEARRAY: i32 = 28;
+const VAL_CHAR: i32 = 29;
+const VAL_FLOATARRAY: i32 = 32;
+
+// 'B' 'N' 'D' 'L' and 'B' 'N' 'D' 'N' for Bundles written by native code
+const BUNDLE_MAGIC: i32 = 0x4C444E42;
+const BUNDLE_MAGIC_NATIVE: i32 = 0x4C444E44;
+
+/// How deeply Bundles can be nested in a reply before giving up on them, the
+/// reply comes from the device so it can't be trusted to be reasonable
+const MAX_BUNDLE_DEPTH: usize = 32;
+
+/// A decoded binder reply
+#[derive(Debug, Default, Serialize)]
+pub struct DecodedReply {
+    /// The exception thrown by the call, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exception: Option<ReplyException>,
+    /// The return value, if the return type was known and could be decoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// Why the return value couldn't be decoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Hex of the bytes that weren't decoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<String>,
+}
+
+/// An exception written to a reply with `Parcel.writeException`
+#[derive(Debug, Serialize)]
+pub struct ReplyException {
+    pub code: i32,
+    pub name: &'static str,
+    pub message: Option<String>,
+    /// The error code of a `ServiceSpecificException`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_error: Option<i32>,
+}
+
+impl ReplyException {
+    pub fn is_security_exception(&self) -> bool {
+        self.code == EX_SECURITY
+    }
+}
+
+/// Decode a raw reply Parcel
+///
+/// The exception header is always read. The rest of the reply is decoded
+/// according to `return_type`, a smali type such as `Ljava/lang/String;`,
+/// the way AIDL would have written it. Anything that can't be decoded is left
+/// in [DecodedReply::remaining].
+pub fn decode_reply(reply: &[u8], return_type: Option<&str>) -> ReplyResult<DecodedReply> {
+    let mut decoded = DecodedReply::default();
+    if reply.is_empty() {
+        return Ok(decoded);
+    }
+    let mut reader = ParcelReader::new(reply);
+    decoded.exception = read_exception(&mut reader)?;
+
+    if decoded.exception.is_none() {
+        if let Some(ty) = return_type.filter(|it| *it != "V") {
+            let start = reader.position();
+            match read_typed(&mut reader, &ParamType::from_smali(ty)) {
+                Ok(v) => decoded.value = Some(v),
+                Err(e) => {
+                    decoded.error = Some(e.to_string());
+                    reader.set_position(start);
+                }
+            }
+        }
+    }
+
+    if !reader.is_empty() {
+        decoded.remaining = Some(bytes_to_hex(reader.remaining()));
+    }
+    Ok(decoded)
+}
+
+/// Reads values the way `android.os.Parcel` writes them
+pub struct ParcelReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// How many Bundles are currently being read
+    bundle_depth: usize,
+}
+
+impl<'a> ParcelReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bundle_depth: 0,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: usize) {
+        self.pos = pos.min(self.data.len());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    /// Read `n` bytes, the position is then aligned to 4 bytes like Parcel
+    /// does for everything it writes
+    pub fn read_bytes(&mut self, n: usize) -> ReplyResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|it| *it <= self.data.len())
+            .ok_or(ReplyError::Truncated {
+                offset: self.pos,
+                wanted: n,
+            })?;
+        let bytes = &self.data[self.pos..end];
+        self.set_position((end + 3) & !3);
+        Ok(bytes)
+    }
+
+    pub fn read_i32(&mut self) -> ReplyResult<i32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.read_bytes(4)?);
+        Ok(i32::from_le_bytes(buf))
+    }
+
+    pub fn read_i64(&mut self) -> ReplyResult<i64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.read_bytes(8)?);
+        Ok(i64::from_le_bytes(buf))
+    }
+
+    pub fn read_f32(&mut self) -> ReplyResult<f32> {
+        Ok(f32::from_bits(self.read_i32()? as u32))
+    }
+
+    pub fn read_f64(&mut self) -> ReplyResult<f64> {
+        Ok(f64::from_bits(self.read_i64()? as u64))
+    }
+
+    /// Read a String written with `Parcel.writeString`
+    pub fn read_string(&mut self) -> ReplyResult<Option<String>> {
+        let Some(len) = self.read_len()? else {
+            return Ok(None);
+        };
+        let offset = self.pos;
+        // UTF-16 with a null terminator
+        let bytes = self.read_bytes((len + 1) * 2)?;
+        let units = bytes[..len * 2]
+            .chunks_exact(2)
+            .map(|it| u16::from_le_bytes([it[0], it[1]]))
+            .collect::<Vec<u16>>();
+        String::from_utf16(&units)
+            .map(Some)
+            .map_err(|_| ReplyError::Invalid {
+                offset,
+                msg: String::from("invalid UTF-16 string"),
+            })
+    }
+
+    /// Read a String written with `Parcel.writeString8`
+    pub fn read_string8(&mut self) -> ReplyResult<Option<String>> {
+        let Some(len) = self.read_len()? else {
+            return Ok(None);
+        };
+        let offset = self.pos;
+        let bytes = self.read_bytes(len + 1)?;
+        String::from_utf8(bytes[..len].to_vec())
+            .map(Some)
+            .map_err(|_| ReplyError::Invalid {
+                offset,
+                msg: String::from("invalid UTF-8 string"),
+            })
+    }
+
+    /// Read a byte array written with `Parcel.writeByteArray`
+    pub fn read_byte_array(&mut self) -> ReplyResult<Option<&'a [u8]>> {
+        match self.read_len()? {
+            Some(len) => self.read_bytes(len).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the length prefix of a string or array, a negative length is null
+    fn read_len(&mut self) -> ReplyResult<Option<usize>> {
+        let len = self.read_i32()?;
+        Ok(usize::try_from(len).ok())
+    }
+
+    /// Read the flag AIDL writes before nullable Parcelables
+    fn read_non_null(&mut self) -> ReplyResult<bool> {
+        Ok(self.read_i32()? != 0)
+    }
+
+    /// Whether the String at the current position looks like it was written
+    /// with `writeString8` rather than `writeString`
+    fn is_string8(&self) -> bool {
+        let rest = self.remaining();
+        let Some(len) = rest
+            .get(..4)
+            .and_then(|it| usize::try_from(i32::from_le_bytes([it[0], it[1], it[2], it[3]])).ok())
+        else {
+            return false;
+        };
+        // UTF-16 ASCII is full of zeros, UTF-8 never has them before the
+        // terminator
+        match rest.get(4..4 + len + 1) {
+            Some(bytes) => bytes[len] == 0 && !bytes[..len].contains(&0),
+            None => false,
+        }
+    }
+
+    fn invalid<S: Into<String>>(&self, msg: S) -> ReplyError {
+        ReplyError::Invalid {
+            offset: self.pos,
+            msg: msg.into(),
+        }
+    }
+}
+
+fn exception_name(code: i32) -> &'static str {
+    match code {
+        EX_SECURITY => "SecurityException",
+        EX_BAD_PARCELABLE => "BadParcelableException",
+        EX_ILLEGAL_ARGUMENT => "IllegalArgumentException",
+        EX_NULL_POINTER => "NullPointerException",
+        EX_ILLEGAL_STATE => "IllegalStateException",
+        EX_NETWORK_MAIN_THREAD => "NetworkOnMainThreadException",
+        EX_UNSUPPORTED_OPERATION => "UnsupportedOperationException",
+        EX_SERVICE_SPECIFIC => "ServiceSpecificException",
+        EX_PARCELABLE => "ParcelableException",
+        _ => "UnknownException",
+    }
+}
+
+/// Read the header `Parcel.writeNoException` or `Parcel.writeException`
+/// starts every AIDL reply with
+fn read_exception(reader: &mut ParcelReader) -> ReplyResult<Option<ReplyException>> {
+    let mut code = reader.read_i32()?;
+    if code == EX_HAS_NOTED_APPOPS_REPLY_HEADER {
+        // Attribution tags with a bitset of the noted ops
+        let count = reader.read_i32()?;
+        for _ in 0..count {
+            reader.read_string()?;
+            reader.read_i64()?;
+            reader.read_i64()?;
+        }
+        code = reader.read_i32()?;
+    }
+    if code == EX_HAS_STRICTMODE_REPLY_HEADER {
+        // The size includes itself and the header is only written when there
+        // is no exception
+        let start = reader.position();
+        let size = reader.read_i32()?;
+        reader.set_position(start + usize::try_from(size).unwrap_or(0).max(4));
+        return Ok(None);
+    }
+    if code == 0 {
+        return Ok(None);
+    }
+
+    let message = reader.read_string()?;
+    if reader.read_i32()? > 0 {
+        // Remote stack trace
+        reader.read_string()?;
+    }
+    let service_error = if code == EX_SERVICE_SPECIFIC {
+        Some(reader.read_i32()?)
+    } else {
+        None
+    };
+    Ok(Some(ReplyException {
+        code,
+        name: exception_name(code),
+        message,
+        service_error,
+    }))
+}
+
+/// Read a value of the given type as AIDL writes it
+fn read_typed(reader: &mut ParcelReader, ty: &ParamType) -> ReplyResult<Value> {
+    Ok(match ty {
+        ParamType::Boolean => Value::Bool(reader.read_i32()? != 0),
+        ParamType::Byte => json!(reader.read_i32()? as i8),
+        ParamType::Char => read_char(reader)?,
+        ParamType::Short => json!(reader.read_i32()? as i16),
+        ParamType::Int => json!(reader.read_i32()?),
+        ParamType::Long => json!(reader.read_i64()?),
+        ParamType::Float => json!(reader.read_f32()?),
+        ParamType::Double => json!(reader.read_f64()?),
+        ParamType::String => json!(reader.read_string()?),
+        // AIDL writes a non-null flag before these
+        ParamType::CharSequence | ParamType::Bundle | ParamType::ComponentName
+            if !reader.read_non_null()? =>
+        {
+            Value::Null
+        }
+        ParamType::CharSequence => read_char_sequence(reader)?,
+        ParamType::Bundle => read_bundle(reader)?,
+        ParamType::ComponentName => {
+            let pkg = reader.read_string()?.unwrap_or_default();
+            let cls = reader.read_string()?.unwrap_or_default();
+            Value::String(format!("{}/{}", pkg, cls))
+        }
+        ParamType::Array(elem) => read_array(reader, elem)?,
+        ParamType::Collection(ty) if ty == "Ljava/util/Map;" => read_map(reader)?,
+        ParamType::Collection(_) => read_list(reader)?,
+        ParamType::Object(ty) => read_parcelable(reader, ty)?,
+        ParamType::Binder(_) | ParamType::ParcelFileDescriptor => {
+            return Err(reader.invalid(format!("can't decode a {}", ty)))
+        }
+    })
+}
+
+fn read_char(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    let raw = reader.read_i32()?;
+    Ok(match char::from_u32(raw as u32) {
+        Some(c) => Value::String(c.to_string()),
+        None => json!(raw),
+    })
+}
+
+/// Read a CharSequence without spans written with `TextUtils.writeToParcel`
+fn read_char_sequence(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    let kind = reader.read_i32()?;
+    let value = json!(reader.read_string()?);
+    if kind != 1 {
+        // Spans follow the text until a 0 kind, there isn't any way to skip
+        // them without parsing each one
+        return Err(reader.invalid("CharSequences with spans aren't supported"));
+    }
+    Ok(value)
+}
+
+fn read_array(reader: &mut ParcelReader, elem: &ParamType) -> ReplyResult<Value> {
+    if *elem == ParamType::Byte {
+        return Ok(match reader.read_byte_array()? {
+            Some(bytes) => Value::String(bytes_to_hex(bytes)),
+            None => Value::Null,
+        });
+    }
+    let Some(len) = reader.read_len()? else {
+        return Ok(Value::Null);
+    };
+    read_n(reader, len, |r| read_typed(r, elem))
+}
+
+/// Read a List, the element type is erased in the signature so this tries
+/// a `List<String>` and then a List written with `Parcel.writeList`
+fn read_list(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    let Some(len) = reader.read_len()? else {
+        return Ok(Value::Null);
+    };
+    let start = reader.position();
+    if let Ok(list) = read_n(reader, len, |r| Ok(json!(r.read_string()?))) {
+        if reader.is_empty() {
+            return Ok(list);
+        }
+    }
+    reader.set_position(start);
+    read_n(reader, len, read_value)
+}
+
+/// Read a Map written with `Parcel.writeMap`
+fn read_map(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    let Some(len) = reader.read_len()? else {
+        return Ok(Value::Null);
+    };
+    let mut map = Map::new();
+    for _ in 0..len {
+        let key = match read_value(reader)? {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        map.insert(key, read_value(reader)?);
+    }
+    Ok(Value::Object(map))
+}
+
+fn read_n<F>(reader: &mut ParcelReader, len: usize, mut read: F) -> ReplyResult<Value>
+where
+    F: FnMut(&mut ParcelReader) -> ReplyResult<Value>,
+{
+    let mut items = Vec::new();
+    for _ in 0..len {
+        items.push(read(reader)?);
+    }
+    Ok(Value::Array(items))
+}
+
+/// Read the Parcelables that can be decoded without knowing the device's
+/// version of the class
+fn read_parcelable(reader: &mut ParcelReader, ty: &str) -> ReplyResult<Value> {
+    if !reader.read_non_null()? {
+        return Ok(Value::Null);
+    }
+    match ty {
+        "Landroid/os/PersistableBundle;" => read_bundle(reader),
+        "Landroid/net/Uri;" => read_uri(reader),
+        _ => Err(reader.invalid(format!("unknown Parcelable {}", smali_to_java(ty)))),
+    }
+}
+
+fn read_uri(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    // Only StringUri is written as a single String
+    if reader.read_i32()? != 1 {
+        return Err(reader.invalid("only string Uris are supported"));
+    }
+    // Newer versions write the String as UTF-8
+    let uri = if reader.is_string8() {
+        reader.read_string8()?
+    } else {
+        reader.read_string()?
+    };
+    Ok(json!(uri))
+}
+
+/// Read a Bundle or PersistableBundle written with `writeToParcel`
+fn read_bundle(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    if reader.bundle_depth >= MAX_BUNDLE_DEPTH {
+        return Err(reader.invalid("Bundles nested too deeply"));
+    }
+    reader.bundle_depth += 1;
+    let res = read_bundle_contents(reader);
+    reader.bundle_depth -= 1;
+    res
+}
+
+fn read_bundle_contents(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    let Some(len) = reader.read_len()? else {
+        return Ok(Value::Null);
+    };
+    let mut map = Map::new();
+    if len == 0 {
+        return Ok(Value::Object(map));
+    }
+    let magic = reader.read_i32()?;
+    if magic != BUNDLE_MAGIC && magic != BUNDLE_MAGIC_NATIVE {
+        return Err(reader.invalid(format!("bad Bundle magic {:#x}", magic)));
+    }
+    let end = reader.position() + len;
+    let count = reader.read_i32()?;
+    for _ in 0..count {
+        let key = reader
+            .read_string()?
+            .ok_or_else(|| reader.invalid("null Bundle key"))?;
+        let start = reader.position();
+        match read_value(reader) {
+            Ok(v) => {
+                map.insert(key, v);
+            }
+            Err(_) => {
+                // Values can't be skipped without decoding them, so the rest
+                // of the Bundle is kept as hex
+                reader.set_position(start);
+                let rest = reader.remaining();
+                let rest = &rest[..rest.len().min(end.saturating_sub(start))];
+                map.insert(key, json!({ "undecoded": bytes_to_hex(rest) }));
+                break;
+            }
+        }
+    }
+    reader.set_position(end);
+    Ok(Value::Object(map))
+}
+
+/// Read a value written with `Parcel.writeValue`
+///
+/// Container and Parcelable values aren't supported since Android 13 started
+/// prefixing them with their length.
+fn read_value(reader: &mut ParcelReader) -> ReplyResult<Value> {
+    let ty = reader.read_i32()?;
+    Ok(match ty {
+        VAL_NULL => Value::Null,
+        VAL_STRING => json!(reader.read_string()?),
+        VAL_INTEGER => json!(reader.read_i32()?),
+        VAL_BUNDLE | VAL_PERSISTABLEBUNDLE => read_bundle(reader)?,
+        VAL_SHORT => json!(reader.read_i32()? as i16),
+        VAL_LONG => json!(reader.read_i64()?),
+        VAL_FLOAT => json!(reader.read_f32()?),
+        VAL_DOUBLE => json!(reader.read_f64()?),
+        VAL_BOOLEAN => Value::Bool(reader.read_i32()? != 0),
+        VAL_CHARSEQUENCE => read_char_sequence(reader)?,
+        VAL_BYTEARRAY => read_array(reader, &ParamType::Byte)?,
+        VAL_STRINGARRAY => read_array(reader, &ParamType::String)?,
+        VAL_INTARRAY => read_array(reader, &ParamType::Int)?,
+        VAL_LONGARRAY => read_array(reader, &ParamType::Long)?,
+        VAL_BYTE => json!(reader.read_i32()? as i8),
+        VAL_BOOLEANARRAY => read_array(reader, &ParamType::Boolean)?,
+        VAL_DOUBLEARRAY => read_array(reader, &ParamType::Double)?,
+        VAL_CHAR => read_char(reader)?,
+        VAL_FLOATARRAY => read_array(reader, &ParamType::Float)?,
+        _ => return Err(reader.invalid(format!("unsupported value type {}", ty))),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct Writer(Vec<u8>);
+
+    impl Writer {
+        fn int(&mut self, v: i32) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn long(&mut self, v: i64) -> &mut Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn string(&mut self, v: &str) -> &mut Self {
+            let units = v.encode_utf16().collect::<Vec<u16>>();
+            self.int(units.len() as i32);
+            for u in units.iter().chain(std::iter::once(&0)) {
+                self.0.extend_from_slice(&u.to_le_bytes());
+            }
+            while self.0.len() % 4 != 0 {
+                self.0.push(0);
+            }
+            self
+        }
+    }
+
+    #[test]
+    fn test_security_exception() {
+        let mut w = Writer::default();
+        w.int(EX_SECURITY)
+            .string("Permission Denial: requires android.permission.DUMP")
+            .int(0);
+        let decoded = decode_reply(&w.0, Some("I")).unwrap();
+        let ex = decoded.exception.expect("exception");
+        assert!(ex.is_security_exception());
+        assert_eq!(ex.name, "SecurityException");
+        assert_eq!(
+            ex.message.as_deref(),
+            Some("Permission Denial: requires android.permission.DUMP")
+        );
+        assert!(decoded.value.is_none());
+        assert!(decoded.remaining.is_none());
+    }
+
+    #[test]
+    fn test_primitives_after_headers() {
+        let mut w = Writer::default();
+        w.int(EX_HAS_NOTED_APPOPS_REPLY_HEADER)
+            .int(1)
+            .string("tag")
+            .long(1)
+            .long(0)
+            .int(EX_HAS_STRICTMODE_REPLY_HEADER)
+            .int(8)
+            .int(0)
+            .long(-5);
+        let decoded = decode_reply(&w.0, Some("J")).unwrap();
+        assert!(decoded.exception.is_none());
+        assert_eq!(decoded.value, Some(json!(-5)));
+        assert!(decoded.remaining.is_none());
+
+        let decoded = decode_reply(&w.0, None).unwrap();
+        assert_eq!(decoded.remaining.as_deref(), Some("fbffffffffffffff"));
+    }
+
+    #[test]
+    fn test_string_list() {
+        let mut w = Writer::default();
+        w.int(0).int(2).string("a").string("bc");
+        let decoded = decode_reply(&w.0, Some("Ljava/util/List;")).unwrap();
+        assert_eq!(decoded.value, Some(json!(["a", "bc"])));
+    }
+
+    #[test]
+    fn test_bundle() {
+        let mut body = Writer::default();
+        body.int(3)
+            .string("name")
+            .int(VAL_STRING)
+            .string("value")
+            .string("count")
+            .int(VAL_INTEGER)
+            .int(7)
+            .string("parcelable")
+            .int(4)
+            .int(12);
+
+        let mut w = Writer::default();
+        w.int(0).int(1).int(body.0.len() as i32).int(BUNDLE_MAGIC);
+        w.0.extend_from_slice(&body.0);
+        w.int(5);
+
+        let decoded = decode_reply(&w.0, Some("Landroid/os/Bundle;")).unwrap();
+        assert_eq!(
+            decoded.value,
+            Some(json!({
+                "name": "value",
+                "count": 7,
+                "parcelable": { "undecoded": "040000000c000000" },
+            }))
+        );
+        // Decoding continues after the Bundle
+        assert_eq!(decoded.remaining.as_deref(), Some("05000000"));
+    }
+
+    #[test]
+    fn test_nested_bundle_depth() {
+        // Each Bundle holds the next one under "b"
+        let mut body = Writer::default();
+        body.int(0);
+        for _ in 0..1000 {
+            let mut outer = Writer::default();
+            outer
+                .int(1)
+                .string("b")
+                .int(VAL_BUNDLE)
+                .int(body.0.len() as i32)
+                .int(BUNDLE_MAGIC);
+            outer.0.extend_from_slice(&body.0);
+            body = outer;
+        }
+
+        let mut w = Writer::default();
+        w.int(0).int(1).int(body.0.len() as i32).int(BUNDLE_MAGIC);
+        w.0.extend_from_slice(&body.0);
+
+        let decoded = decode_reply(&w.0, Some("Landroid/os/Bundle;")).unwrap();
+        let mut value = decoded.value.as_ref().unwrap();
+        let mut bundles = 0;
+        while let Some(inner) = value.get("b") {
+            bundles += 1;
+            value = inner;
+        }
+        // The Bundle past the limit is left undecoded
+        assert_eq!(bundles, MAX_BUNDLE_DEPTH);
+        assert!(value.get("undecoded").is_some());
+    }
+}
@@ -7,8 +7,12 @@ use std::borrow::Cow;
 use std::io;
 use std::io::{Read, Write};
 use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::time::Duration;
 
-use super::{IntentString, ParcelString};
+use super::{
+    decode_reply, DecodedReply, IntentString, ParcelString, ReplyException, ReplyResult,
+    SessionRecorder,
+};
 use crate::command::split;
 use crate::Context;
 
@@ -49,7 +53,7 @@ pub trait AppServer {
         txn: u32,
         iface: Option<&ClassName>,
         parcel_data: Option<&ParcelString>,
-    ) -> Result<String>;
+    ) -> Result<BinderReply>;
 
     fn call_app_service(
         &mut self,
@@ -59,7 +63,7 @@ pub trait AppServer {
         iface: Option<&ClassName>,
         action: Option<&str>,
         parcel_data: Option<&ParcelString>,
-    ) -> Result<String>;
+    ) -> Result<BinderReply>;
 
     fn broadcast(
         &mut self,
@@ -137,6 +141,7 @@ impl CommandResult {
 
 pub struct TcpAppServer {
     stream: TcpStream,
+    recorder: Option<SessionRecorder>,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -314,6 +319,7 @@ pub const fn pack(a: char, b: char, c: char, d: char) -> u32 {
 }
 
 #[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Command {
     Sh = pack('_', '_', 's', 'h'),
     Provider = pack('p', 'r', 'o', 'v'),
@@ -326,6 +332,38 @@ pub enum Command {
     SystemServiceShellCommand = pack('s', 's', 'h', 'l'),
 }
 
+impl Command {
+    const ALL: [Command; 9] = [
+        Self::Sh,
+        Self::Provider,
+        Self::AppService,
+        Self::SystemService,
+        Self::Broadcast,
+        Self::StartActivity,
+        Self::StartService,
+        Self::RunTest,
+        Self::SystemServiceShellCommand,
+    ];
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|it| *it as u32 == code)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sh => "sh",
+            Self::Provider => "provider",
+            Self::AppService => "app-service",
+            Self::SystemService => "system-service",
+            Self::Broadcast => "broadcast",
+            Self::StartActivity => "start-activity",
+            Self::StartService => "start-service",
+            Self::RunTest => "run-test",
+            Self::SystemServiceShellCommand => "system-service-shell-cmd",
+        }
+    }
+}
+
 impl AppServer for TcpAppServer {
     fn system_service_shell_cmd(
         &mut self,
@@ -436,7 +474,7 @@ impl AppServer for TcpAppServer {
         txn: u32,
         iface: Option<&ClassName>,
         parcel_data: Option<&ParcelString>,
-    ) -> Result<String> {
+    ) -> Result<BinderReply> {
         let parcel_data = parcel_data.map(|it| it.build());
         let parcel_data = parcel_data.as_ref().map(|it| it.as_str());
         let payload = CallSystemService {
@@ -445,8 +483,7 @@ impl AppServer for TcpAppServer {
             iface,
             parcel_data,
         };
-        let res = self.send_command(Command::SystemService, &payload)?;
-        extract_from_json("response", &res, value_to_string)
+        self.transact(Command::SystemService, &payload)
     }
 
     fn call_app_service(
@@ -457,7 +494,7 @@ impl AppServer for TcpAppServer {
         iface: Option<&ClassName>,
         action: Option<&str>,
         parcel_data: Option<&ParcelString>,
-    ) -> Result<String> {
+    ) -> Result<BinderReply> {
         let parcel_data = parcel_data.map(|it| it.build());
         let parcel_data = parcel_data.as_ref().map(|it| it.as_str());
         let payload = CallAppService {
@@ -468,8 +505,7 @@ impl AppServer for TcpAppServer {
             action,
             parcel_data,
         };
-        let res = self.send_command(Command::AppService, &payload)?;
-        extract_from_json("response", &res, value_to_string)
+        self.transact(Command::AppService, &payload)
     }
 
     fn broadcast(
@@ -555,6 +591,35 @@ impl AppServer for TcpAppServer {
     }
 }
 
+/// The reply to a binder transaction made by the app server
+#[derive(Deserialize)]
+pub struct BinderReply {
+    /// A hex dump of the reply parcel, or the interface descriptor for
+    /// `INTERFACE_TRANSACTION`
+    pub response: String,
+    /// The raw reply parcel, including the exception header
+    #[serde(default, deserialize_with = "deserialize_b64")]
+    pub reply: Vec<u8>,
+}
+
+impl BinderReply {
+    /// Decode the reply parcel, see [decode_reply]
+    pub fn decode(&self, return_type: Option<&str>) -> ReplyResult<DecodedReply> {
+        decode_reply(&self.reply, return_type)
+    }
+
+    /// The exception written to the reply, if any
+    ///
+    /// `INTERFACE_TRANSACTION` replies only contain the interface descriptor
+    /// without an exception header, so they never have one.
+    pub fn exception(&self, interface_transaction: bool) -> ReplyResult<Option<ReplyException>> {
+        if interface_transaction {
+            return Ok(None);
+        }
+        Ok(self.decode(None)?.exception)
+    }
+}
+
 #[derive(Deserialize)]
 pub struct ProviderReadContent {
     content: String,
@@ -699,7 +764,23 @@ impl TcpAppServer {
             err,
         })?;
 
-        Ok(Self { stream })
+        Ok(Self {
+            stream,
+            recorder: None,
+        })
+    }
+
+    /// Record every command sent and its response to the given session
+    pub fn set_recorder(&mut self, recorder: SessionRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Set the read and write timeouts of the connection, `None` blocks
+    /// forever
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        self.stream.set_read_timeout(timeout)?;
+        self.stream.set_write_timeout(timeout)?;
+        Ok(())
     }
 
     #[inline]
@@ -729,7 +810,14 @@ impl TcpAppServer {
         self.write_bytes(header.as_slice())?;
         log::debug!("sending payload: {}", serialized);
         self.write_bytes(as_bytes)?;
-        self.read_response()
+        let res = self.read_response();
+        if let Some(recorder) = self.recorder.as_mut() {
+            // Failing to record shouldn't fail the command itself
+            if let Err(e) = recorder.record(cmd, serialized, &res) {
+                log::error!("failed to record session entry: {}", e);
+            }
+        }
+        res
     }
 
     #[inline]
@@ -996,4 +1084,25 @@ mod test {
             r#"{"cmd":"id","shell":"/system/bin/sh"}"#
         );
     }
+
+    #[test]
+    fn test_interface_transaction_exception() {
+        // INTERFACE_TRANSACTION replies are just the descriptor as a String16
+        let descriptor = "android.app.IActivityManager";
+        let mut reply = Vec::new();
+        reply.extend_from_slice(&(descriptor.len() as i32).to_le_bytes());
+        for u in descriptor.encode_utf16().chain(std::iter::once(0)) {
+            reply.extend_from_slice(&u.to_le_bytes());
+        }
+        while reply.len() % 4 != 0 {
+            reply.push(0);
+        }
+        let reply = BinderReply {
+            response: String::from(descriptor),
+            reply,
+        };
+        assert!(reply.exception(true).unwrap().is_none());
+        // The descriptor length would be read as an exception code
+        assert!(!matches!(reply.exception(false), Ok(None)));
+    }
 }
@@ -0,0 +1,236 @@
+//! Recording and replaying app server sessions
+//!
+//! A session file is JSON lines, one [SessionEntry] per command sent to the
+//! app server. Set `DTU_SESSION_FILE` to record commands sent by the CLI.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::server::{self, Command, TcpAppServer};
+use crate::Context;
+
+/// Env var holding the session file to record to
+pub const SESSION_FILE_ENV: &str = "DTU_SESSION_FILE";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("io error: {0}")]
+    IO(io::Error),
+
+    #[error("invalid session entry on line {line}: {err}")]
+    InvalidEntry { line: usize, err: serde_json::Error },
+}
+
+impl From<io::Error> for SessionError {
+    fn from(value: io::Error) -> Self {
+        Self::IO(value)
+    }
+}
+
+pub type SessionResult<T> = std::result::Result<T, SessionError>;
+
+/// A single command sent to the app server and what it returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// The command name, such as `broadcast`, only for readability
+    pub command: String,
+    /// The command code actually sent
+    pub code: u32,
+    pub payload: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SessionEntry {
+    pub fn new(code: u32, payload: &str, res: &server::Result<String>) -> Self {
+        let command = Command::from_code(code).map_or("unknown", |it| it.name());
+        let (response, error) = match res {
+            Ok(v) => (Some(json_or_string(v)), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Self {
+            command: String::from(command),
+            code,
+            payload: json_or_string(payload),
+            response,
+            error,
+        }
+    }
+
+    /// Send the entry's command again, returning the new entry
+    pub fn replay(&self, srv: &mut TcpAppServer) -> SessionEntry {
+        let payload = match &self.payload {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        };
+        let res = srv.send_raw_command_serialized(self.code, &payload);
+        SessionEntry::new(self.code, &payload, &res)
+    }
+
+    /// Get the paths of the response values that differ from `other`,
+    /// ignoring any object keys in `ignore`
+    ///
+    /// Paths are `.` separated object keys and array indexes, an empty path
+    /// means the whole response differs. A change between success and
+    /// failure is reported as `error`.
+    pub fn differences(&self, other: &SessionEntry, ignore: &[String]) -> Vec<String> {
+        let mut diffs = Vec::new();
+        match (&self.response, &other.response) {
+            (Some(a), Some(b)) => diff_values("", a, b, ignore, &mut diffs),
+            (None, None) => {
+                if self.error != other.error {
+                    diffs.push(String::from("error"));
+                }
+            }
+            _ => diffs.push(String::from("error")),
+        }
+        diffs
+    }
+}
+
+/// Responses and payloads are stored as JSON so they are readable, anything
+/// that isn't valid JSON is kept as a string
+fn json_or_string(s: &str) -> Value {
+    serde_json::from_str(s).unwrap_or_else(|_| Value::String(String::from(s)))
+}
+
+fn diff_values(path: &str, a: &Value, b: &Value, ignore: &[String], into: &mut Vec<String>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            String::from(key)
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys = a.keys().chain(b.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                if ignore.iter().any(|it| it == key) {
+                    continue;
+                }
+                match (a.get(key), b.get(key)) {
+                    (Some(av), Some(bv)) => diff_values(&child(key), av, bv, ignore, into),
+                    _ => into.push(child(key)),
+                }
+            }
+        }
+        (Value::Array(av), Value::Array(bv)) if av.len() == bv.len() => {
+            for (i, (a, b)) in av.iter().zip(bv.iter()).enumerate() {
+                diff_values(&child(&i.to_string()), a, b, ignore, into);
+            }
+        }
+        _ => {
+            if a != b {
+                into.push(String::from(path));
+            }
+        }
+    }
+}
+
+/// Appends every command sent by a [TcpAppServer] to a session file
+pub struct SessionRecorder {
+    file: File,
+}
+
+impl SessionRecorder {
+    /// Open the session file for appending, creating it if needed
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Get a recorder for the file in `DTU_SESSION_FILE`, if set
+    pub fn from_ctx(ctx: &dyn Context) -> io::Result<Option<Self>> {
+        match ctx.maybe_get_env(SESSION_FILE_ENV) {
+            Some(path) if !path.is_empty() => Self::open(Path::new(&path)).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        code: u32,
+        payload: &str,
+        res: &server::Result<String>,
+    ) -> io::Result<()> {
+        let entry = SessionEntry::new(code, payload, res);
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())
+    }
+}
+
+/// Read every entry from a session file
+pub fn read_session(path: &Path) -> SessionResult<Vec<SessionEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|err| SessionError::InvalidEntry { line: i + 1, err })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_record_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let mut recorder = SessionRecorder::open(&path).unwrap();
+
+        let code = Command::Broadcast as u32;
+        let payload = r#"{"action":"dtu.TEST"}"#;
+        recorder
+            .record(code, payload, &Ok(String::from("\"sent\"")))
+            .unwrap();
+        let err = server::Error::ServerError(String::from("denied"));
+        recorder.record(code, payload, &Err(err)).unwrap();
+
+        let entries = read_session(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "broadcast");
+        assert_eq!(entries[0].code, code);
+        assert_eq!(entries[0].payload, json!({"action": "dtu.TEST"}));
+        assert_eq!(entries[0].response, Some(json!("sent")));
+        assert_eq!(entries[1].response, None);
+        assert_eq!(entries[1].error.as_deref(), Some("server error: denied"));
+        assert_eq!(entries[0].differences(&entries[1], &[]), vec!["error"]);
+    }
+
+    #[test]
+    fn test_differences() {
+        let entry = |response: Value| SessionEntry {
+            command: String::from("system-service"),
+            code: Command::SystemService as u32,
+            payload: json!({}),
+            response: Some(response),
+            error: None,
+        };
+        let a = entry(json!({"response": "ok", "reply": "AAAA", "list": [1, 2]}));
+        let b = entry(json!({"response": "ok", "reply": "AAAB", "list": [1, 3]}));
+        assert_eq!(a.differences(&b, &[]), vec!["list.1", "reply"]);
+        assert_eq!(a.differences(&b, &[String::from("reply")]), vec!["list.1"]);
+        assert!(a.differences(&a, &[]).is_empty());
+
+        let c = entry(json!("plain"));
+        assert_eq!(a.differences(&c, &[]), vec![""]);
+    }
+}
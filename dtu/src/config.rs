@@ -2,6 +2,7 @@ use std::{borrow::Cow, path::PathBuf};
 
 use serde::Deserialize;
 
+use crate::prop_audit::PropertyRule;
 use crate::Context;
 
 #[derive(Deserialize, Clone)]
@@ -71,6 +72,9 @@ pub struct ProjectConfig {
 
     #[serde(rename = "device-access", default = "DeviceAccessConfig::default")]
     pub device_access: DeviceAccessConfig,
+
+    #[serde(rename = "property-audit", default)]
+    pub property_audit: PropertyAuditConfig,
 }
 
 impl Default for ProjectConfig {
@@ -78,10 +82,25 @@ impl Default for ProjectConfig {
         Self {
             can_adb: true,
             device_access: DeviceAccessConfig::default(),
+            property_audit: PropertyAuditConfig::default(),
         }
     }
 }
 
+/// Configures `dtu db audit-props`
+#[derive(Deserialize, Clone, Default)]
+pub struct PropertyAuditConfig {
+    /// Don't use the rules shipped with dtu
+    #[serde(rename = "no-default-rules", default = "bool_false")]
+    pub no_default_rules: bool,
+    /// Ids of default rules to disable
+    #[serde(default)]
+    pub disable: Vec<String>,
+    /// Additional rules, replacing any default rule with the same id
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<PropertyRule>,
+}
+
 impl ProjectConfig {
     pub fn is_adb(&self) -> bool {
         matches!(self.device_access, DeviceAccessConfig::Adb(_))
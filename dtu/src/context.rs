@@ -164,6 +164,18 @@ pub trait Context: Send + Sync {
         self.get_output_dir_child("smali")
     }
 
+    /// Directory holding the native libraries pulled from the device, named by their squashed
+    /// device paths
+    fn get_native_libs_dir(&self) -> crate::Result<PathBuf> {
+        self.get_output_dir_child("native_libs")
+    }
+
+    /// Directory holding the init `.rc` files pulled from the device, named by their squashed
+    /// device paths
+    fn get_init_rc_dir(&self) -> crate::Result<PathBuf> {
+        self.get_output_dir_child("init_rc")
+    }
+
     fn get_user_local_dir(&self) -> crate::Result<PathBuf> {
         let bd = BaseDirs::new().ok_or(Error::NoBaseDirs)?;
         Ok(bd.data_local_dir().join("dtu"))
@@ -15,6 +15,7 @@ use diesel::{ConnectionError, SqliteConnection};
 use diesel_migrations::MigrationHarness;
 use lazy_static::lazy_static;
 use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
 
 use crate::utils::{ensure_dir_exists, ClassName};
 use crate::Context;
@@ -165,7 +166,8 @@ pub trait PermissionProtected {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ApkIPCKind {
     Receiver,
     Activity,
@@ -173,6 +175,17 @@ pub enum ApkIPCKind {
     Service,
 }
 
+impl ApkIPCKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Receiver => "receiver",
+            Self::Activity => "activity",
+            Self::Provider => "provider",
+            Self::Service => "service",
+        }
+    }
+}
+
 /// A trait for anything that can be included in the diff
 pub trait Diffable {
     /// Whether the item exists in the diff or not
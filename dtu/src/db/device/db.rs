@@ -5,7 +5,15 @@ use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 
 pub const EMULATOR_DIFF_SOURCE: &'static str = "emulator";
 
-use super::schema::{system_service_impls, system_services};
+/// The package name of `framework-res.apk`
+pub const PLATFORM_APP_NAME: &'static str = "android";
+
+use super::schema::{
+    activities, apk_certificates, apks, binder_fuzz_results, diff_sources, fuzz_results,
+    intent_filter_actions, intent_filter_categories, intent_filter_data, intent_filters,
+    permission_checks, property_findings, receivers, services, system_service_impls,
+    system_service_methods, system_services,
+};
 use crate::utils::ClassName;
 use crate::Context;
 
@@ -222,6 +230,38 @@ impl DeviceDatabase {
         name.like
     );
 
+    impl_diff_item!(pub
+        add_device_property_diff,
+        InsertDevicePropertyDiff,
+        get_device_property_diffs_by_diff_name,
+        get_device_property_diffs_by_diff_id,
+        DeviceProperty,
+        DevicePropertyDiff,
+        DiffedDeviceProperty,
+        device_properties,
+        device_property_diffs
+    );
+
+    impl_simple_gets!(pub
+        property_findings,
+        PropertyFinding,
+        get_property_findings,
+        get_property_finding_by_id
+    );
+
+    /// Replace all stored property audit findings
+    pub fn replace_property_findings(&self, findings: &[InsertPropertyFinding]) -> Result<()> {
+        self.with_transaction(|c| {
+            diesel::delete(property_findings::table).execute(c)?;
+            for it in findings {
+                diesel::insert_into(property_findings::table)
+                    .values(it)
+                    .execute(c)?;
+            }
+            Ok(())
+        })
+    }
+
     pub fn get_permissions_for_apk(&self, apk: &Apk) -> Result<Vec<ApkPermission>> {
         self.with_connection(|conn| {
             let perms = ApkPermission::belonging_to(apk)
@@ -304,6 +344,50 @@ impl DeviceDatabase {
     impl_get_one_by!(pub get_apk_by_app_name, &str, Apk, apks, app_name.eq);
     impl_get_one_by!(pub get_apk_by_apk_name, &str, Apk, apks, name.eq);
     impl_get_one_by!(pub get_apk_by_device_path, &str, Apk, apks, device_path.eq);
+    impl_get_multi_by!(pub get_apks_by_shared_user_id, &str, Apk, apks, shared_user_id.eq);
+
+    impl_get_all!(pub get_apk_certificates, ApkCertificate, apk_certificates);
+    impl_get_multi_by!(pub
+        get_certificates_by_apk_id,
+        i32,
+        ApkCertificate,
+        apk_certificates,
+        apk_id.eq
+    );
+
+    /// Get all APKs signed with any of the certificates with the given SHA-256
+    /// digests
+    pub fn get_apks_signed_with(&self, digests: &[&str]) -> Result<Vec<Apk>> {
+        self.with_connection(|c| {
+            let found = apks::table
+                .filter(
+                    apks::id.eq_any(
+                        apk_certificates::table
+                            .select(apk_certificates::apk_id)
+                            .filter(apk_certificates::sha256.eq_any(digests)),
+                    ),
+                )
+                .load::<Apk>(c)?;
+            Ok(found)
+        })
+    }
+
+    /// Get the certificates `framework-res.apk` was signed with, this is the
+    /// platform key
+    pub fn get_platform_certificates(&self) -> Result<Vec<ApkCertificate>> {
+        let android = self.get_apk_by_app_name(PLATFORM_APP_NAME)?;
+        self.get_certificates_by_apk_id(android.id)
+    }
+
+    /// Get all APKs signed with the platform key, including `framework-res.apk`
+    pub fn get_platform_signed_apks(&self) -> Result<Vec<Apk>> {
+        let certs = self.get_platform_certificates()?;
+        let digests = certs
+            .iter()
+            .map(|it| it.sha256.as_str())
+            .collect::<Vec<&str>>();
+        self.get_apks_signed_with(&digests)
+    }
 
     impl_get_all!(pub
         get_system_service_methods,
@@ -373,6 +457,14 @@ impl DeviceDatabase {
         system_service_id.eq
     );
 
+    impl_get_one_by!(pub
+        get_system_service_method_by_id,
+        i32,
+        SystemServiceMethod,
+        system_service_methods,
+        id.eq
+    );
+
     pub fn get_provider_containing_authority(&self, sel: &str) -> Result<Provider> {
         use super::schema::providers::dsl::*;
         let like_middle = format!("%:{}:%", sel);
@@ -391,6 +483,61 @@ impl DeviceDatabase {
         })
     }
 
+    impl_get_all!(pub
+        get_provider_path_permissions,
+        ProviderPathPermission,
+        provider_path_permissions
+    );
+
+    impl_get_multi_by!(pub
+        get_path_permissions_by_provider_id,
+        i32,
+        ProviderPathPermission,
+        provider_path_permissions,
+        provider_id.eq
+    );
+
+    impl_get_all!(pub
+        get_provider_grant_uri_permissions,
+        ProviderGrantUriPermission,
+        provider_grant_uri_permissions
+    );
+
+    impl_get_multi_by!(pub
+        get_grant_uri_permissions_by_provider_id,
+        i32,
+        ProviderGrantUriPermission,
+        provider_grant_uri_permissions,
+        provider_id.eq
+    );
+
+    /// Attach the `<path-permission>` and `<grant-uri-permission>` entries to
+    /// each of the given providers
+    pub fn get_provider_paths(&self, providers: Vec<Provider>) -> Result<Vec<ProviderWithPaths>> {
+        self.with_connection(|c| {
+            let path_perms = ProviderPathPermission::belonging_to(&providers)
+                .select(ProviderPathPermission::as_select())
+                .load::<ProviderPathPermission>(c)?
+                .grouped_by(&providers);
+            let grants = ProviderGrantUriPermission::belonging_to(&providers)
+                .select(ProviderGrantUriPermission::as_select())
+                .load::<ProviderGrantUriPermission>(c)?
+                .grouped_by(&providers);
+
+            Ok(providers
+                .into_iter()
+                .zip(path_perms.into_iter().zip(grants))
+                .map(
+                    |(provider, (path_permissions, grant_uri_patterns))| ProviderWithPaths {
+                        provider,
+                        path_permissions,
+                        grant_uri_patterns,
+                    },
+                )
+                .collect())
+        })
+    }
+
     impl_get_multi_by!(pub get_receivers_by_apk_id, i32, Receiver, receivers, apk_id.eq);
     impl_get_multi_by!(pub get_services_by_apk_id, i32, Service, services, apk_id.eq);
     impl_get_multi_by!(pub
@@ -402,6 +549,174 @@ impl DeviceDatabase {
     );
     impl_get_multi_by!(pub get_providers_by_apk_id, i32, Provider, providers, apk_id.eq);
 
+    impl_get_all!(pub get_intent_filters, IntentFilter, intent_filters);
+    impl_get_multi_by!(pub
+        get_intent_filters_by_activity_id,
+        i32,
+        IntentFilter,
+        intent_filters,
+        activity_id.eq
+    );
+    impl_get_multi_by!(pub
+        get_intent_filters_by_receiver_id,
+        i32,
+        IntentFilter,
+        intent_filters,
+        receiver_id.eq
+    );
+    impl_get_multi_by!(pub
+        get_intent_filters_by_service_id,
+        i32,
+        IntentFilter,
+        intent_filters,
+        service_id.eq
+    );
+
+    /// Get the intent filters for the given activity with their actions, categories, and data
+    pub fn get_activity_intent_filters(&self, id: i32) -> Result<Vec<IntentFilterDetails>> {
+        let filters = self.get_intent_filters_by_activity_id(id)?;
+        self.get_intent_filter_details(filters)
+    }
+
+    /// Get the intent filters for the given receiver with their actions, categories, and data
+    pub fn get_receiver_intent_filters(&self, id: i32) -> Result<Vec<IntentFilterDetails>> {
+        let filters = self.get_intent_filters_by_receiver_id(id)?;
+        self.get_intent_filter_details(filters)
+    }
+
+    /// Get the intent filters for the given service with their actions, categories, and data
+    pub fn get_service_intent_filters(&self, id: i32) -> Result<Vec<IntentFilterDetails>> {
+        let filters = self.get_intent_filters_by_service_id(id)?;
+        self.get_intent_filter_details(filters)
+    }
+
+    /// Load the actions, categories, and data for the given intent filters
+    pub fn get_intent_filter_details(
+        &self,
+        filters: Vec<IntentFilter>,
+    ) -> Result<Vec<IntentFilterDetails>> {
+        let ids = filters.iter().map(|it| it.id).collect::<Vec<i32>>();
+        self.with_connection(|c| {
+            let actions = intent_filter_actions::table
+                .filter(intent_filter_actions::filter_id.eq_any(&ids))
+                .load::<IntentFilterAction>(c)?;
+            let categories = intent_filter_categories::table
+                .filter(intent_filter_categories::filter_id.eq_any(&ids))
+                .load::<IntentFilterCategory>(c)?;
+            let data = intent_filter_data::table
+                .filter(intent_filter_data::filter_id.eq_any(&ids))
+                .load::<IntentFilterData>(c)?;
+            Ok(group_intent_filter_details(
+                filters, actions, categories, data,
+            ))
+        })
+    }
+
+    /// Get every intent filter in the database with its actions, categories, and data
+    pub fn get_all_intent_filter_details(&self) -> Result<Vec<IntentFilterDetails>> {
+        self.with_connection(|c| {
+            let filters = intent_filters::table.load::<IntentFilter>(c)?;
+            let actions = intent_filter_actions::table.load::<IntentFilterAction>(c)?;
+            let categories = intent_filter_categories::table.load::<IntentFilterCategory>(c)?;
+            let data = intent_filter_data::table.load::<IntentFilterData>(c)?;
+            Ok(group_intent_filter_details(
+                filters, actions, categories, data,
+            ))
+        })
+    }
+
+    /// Find all intent filters matching every value in the search
+    pub fn find_intent_filters(&self, search: &IntentFilterSearch) -> Result<Vec<IntentFilter>> {
+        self.with_connection(|c| {
+            let mut q = intent_filters::table.into_boxed();
+            if let Some(action) = search.action {
+                q = q.filter(
+                    intent_filters::id.eq_any(
+                        intent_filter_actions::table
+                            .select(intent_filter_actions::filter_id)
+                            .filter(intent_filter_actions::name.eq(action)),
+                    ),
+                );
+            }
+            if let Some(category) = search.category {
+                q = q.filter(
+                    intent_filters::id.eq_any(
+                        intent_filter_categories::table
+                            .select(intent_filter_categories::filter_id)
+                            .filter(intent_filter_categories::name.eq(category)),
+                    ),
+                );
+            }
+            if let Some(scheme) = search.scheme {
+                q = q.filter(
+                    intent_filters::id.eq_any(
+                        intent_filter_data::table
+                            .select(intent_filter_data::filter_id)
+                            .filter(intent_filter_data::scheme.eq(scheme)),
+                    ),
+                );
+            }
+            if let Some(host) = search.host {
+                q = q.filter(
+                    intent_filters::id.eq_any(
+                        intent_filter_data::table
+                            .select(intent_filter_data::filter_id)
+                            .filter(intent_filter_data::host.eq(host)),
+                    ),
+                );
+            }
+            if let Some(mime_type) = search.mime_type {
+                q = q.filter(
+                    intent_filters::id.eq_any(
+                        intent_filter_data::table
+                            .select(intent_filter_data::filter_id)
+                            .filter(intent_filter_data::mime_type.eq(mime_type)),
+                    ),
+                );
+            }
+            Ok(query!(q).load(c)?)
+        })
+    }
+
+    /// Get all activities with an intent filter matching the search
+    pub fn get_activities_handling(&self, search: &IntentFilterSearch) -> Result<Vec<Activity>> {
+        let ids = self
+            .find_intent_filters(search)?
+            .into_iter()
+            .filter_map(|it| it.activity_id)
+            .collect::<Vec<i32>>();
+        self.with_connection(|c| {
+            let __query = activities::table.filter(activities::id.eq_any(ids));
+            Ok(query!(__query).load(c)?)
+        })
+    }
+
+    /// Get all receivers with an intent filter matching the search
+    pub fn get_receivers_handling(&self, search: &IntentFilterSearch) -> Result<Vec<Receiver>> {
+        let ids = self
+            .find_intent_filters(search)?
+            .into_iter()
+            .filter_map(|it| it.receiver_id)
+            .collect::<Vec<i32>>();
+        self.with_connection(|c| {
+            let __query = receivers::table.filter(receivers::id.eq_any(ids));
+            Ok(query!(__query).load(c)?)
+        })
+    }
+
+    /// Get all services with an intent filter matching the search
+    pub fn get_services_handling(&self, search: &IntentFilterSearch) -> Result<Vec<Service>> {
+        let ids = self
+            .find_intent_filters(search)?
+            .into_iter()
+            .filter_map(|it| it.service_id)
+            .collect::<Vec<i32>>();
+        self.with_connection(|c| {
+            let __query = services::table.filter(services::id.eq_any(ids));
+            Ok(query!(__query).load(c)?)
+        })
+    }
+
     impl_get_all!(pub get_diff_sources, DiffSource, diff_sources);
     impl_get_one_by!(pub
         get_diff_source_by_name,
@@ -410,6 +725,16 @@ impl DeviceDatabase {
         diff_sources,
         name.eq
     );
+
+    /// Set or clear the directory holding the smali for the given diff source
+    pub fn set_diff_source_smali_dir(&self, id: i32, smali_dir: Option<&str>) -> Result<()> {
+        self.with_connection(|c| {
+            diesel::update(diff_sources::table.filter(diff_sources::id.eq(id)))
+                .set(diff_sources::smali_dir.eq(smali_dir))
+                .execute(c)
+        })?;
+        Ok(())
+    }
     impl_delete_by!(pub
         delete_system_service_diff_by_service_id,
         i32,
@@ -538,6 +863,152 @@ impl DeviceDatabase {
         get_receiver_diffs_for_apk,
         apk_id.eq
     );
+    impl_get_all!(pub get_permission_checks, PermissionCheck, permission_checks);
+
+    impl_get_multi_by!(pub
+        get_permission_checks_by_method_id,
+        i32,
+        PermissionCheck,
+        permission_checks,
+        method.eq
+    );
+
+    /// Get all system service methods that have an implementation but didn't
+    /// reach any permission checks
+    ///
+    /// This is only meaningful after a [super::PermissionCheckTask] has been
+    /// run.
+    pub fn get_unchecked_system_service_methods(&self) -> Result<Vec<SystemServiceMethod>> {
+        self.with_connection(|c| {
+            let __query = system_service_methods::table
+                .filter(system_service_methods::system_service_id.eq_any(
+                    system_service_impls::table.select(system_service_impls::system_service_id),
+                ))
+                .filter(diesel::dsl::not(diesel::dsl::exists(
+                    permission_checks::table
+                        .filter(permission_checks::method.eq(system_service_methods::id)),
+                )));
+            Ok(query!(__query).load(c)?)
+        })
+    }
+
+    impl_simple_gets!(pub
+        native_services,
+        NativeService,
+        get_native_services,
+        get_native_service_by_id
+    );
+    impl_get_one_by!(pub
+        get_native_service_by_name,
+        &str,
+        NativeService,
+        native_services,
+        name.eq
+    );
+    impl_get_all!(pub
+        get_native_service_sockets,
+        NativeServiceSocket,
+        native_service_sockets
+    );
+    impl_get_multi_by!(pub
+        get_sockets_by_native_service_id,
+        i32,
+        NativeServiceSocket,
+        native_service_sockets,
+        service_id.eq
+    );
+    impl_get_multi_by!(pub
+        get_triggers_by_native_service_id,
+        i32,
+        NativeServiceTrigger,
+        native_service_triggers,
+        service_id.eq
+    );
+
+    impl_diff_item!(pub
+        add_native_service_diff,
+        InsertNativeServiceDiff,
+        get_native_service_diffs_by_diff_name,
+        get_native_service_diffs_by_diff_id,
+        NativeService,
+        NativeServiceDiff,
+        DiffedNativeService,
+        native_services,
+        native_service_diffs
+    );
+
+    /// Attach the sockets and triggers to each of the given native services
+    pub fn get_native_service_details(
+        &self,
+        services: Vec<NativeService>,
+    ) -> Result<Vec<NativeServiceWithDetails>> {
+        self.with_connection(|c| {
+            let sockets = NativeServiceSocket::belonging_to(&services)
+                .select(NativeServiceSocket::as_select())
+                .load::<NativeServiceSocket>(c)?
+                .grouped_by(&services);
+            let triggers = NativeServiceTrigger::belonging_to(&services)
+                .select(NativeServiceTrigger::as_select())
+                .load::<NativeServiceTrigger>(c)?
+                .grouped_by(&services);
+
+            Ok(services
+                .into_iter()
+                .zip(sockets.into_iter().zip(triggers))
+                .map(|(service, (sockets, triggers))| NativeServiceWithDetails {
+                    service,
+                    sockets,
+                    triggers,
+                })
+                .collect())
+        })
+    }
+
+    impl_simple_gets!(pub
+        hal_services,
+        HalService,
+        get_hal_services,
+        get_hal_service_by_id
+    );
+
+    impl_diff_item!(pub
+        add_hal_service_diff,
+        InsertHalServiceDiff,
+        get_hal_service_diffs_by_diff_name,
+        get_hal_service_diffs_by_diff_id,
+        HalService,
+        HalServiceDiff,
+        DiffedHalService,
+        hal_services,
+        hal_service_diffs
+    );
+
+    impl_simple_gets!(pub
+        fs_survey_entries,
+        FsSurveyEntry,
+        get_fs_survey_entries,
+        get_fs_survey_entry_by_id
+    );
+    impl_get_one_by!(pub
+        get_fs_survey_entry_by_path,
+        &str,
+        FsSurveyEntry,
+        fs_survey_entries,
+        path.eq
+    );
+
+    impl_diff_item!(pub
+        add_fs_survey_entry_diff,
+        InsertFsSurveyEntryDiff,
+        get_fs_survey_entry_diffs_by_diff_name,
+        get_fs_survey_entry_diffs_by_diff_id,
+        FsSurveyEntry,
+        FsSurveyEntryDiff,
+        DiffedFsSurveyEntry,
+        fs_survey_entries,
+        fs_survey_entry_diffs
+    );
+
     impl_simple_gets!(pub
         fuzz_results,
         FuzzResult,
@@ -551,6 +1022,90 @@ impl DeviceDatabase {
         fuzz_results,
         security_exception_thrown.eq
     );
+
+    impl_simple_gets!(pub
+        binder_fuzz_results,
+        BinderFuzzResult,
+        get_binder_fuzz_results,
+        get_binder_fuzz_result_by_id
+    );
+    impl_get_multi_by!(pub
+        get_binder_fuzz_results_by_method,
+        i32,
+        BinderFuzzResult,
+        binder_fuzz_results,
+        method.eq
+    );
+
+    /// Replace the results of fuzzing `method`, both the individual calls
+    /// and its summary in `fuzz_results`
+    pub fn replace_binder_fuzz_results(
+        &self,
+        method: i32,
+        results: &[InsertBinderFuzzResult],
+        summary: &InsertFuzzResult,
+    ) -> Result<()> {
+        self.with_transaction(|c| {
+            diesel::delete(
+                binder_fuzz_results::table.filter(binder_fuzz_results::method.eq(method)),
+            )
+            .execute(c)?;
+            diesel::delete(
+                fuzz_results::table
+                    .filter(fuzz_results::service_name.eq(summary.service_name))
+                    .filter(fuzz_results::method_name.eq(summary.method_name)),
+            )
+            .execute(c)?;
+            for it in results {
+                diesel::insert_into(binder_fuzz_results::table)
+                    .values(it)
+                    .execute(c)?;
+            }
+            diesel::insert_into(fuzz_results::table)
+                .values(summary)
+                .execute(c)?;
+            Ok(())
+        })
+    }
+}
+
+fn group_intent_filter_details(
+    filters: Vec<IntentFilter>,
+    actions: Vec<IntentFilterAction>,
+    categories: Vec<IntentFilterCategory>,
+    data: Vec<IntentFilterData>,
+) -> Vec<IntentFilterDetails> {
+    let mut details = filters
+        .into_iter()
+        .map(|filter| IntentFilterDetails {
+            filter,
+            actions: Vec::new(),
+            categories: Vec::new(),
+            data: Vec::new(),
+        })
+        .collect::<Vec<IntentFilterDetails>>();
+    let index = details
+        .iter()
+        .enumerate()
+        .map(|(i, it)| (it.filter.id, i))
+        .collect::<HashMap<i32, usize>>();
+
+    for it in actions {
+        if let Some(i) = index.get(&it.filter_id) {
+            details[*i].actions.push(it.name);
+        }
+    }
+    for it in categories {
+        if let Some(i) = index.get(&it.filter_id) {
+            details[*i].categories.push(it.name);
+        }
+    }
+    for it in data {
+        if let Some(i) = index.get(&it.filter_id) {
+            details[*i].data.push(it);
+        }
+    }
+    details
 }
 
 impl From<ConnectionError> for Error {
@@ -560,7 +1115,7 @@ impl From<ConnectionError> for Error {
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
     use rstest::*;
     use std::panic;
@@ -569,6 +1124,7 @@ mod test {
     use super::super::common::cleanup_database;
     use crate::testing::{tmp_context, TestContext};
     use crate::utils::ensure_dir_exists;
+    use crate::UnknownBool;
 
     fn get_db_url(context: &dyn Context) -> String {
         let dir = context.get_sqlite_dir().expect("failed to get sqlite dir");
@@ -593,7 +1149,7 @@ mod test {
         }};
     }
 
-    fn db_test(context: &dyn Context, func: impl FnOnce(DeviceDatabase)) {
+    pub(crate) fn db_test(context: &dyn Context, func: impl FnOnce(DeviceDatabase)) {
         let url = get_db_url(&context);
         let db = DeviceDatabase::new_from_url(&url).expect("failed to get database");
         let res = panic::catch_unwind(AssertUnwindSafe(|| func(db)));
@@ -659,6 +1215,292 @@ mod test {
         });
     }
 
+    #[rstest]
+    fn test_get_unchecked_system_service_methods(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let unchecked = db
+                .get_unchecked_system_service_methods()
+                .expect("should not have errored");
+            let names = unchecked
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<&str>>();
+            assert_eq!(names, vec!["unchecked"]);
+
+            let checks = db
+                .get_permission_checks_by_method_id(0)
+                .expect("should not have errored");
+            assert_eq!(checks.len(), 1);
+            assert_eq!(
+                checks[0].permission.as_ref().map(|it| it.as_str()),
+                Some("android.permission.DUMP")
+            );
+        });
+    }
+
+    #[rstest]
+    fn test_replace_binder_fuzz_results(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let summary = InsertFuzzResult::new("test_can", "checked", true, false);
+            let results = vec![
+                InsertBinderFuzzResult::new(0, "baseline", "_INT0", "ok"),
+                InsertBinderFuzzResult::new(0, "arg 0 (int): -1", "_INT-1", "exception")
+                    .set_exception(Some("IllegalArgumentException")),
+            ];
+            db.replace_binder_fuzz_results(0, &results, &summary)
+                .expect("should not have errored");
+
+            let summary = InsertFuzzResult::new("test_can", "checked", true, true);
+            let results =
+                vec![
+                    InsertBinderFuzzResult::new(0, "baseline", "_INT0", "security-exception")
+                        .set_exception(Some("SecurityException")),
+                ];
+            db.replace_binder_fuzz_results(0, &results, &summary)
+                .expect("should not have errored");
+
+            let stored = db.get_binder_fuzz_results_by_method(0).unwrap();
+            assert_eq!(stored.len(), 1);
+            assert_eq!(stored[0].outcome, "security-exception");
+            assert_eq!(stored[0].exception.as_deref(), Some("SecurityException"));
+
+            let summaries = db.get_fuzz_results().unwrap();
+            assert_eq!(summaries.len(), 1);
+            assert!(summaries[0].security_exception_thrown);
+        });
+    }
+
+    #[rstest]
+    fn test_get_components_handling(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let search = IntentFilterSearch {
+                action: Some("android.intent.action.VIEW"),
+                scheme: Some("https"),
+                ..Default::default()
+            };
+            let mut names = db
+                .get_activities_handling(&search)
+                .expect("should not have errored")
+                .into_iter()
+                .map(|it| it.class_name.to_string())
+                .collect::<Vec<String>>();
+            names.sort();
+            assert_eq!(
+                names,
+                vec![
+                    "just.an.app.BrowserActivity",
+                    "just.an.app.PrivateViewerActivity"
+                ]
+            );
+
+            // The host and scheme come from different data elements
+            let search = IntentFilterSearch {
+                scheme: Some("http"),
+                host: Some("example.com"),
+                ..Default::default()
+            };
+            let found = db.get_activities_handling(&search).unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].id, 0);
+
+            let search = IntentFilterSearch {
+                action: Some("android.intent.action.VIEW"),
+                category: Some("android.intent.category.LAUNCHER"),
+                ..Default::default()
+            };
+            assert!(db.get_activities_handling(&search).unwrap().is_empty());
+
+            let search = IntentFilterSearch {
+                mime_type: Some("text/plain"),
+                ..Default::default()
+            };
+            assert_eq!(db.get_services_handling(&search).unwrap().len(), 1);
+            assert!(db.get_receivers_handling(&search).unwrap().is_empty());
+        });
+    }
+
+    #[rstest]
+    fn test_get_intent_filter_details(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let filters = db
+                .get_activity_intent_filters(0)
+                .expect("should not have errored");
+            assert_eq!(filters.len(), 1);
+            let filter = &filters[0];
+            assert!(filter.has_action("android.intent.action.VIEW"));
+            assert!(filter.has_category("android.intent.category.BROWSABLE"));
+            assert_eq!(
+                filter.schemes().collect::<Vec<&str>>(),
+                vec!["https", "http"]
+            );
+            assert_eq!(filter.hosts().collect::<Vec<&str>>(), vec!["example.com"]);
+
+            let filters = db.get_receiver_intent_filters(0).unwrap();
+            assert_eq!(filters.len(), 1);
+            assert_eq!(filters[0].priority, 100);
+
+            assert_eq!(db.get_all_intent_filter_details().unwrap().len(), 5);
+        });
+    }
+
+    #[rstest]
+    fn test_apk_signing(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let mut names = db
+                .get_platform_signed_apks()
+                .unwrap()
+                .into_iter()
+                .map(|it| it.app_name)
+                .collect::<Vec<String>>();
+            names.sort();
+            assert_eq!(names, vec!["android", "just.an.app"]);
+
+            let system = db.get_apks_by_shared_user_id("android.uid.system").unwrap();
+            assert_eq!(system.len(), 2);
+
+            let certs = db.get_certificates_by_apk_id(1).unwrap();
+            assert_eq!(certs.len(), 1);
+            assert_eq!(certs[0].subject, "CN=Debuggable");
+
+            let signed = db
+                .get_apks_signed_with(&[certs[0].sha256.as_str()])
+                .unwrap();
+            assert_eq!(signed.len(), 1);
+            assert_eq!(signed[0].app_name, "is.debuggable");
+            assert_eq!(signed[0].shared_user_id, None);
+        });
+    }
+
+    #[rstest]
+    fn test_get_provider_paths(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let providers = db.get_providers().unwrap();
+            let with_paths = db.get_provider_paths(providers).unwrap();
+
+            let exact = with_paths.iter().find(|it| it.name == "exact").unwrap();
+            assert_eq!(exact.path_permissions.len(), 2);
+            assert!(exact.grant_uri_patterns.is_empty());
+            let public = exact
+                .path_permissions
+                .iter()
+                .find(|it| it.uri_pattern() == Some(UriPathPattern::Prefix("/public")))
+                .unwrap();
+            assert_eq!(
+                public.to_string(),
+                "pathPrefix=/public read=none write=exact.WRITE"
+            );
+
+            let left = with_paths.iter().find(|it| it.name == "left").unwrap();
+            assert!(left.path_permissions.is_empty());
+            assert_eq!(
+                left.grant_uri_patterns[0].uri_pattern(),
+                Some(UriPathPattern::Pattern("/shared/.*"))
+            );
+
+            assert_eq!(db.get_path_permissions_by_provider_id(1).unwrap().len(), 2);
+        });
+    }
+
+    #[rstest]
+    fn test_get_native_service_details(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let services = db.get_native_services().unwrap();
+            assert_eq!(services.len(), 3);
+            let details = db.get_native_service_details(services).unwrap();
+
+            let rootd = details.iter().find(|it| it.name == "vendor.rootd").unwrap();
+            assert!(rootd.runs_as_root());
+            assert!(rootd.is_vendor());
+            assert_eq!(rootd.capability_list(), None);
+            assert_eq!(rootd.sockets.len(), 1);
+            assert_eq!(
+                rootd.sockets[0].to_string(),
+                "rootd stream 0666 root:system"
+            );
+            assert_eq!(rootd.triggers[0].on_trigger, "late-init");
+
+            let lazy = db.get_native_service_by_name("vendor.lazy").unwrap();
+            assert!(!lazy.runs_as_root());
+            assert_eq!(lazy.capability_list(), Some(vec![]));
+            assert!(db
+                .get_sockets_by_native_service_id(lazy.id)
+                .unwrap()
+                .is_empty());
+        });
+    }
+
+    #[rstest]
+    fn test_get_hal_services(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let hals = db.get_hal_services().unwrap();
+            assert_eq!(hals.len(), 3);
+
+            let audio = db.get_hal_service_by_id(0).unwrap();
+            assert!(audio.is_hidl());
+            assert!(audio.is_vendor());
+            assert_eq!(audio.reachable, UnknownBool::False);
+
+            let power = db.get_hal_service_by_id(1).unwrap();
+            assert!(power.is_aidl());
+            assert_eq!(power.reachable, UnknownBool::True);
+            assert_eq!(
+                power.to_string(),
+                "android.hardware.power.IPower/default (aidl binder) /vendor/bin/hw/android.hardware.power-service"
+            );
+
+            let vnd = db.get_hal_service_by_id(2).unwrap();
+            assert_eq!(vnd.server_pid, None);
+            assert_eq!(vnd.reachable, UnknownBool::Unknown);
+        });
+    }
+
+    #[rstest]
+    fn test_get_fs_survey_entries(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            assert_eq!(db.get_fs_survey_entries().unwrap().len(), 3);
+
+            let tmp = db.get_fs_survey_entry_by_path("/data/local/tmp").unwrap();
+            assert!(tmp.is_dir());
+            assert!(!tmp.is_world_writable());
+            assert_eq!(tmp.mode_string(), "drwxrwx--x");
+
+            let cfg = db
+                .get_fs_survey_entry_by_path("/vendor/etc/widevine.cfg")
+                .unwrap();
+            assert!(cfg.is_file());
+            assert!(cfg.is_world_writable());
+            assert_eq!(cfg.perms(), 0o666);
+
+            let run_as = db
+                .get_fs_survey_entry_by_path("/system/bin/run-as")
+                .unwrap();
+            assert!(run_as.is_setuid());
+            assert_eq!(
+                run_as.to_string(),
+                "-rwsr-xr-x root shell ? /system/bin/run-as"
+            );
+
+            assert_err!(db.get_fs_survey_entry_by_path("/nope"), NotFound);
+        });
+    }
+
+    #[rstest]
+    fn test_set_diff_source_smali_dir(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let emu = db.get_diff_source_by_name(EMULATOR_DIFF_SOURCE).unwrap();
+            assert_eq!(emu.smali_dir, None);
+
+            db.set_diff_source_smali_dir(emu.id, Some("/aosp/smali"))
+                .unwrap();
+            let emu = db.get_diff_source_by_name(EMULATOR_DIFF_SOURCE).unwrap();
+            assert_eq!(emu.smali_dir.as_deref(), Some("/aosp/smali"));
+
+            db.set_diff_source_smali_dir(emu.id, None).unwrap();
+            let emu = db.get_diff_source_by_name(EMULATOR_DIFF_SOURCE).unwrap();
+            assert_eq!(emu.smali_dir, None);
+        });
+    }
+
     #[rstest]
     fn test_get_apks(tmp_context: TestContext) {
         db_test(&tmp_context, |db| {
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use diesel::insert_into;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::db::device::db::DeviceDatabase;
+use crate::db::device::diff::{DiffError, DiffEvent, DiffOptions, DiffResult, DiffTask};
+use crate::db::device::models::*;
+use crate::db::device::schema::diff_sources;
+use crate::db::{Diffable, Error};
+use crate::tasks::{EventMonitor, TaskCancelCheck};
+use crate::utils::path_must_str;
+use crate::UnknownBool;
+
+/// Name of the diff source added to the temporary database copies
+const DB_DIFF_SOURCE: &str = "dtu-diff-dbs";
+
+/// Entry in a [DatabaseDiffReport] that exists in both databases but differs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffReportChange {
+    pub name: String,
+    /// Human readable descriptions of each difference
+    pub changes: Vec<String>,
+}
+
+/// Differences for a single type of entry, such as APKs or permissions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffReportSection {
+    /// Only in the new database
+    pub added: Vec<String>,
+    /// Only in the old database
+    pub removed: Vec<String>,
+    pub changed: Vec<DiffReportChange>,
+}
+
+impl DiffReportSection {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Build the section from the diff of the new database against the old
+    /// and the diff of the old database against the new
+    fn build<T, N, C>(new_diffs: Vec<T>, old_diffs: Vec<T>, name: N, changes: C) -> Self
+    where
+        T: Diffable,
+        N: Fn(&T) -> String,
+        C: Fn(&T) -> Vec<String>,
+    {
+        let mut section = Self::default();
+        for it in new_diffs.iter() {
+            if !it.in_diff() {
+                section.added.push(name(it));
+                continue;
+            }
+            let changes = changes(it);
+            if !changes.is_empty() {
+                section.changed.push(DiffReportChange {
+                    name: name(it),
+                    changes,
+                });
+            }
+        }
+        section
+            .removed
+            .extend(old_diffs.iter().filter(|it| !it.in_diff()).map(&name));
+        section.added.sort();
+        section.removed.sort();
+        section.changed.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        section
+    }
+}
+
+/// The result of a [DatabaseDiffTask]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseDiffReport {
+    /// Path to the old database
+    pub old: String,
+    /// Path to the new database
+    pub new: String,
+    pub system_services: DiffReportSection,
+    pub system_service_methods: DiffReportSection,
+    pub hal_services: DiffReportSection,
+    pub native_services: DiffReportSection,
+    pub fs_survey: DiffReportSection,
+    pub properties: DiffReportSection,
+    pub apks: DiffReportSection,
+    pub activities: DiffReportSection,
+    pub receivers: DiffReportSection,
+    pub services: DiffReportSection,
+    pub providers: DiffReportSection,
+    pub permissions: DiffReportSection,
+}
+
+impl DatabaseDiffReport {
+    /// All sections with a human readable title
+    pub fn sections(&self) -> Vec<(&'static str, &DiffReportSection)> {
+        vec![
+            ("System services", &self.system_services),
+            ("System service methods", &self.system_service_methods),
+            ("HAL services", &self.hal_services),
+            ("Native services", &self.native_services),
+            ("Filesystem", &self.fs_survey),
+            ("Device properties", &self.properties),
+            ("APKs", &self.apks),
+            ("Activities", &self.activities),
+            ("Receivers", &self.receivers),
+            ("Services", &self.services),
+            ("Providers", &self.providers),
+            ("Permissions", &self.permissions),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections().iter().all(|(_, it)| it.is_empty())
+    }
+}
+
+impl Display for DatabaseDiffReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- {}", self.old)?;
+        writeln!(f, "+++ {}", self.new)?;
+        if self.is_empty() {
+            return writeln!(f, "\nNo differences");
+        }
+        for (title, section) in self.sections() {
+            if section.is_empty() {
+                continue;
+            }
+            writeln!(
+                f,
+                "\n== {} (+{} -{} ~{}) ==",
+                title,
+                section.added.len(),
+                section.removed.len(),
+                section.changed.len()
+            )?;
+            for it in section.added.iter() {
+                writeln!(f, "+ {}", it)?;
+            }
+            for it in section.removed.iter() {
+                writeln!(f, "- {}", it)?;
+            }
+            for it in section.changed.iter() {
+                writeln!(f, "~ {}: {}", it.name, it.changes.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Diff two arbitrary device databases
+///
+/// Neither database is modified: both are copied to a temporary directory and
+/// each copy is diffed against the other with a [DiffTask]. The diff of the
+/// new database gives the added and changed entries and the diff of the old
+/// database gives the removed entries. [DiffEvent]s from both passes are sent
+/// to the monitor.
+pub struct DatabaseDiffTask<'a> {
+    old: &'a Path,
+    new: &'a Path,
+    monitor: &'a dyn EventMonitor<DiffEvent>,
+    cancel: TaskCancelCheck,
+}
+
+impl<'a> DatabaseDiffTask<'a> {
+    pub fn new(
+        old: &'a Path,
+        new: &'a Path,
+        cancel: TaskCancelCheck,
+        monitor: &'a dyn EventMonitor<DiffEvent>,
+    ) -> Self {
+        Self {
+            old,
+            new,
+            monitor,
+            cancel,
+        }
+    }
+
+    pub fn run(&self) -> DiffResult<DatabaseDiffReport> {
+        let td = tempfile::Builder::new().prefix("dtu_diff_dbs_").tempdir()?;
+        let old_path = td.path().join("old.db");
+        let new_path = td.path().join("new.db");
+        fs::copy(self.old, &old_path)?;
+        fs::copy(self.new, &new_path)?;
+
+        let old_db = DeviceDatabase::new_from_path(path_must_str(&old_path))?;
+        let new_db = DeviceDatabase::new_from_path(path_must_str(&new_path))?;
+
+        let new_source = self.diff(&new_db, &old_db)?;
+        self.cancel_check()?;
+        let old_source = self.diff(&old_db, &new_db)?;
+
+        let new_services = get_service_names(&new_db)?;
+        let old_services = get_service_names(&old_db)?;
+
+        Ok(DatabaseDiffReport {
+            old: path_must_str(self.old).into(),
+            new: path_must_str(self.new).into(),
+            system_services: DiffReportSection::build(
+                new_db.get_system_service_diffs_by_diff_id(new_source)?,
+                old_db.get_system_service_diffs_by_diff_id(old_source)?,
+                |it| it.service.name.clone(),
+                |_| Vec::new(),
+            ),
+            system_service_methods: build_methods_section(
+                new_db.get_system_service_method_diffs_by_diff_id(new_source)?,
+                old_db.get_system_service_method_diffs_by_diff_id(old_source)?,
+                &new_services,
+                &old_services,
+            ),
+            hal_services: DiffReportSection::build(
+                new_db.get_hal_service_diffs_by_diff_id(new_source)?,
+                old_db.get_hal_service_diffs_by_diff_id(old_source)?,
+                |it| format!("{}/{}", it.service.interface, it.service.instance),
+                |it| {
+                    let mut changes = Vec::new();
+                    if !it.transport_matches_diff {
+                        changes.push(changed(
+                            "transport",
+                            it.diff_transport.as_deref(),
+                            &it.service.transport,
+                        ));
+                    }
+                    changes
+                },
+            ),
+            native_services: DiffReportSection::build(
+                new_db.get_native_service_diffs_by_diff_id(new_source)?,
+                old_db.get_native_service_diffs_by_diff_id(old_source)?,
+                |it| it.service.name.clone(),
+                native_service_changes,
+            ),
+            fs_survey: DiffReportSection::build(
+                new_db.get_fs_survey_entry_diffs_by_diff_id(new_source)?,
+                old_db.get_fs_survey_entry_diffs_by_diff_id(old_source)?,
+                |it| it.entry.path.clone(),
+                fs_survey_changes,
+            ),
+            properties: DiffReportSection::build(
+                new_db.get_device_property_diffs_by_diff_id(new_source)?,
+                old_db.get_device_property_diffs_by_diff_id(old_source)?,
+                |it| it.property.name.clone(),
+                property_changes,
+            ),
+            apks: DiffReportSection::build(
+                new_db.get_apk_diffs_by_diff_id(new_source)?,
+                old_db.get_apk_diffs_by_diff_id(old_source)?,
+                |it| it.apk.name.clone(),
+                |_| Vec::new(),
+            ),
+            activities: DiffReportSection::build(
+                new_db.get_activity_diffs_by_diff_id(new_source)?,
+                old_db.get_activity_diffs_by_diff_id(old_source)?,
+                |it| it.activity.class_name.get_java_name().into_owned(),
+                |it| {
+                    ipc_changes(
+                        it.activity.exported,
+                        it.exported_matches_diff,
+                        it.activity.permission.as_deref(),
+                        it.permission_matches_diff,
+                    )
+                },
+            ),
+            receivers: DiffReportSection::build(
+                new_db.get_receiver_diffs_by_diff_id(new_source)?,
+                old_db.get_receiver_diffs_by_diff_id(old_source)?,
+                |it| it.receiver.class_name.get_java_name().into_owned(),
+                |it| {
+                    ipc_changes(
+                        it.receiver.exported,
+                        it.exported_matches_diff,
+                        it.receiver.permission.as_deref(),
+                        it.permission_matches_diff,
+                    )
+                },
+            ),
+            services: DiffReportSection::build(
+                new_db.get_service_diffs_by_diff_id(new_source)?,
+                old_db.get_service_diffs_by_diff_id(old_source)?,
+                |it| it.service.class_name.get_java_name().into_owned(),
+                |it| {
+                    ipc_changes(
+                        it.service.exported,
+                        it.exported_matches_diff,
+                        it.service.permission.as_deref(),
+                        it.permission_matches_diff,
+                    )
+                },
+            ),
+            providers: DiffReportSection::build(
+                new_db.get_provider_diffs_by_diff_id(new_source)?,
+                old_db.get_provider_diffs_by_diff_id(old_source)?,
+                |it| it.provider.name.clone(),
+                provider_changes,
+            ),
+            permissions: DiffReportSection::build(
+                new_db.get_permission_diffs_by_diff_id(new_source)?,
+                old_db.get_permission_diffs_by_diff_id(old_source)?,
+                |it| it.permission.name.clone(),
+                |it| {
+                    let mut changes = Vec::new();
+                    if !it.protection_level_matches_diff {
+                        changes.push(changed(
+                            "protection level",
+                            it.diff_protection_level.as_deref(),
+                            &it.permission.protection_level,
+                        ));
+                    }
+                    changes
+                },
+            ),
+        })
+    }
+
+    /// Diff `db` against `diff_db`, returning the id of the diff source
+    /// added to `db`
+    fn diff(&self, db: &DeviceDatabase, diff_db: &DeviceDatabase) -> DiffResult<i32> {
+        let ins = InsertDiffSource::new(DB_DIFF_SOURCE);
+        let id = db.with_connection(|c| {
+            insert_into(diff_sources::table)
+                .values(&ins)
+                .returning(diff_sources::id)
+                .get_result::<i32>(c)
+                .map_err(Error::from)
+        })?;
+        let source = DiffSource {
+            id,
+            name: String::from(DB_DIFF_SOURCE),
+            smali_dir: None,
+        };
+        let cancel = self.cancel.clone();
+        let task = DiffTask::new(DiffOptions::new(source), db, diff_db, cancel, self.monitor);
+        task.run()?;
+        Ok(id)
+    }
+
+    fn cancel_check(&self) -> DiffResult<()> {
+        self.cancel.check(DiffError::Cancelled)
+    }
+}
+
+fn get_service_names(db: &DeviceDatabase) -> DiffResult<HashMap<i32, String>> {
+    Ok(db
+        .get_system_services()?
+        .into_iter()
+        .map(|it| (it.id, it.name))
+        .collect())
+}
+
+fn build_methods_section(
+    new_diffs: Vec<DiffedSystemServiceMethod>,
+    old_diffs: Vec<DiffedSystemServiceMethod>,
+    new_services: &HashMap<i32, String>,
+    old_services: &HashMap<i32, String>,
+) -> DiffReportSection {
+    let method_name = |services: &HashMap<i32, String>, m: &SystemServiceMethod| {
+        format!(
+            "{}.{}({}){}",
+            services
+                .get(&m.system_service_id)
+                .map_or("?", |it| it.as_str()),
+            m.name,
+            m.signature.as_deref().unwrap_or("?"),
+            m.return_type.as_deref().unwrap_or("?"),
+        )
+    };
+    let mut section = DiffReportSection::build(
+        new_diffs,
+        Vec::new(),
+        |it| method_name(new_services, &it.method),
+        |it| {
+            if it.hash_matches_diff == UnknownBool::False {
+                vec![String::from("implementation hash")]
+            } else {
+                Vec::new()
+            }
+        },
+    );
+    // The names of the old methods need the old database's service names
+    section.removed = old_diffs
+        .iter()
+        .filter(|it| !it.in_diff())
+        .map(|it| method_name(old_services, &it.method))
+        .collect();
+    section.removed.sort();
+    section
+}
+
+/// Describe a changed value, `old` is None if it wasn't set
+fn changed(what: &str, old: Option<&str>, new: &str) -> String {
+    format!("{} {} -> {}", what, old.unwrap_or("none"), new)
+}
+
+fn ipc_changes(
+    exported: bool,
+    exported_matches: bool,
+    permission: Option<&str>,
+    permission_matches: bool,
+) -> Vec<String> {
+    let mut changes = Vec::new();
+    if !exported_matches {
+        changes.push(format!("exported {} -> {}", !exported, exported));
+    }
+    if !permission_matches {
+        changes.push(format!("permission now {}", permission.unwrap_or("none")));
+    }
+    changes
+}
+
+fn provider_changes(it: &DiffedProvider) -> Vec<String> {
+    let mut changes = ipc_changes(
+        it.provider.exported,
+        it.exported_matches_diff,
+        it.provider.permission.as_deref(),
+        it.permission_matches_diff,
+    );
+    if !it.read_permission_matches_diff {
+        changes.push(changed(
+            "read permission",
+            it.diff_read_permission.as_deref(),
+            it.provider.read_permission.as_deref().unwrap_or("none"),
+        ));
+    }
+    if !it.write_permission_matches_diff {
+        changes.push(changed(
+            "write permission",
+            it.diff_write_permission.as_deref(),
+            it.provider.write_permission.as_deref().unwrap_or("none"),
+        ));
+    }
+    if !it.path_permissions_match_diff {
+        changes.push(String::from("path permissions"));
+    }
+    if !it.grant_uri_permissions_match_diff {
+        changes.push(String::from("grant uri permissions"));
+    }
+    changes
+}
+
+fn native_service_changes(it: &DiffedNativeService) -> Vec<String> {
+    let mut changes = Vec::new();
+    if !it.path_matches_diff {
+        changes.push(changed("path", it.diff_path.as_deref(), &it.service.path));
+    }
+    if !it.user_matches_diff {
+        changes.push(changed("user", it.diff_user.as_deref(), &it.service.user));
+    }
+    if !it.capabilities_matches_diff {
+        changes.push(changed(
+            "capabilities",
+            it.diff_capabilities.as_deref(),
+            it.service.capabilities.as_deref().unwrap_or("none"),
+        ));
+    }
+    if !it.seclabel_matches_diff {
+        changes.push(changed(
+            "seclabel",
+            it.diff_seclabel.as_deref(),
+            it.service.seclabel.as_deref().unwrap_or("none"),
+        ));
+    }
+    if !it.sockets_match_diff {
+        changes.push(String::from("sockets"));
+    }
+    changes
+}
+
+fn property_changes(it: &DiffedDeviceProperty) -> Vec<String> {
+    if it.value_matches_diff {
+        return Vec::new();
+    }
+    vec![changed(
+        "value",
+        it.diff_value.as_deref(),
+        &it.property.value,
+    )]
+}
+
+fn fs_survey_changes(it: &DiffedFsSurveyEntry) -> Vec<String> {
+    let mut changes = Vec::new();
+    if !it.mode_matches_diff {
+        let old = it.diff_mode.map(fmt_mode);
+        changes.push(changed("mode", old.as_deref(), &it.entry.mode_string()));
+    }
+    if !it.owner_matches_diff {
+        changes.push(changed("owner", it.diff_owner.as_deref(), &it.entry.owner));
+    }
+    if !it.group_matches_diff {
+        changes.push(changed(
+            "group",
+            it.diff_group.as_deref(),
+            &it.entry.group_name,
+        ));
+    }
+    if !it.label_matches_diff {
+        changes.push(changed(
+            "label",
+            it.diff_label.as_deref(),
+            it.entry.selinux_label.as_deref().unwrap_or("none"),
+        ));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::device::schema::permissions;
+    use crate::tasks::{NoopMonitor, TaskCanceller};
+
+    fn add_permissions(path: &Path, perms: &[(&str, &str)]) {
+        let db = DeviceDatabase::new_from_path(path_must_str(path)).unwrap();
+        let apk_id = db.get_apks().unwrap()[0].id;
+        db.with_connection(|c| {
+            for (name, level) in perms {
+                insert_into(permissions::table)
+                    .values(&InsertPermission::new(name, level, apk_id))
+                    .execute(c)?;
+            }
+            Ok::<(), diesel::result::Error>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_database_diff_task() {
+        let td = tempfile::tempdir().unwrap();
+        let old_path = td.path().join("old.db");
+        let new_path = td.path().join("new.db");
+
+        add_permissions(
+            &old_path,
+            &[
+                ("test.SAME", "normal"),
+                ("test.REMOVED", "normal"),
+                ("test.CHANGED", "normal"),
+            ],
+        );
+        add_permissions(
+            &new_path,
+            &[
+                ("test.SAME", "normal"),
+                ("test.ADDED", "normal"),
+                ("test.CHANGED", "signature"),
+            ],
+        );
+
+        let old_contents = fs::read(&old_path).unwrap();
+        let new_contents = fs::read(&new_path).unwrap();
+
+        let (_canceller, check) = TaskCanceller::new();
+        let monitor = NoopMonitor::new();
+        let report = DatabaseDiffTask::new(&old_path, &new_path, check, &monitor)
+            .run()
+            .expect("diff should succeed");
+
+        assert_eq!(fs::read(&old_path).unwrap(), old_contents);
+        assert_eq!(fs::read(&new_path).unwrap(), new_contents);
+
+        assert_eq!(report.permissions.added, vec![String::from("test.ADDED")]);
+        assert_eq!(
+            report.permissions.removed,
+            vec![String::from("test.REMOVED")]
+        );
+        assert_eq!(
+            report.permissions.changed,
+            vec![DiffReportChange {
+                name: String::from("test.CHANGED"),
+                changes: vec![String::from("protection level normal -> signature")],
+            }]
+        );
+        assert!(report.apks.is_empty());
+        assert!(report.system_services.is_empty());
+        assert!(report.system_service_methods.is_empty());
+        assert!(report.to_string().contains("== Permissions (+1 -1 ~1) =="));
+    }
+}
@@ -82,6 +82,46 @@ pub enum DiffEvent {
         exists: bool,
     },
     PermissionsEnded,
+
+    NativeServicesStarted {
+        count: usize,
+    },
+    NativeService {
+        id: i32,
+        name: String,
+        exists: bool,
+    },
+    NativeServicesEnded,
+
+    HalServicesStarted {
+        count: usize,
+    },
+    HalService {
+        id: i32,
+        name: String,
+        exists: bool,
+    },
+    HalServicesEnded,
+
+    FsSurveyStarted {
+        count: usize,
+    },
+    FsSurveyEntry {
+        id: i32,
+        path: String,
+        exists: bool,
+    },
+    FsSurveyEnded,
+
+    PropertiesStarted {
+        count: usize,
+    },
+    Property {
+        id: i32,
+        name: String,
+        exists: bool,
+    },
+    PropertiesEnded,
 }
 
 type Evt = DiffEvent;
@@ -228,19 +268,11 @@ impl<'a> SystemServiceDiffTask<'a> {
             Some(m) => m,
         };
 
-        let hash_matches = match device_method.smalisa_hash.as_ref() {
-            Some(dev) => match diff_method.signature.as_ref() {
-                Some(diff) => UnknownBool::from(dev == diff),
-                None => UnknownBool::Unknown,
-            },
-            None => UnknownBool::Unknown,
-        };
-
         let ins = InsertSystemServiceMethodDiff {
             method: device_method.id,
             diff_source: self.source.id,
             exists_in_diff: true,
-            hash_matches_diff: hash_matches,
+            hash_matches_diff: method_hash_matches(device_method, diff_method),
         };
 
         self.db.add_system_service_method_diff(&ins)?;
@@ -279,6 +311,9 @@ pub struct DiffTask<'a> {
     source: DiffSource,
     pub do_system_services: bool,
     pub do_apks: bool,
+    pub do_native_services: bool,
+    pub do_fs_survey: bool,
+    pub do_properties: bool,
 }
 
 impl<'a> DiffTask<'a> {
@@ -297,6 +332,9 @@ impl<'a> DiffTask<'a> {
             monitor,
             do_apks: true,
             do_system_services: true,
+            do_native_services: true,
+            do_fs_survey: true,
+            do_properties: true,
         }
     }
 
@@ -305,6 +343,27 @@ impl<'a> DiffTask<'a> {
         log::debug!("diffing system services");
         if self.do_system_services {
             self.diff_system_services()?;
+            self.cancel_check()?;
+            log::debug!("diffing hal services");
+            self.diff_hal_services()?;
+        }
+
+        self.cancel_check()?;
+        log::debug!("diffing native services");
+        if self.do_native_services {
+            self.diff_native_services()?;
+        }
+
+        self.cancel_check()?;
+        log::debug!("diffing fs survey");
+        if self.do_fs_survey {
+            self.diff_fs_survey()?;
+        }
+
+        self.cancel_check()?;
+        log::debug!("diffing device properties");
+        if self.do_properties {
+            self.diff_device_properties()?;
         }
 
         if !self.do_apks {
@@ -382,6 +441,34 @@ impl<'a> DiffTask<'a> {
         Ok(filtered)
     }
 
+    fn get_diffable_native_services(&self) -> DiffResult<Vec<NativeService>> {
+        self.get_diffable(
+            |db| db.get_native_services(),
+            |db, id| db.get_native_service_diffs_by_diff_id(id),
+        )
+    }
+
+    fn get_diffable_hal_services(&self) -> DiffResult<Vec<HalService>> {
+        self.get_diffable(
+            |db| db.get_hal_services(),
+            |db, id| db.get_hal_service_diffs_by_diff_id(id),
+        )
+    }
+
+    fn get_diffable_fs_survey_entries(&self) -> DiffResult<Vec<FsSurveyEntry>> {
+        self.get_diffable(
+            |db| db.get_fs_survey_entries(),
+            |db, id| db.get_fs_survey_entry_diffs_by_diff_id(id),
+        )
+    }
+
+    fn get_diffable_device_properties(&self) -> DiffResult<Vec<DeviceProperty>> {
+        self.get_diffable(
+            |db| db.get_device_properties(),
+            |db, id| db.get_device_property_diffs_by_diff_id(id),
+        )
+    }
+
     fn get_diffable_apks(&self) -> DiffResult<Vec<Apk>> {
         self.get_diffable(|db| db.get_apks(), |db, id| db.get_apk_diffs_by_diff_id(id))
     }
@@ -421,6 +508,270 @@ impl<'a> DiffTask<'a> {
         )
     }
 
+    fn diff_native_services(&self) -> DiffResult<()> {
+        let device = self.get_diffable_native_services()?;
+        let diff_lst = self.diff_db.get_native_services()?;
+        let mut diff = HashMap::new();
+        diff.extend(diff_lst.into_iter().map(|it| (it.name.clone(), it)));
+
+        self.trigger(Evt::NativeServicesStarted {
+            count: device.len(),
+        });
+
+        let device_sockets = load_native_service_sockets(self.db)?;
+        let diff_sockets = load_native_service_sockets(self.diff_db)?;
+
+        for svc in device.iter() {
+            let sockets = device_sockets.get(&svc.id);
+            let diff_svc = diff.get(&svc.name);
+            let diff_sockets = diff_svc.and_then(|it| diff_sockets.get(&it.id));
+            self.do_native_service_diff(svc, diff_svc, sockets == diff_sockets)?;
+        }
+
+        self.trigger(Evt::NativeServicesEnded);
+
+        Ok(())
+    }
+
+    fn do_native_service_diff(
+        &self,
+        device: &NativeService,
+        diff: Option<&NativeService>,
+        sockets_match: bool,
+    ) -> DiffResult<()> {
+        let ins = match diff {
+            Some(diff) => {
+                let (path_matches, diff_path) = get_and_cmp(Some(&device.path), Some(&diff.path));
+                let (user_matches, diff_user) = get_and_cmp(Some(&device.user), Some(&diff.user));
+                let (caps_match, diff_caps) =
+                    get_and_cmp(device.capabilities.as_ref(), diff.capabilities.as_ref());
+                let (seclabel_matches, diff_seclabel) =
+                    get_and_cmp(device.seclabel.as_ref(), diff.seclabel.as_ref());
+                InsertNativeServiceDiff {
+                    native_service: device.id,
+                    diff_source: self.source.id,
+                    exists_in_diff: true,
+                    path_matches_diff: path_matches,
+                    diff_path,
+                    user_matches_diff: user_matches,
+                    diff_user,
+                    capabilities_matches_diff: caps_match,
+                    diff_capabilities: diff_caps,
+                    seclabel_matches_diff: seclabel_matches,
+                    diff_seclabel,
+                    sockets_match_diff: sockets_match,
+                }
+            }
+            None => InsertNativeServiceDiff {
+                native_service: device.id,
+                diff_source: self.source.id,
+                exists_in_diff: false,
+                path_matches_diff: false,
+                diff_path: None,
+                user_matches_diff: false,
+                diff_user: None,
+                capabilities_matches_diff: false,
+                diff_capabilities: None,
+                seclabel_matches_diff: false,
+                diff_seclabel: None,
+                sockets_match_diff: false,
+            },
+        };
+        self.db.add_native_service_diff(&ins)?;
+        self.trigger(Evt::NativeService {
+            id: device.id,
+            name: device.name.clone(),
+            exists: diff.is_some(),
+        });
+        Ok(())
+    }
+
+    fn diff_hal_services(&self) -> DiffResult<()> {
+        let device = self.get_diffable_hal_services()?;
+        let diff_lst = self.diff_db.get_hal_services()?;
+        let mut diff = HashMap::new();
+        diff.extend(
+            diff_lst
+                .into_iter()
+                .map(|it| ((it.interface.clone(), it.instance.clone()), it)),
+        );
+
+        self.trigger(Evt::HalServicesStarted {
+            count: device.len(),
+        });
+
+        for hal in device.iter() {
+            let key = (hal.interface.clone(), hal.instance.clone());
+            self.do_hal_service_diff(hal, diff.get(&key))?;
+        }
+
+        self.trigger(Evt::HalServicesEnded);
+
+        Ok(())
+    }
+
+    fn do_hal_service_diff(
+        &self,
+        device: &HalService,
+        diff: Option<&HalService>,
+    ) -> DiffResult<()> {
+        let ins = match diff {
+            Some(diff) => {
+                let (transport_matches, diff_transport) =
+                    get_and_cmp(Some(&device.transport), Some(&diff.transport));
+                InsertHalServiceDiff {
+                    hal_service: device.id,
+                    diff_source: self.source.id,
+                    exists_in_diff: true,
+                    transport_matches_diff: transport_matches,
+                    diff_transport,
+                }
+            }
+            None => InsertHalServiceDiff {
+                hal_service: device.id,
+                diff_source: self.source.id,
+                exists_in_diff: false,
+                transport_matches_diff: false,
+                diff_transport: None,
+            },
+        };
+        self.db.add_hal_service_diff(&ins)?;
+        self.trigger(Evt::HalService {
+            id: device.id,
+            name: format!("{}/{}", device.interface, device.instance),
+            exists: diff.is_some(),
+        });
+        Ok(())
+    }
+
+    fn diff_fs_survey(&self) -> DiffResult<()> {
+        let diff_lst = self.diff_db.get_fs_survey_entries()?;
+        // Everything would show up as new, which isn't useful
+        if diff_lst.is_empty() {
+            log::info!("no filesystem survey in the diff database, skipping");
+            return Ok(());
+        }
+        let device = self.get_diffable_fs_survey_entries()?;
+        let mut diff = HashMap::new();
+        diff.extend(diff_lst.into_iter().map(|it| (it.path.clone(), it)));
+
+        self.trigger(Evt::FsSurveyStarted {
+            count: device.len(),
+        });
+
+        for ent in device.iter() {
+            self.cancel_check()?;
+            self.do_fs_survey_entry_diff(ent, diff.get(&ent.path))?;
+        }
+
+        self.trigger(Evt::FsSurveyEnded);
+
+        Ok(())
+    }
+
+    fn do_fs_survey_entry_diff(
+        &self,
+        device: &FsSurveyEntry,
+        diff: Option<&FsSurveyEntry>,
+    ) -> DiffResult<()> {
+        let ins = match diff {
+            Some(diff) => {
+                let (owner_matches, diff_owner) =
+                    get_and_cmp(Some(&device.owner), Some(&diff.owner));
+                let (group_matches, diff_group) =
+                    get_and_cmp(Some(&device.group_name), Some(&diff.group_name));
+                let (label_matches, diff_label) =
+                    get_and_cmp(device.selinux_label.as_ref(), diff.selinux_label.as_ref());
+                InsertFsSurveyEntryDiff {
+                    fs_survey_entry: device.id,
+                    diff_source: self.source.id,
+                    exists_in_diff: true,
+                    mode_matches_diff: device.mode == diff.mode,
+                    diff_mode: Some(diff.mode),
+                    owner_matches_diff: owner_matches,
+                    diff_owner,
+                    group_matches_diff: group_matches,
+                    diff_group,
+                    label_matches_diff: label_matches,
+                    diff_label,
+                }
+            }
+            None => InsertFsSurveyEntryDiff {
+                fs_survey_entry: device.id,
+                diff_source: self.source.id,
+                exists_in_diff: false,
+                mode_matches_diff: false,
+                diff_mode: None,
+                owner_matches_diff: false,
+                diff_owner: None,
+                group_matches_diff: false,
+                diff_group: None,
+                label_matches_diff: false,
+                diff_label: None,
+            },
+        };
+        self.db.add_fs_survey_entry_diff(&ins)?;
+        self.trigger(Evt::FsSurveyEntry {
+            id: device.id,
+            path: device.path.clone(),
+            exists: diff.is_some(),
+        });
+        Ok(())
+    }
+
+    fn diff_device_properties(&self) -> DiffResult<()> {
+        let device = self.get_diffable_device_properties()?;
+        let diff_lst = self.diff_db.get_device_properties()?;
+        let mut diff = HashMap::new();
+        diff.extend(diff_lst.into_iter().map(|it| (it.name.clone(), it)));
+
+        self.trigger(Evt::PropertiesStarted {
+            count: device.len(),
+        });
+
+        for prop in device.iter() {
+            self.do_device_property_diff(prop, diff.get(&prop.name))?;
+        }
+
+        self.trigger(Evt::PropertiesEnded);
+
+        Ok(())
+    }
+
+    fn do_device_property_diff(
+        &self,
+        device: &DeviceProperty,
+        diff: Option<&DeviceProperty>,
+    ) -> DiffResult<()> {
+        let ins = match diff {
+            Some(diff) => {
+                let (value_matches, diff_value) =
+                    get_and_cmp(Some(&device.value), Some(&diff.value));
+                InsertDevicePropertyDiff {
+                    device_property: device.id,
+                    diff_source: self.source.id,
+                    exists_in_diff: true,
+                    value_matches_diff: value_matches,
+                    diff_value,
+                }
+            }
+            None => InsertDevicePropertyDiff {
+                device_property: device.id,
+                diff_source: self.source.id,
+                exists_in_diff: false,
+                value_matches_diff: false,
+                diff_value: None,
+            },
+        };
+        self.db.add_device_property_diff(&ins)?;
+        self.trigger(Evt::Property {
+            id: device.id,
+            name: device.name.clone(),
+            exists: diff.is_some(),
+        });
+        Ok(())
+    }
+
     fn diff_apks(&self) -> DiffResult<()> {
         let device = self.get_diffable_apks()?;
         let diff_lst = self.diff_db.get_apks()?;
@@ -620,15 +971,24 @@ impl<'a> DiffTask<'a> {
         let mut diff = HashMap::new();
         diff.extend(diff_lst.into_iter().map(|it| (it.name.clone(), it)));
 
+        let device_paths = ProviderPathKeys::load(self.db)?;
+        let diff_paths = ProviderPathKeys::load(self.diff_db)?;
+
         for d in device.iter() {
-            self.do_provider_diff(d, diff.get(&d.name))?
+            self.do_provider_diff(d, diff.get(&d.name), &device_paths, &diff_paths)?
         }
         self.trigger(Evt::ProvidersEnded);
 
         Ok(())
     }
 
-    fn do_provider_diff(&self, device: &Provider, diff: Option<&Provider>) -> DiffResult<()> {
+    fn do_provider_diff(
+        &self,
+        device: &Provider,
+        diff: Option<&Provider>,
+        device_paths: &ProviderPathKeys,
+        diff_paths: &ProviderPathKeys,
+    ) -> DiffResult<()> {
         let diff = match diff {
             Some(v) => v,
             None => return self.do_provider_not_in_diff(device),
@@ -651,6 +1011,10 @@ impl<'a> DiffTask<'a> {
             diff.write_permission.as_ref(),
         );
 
+        let path_perms_match = device_paths.path_permissions_match(device.id, diff_paths, diff.id);
+        let grant_uris_match = device.grant_uri_permissions == diff.grant_uri_permissions
+            && device_paths.grant_uris_match(device.id, diff_paths, diff.id);
+
         let ins = InsertProviderDiff {
             provider: device.id,
             diff_source: self.source.id,
@@ -662,6 +1026,8 @@ impl<'a> DiffTask<'a> {
             diff_read_permission: read_diff_perm,
             write_permission_matches_diff: write_perm_matches,
             diff_write_permission: write_diff_perm,
+            path_permissions_match_diff: path_perms_match,
+            grant_uri_permissions_match_diff: grant_uris_match,
         };
 
         self.db.add_provider_diff(&ins)?;
@@ -687,6 +1053,8 @@ impl<'a> DiffTask<'a> {
             diff_read_permission: None,
             write_permission_matches_diff: false,
             diff_write_permission: None,
+            path_permissions_match_diff: false,
+            grant_uri_permissions_match_diff: false,
         };
         self.db.add_provider_diff(&ins)?;
         self.trigger(Evt::Provider {
@@ -703,6 +1071,76 @@ impl<'a> DiffTask<'a> {
     }
 }
 
+/// The `<path-permission>` and `<grant-uri-permission>` entries of every
+/// provider in a database keyed by provider id, in a form that can be compared
+/// across databases
+struct ProviderPathKeys {
+    path_permissions: HashMap<i32, Vec<String>>,
+    grant_uris: HashMap<i32, Vec<String>>,
+}
+
+impl ProviderPathKeys {
+    fn load(db: &DeviceDatabase) -> DiffResult<Self> {
+        let path_permissions = db
+            .get_provider_path_permissions()?
+            .into_iter()
+            .map(|it| (it.provider_id, it.to_string()));
+        let grant_uris = db
+            .get_provider_grant_uri_permissions()?
+            .into_iter()
+            .map(|it| (it.provider_id, it.to_string()));
+        Ok(Self::from_entries(path_permissions, grant_uris))
+    }
+
+    fn from_entries(
+        path_permissions: impl IntoIterator<Item = (i32, String)>,
+        grant_uris: impl IntoIterator<Item = (i32, String)>,
+    ) -> Self {
+        fn collect(entries: impl IntoIterator<Item = (i32, String)>) -> HashMap<i32, Vec<String>> {
+            let mut map: HashMap<i32, Vec<String>> = HashMap::new();
+            for (id, key) in entries {
+                map.entry(id).or_default().push(key);
+            }
+            for v in map.values_mut() {
+                v.sort_unstable();
+            }
+            map
+        }
+        Self {
+            path_permissions: collect(path_permissions),
+            grant_uris: collect(grant_uris),
+        }
+    }
+
+    /// Whether provider `id` has the same path permissions as provider
+    /// `other_id` in `other`
+    fn path_permissions_match(&self, id: i32, other: &Self, other_id: i32) -> bool {
+        self.path_permissions.get(&id) == other.path_permissions.get(&other_id)
+    }
+
+    /// Whether provider `id` has the same grant URI patterns as provider
+    /// `other_id` in `other`
+    fn grant_uris_match(&self, id: i32, other: &Self, other_id: i32) -> bool {
+        self.grant_uris.get(&id) == other.grant_uris.get(&other_id)
+    }
+}
+
+/// The sockets of every native service in a database keyed by service id, in a
+/// form that can be compared across databases
+fn load_native_service_sockets(db: &DeviceDatabase) -> DiffResult<HashMap<i32, Vec<String>>> {
+    let mut sockets: HashMap<i32, Vec<String>> = HashMap::new();
+    for sock in db.get_native_service_sockets()? {
+        sockets
+            .entry(sock.service_id)
+            .or_default()
+            .push(sock.to_string());
+    }
+    for v in sockets.values_mut() {
+        v.sort_unstable();
+    }
+    Ok(sockets)
+}
+
 fn get_and_cmp<'a>(dev: Option<&'a String>, diff: Option<&'a String>) -> (bool, Option<&'a str>) {
     match diff {
         Some(diff) => match dev {
@@ -712,3 +1150,83 @@ fn get_and_cmp<'a>(dev: Option<&'a String>, diff: Option<&'a String>) -> (bool,
         None => (dev.is_none(), None),
     }
 }
+
+/// Compare the implementation hashes of two system service methods, unknown
+/// if either doesn't have one
+fn method_hash_matches(device: &SystemServiceMethod, diff: &SystemServiceMethod) -> UnknownBool {
+    match (device.smalisa_hash.as_ref(), diff.smalisa_hash.as_ref()) {
+        (Some(dev), Some(diff)) => UnknownBool::from(dev == diff),
+        _ => UnknownBool::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn method(signature: &str, hash: Option<&str>) -> SystemServiceMethod {
+        SystemServiceMethod {
+            id: 0,
+            system_service_id: 0,
+            transaction_id: 1,
+            name: String::from("doThing"),
+            signature: Some(String::from(signature)),
+            return_type: Some(String::from("V")),
+            smalisa_hash: hash.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_provider_path_keys_match() {
+        let entries = |it: &[(i32, &str)]| {
+            it.iter()
+                .map(|(id, key)| (*id, String::from(*key)))
+                .collect::<Vec<(i32, String)>>()
+        };
+
+        // Provider ids differ between databases and entries come in any order
+        let device = ProviderPathKeys::from_entries(
+            entries(&[(1, "/a read=foo"), (1, "/b write=bar"), (2, "/c read=baz")]),
+            entries(&[(1, "/grant")]),
+        );
+        let diff = ProviderPathKeys::from_entries(
+            entries(&[
+                (7, "/b write=bar"),
+                (7, "/a read=foo"),
+                (8, "/c read=other"),
+            ]),
+            entries(&[(8, "/grant")]),
+        );
+
+        assert!(device.path_permissions_match(1, &diff, 7));
+        assert!(!device.path_permissions_match(2, &diff, 8));
+        assert!(!device.path_permissions_match(1, &diff, 8));
+        // Neither provider has any entries
+        assert!(device.path_permissions_match(3, &diff, 9));
+
+        assert!(device.grant_uris_match(1, &diff, 8));
+        assert!(!device.grant_uris_match(1, &diff, 7));
+        assert!(device.grant_uris_match(2, &diff, 7));
+    }
+
+    #[test]
+    fn test_method_hash_matches() {
+        // Same signature, different implementation
+        assert_eq!(
+            method_hash_matches(&method("I", Some("aaaa")), &method("I", Some("bbbb"))),
+            UnknownBool::False
+        );
+        assert_eq!(
+            method_hash_matches(&method("I", Some("aaaa")), &method("I", Some("aaaa"))),
+            UnknownBool::True
+        );
+        assert_eq!(
+            method_hash_matches(&method("I", Some("aaaa")), &method("I", None)),
+            UnknownBool::Unknown
+        );
+        assert_eq!(
+            method_hash_matches(&method("I", None), &method("I", Some("aaaa"))),
+            UnknownBool::Unknown
+        );
+    }
+}
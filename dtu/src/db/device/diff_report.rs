@@ -0,0 +1,773 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::db::device::db::DeviceDatabase;
+use crate::db::device::models::*;
+use crate::db::{ApkComponent, PermissionProtected};
+use crate::utils::ClassName;
+use crate::{UnknownBool, VERSION};
+
+/// Output formats for a [DiffReport]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiffReportFormat {
+    Markdown,
+    /// A single HTML page with no external resources
+    Html,
+    /// SARIF 2.1.0, for tools that already understand static analysis results
+    Sarif,
+}
+
+impl DiffReportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+            Self::Sarif => "sarif",
+        }
+    }
+}
+
+impl FromStr for DiffReportFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "markdown" | "md" => Self::Markdown,
+            "html" => Self::Html,
+            "sarif" => Self::Sarif,
+            _ => return Err("valid values are 'markdown', 'html', and 'sarif'"),
+        })
+    }
+}
+
+impl Display for DiffReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Items left out of a [DiffReport], by database id. These are the same sets
+/// `diff ui` hides, and hiding a system service or APK also hides its methods
+/// or components.
+#[derive(Default, Clone)]
+pub struct DiffReportFilter {
+    pub hidden_system_services: HashSet<i32>,
+    pub hidden_system_service_methods: HashSet<i32>,
+    pub hidden_apks: HashSet<i32>,
+    pub hidden_providers: HashSet<i32>,
+    pub hidden_activities: HashSet<i32>,
+    pub hidden_receivers: HashSet<i32>,
+    pub hidden_services: HashSet<i32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffReportKind {
+    SystemService,
+    SystemServiceMethod,
+    Apk,
+    Provider,
+    Receiver,
+    Service,
+    Activity,
+    Permission,
+}
+
+impl DiffReportKind {
+    pub const ALL: [DiffReportKind; 8] = [
+        Self::SystemService,
+        Self::SystemServiceMethod,
+        Self::Apk,
+        Self::Provider,
+        Self::Receiver,
+        Self::Service,
+        Self::Activity,
+        Self::Permission,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SystemService => "system-service",
+            Self::SystemServiceMethod => "system-service-method",
+            Self::Apk => "apk",
+            Self::Provider => "provider",
+            Self::Receiver => "receiver",
+            Self::Service => "service",
+            Self::Activity => "activity",
+            Self::Permission => "permission",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::SystemService => "System services",
+            Self::SystemServiceMethod => "System service methods",
+            Self::Apk => "APKs",
+            Self::Provider => "Providers",
+            Self::Receiver => "Receivers",
+            Self::Service => "Services",
+            Self::Activity => "Activities",
+            Self::Permission => "Permissions",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffReportStatus {
+    /// Not in the diff source at all
+    New,
+    /// In the diff source, but something relevant differs
+    Changed,
+}
+
+impl DiffReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiffReportItem {
+    pub kind: DiffReportKind,
+    pub status: DiffReportStatus,
+    /// Database id of the item
+    pub id: i32,
+    pub name: String,
+    /// Extra information, such as the permission protecting a component or
+    /// what changed
+    pub details: Vec<String>,
+    /// Device path of the APK the item comes from, if any
+    pub location: Option<String>,
+}
+
+/// Everything that differs between the device and a diff source
+///
+/// Like `diff ui`, only new system services and APKs are included, and only
+/// enabled and exported components. Components and permissions whose
+/// protection changed and methods whose implementation hash changed are
+/// included as well.
+#[derive(Clone, Debug, Serialize)]
+pub struct DiffReport {
+    pub diff_source: String,
+    pub items: Vec<DiffReportItem>,
+}
+
+impl DiffReport {
+    pub fn from_db(
+        db: &DeviceDatabase,
+        diff_source: &DiffSource,
+        filter: &DiffReportFilter,
+    ) -> crate::Result<Self> {
+        let id = diff_source.id;
+        let apk_paths = db
+            .get_apks()?
+            .into_iter()
+            .map(|it| (it.id, String::from(it.device_path.as_device_str())))
+            .collect::<HashMap<i32, String>>();
+        let service_names = db
+            .get_system_services()?
+            .into_iter()
+            .map(|it| (it.id, it.name))
+            .collect::<HashMap<i32, String>>();
+
+        let mut items = Vec::new();
+
+        for it in db.get_system_service_diffs_by_diff_id(id)? {
+            if it.exists_in_diff || filter.hidden_system_services.contains(&it.service.id) {
+                continue;
+            }
+            items.push(DiffReportItem {
+                kind: DiffReportKind::SystemService,
+                status: DiffReportStatus::New,
+                id: it.service.id,
+                name: it.service.name.clone(),
+                details: match &it.service.iface {
+                    Some(iface) => vec![format!("interface {}", iface.get_java_name())],
+                    None => Vec::new(),
+                },
+                location: None,
+            });
+        }
+
+        for it in db.get_system_service_method_diffs_by_diff_id(id)? {
+            let m = &it.method;
+            if filter.hidden_system_services.contains(&m.system_service_id)
+                || filter.hidden_system_service_methods.contains(&m.id)
+            {
+                continue;
+            }
+            let (status, details) = if !it.exists_in_diff {
+                (DiffReportStatus::New, Vec::new())
+            } else if it.hash_matches_diff == UnknownBool::False {
+                (
+                    DiffReportStatus::Changed,
+                    vec![String::from("implementation hash differs")],
+                )
+            } else {
+                continue;
+            };
+            let service = service_names
+                .get(&m.system_service_id)
+                .map_or("?", |it| it.as_str());
+            items.push(DiffReportItem {
+                kind: DiffReportKind::SystemServiceMethod,
+                status,
+                id: m.id,
+                name: format!("{}.{}", service, m),
+                details,
+                location: None,
+            });
+        }
+
+        for it in db.get_apk_diffs_by_diff_id(id)? {
+            if it.exists_in_diff || filter.hidden_apks.contains(&it.apk.id) {
+                continue;
+            }
+            let mut details = Vec::new();
+            if it.apk.is_priv {
+                details.push(String::from("privileged"));
+            }
+            if it.apk.is_debuggable {
+                details.push(String::from("debuggable"));
+            }
+            if let Some(uid) = &it.apk.shared_user_id {
+                details.push(format!("sharedUserId {}", uid));
+            }
+            items.push(DiffReportItem {
+                kind: DiffReportKind::Apk,
+                status: DiffReportStatus::New,
+                id: it.apk.id,
+                name: it.apk.app_name.clone(),
+                details,
+                location: Some(String::from(it.apk.device_path.as_device_str())),
+            });
+        }
+
+        for it in db.get_provider_diffs_by_diff_id(id)? {
+            let p = &it.provider;
+            if !(p.enabled && p.exported)
+                || filter.hidden_apks.contains(&p.apk_id)
+                || filter.hidden_providers.contains(&p.id)
+            {
+                continue;
+            }
+            let mut details = vec![format!("authorities {}", p.authorities)];
+            let status = if !it.exists_in_diff {
+                DiffReportStatus::New
+            } else {
+                if !it.exported_matches_diff {
+                    details.push(String::from("now exported"));
+                }
+                if !it.permission_matches_diff {
+                    details.push(changed("permission", &it.diff_permission));
+                }
+                if !it.read_permission_matches_diff {
+                    details.push(changed("read permission", &it.diff_read_permission));
+                }
+                if !it.write_permission_matches_diff {
+                    details.push(changed("write permission", &it.diff_write_permission));
+                }
+                if !it.path_permissions_match_diff {
+                    details.push(String::from("path permissions differ"));
+                }
+                if !it.grant_uri_permissions_match_diff {
+                    details.push(String::from("grant uri permissions differ"));
+                }
+                if details.len() == 1 {
+                    continue;
+                }
+                DiffReportStatus::Changed
+            };
+            for (what, perm) in [
+                ("permission", &p.permission),
+                ("read permission", &p.read_permission),
+                ("write permission", &p.write_permission),
+            ] {
+                if let Some(perm) = perm {
+                    details.push(format!("{} {}", what, perm));
+                }
+            }
+            items.push(component_item(
+                DiffReportKind::Provider,
+                status,
+                p.id,
+                &p.name,
+                details,
+                apk_paths.get(&p.apk_id),
+            ));
+        }
+
+        macro_rules! ipc_items {
+            ($get:ident, $field:ident, $hidden:ident, $kind:expr) => {
+                for it in db.$get(id)? {
+                    let c = &it.$field;
+                    if !(c.enabled && c.exported)
+                        || filter.hidden_apks.contains(&c.get_apk_id())
+                        || filter.$hidden.contains(&c.id)
+                    {
+                        continue;
+                    }
+                    let mut details = Vec::new();
+                    let status = if !it.exists_in_diff {
+                        DiffReportStatus::New
+                    } else {
+                        if !it.exported_matches_diff {
+                            details.push(String::from("now exported"));
+                        }
+                        if !it.permission_matches_diff {
+                            details.push(changed("permission", &it.diff_permission));
+                        }
+                        if details.is_empty() {
+                            continue;
+                        }
+                        DiffReportStatus::Changed
+                    };
+                    match c.get_generic_permission() {
+                        Some(perm) => details.push(format!("permission {}", perm)),
+                        None => details.push(String::from("no permission required")),
+                    }
+                    items.push(component_item(
+                        $kind,
+                        status,
+                        c.id,
+                        &class_name(&c.class_name, &c.pkg),
+                        details,
+                        apk_paths.get(&c.get_apk_id()),
+                    ));
+                }
+            };
+        }
+
+        ipc_items!(
+            get_receiver_diffs_by_diff_id,
+            receiver,
+            hidden_receivers,
+            DiffReportKind::Receiver
+        );
+        ipc_items!(
+            get_service_diffs_by_diff_id,
+            service,
+            hidden_services,
+            DiffReportKind::Service
+        );
+        ipc_items!(
+            get_activity_diffs_by_diff_id,
+            activity,
+            hidden_activities,
+            DiffReportKind::Activity
+        );
+
+        for it in db.get_permission_diffs_by_diff_id(id)? {
+            let p = &it.permission;
+            if filter.hidden_apks.contains(&p.source_apk_id) {
+                continue;
+            }
+            let mut details = vec![format!("protection level {}", p.protection_level)];
+            let status = if !it.exists_in_diff {
+                DiffReportStatus::New
+            } else if !it.protection_level_matches_diff {
+                details.push(changed("protection level", &it.diff_protection_level));
+                DiffReportStatus::Changed
+            } else {
+                continue;
+            };
+            items.push(component_item(
+                DiffReportKind::Permission,
+                status,
+                p.id,
+                &p.name,
+                details,
+                apk_paths.get(&p.source_apk_id),
+            ));
+        }
+
+        Ok(Self {
+            diff_source: diff_source.name.clone(),
+            items,
+        })
+    }
+
+    /// Get all items of the given kind
+    pub fn items_of(&self, kind: DiffReportKind) -> impl Iterator<Item = &DiffReportItem> {
+        self.items.iter().filter(move |it| it.kind == kind)
+    }
+
+    fn count(&self, kind: DiffReportKind, status: DiffReportStatus) -> usize {
+        self.items_of(kind).filter(|it| it.status == status).count()
+    }
+
+    pub fn write(&self, format: DiffReportFormat, w: &mut dyn Write) -> io::Result<()> {
+        match format {
+            DiffReportFormat::Markdown => self.write_markdown(w),
+            DiffReportFormat::Html => self.write_html(w),
+            DiffReportFormat::Sarif => self.write_sarif(w),
+        }
+    }
+
+    pub fn write_markdown(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "# Diff report\n")?;
+        writeln!(w, "Diff source: `{}`\n", self.diff_source)?;
+        writeln!(w, "| | New | Changed |")?;
+        writeln!(w, "|---|---|---|")?;
+        for kind in DiffReportKind::ALL {
+            writeln!(
+                w,
+                "| {} | {} | {} |",
+                kind.title(),
+                self.count(kind, DiffReportStatus::New),
+                self.count(kind, DiffReportStatus::Changed)
+            )?;
+        }
+
+        for kind in DiffReportKind::ALL {
+            let mut items = self.items_of(kind).peekable();
+            if items.peek().is_none() {
+                continue;
+            }
+            writeln!(w, "\n## {}\n", kind.title())?;
+            for it in items {
+                write!(w, "- **{}** `{}`", it.status.as_str(), it.name)?;
+                if let Some(loc) = &it.location {
+                    write!(w, " in `{}`", loc)?;
+                }
+                if !it.details.is_empty() {
+                    write!(w, ": {}", md_escape(&it.details.join(", ")))?;
+                }
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_html(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(
+            w,
+            "<title>Diff report: {}</title>",
+            html_escape(&self.diff_source)
+        )?;
+        writeln!(w, "<style>{}</style>", HTML_STYLE)?;
+        writeln!(w, "</head>\n<body>")?;
+        writeln!(w, "<h1>Diff report</h1>")?;
+        writeln!(
+            w,
+            "<p>Diff source: <code>{}</code></p>",
+            html_escape(&self.diff_source)
+        )?;
+
+        writeln!(w, "<table>\n<tr><th></th><th>New</th><th>Changed</th></tr>")?;
+        for kind in DiffReportKind::ALL {
+            writeln!(
+                w,
+                "<tr><td><a href=\"#{}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+                kind.as_str(),
+                kind.title(),
+                self.count(kind, DiffReportStatus::New),
+                self.count(kind, DiffReportStatus::Changed)
+            )?;
+        }
+        writeln!(w, "</table>")?;
+
+        for kind in DiffReportKind::ALL {
+            let mut items = self.items_of(kind).peekable();
+            if items.peek().is_none() {
+                continue;
+            }
+            writeln!(w, "<h2 id=\"{}\">{}</h2>", kind.as_str(), kind.title())?;
+            writeln!(
+                w,
+                "<table>\n<tr><th>Status</th><th>Name</th><th>Location</th><th>Details</th></tr>"
+            )?;
+            for it in items {
+                writeln!(
+                    w,
+                    "<tr class=\"{}\"><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                    it.status.as_str(),
+                    it.status.as_str(),
+                    html_escape(&it.name),
+                    it.location.as_deref().map(html_escape).unwrap_or_default(),
+                    html_escape(&it.details.join(", "))
+                )?;
+            }
+            writeln!(w, "</table>")?;
+        }
+        writeln!(w, "</body>\n</html>")
+    }
+
+    pub fn write_sarif(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut rules = Vec::new();
+        for kind in DiffReportKind::ALL {
+            for status in [DiffReportStatus::New, DiffReportStatus::Changed] {
+                rules.push(json!({
+                    "id": sarif_rule_id(kind, status),
+                    "shortDescription": {
+                        "text": format!("{} {} relative to the diff source", status.as_str(), kind.as_str()),
+                    },
+                }));
+            }
+        }
+
+        let results = self
+            .items
+            .iter()
+            .map(|it| {
+                let mut message =
+                    format!("{} {} {}", it.status.as_str(), it.kind.as_str(), it.name);
+                if !it.details.is_empty() {
+                    message.push_str(": ");
+                    message.push_str(&it.details.join(", "));
+                }
+                let mut res = json!({
+                    "ruleId": sarif_rule_id(it.kind, it.status),
+                    "level": match it.status {
+                        DiffReportStatus::New => "note",
+                        DiffReportStatus::Changed => "warning",
+                    },
+                    "message": { "text": message },
+                    "locations": [{
+                        "logicalLocations": [{
+                            "fullyQualifiedName": it.name,
+                            "kind": it.kind.as_str(),
+                        }],
+                    }],
+                });
+                if let Some(loc) = &it.location {
+                    res["locations"][0]["physicalLocation"] = json!({
+                        "artifactLocation": { "uri": loc.trim_start_matches('/') },
+                    });
+                }
+                res
+            })
+            .collect::<Vec<_>>();
+
+        let sarif = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "dtu",
+                        "version": VERSION.to_string(),
+                        "informationUri": "https://github.com/ivision-research/dtu",
+                        "rules": rules,
+                    },
+                },
+                "properties": { "diffSource": self.diff_source },
+                "results": results,
+            }],
+        });
+        serde_json::to_writer_pretty(&mut *w, &sarif)?;
+        writeln!(w)
+    }
+}
+
+fn component_item(
+    kind: DiffReportKind,
+    status: DiffReportStatus,
+    id: i32,
+    name: &str,
+    details: Vec<String>,
+    location: Option<&String>,
+) -> DiffReportItem {
+    DiffReportItem {
+        kind,
+        status,
+        id,
+        name: String::from(name),
+        details,
+        location: location.cloned(),
+    }
+}
+
+fn class_name(class: &ClassName, pkg: &str) -> String {
+    format!("{}/{}", pkg, class.get_java_name())
+}
+
+/// Describe a changed value given what it was in the diff source
+fn changed(what: &str, old: &Option<String>) -> String {
+    format!("{} changed, was {}", what, old.as_deref().unwrap_or("none"))
+}
+
+fn sarif_rule_id(kind: DiffReportKind, status: DiffReportStatus) -> String {
+    format!("{}-{}", status.as_str(), kind.as_str())
+}
+
+fn md_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '<' | '>' | '[' | ']' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn html_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+tr.new td:first-child { color: #1a7f37; }
+tr.changed td:first-child { color: #9a6700; }
+code { word-break: break-all; }";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::device::db::test::db_test;
+    use crate::testing::{tmp_context, TestContext};
+    use rstest::*;
+
+    fn report() -> DiffReport {
+        DiffReport {
+            diff_source: String::from("emulator"),
+            items: vec![
+                DiffReportItem {
+                    kind: DiffReportKind::Receiver,
+                    status: DiffReportStatus::New,
+                    id: 1,
+                    name: String::from("com.example/com.example.Receiver"),
+                    details: vec![String::from("no permission required")],
+                    location: Some(String::from("/system/app/Example/Example.apk")),
+                },
+                DiffReportItem {
+                    kind: DiffReportKind::Permission,
+                    status: DiffReportStatus::Changed,
+                    id: 2,
+                    name: String::from("com.example.<PERM>"),
+                    details: vec![String::from("protection level changed, was signature")],
+                    location: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        let mut out = Vec::new();
+        report().write_markdown(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("| Receivers | 1 | 0 |"));
+        assert!(out.contains(
+            "- **new** `com.example/com.example.Receiver` in `/system/app/Example/Example.apk`: no permission required"
+        ));
+        assert!(!out.contains("## Activities"));
+    }
+
+    #[test]
+    fn test_write_html() {
+        let mut out = Vec::new();
+        report().write_html(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("<code>com.example.&lt;PERM&gt;</code>"));
+        assert!(!out.contains("<PERM>"));
+    }
+
+    #[test]
+    fn test_write_sarif() {
+        let mut out = Vec::new();
+        report().write_sarif(&mut out).unwrap();
+        let sarif: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "new-receiver");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "system/app/Example/Example.apk"
+        );
+        assert_eq!(results[1]["level"], "warning");
+    }
+
+    #[rstest]
+    fn test_from_db_hidden(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let emulator = db.get_diff_source_by_name("emulator").unwrap();
+            for apk in [0, 1] {
+                db.add_apk_diff(&InsertApkDiff::new(apk, emulator.id, false))
+                    .unwrap();
+            }
+            for provider in [1, 2] {
+                db.add_provider_diff(&InsertProviderDiff {
+                    provider,
+                    diff_source: emulator.id,
+                    exists_in_diff: false,
+                    exported_matches_diff: false,
+                    permission_matches_diff: false,
+                    diff_permission: None,
+                    read_permission_matches_diff: false,
+                    diff_read_permission: None,
+                    write_permission_matches_diff: false,
+                    diff_write_permission: None,
+                    path_permissions_match_diff: false,
+                    grant_uri_permissions_match_diff: false,
+                })
+                .unwrap();
+            }
+            for method in [0, 1] {
+                db.add_system_service_method_diff(&InsertSystemServiceMethodDiff {
+                    method,
+                    diff_source: emulator.id,
+                    exists_in_diff: false,
+                    hash_matches_diff: UnknownBool::False,
+                })
+                .unwrap();
+            }
+
+            let ids = |report: &DiffReport, kind: DiffReportKind| {
+                let mut ids = report.items_of(kind).map(|it| it.id).collect::<Vec<_>>();
+                ids.sort();
+                ids
+            };
+
+            let all = DiffReport::from_db(&db, &emulator, &DiffReportFilter::default()).unwrap();
+            assert_eq!(ids(&all, DiffReportKind::Apk), vec![0, 1]);
+            assert_eq!(ids(&all, DiffReportKind::Provider), vec![1, 2]);
+            assert_eq!(ids(&all, DiffReportKind::SystemServiceMethod), vec![0, 1]);
+
+            let filter = DiffReportFilter {
+                hidden_apks: HashSet::from([1]),
+                hidden_providers: HashSet::from([1]),
+                hidden_system_service_methods: HashSet::from([1]),
+                ..Default::default()
+            };
+            let report = DiffReport::from_db(&db, &emulator, &filter).unwrap();
+            assert_eq!(ids(&report, DiffReportKind::Apk), vec![0]);
+            assert_eq!(ids(&report, DiffReportKind::Provider), vec![2]);
+            assert_eq!(ids(&report, DiffReportKind::SystemServiceMethod), vec![0]);
+
+            // Hiding an APK hides its components too
+            let filter = DiffReportFilter {
+                hidden_apks: HashSet::from([0]),
+                ..Default::default()
+            };
+            let report = DiffReport::from_db(&db, &emulator, &filter).unwrap();
+            assert_eq!(ids(&report, DiffReportKind::Apk), vec![1]);
+            assert!(ids(&report, DiffReportKind::Provider).is_empty());
+        });
+    }
+}
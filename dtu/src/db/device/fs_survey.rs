@@ -0,0 +1,278 @@
+use diesel::prelude::*;
+use diesel::{delete, insert_or_ignore_into};
+use dtu_proc_macro::wraps_base_error;
+
+use crate::app_server::server::{self, AppServer};
+use crate::command::quote;
+use crate::db::device::db::DeviceDatabase;
+use crate::db::device::models::InsertFsSurveyEntry;
+use crate::db::device::schema::fs_survey_entries;
+use crate::db::Error;
+use crate::tasks::{EventMonitor, TaskCancelCheck};
+
+/// The directories surveyed by default
+pub const DEFAULT_FS_SURVEY_ROOTS: &[&str] = &["/data", "/system", "/vendor"];
+
+/// `stat` format: raw hex mode, owner, group, SELinux label, and the name
+/// last so names with spaces survive
+const STAT_FORMAT: &str = "%f %U %G %C %n";
+
+/// Events fired by the FsSurveyTask
+pub enum FsSurveyEvent {
+    RootStarted {
+        root: String,
+        /// Number of directories directly under the root that will be
+        /// searched
+        count: usize,
+    },
+    Directory {
+        path: String,
+        /// Number of entries the app could stat
+        entries: usize,
+    },
+    RootEnded {
+        root: String,
+        entries: usize,
+    },
+}
+
+type Evt = FsSurveyEvent;
+
+#[wraps_base_error]
+#[derive(Debug, thiserror::Error)]
+pub enum FsSurveyError {
+    #[error("user cancelled")]
+    Cancelled,
+    #[error("database error {0}")]
+    DB(Error),
+    #[error("app server error {0}")]
+    AppServer(server::Error),
+}
+
+impl From<Error> for FsSurveyError {
+    fn from(value: Error) -> Self {
+        Self::DB(value)
+    }
+}
+
+impl From<diesel::result::Error> for FsSurveyError {
+    fn from(value: diesel::result::Error) -> Self {
+        Self::DB(value.into())
+    }
+}
+
+impl From<server::Error> for FsSurveyError {
+    fn from(value: server::Error) -> Self {
+        Self::AppServer(value)
+    }
+}
+
+pub type FsSurveyResult<T> = Result<T, FsSurveyError>;
+
+/// A single line of `stat` output
+#[derive(Debug, PartialEq, Eq)]
+pub struct StatEntry<'a> {
+    pub path: &'a str,
+    pub mode: i32,
+    pub owner: &'a str,
+    pub group: &'a str,
+    pub selinux_label: Option<&'a str>,
+}
+
+impl<'a> StatEntry<'a> {
+    /// Parse a line of `stat -c '%f %U %G %C %n'` output
+    pub fn from_line(line: &'a str) -> Option<Self> {
+        let mut split = line.splitn(5, ' ');
+        let mode = i32::from_str_radix(split.next()?, 16).ok()?;
+        let owner = split.next()?;
+        let group = split.next()?;
+        let label = split.next()?;
+        let path = split.next()?;
+        if path.is_empty() {
+            return None;
+        }
+        Some(Self {
+            path,
+            mode,
+            owner,
+            group,
+            selinux_label: if label == "?" || label.is_empty() {
+                None
+            } else {
+                Some(label)
+            },
+        })
+    }
+
+    fn is_dir(&self) -> bool {
+        self.mode & 0o170000 == 0o040000
+    }
+
+    fn as_insert(&self) -> InsertFsSurveyEntry<'a> {
+        InsertFsSurveyEntry::new(self.path, self.mode, self.owner, self.group)
+            .set_selinux_label(self.selinux_label)
+    }
+}
+
+/// Record the mode, owner, group, and SELinux label of every file the test
+/// application can stat.
+///
+/// The searches run through the application server's `sh`, so the results
+/// reflect what an untrusted app can see. Each root is searched one
+/// directory at a time to keep the responses from the server reasonably
+/// sized. Results are stored in the `fs_survey_entries` table, replacing any
+/// previous results once every root has been surveyed.
+pub struct FsSurveyTask<'a> {
+    db: &'a DeviceDatabase,
+    server: &'a mut dyn AppServer,
+    monitor: &'a dyn EventMonitor<FsSurveyEvent>,
+    cancel: TaskCancelCheck,
+    roots: Vec<String>,
+}
+
+impl<'a> FsSurveyTask<'a> {
+    pub fn new(
+        db: &'a DeviceDatabase,
+        server: &'a mut dyn AppServer,
+        cancel: TaskCancelCheck,
+        monitor: &'a dyn EventMonitor<FsSurveyEvent>,
+    ) -> Self {
+        Self {
+            db,
+            server,
+            monitor,
+            cancel,
+            roots: DEFAULT_FS_SURVEY_ROOTS
+                .iter()
+                .map(|it| String::from(*it))
+                .collect(),
+        }
+    }
+
+    /// Set the directories to survey
+    pub fn set_roots(&mut self, roots: Vec<String>) -> &mut Self {
+        self.roots = roots;
+        self
+    }
+
+    pub fn run(&mut self) -> FsSurveyResult<()> {
+        self.cancel_check()?;
+
+        // The `stat` output is only stored once the whole survey succeeded so
+        // a cancelled or failed survey doesn't replace the previous one
+        let mut outputs = Vec::new();
+        let roots = self.roots.clone();
+        for root in roots.iter() {
+            self.survey_root(root, &mut outputs)?;
+        }
+        self.cancel_check()?;
+        self.replace_entries(&outputs)
+    }
+
+    fn survey_root(&mut self, root: &str, outputs: &mut Vec<String>) -> FsSurveyResult<()> {
+        let output = self.stat_find(root, "-maxdepth 1")?;
+        let top = output
+            .lines()
+            .filter_map(StatEntry::from_line)
+            .collect::<Vec<StatEntry>>();
+
+        let dirs = top
+            .iter()
+            .filter(|it| it.is_dir() && it.path.trim_end_matches('/') != root.trim_end_matches('/'))
+            .map(|it| String::from(it.path))
+            .collect::<Vec<String>>();
+
+        self.monitor.on_event(Evt::RootStarted {
+            root: String::from(root),
+            count: dirs.len(),
+        });
+
+        let mut total = top.len();
+        outputs.push(output);
+        for dir in dirs {
+            self.cancel_check()?;
+            let output = self.stat_find(&dir, "-mindepth 1")?;
+            let entries = output.lines().filter_map(StatEntry::from_line).count();
+            outputs.push(output);
+            total += entries;
+            self.monitor.on_event(Evt::Directory { path: dir, entries });
+        }
+
+        self.monitor.on_event(Evt::RootEnded {
+            root: String::from(root),
+            entries: total,
+        });
+        Ok(())
+    }
+
+    /// Run `find` from the application and `stat` everything it finds,
+    /// following `dir` if it is a symlink (`/vendor` often is)
+    fn stat_find(&mut self, dir: &str, args: &str) -> FsSurveyResult<String> {
+        let cmd = format!(
+            "find -H {} {} -exec stat -c {} {{}} + 2> /dev/null",
+            quote(dir),
+            args,
+            quote(STAT_FORMAT)
+        );
+        let res = self.server.sh(&cmd)?;
+        // Permission denied errors in subdirectories make find exit with an
+        // error, so the exit status is meaningless here
+        if !res.ok() {
+            log::debug!("`{}` exited with {}", cmd, res.exit);
+        }
+        Ok(res.stdout_string().into_owned())
+    }
+
+    /// Replace the stored entries with the ones in the given `stat` output
+    fn replace_entries(&self, outputs: &[String]) -> FsSurveyResult<()> {
+        self.db.with_transaction(|c| {
+            delete(fs_survey_entries::table).execute(c)?;
+            for ent in outputs
+                .iter()
+                .flat_map(|it| it.lines())
+                .filter_map(StatEntry::from_line)
+            {
+                insert_or_ignore_into(fs_survey_entries::table)
+                    .values(&ent.as_insert())
+                    .execute(c)?;
+            }
+            Ok::<(), FsSurveyError>(())
+        })
+    }
+
+    fn cancel_check(&self) -> FsSurveyResult<()> {
+        self.cancel.check(FsSurveyError::Cancelled)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stat_entry_from_line() {
+        let parsed =
+            StatEntry::from_line("41f9 shell shell u:object_r:shell_data_file:s0 /data/local/tmp")
+                .expect("should parse stat line");
+        assert_eq!(
+            parsed,
+            StatEntry {
+                path: "/data/local/tmp",
+                mode: 0o40771,
+                owner: "shell",
+                group: "shell",
+                selinux_label: Some("u:object_r:shell_data_file:s0"),
+            }
+        );
+        assert!(parsed.is_dir());
+
+        let parsed = StatEntry::from_line("81b6 root root ? /vendor/etc/some file.cfg")
+            .expect("should parse stat line with spaces");
+        assert_eq!(parsed.path, "/vendor/etc/some file.cfg");
+        assert_eq!(parsed.mode, 0o100666);
+        assert_eq!(parsed.selinux_label, None);
+
+        assert!(StatEntry::from_line("stat: permission denied").is_none());
+        assert!(StatEntry::from_line("").is_none());
+    }
+}
@@ -1,14 +1,35 @@
 pub mod db;
+pub mod diff_report;
 pub mod models;
 pub mod schema;
+pub mod smali_diff;
+pub mod weak_permissions;
 
-pub use db::EMULATOR_DIFF_SOURCE;
+pub use db::{EMULATOR_DIFF_SOURCE, PLATFORM_APP_NAME};
+pub use diff_report::*;
+pub use smali_diff::*;
+pub use weak_permissions::*;
 
 #[cfg(feature = "setup")]
 pub mod diff;
 #[cfg(feature = "setup")]
 pub use diff::*;
 
+#[cfg(feature = "setup")]
+pub mod db_diff;
+#[cfg(feature = "setup")]
+pub use db_diff::*;
+
+#[cfg(all(feature = "setup", feature = "app-server"))]
+pub mod fs_survey;
+#[cfg(all(feature = "setup", feature = "app-server"))]
+pub use fs_survey::*;
+
+#[cfg(feature = "graph")]
+pub mod permission_checks;
+#[cfg(feature = "graph")]
+pub use permission_checks::*;
+
 #[cfg(feature = "setup")]
 pub mod setup;
 #[cfg(feature = "setup")]
@@ -33,6 +33,83 @@ impl Display for DeviceProperty {
     }
 }
 
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct DevicePropertyDiff {
+    pub id: i32,
+    pub device_property: i32,
+    pub diff_source: i32,
+    pub exists_in_diff: bool,
+    pub value_matches_diff: bool,
+    pub diff_value: Option<String>,
+}
+
+/// The result of combining a DeviceProperty with a DevicePropertyDiff
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiffedDeviceProperty {
+    pub property: DeviceProperty,
+    pub exists_in_diff: bool,
+    pub value_matches_diff: bool,
+    pub diff_value: Option<String>,
+}
+
+impl Diffable for DiffedDeviceProperty {
+    fn in_diff(&self) -> bool {
+        self.exists_in_diff
+    }
+}
+
+impl Idable for DiffedDeviceProperty {
+    fn get_id(&self) -> i32 {
+        self.property.id
+    }
+}
+
+impl Display for DiffedDeviceProperty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.property)
+    }
+}
+
+impl From<(DeviceProperty, DevicePropertyDiff)> for DiffedDeviceProperty {
+    fn from(value: (DeviceProperty, DevicePropertyDiff)) -> Self {
+        let (property, diff) = value;
+        Self {
+            property,
+            exists_in_diff: diff.exists_in_diff,
+            value_matches_diff: diff.value_matches_diff,
+            diff_value: diff.diff_value,
+        }
+    }
+}
+
+impl AsRef<DeviceProperty> for DiffedDeviceProperty {
+    fn as_ref(&self) -> &DeviceProperty {
+        &self.property
+    }
+}
+
+impl Deref for DiffedDeviceProperty {
+    type Target = DeviceProperty;
+
+    fn deref(&self) -> &Self::Target {
+        &self.property
+    }
+}
+
+/// A device property flagged by a property audit rule
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct PropertyFinding {
+    pub id: i32,
+    /// Id of the rule that flagged the property
+    pub rule: String,
+    pub device_property: i32,
+    /// One of `info`, `low`, `medium` or `high`
+    pub severity: String,
+    pub description: String,
+}
+
 #[sql_db_row]
 #[derive(Serialize, Deserialize)]
 pub struct Permission {
@@ -53,6 +130,22 @@ pub struct ApkPermission {
     pub apk_id: i32,
 }
 
+/// A certificate an APK was signed with
+#[sql_db_row]
+#[derive(Associations, Selectable)]
+#[diesel(belongs_to(Apk))]
+#[diesel(table_name = apk_certificates)]
+#[derive(Serialize, Deserialize)]
+pub struct ApkCertificate {
+    pub id: i32,
+    pub apk_id: i32,
+    /// The signature scheme the certificate was found in: v1, v2, or v3
+    pub scheme: String,
+    /// Lowercase hex SHA-256 digest of the DER encoded certificate
+    pub sha256: String,
+    pub subject: String,
+}
+
 impl Display for Permission {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} - {}", self.name, self.protection_level)
@@ -159,6 +252,7 @@ pub struct Apk {
     pub is_debuggable: bool,
     pub is_priv: bool,
     pub device_path: DevicePath,
+    pub shared_user_id: Option<String>,
 }
 
 impl Apk {
@@ -586,6 +680,120 @@ impl_apk_ipc!(Receiver);
 impl_apk_ipc!(Activity);
 impl_apk_ipc!(Service);
 
+/// An intent filter declared on an activity, receiver, or service
+///
+/// Exactly one of `activity_id`, `receiver_id`, and `service_id` is set.
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct IntentFilter {
+    pub id: i32,
+    pub activity_id: Option<i32>,
+    pub receiver_id: Option<i32>,
+    pub service_id: Option<i32>,
+    pub priority: i32,
+}
+
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct IntentFilterAction {
+    pub id: i32,
+    pub filter_id: i32,
+    pub name: String,
+}
+
+#[sql_db_row]
+#[diesel(table_name = intent_filter_categories)]
+#[derive(Serialize, Deserialize)]
+pub struct IntentFilterCategory {
+    pub id: i32,
+    pub filter_id: i32,
+    pub name: String,
+}
+
+/// A single `<data>` element from an intent filter
+#[sql_db_row]
+#[diesel(table_name = intent_filter_data)]
+#[derive(Serialize, Deserialize)]
+pub struct IntentFilterData {
+    pub id: i32,
+    pub filter_id: i32,
+    pub scheme: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub path: Option<String>,
+    pub path_pattern: Option<String>,
+    pub path_prefix: Option<String>,
+    pub path_suffix: Option<String>,
+    pub path_advanced_pattern: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+/// An [IntentFilter] along with all of its actions, categories, and data
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IntentFilterDetails {
+    pub filter: IntentFilter,
+    pub actions: Vec<String>,
+    pub categories: Vec<String>,
+    pub data: Vec<IntentFilterData>,
+}
+
+impl IntentFilterDetails {
+    pub fn has_action(&self, action: &str) -> bool {
+        self.actions.iter().any(|it| it == action)
+    }
+
+    pub fn has_category(&self, category: &str) -> bool {
+        self.categories.iter().any(|it| it == category)
+    }
+
+    /// All schemes accepted by the filter
+    pub fn schemes(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().filter_map(|it| it.scheme.as_deref())
+    }
+
+    /// All hosts accepted by the filter
+    pub fn hosts(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().filter_map(|it| it.host.as_deref())
+    }
+
+    /// All MIME types accepted by the filter
+    pub fn mime_types(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().filter_map(|it| it.mime_type.as_deref())
+    }
+}
+
+impl Deref for IntentFilterDetails {
+    type Target = IntentFilter;
+
+    fn deref(&self) -> &Self::Target {
+        &self.filter
+    }
+}
+
+/// Search parameters for finding components by their intent filters
+///
+/// Every given value has to match the same intent filter. Android combines the
+/// attributes of all `<data>` elements in a filter, so the scheme and host
+/// don't need to come from the same element.
+#[derive(Default, Clone, Copy)]
+pub struct IntentFilterSearch<'a> {
+    pub action: Option<&'a str>,
+    pub category: Option<&'a str>,
+    pub scheme: Option<&'a str>,
+    pub host: Option<&'a str>,
+    pub mime_type: Option<&'a str>,
+}
+
+impl<'a> IntentFilterSearch<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.action.is_none()
+            && self.category.is_none()
+            && self.scheme.is_none()
+            && self.host.is_none()
+            && self.mime_type.is_none()
+    }
+}
+
 // TODO Eventually the schema should just have another table for authorities
 //  so we can do a join
 
@@ -669,6 +877,167 @@ impl Provider {
     }
 }
 
+/// The path a `<path-permission>` or `<grant-uri-permission>` applies to
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize)]
+pub enum UriPathPattern<'a> {
+    Path(&'a str),
+    Prefix(&'a str),
+    Pattern(&'a str),
+    AdvancedPattern(&'a str),
+    Suffix(&'a str),
+}
+
+impl<'a> UriPathPattern<'a> {
+    /// Get the pattern from the path attributes of an element, Android only
+    /// looks at the first one set in this order
+    fn from_attrs(
+        path: &'a Option<String>,
+        prefix: &'a Option<String>,
+        pattern: &'a Option<String>,
+        advanced: &'a Option<String>,
+        suffix: &'a Option<String>,
+    ) -> Option<Self> {
+        if let Some(v) = path {
+            Some(Self::Path(v))
+        } else if let Some(v) = prefix {
+            Some(Self::Prefix(v))
+        } else if let Some(v) = pattern {
+            Some(Self::Pattern(v))
+        } else if let Some(v) = advanced {
+            Some(Self::AdvancedPattern(v))
+        } else {
+            suffix.as_deref().map(Self::Suffix)
+        }
+    }
+}
+
+impl Display for UriPathPattern<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path(v) => write!(f, "path={}", v),
+            Self::Prefix(v) => write!(f, "pathPrefix={}", v),
+            Self::Pattern(v) => write!(f, "pathPattern={}", v),
+            Self::AdvancedPattern(v) => write!(f, "pathAdvancedPattern={}", v),
+            Self::Suffix(v) => write!(f, "pathSuffix={}", v),
+        }
+    }
+}
+
+/// A `<path-permission>` on a provider
+#[sql_db_row]
+#[derive(Associations, Selectable)]
+#[diesel(belongs_to(Provider))]
+#[diesel(table_name = provider_path_permissions)]
+#[derive(Serialize, Deserialize)]
+pub struct ProviderPathPermission {
+    pub id: i32,
+    pub provider_id: i32,
+    pub path: Option<String>,
+    pub path_prefix: Option<String>,
+    pub path_pattern: Option<String>,
+    pub path_advanced_pattern: Option<String>,
+    pub path_suffix: Option<String>,
+    pub permission: Option<String>,
+    pub read_permission: Option<String>,
+    pub write_permission: Option<String>,
+}
+
+impl ProviderPathPermission {
+    pub fn uri_pattern(&self) -> Option<UriPathPattern<'_>> {
+        UriPathPattern::from_attrs(
+            &self.path,
+            &self.path_prefix,
+            &self.path_pattern,
+            &self.path_advanced_pattern,
+            &self.path_suffix,
+        )
+    }
+}
+
+impl PermissionProtected for ProviderPathPermission {
+    fn get_generic_permission(&self) -> Option<&str> {
+        self.permission.as_deref()
+    }
+
+    fn get_permission_for_mode(&self, mode: PermissionMode) -> Option<&str> {
+        match mode {
+            PermissionMode::Read => self.read_permission.as_deref(),
+            PermissionMode::Write => self.write_permission.as_deref(),
+            PermissionMode::Generic => self.get_generic_permission(),
+            PermissionMode::Any => self
+                .get_generic_permission()
+                .or_else(|| self.get_permission_for_mode(PermissionMode::Read))
+                .or_else(|| self.get_permission_for_mode(PermissionMode::Write)),
+        }
+    }
+}
+
+impl Display for ProviderPathPermission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.uri_pattern() {
+            Some(p) => write!(f, "{}", p)?,
+            None => f.write_str("<no path>")?,
+        }
+        let read = self.read_permission.as_deref().unwrap_or("none");
+        let write = self.write_permission.as_deref().unwrap_or("none");
+        write!(f, " read={} write={}", read, write)
+    }
+}
+
+/// A `<grant-uri-permission>` on a provider
+#[sql_db_row]
+#[derive(Associations, Selectable)]
+#[diesel(belongs_to(Provider))]
+#[diesel(table_name = provider_grant_uri_permissions)]
+#[derive(Serialize, Deserialize)]
+pub struct ProviderGrantUriPermission {
+    pub id: i32,
+    pub provider_id: i32,
+    pub path: Option<String>,
+    pub path_prefix: Option<String>,
+    pub path_pattern: Option<String>,
+    pub path_advanced_pattern: Option<String>,
+    pub path_suffix: Option<String>,
+}
+
+impl ProviderGrantUriPermission {
+    pub fn uri_pattern(&self) -> Option<UriPathPattern<'_>> {
+        UriPathPattern::from_attrs(
+            &self.path,
+            &self.path_prefix,
+            &self.path_pattern,
+            &self.path_advanced_pattern,
+            &self.path_suffix,
+        )
+    }
+}
+
+impl Display for ProviderGrantUriPermission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.uri_pattern() {
+            Some(p) => write!(f, "{}", p),
+            None => f.write_str("<no path>"),
+        }
+    }
+}
+
+/// A provider along with its `<path-permission>` and `<grant-uri-permission>`
+/// entries
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProviderWithPaths {
+    pub provider: Provider,
+    pub path_permissions: Vec<ProviderPathPermission>,
+    pub grant_uri_patterns: Vec<ProviderGrantUriPermission>,
+}
+
+impl Deref for ProviderWithPaths {
+    type Target = Provider;
+
+    fn deref(&self) -> &Self::Target {
+        &self.provider
+    }
+}
+
 #[sql_db_row]
 #[derive(Serialize, Deserialize)]
 pub struct ProviderDiff {
@@ -683,6 +1052,8 @@ pub struct ProviderDiff {
     pub diff_write_permission: Option<String>,
     pub read_permission_matches_diff: bool,
     pub diff_read_permission: Option<String>,
+    pub path_permissions_match_diff: bool,
+    pub grant_uri_permissions_match_diff: bool,
 }
 
 /// The result of combining an Provider with an ProviderDiff
@@ -697,6 +1068,11 @@ pub struct DiffedProvider {
     pub diff_write_permission: Option<String>,
     pub read_permission_matches_diff: bool,
     pub diff_read_permission: Option<String>,
+    /// Whether the `<path-permission>` entries are the same in the diff
+    pub path_permissions_match_diff: bool,
+    /// Whether `grantUriPermissions` and the `<grant-uri-permission>` entries
+    /// are the same in the diff
+    pub grant_uri_permissions_match_diff: bool,
 }
 
 impl Diffable for DiffedProvider {
@@ -732,6 +1108,9 @@ impl From<(Provider, ProviderDiff)> for DiffedProvider {
 
             write_permission_matches_diff: diff.write_permission_matches_diff,
             diff_write_permission: diff.diff_write_permission,
+
+            path_permissions_match_diff: diff.path_permissions_match_diff,
+            grant_uri_permissions_match_diff: diff.grant_uri_permissions_match_diff,
         }
     }
 }
@@ -899,6 +1278,35 @@ impl Deref for DiffedSystemServiceMethod {
     }
 }
 
+/// A permission check reachable from a system service method
+///
+/// These come from statically analyzing the graph database, see
+/// [crate::db::device::PermissionCheckTask].
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct PermissionCheck {
+    pub id: i32,
+    pub method: i32,
+    pub system_service_impl: i32,
+    pub check_class: ClassName,
+    pub check_method: String,
+    /// The permission being checked if a constant string could be found
+    pub permission: Option<String>,
+    /// How many calls away from the system service method the check is
+    pub depth: i32,
+}
+
+impl Display for PermissionCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let perm = self
+            .permission
+            .as_ref()
+            .map(|it| it.as_str())
+            .unwrap_or("?");
+        write!(f, "{}->{}({})", self.check_class, self.check_method, perm)
+    }
+}
+
 #[sql_db_row]
 #[derive(Serialize, Deserialize)]
 pub struct SystemService {
@@ -979,11 +1387,524 @@ impl Deref for DiffedSystemService {
     }
 }
 
+/// A native service defined by a `service` block in an init `.rc` file
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct NativeService {
+    pub id: i32,
+    pub name: String,
+    /// Path to the binary being executed
+    pub path: String,
+    /// Space separated arguments passed to the binary
+    pub args: String,
+    pub user: String,
+    /// Space separated primary and supplementary groups
+    pub groups: String,
+    /// Space separated capabilities, `None` if the service didn't restrict
+    /// them
+    pub capabilities: Option<String>,
+    pub seclabel: Option<String>,
+    /// Space separated init classes
+    pub classes: String,
+    pub disabled: bool,
+    pub oneshot: bool,
+    /// The `.rc` file the service was defined in
+    pub rc_file: String,
+}
+
+impl NativeService {
+    pub fn runs_as_root(&self) -> bool {
+        self.user == "root" || self.user == "0"
+    }
+
+    /// Whether the service is defined in one of the vendor or odm partitions
+    pub fn is_vendor(&self) -> bool {
+        self.rc_file.starts_with("/vendor/") || self.rc_file.starts_with("/odm/")
+    }
+
+    pub fn group_list(&self) -> Vec<&str> {
+        self.groups.split_whitespace().collect()
+    }
+
+    pub fn capability_list(&self) -> Option<Vec<&str>> {
+        self.capabilities
+            .as_ref()
+            .map(|it| it.split_whitespace().collect())
+    }
+}
+
+impl Display for NativeService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}) [{}]", self.name, self.path, self.user)
+    }
+}
+
+/// A `socket` option on a native service
+#[sql_db_row]
+#[derive(Associations, Selectable)]
+#[diesel(belongs_to(NativeService, foreign_key = service_id))]
+#[diesel(table_name = native_service_sockets)]
+#[derive(Serialize, Deserialize)]
+pub struct NativeServiceSocket {
+    pub id: i32,
+    pub service_id: i32,
+    /// Name of the socket, it is created as `/dev/socket/<name>`
+    pub name: String,
+    pub socket_type: String,
+    /// Octal permissions of the socket
+    pub perm: String,
+    pub user: Option<String>,
+    pub group_name: Option<String>,
+    pub seclabel: Option<String>,
+}
+
+impl Display for NativeServiceSocket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}:{}",
+            self.name,
+            self.socket_type,
+            self.perm,
+            self.user.as_ref().map(|it| it.as_str()).unwrap_or("root"),
+            self.group_name
+                .as_ref()
+                .map(|it| it.as_str())
+                .unwrap_or("root"),
+        )?;
+        if let Some(label) = &self.seclabel {
+            write!(f, " {}", label)?;
+        }
+        Ok(())
+    }
+}
+
+/// The trigger of an `on` action that starts a native service
+#[sql_db_row]
+#[derive(Associations, Selectable)]
+#[diesel(belongs_to(NativeService, foreign_key = service_id))]
+#[diesel(table_name = native_service_triggers)]
+#[derive(Serialize, Deserialize)]
+pub struct NativeServiceTrigger {
+    pub id: i32,
+    pub service_id: i32,
+    pub on_trigger: String,
+}
+
+impl Display for NativeServiceTrigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "on {}", self.on_trigger)
+    }
+}
+
+/// A native service along with its sockets and triggers
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NativeServiceWithDetails {
+    pub service: NativeService,
+    pub sockets: Vec<NativeServiceSocket>,
+    pub triggers: Vec<NativeServiceTrigger>,
+}
+
+impl Deref for NativeServiceWithDetails {
+    type Target = NativeService;
+
+    fn deref(&self) -> &Self::Target {
+        &self.service
+    }
+}
+
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct NativeServiceDiff {
+    pub id: i32,
+    pub native_service: i32,
+    pub diff_source: i32,
+    pub exists_in_diff: bool,
+    pub path_matches_diff: bool,
+    pub diff_path: Option<String>,
+    pub user_matches_diff: bool,
+    pub diff_user: Option<String>,
+    pub capabilities_matches_diff: bool,
+    pub diff_capabilities: Option<String>,
+    pub seclabel_matches_diff: bool,
+    pub diff_seclabel: Option<String>,
+    pub sockets_match_diff: bool,
+}
+
+/// The result of combining a NativeService with a NativeServiceDiff
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiffedNativeService {
+    pub service: NativeService,
+    pub exists_in_diff: bool,
+    pub path_matches_diff: bool,
+    pub diff_path: Option<String>,
+    pub user_matches_diff: bool,
+    pub diff_user: Option<String>,
+    pub capabilities_matches_diff: bool,
+    pub diff_capabilities: Option<String>,
+    pub seclabel_matches_diff: bool,
+    pub diff_seclabel: Option<String>,
+    pub sockets_match_diff: bool,
+}
+
+impl Diffable for DiffedNativeService {
+    fn in_diff(&self) -> bool {
+        self.exists_in_diff
+    }
+}
+
+impl Idable for DiffedNativeService {
+    fn get_id(&self) -> i32 {
+        self.service.id
+    }
+}
+
+impl Display for DiffedNativeService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.service)
+    }
+}
+
+impl From<(NativeService, NativeServiceDiff)> for DiffedNativeService {
+    fn from(value: (NativeService, NativeServiceDiff)) -> Self {
+        let (service, diff) = value;
+        Self {
+            service,
+            exists_in_diff: diff.exists_in_diff,
+            path_matches_diff: diff.path_matches_diff,
+            diff_path: diff.diff_path,
+            user_matches_diff: diff.user_matches_diff,
+            diff_user: diff.diff_user,
+            capabilities_matches_diff: diff.capabilities_matches_diff,
+            diff_capabilities: diff.diff_capabilities,
+            seclabel_matches_diff: diff.seclabel_matches_diff,
+            diff_seclabel: diff.diff_seclabel,
+            sockets_match_diff: diff.sockets_match_diff,
+        }
+    }
+}
+
+impl AsRef<NativeService> for DiffedNativeService {
+    fn as_ref(&self) -> &NativeService {
+        &self.service
+    }
+}
+
+impl Deref for DiffedNativeService {
+    type Target = NativeService;
+
+    fn deref(&self) -> &Self::Target {
+        &self.service
+    }
+}
+
+/// A HIDL or AIDL HAL service
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct HalService {
+    pub id: i32,
+    /// Fully qualified interface name, `package@version::IName` for HIDL and
+    /// `package.IName` for AIDL
+    pub interface: String,
+    pub instance: String,
+    /// Either `hidl` or `aidl`
+    pub kind: String,
+    /// `hwbinder` or `passthrough` for HIDL, `binder` or `vndbinder` for AIDL
+    pub transport: String,
+    pub server_pid: Option<i32>,
+    pub server_binary: Option<String>,
+    /// The SELinux type of the service from the service contexts
+    pub selinux_type: Option<String>,
+    /// Whether an untrusted app is allowed to find the service
+    pub reachable: UnknownBool,
+}
+
+impl HalService {
+    pub fn is_hidl(&self) -> bool {
+        self.kind == "hidl"
+    }
+
+    pub fn is_aidl(&self) -> bool {
+        self.kind == "aidl"
+    }
+
+    /// Whether the HAL is served by a binary in the vendor or odm partitions
+    pub fn is_vendor(&self) -> bool {
+        self.server_binary.as_ref().map_or(false, |it| {
+            it.starts_with("/vendor/") || it.starts_with("/odm/")
+        }) || self.interface.starts_with("vendor.")
+    }
+}
+
+impl Display for HalService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} ({} {})",
+            self.interface, self.instance, self.kind, self.transport
+        )?;
+        if let Some(bin) = &self.server_binary {
+            write!(f, " {}", bin)?;
+        }
+        Ok(())
+    }
+}
+
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct HalServiceDiff {
+    pub id: i32,
+    pub hal_service: i32,
+    pub diff_source: i32,
+    pub exists_in_diff: bool,
+    pub transport_matches_diff: bool,
+    pub diff_transport: Option<String>,
+}
+
+/// The result of combining a HalService with a HalServiceDiff
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiffedHalService {
+    pub service: HalService,
+    pub exists_in_diff: bool,
+    pub transport_matches_diff: bool,
+    pub diff_transport: Option<String>,
+}
+
+impl Diffable for DiffedHalService {
+    fn in_diff(&self) -> bool {
+        self.exists_in_diff
+    }
+}
+
+impl Idable for DiffedHalService {
+    fn get_id(&self) -> i32 {
+        self.service.id
+    }
+}
+
+impl Display for DiffedHalService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.service)
+    }
+}
+
+impl From<(HalService, HalServiceDiff)> for DiffedHalService {
+    fn from(value: (HalService, HalServiceDiff)) -> Self {
+        let (service, diff) = value;
+        Self {
+            service,
+            exists_in_diff: diff.exists_in_diff,
+            transport_matches_diff: diff.transport_matches_diff,
+            diff_transport: diff.diff_transport,
+        }
+    }
+}
+
+impl AsRef<HalService> for DiffedHalService {
+    fn as_ref(&self) -> &HalService {
+        &self.service
+    }
+}
+
+impl Deref for DiffedHalService {
+    type Target = HalService;
+
+    fn deref(&self) -> &Self::Target {
+        &self.service
+    }
+}
+
+/// A file found by the filesystem survey, run as the test application
+#[sql_db_row]
+#[diesel(table_name = fs_survey_entries)]
+#[derive(Serialize, Deserialize)]
+pub struct FsSurveyEntry {
+    pub id: i32,
+    pub path: String,
+    /// The full `st_mode`, including the file type bits
+    pub mode: i32,
+    pub owner: String,
+    pub group_name: String,
+    pub selinux_label: Option<String>,
+}
+
+const S_IFMT: i32 = 0o170000;
+const S_IFDIR: i32 = 0o040000;
+const S_IFLNK: i32 = 0o120000;
+const S_IFREG: i32 = 0o100000;
+
+impl FsSurveyEntry {
+    /// The permission bits, including setuid, setgid, and sticky
+    pub fn perms(&self) -> i32 {
+        self.mode & 0o7777
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+
+    pub fn is_world_writable(&self) -> bool {
+        !self.is_symlink() && self.mode & 0o002 != 0
+    }
+
+    pub fn is_world_readable(&self) -> bool {
+        !self.is_symlink() && self.mode & 0o004 != 0
+    }
+
+    pub fn is_setuid(&self) -> bool {
+        self.mode & 0o4000 != 0
+    }
+
+    pub fn is_setgid(&self) -> bool {
+        self.mode & 0o2000 != 0
+    }
+
+    /// Get the mode in `ls -l` format, for example `drwxrwx--x`
+    pub fn mode_string(&self) -> String {
+        fmt_mode(self.mode)
+    }
+}
+
+/// Format a `st_mode` in `ls -l` format
+pub fn fmt_mode(mode: i32) -> String {
+    let mut s = String::with_capacity(10);
+    s.push(match mode & S_IFMT {
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFREG => '-',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '?',
+    });
+    let special = [(0o4000, 's'), (0o2000, 's'), (0o1000, 't')];
+    for (i, (bit, c)) in special.into_iter().enumerate() {
+        let shift = 6 - i * 3;
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(match (bits & 0o1 != 0, mode & bit != 0) {
+            (true, true) => c,
+            (false, true) => c.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+impl Display for FsSurveyEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.mode_string(),
+            self.owner,
+            self.group_name,
+            self.selinux_label.as_deref().unwrap_or("?"),
+            self.path
+        )
+    }
+}
+
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct FsSurveyEntryDiff {
+    pub id: i32,
+    pub fs_survey_entry: i32,
+    pub diff_source: i32,
+    pub exists_in_diff: bool,
+    pub mode_matches_diff: bool,
+    pub diff_mode: Option<i32>,
+    pub owner_matches_diff: bool,
+    pub diff_owner: Option<String>,
+    pub group_matches_diff: bool,
+    pub diff_group: Option<String>,
+    pub label_matches_diff: bool,
+    pub diff_label: Option<String>,
+}
+
+/// The result of combining a FsSurveyEntry with a FsSurveyEntryDiff
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiffedFsSurveyEntry {
+    pub entry: FsSurveyEntry,
+    pub exists_in_diff: bool,
+    pub mode_matches_diff: bool,
+    pub diff_mode: Option<i32>,
+    pub owner_matches_diff: bool,
+    pub diff_owner: Option<String>,
+    pub group_matches_diff: bool,
+    pub diff_group: Option<String>,
+    pub label_matches_diff: bool,
+    pub diff_label: Option<String>,
+}
+
+impl Diffable for DiffedFsSurveyEntry {
+    fn in_diff(&self) -> bool {
+        self.exists_in_diff
+    }
+}
+
+impl Idable for DiffedFsSurveyEntry {
+    fn get_id(&self) -> i32 {
+        self.entry.id
+    }
+}
+
+impl Display for DiffedFsSurveyEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.entry)
+    }
+}
+
+impl From<(FsSurveyEntry, FsSurveyEntryDiff)> for DiffedFsSurveyEntry {
+    fn from(value: (FsSurveyEntry, FsSurveyEntryDiff)) -> Self {
+        let (entry, diff) = value;
+        Self {
+            entry,
+            exists_in_diff: diff.exists_in_diff,
+            mode_matches_diff: diff.mode_matches_diff,
+            diff_mode: diff.diff_mode,
+            owner_matches_diff: diff.owner_matches_diff,
+            diff_owner: diff.diff_owner,
+            group_matches_diff: diff.group_matches_diff,
+            diff_group: diff.diff_group,
+            label_matches_diff: diff.label_matches_diff,
+            diff_label: diff.diff_label,
+        }
+    }
+}
+
+impl AsRef<FsSurveyEntry> for DiffedFsSurveyEntry {
+    fn as_ref(&self) -> &FsSurveyEntry {
+        &self.entry
+    }
+}
+
+impl Deref for DiffedFsSurveyEntry {
+    type Target = FsSurveyEntry;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entry
+    }
+}
+
 #[sql_db_row]
 #[derive(Serialize, Deserialize)]
 pub struct DiffSource {
     pub id: i32,
     pub name: String,
+    /// Directory holding the diff source's smali, laid out like the project smali directory
+    pub smali_dir: Option<String>,
 }
 
 impl Display for DiffSource {
@@ -1002,3 +1923,23 @@ pub struct FuzzResult {
     pub exception_thrown: bool,
     pub security_exception_thrown: bool,
 }
+
+/// A single call made by `fuzz binder`
+#[sql_db_row]
+#[derive(Serialize, Deserialize)]
+pub struct BinderFuzzResult {
+    pub id: i32,
+    /// The system service method that was called
+    pub method: i32,
+    /// What the input was meant to test
+    pub description: String,
+    /// The Parcel string that was sent
+    pub parcel: String,
+    /// One of `ok`, `exception`, `security-exception`, `timeout`, `crash` or
+    /// `error`
+    pub outcome: String,
+    /// The name of the exception in the reply, if any
+    pub exception: Option<String>,
+    /// The exception message, or the error for failed calls
+    pub message: Option<String>,
+}
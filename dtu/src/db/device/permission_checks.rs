@@ -0,0 +1,413 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use diesel::{delete, insert_into};
+use dtu_proc_macro::wraps_base_error;
+
+use crate::db::device::db::DeviceDatabase;
+use crate::db::device::models::{InsertPermissionCheck, SystemServiceImpl, SystemServiceMethod};
+use crate::db::device::schema::permission_checks;
+use crate::db::graph::models::{MethodSearch, MethodSearchParams, MethodSpec};
+use crate::db::graph::GraphDatabase;
+use crate::db::Error;
+use crate::tasks::{EventMonitor, TaskCancelCheck};
+use crate::utils::ClassName;
+
+/// Method names that check or enforce the calling permission when invoked on
+/// a `Context` (or anything that looks like one)
+const CONTEXT_CHECK_METHODS: &[&str] = &[
+    "enforceCallingOrSelfPermission",
+    "enforceCallingPermission",
+    "enforcePermission",
+    "checkCallingOrSelfPermission",
+    "checkCallingPermission",
+    "checkPermission",
+];
+
+/// Classes where every method is considered a permission check
+const PERMISSION_CHECKER_CLASSES: &[&str] = &[
+    "Landroid/content/PermissionChecker;",
+    "Landroid/permission/PermissionChecker;",
+    "Landroid/permission/PermissionCheckerManager;",
+    "Landroidx/core/content/PermissionChecker;",
+];
+
+/// Default call depth to search for permission checks
+pub const DEFAULT_PERMISSION_CHECK_DEPTH: usize = 3;
+
+/// Events fired by the PermissionCheckTask
+pub enum PermissionCheckEvent {
+    SystemServicesStarted {
+        count: usize,
+    },
+    SystemService {
+        id: i32,
+        name: String,
+        /// Number of methods analyzed
+        methods: usize,
+        /// Number of methods that didn't reach any permission check
+        unchecked: usize,
+    },
+    SystemServicesEnded,
+}
+
+type Evt = PermissionCheckEvent;
+
+#[wraps_base_error]
+#[derive(Debug, thiserror::Error)]
+pub enum PermissionCheckError {
+    #[error("user cancelled")]
+    Cancelled,
+    #[error("database error {0}")]
+    DB(Error),
+}
+
+impl From<Error> for PermissionCheckError {
+    fn from(value: Error) -> Self {
+        Self::DB(value)
+    }
+}
+
+impl From<diesel::result::Error> for PermissionCheckError {
+    fn from(value: diesel::result::Error) -> Self {
+        Self::DB(value.into())
+    }
+}
+
+pub type PermissionCheckResult<T> = Result<T, PermissionCheckError>;
+
+/// Statically determine which permission checks every system service method
+/// reaches.
+///
+/// For each method of each [SystemServiceImpl], the outgoing calls in the
+/// graph database are searched for calls to known permission checks such as
+/// `Context.enforceCallingOrSelfPermission` or `PermissionChecker`. The
+/// permission strings are taken from the constant strings in the method that
+/// performs the check. Results are stored in the `permission_checks` table,
+/// replacing any previous results once every service has been analyzed.
+pub struct PermissionCheckTask<'a> {
+    db: &'a DeviceDatabase,
+    graph: &'a dyn GraphDatabase,
+    monitor: &'a dyn EventMonitor<PermissionCheckEvent>,
+    cancel: TaskCancelCheck,
+    depth: usize,
+    known_permissions: HashSet<String>,
+}
+
+impl<'a> PermissionCheckTask<'a> {
+    pub fn new(
+        db: &'a DeviceDatabase,
+        graph: &'a dyn GraphDatabase,
+        cancel: TaskCancelCheck,
+        monitor: &'a dyn EventMonitor<PermissionCheckEvent>,
+    ) -> Self {
+        Self {
+            db,
+            graph,
+            monitor,
+            cancel,
+            depth: DEFAULT_PERMISSION_CHECK_DEPTH,
+            known_permissions: HashSet::new(),
+        }
+    }
+
+    /// Set the call depth to search for permission checks
+    pub fn set_depth(&mut self, depth: usize) -> &mut Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn run(&mut self) -> PermissionCheckResult<()> {
+        self.cancel_check()?;
+
+        self.known_permissions = self
+            .db
+            .get_permissions()?
+            .into_iter()
+            .map(|it| it.name)
+            .collect();
+
+        let services = self.db.get_system_services()?;
+        let impls = self.db.get_all_system_service_impls()?;
+
+        self.monitor.on_event(Evt::SystemServicesStarted {
+            count: services.len(),
+        });
+
+        let mut checks = Vec::new();
+
+        for service in services {
+            self.cancel_check()?;
+            let service_impls = match impls.get(&service.name) {
+                Some(v) => v,
+                None => {
+                    log::debug!("no implementations for {}", service.name);
+                    continue;
+                }
+            };
+
+            let methods = self
+                .db
+                .get_system_service_methods_by_service_id(service.id)?;
+            let mut unchecked = 0;
+
+            for m in methods.iter() {
+                self.cancel_check()?;
+                let before = checks.len();
+                for imp in service_impls {
+                    self.find_checks(imp, m, &mut checks)?;
+                }
+                if checks.len() == before {
+                    unchecked += 1;
+                }
+            }
+
+            self.monitor.on_event(Evt::SystemService {
+                id: service.id,
+                name: service.name,
+                methods: methods.len(),
+                unchecked,
+            });
+        }
+
+        self.cancel_check()?;
+
+        // Replace the previous results all at once, a partial table would
+        // report every method that wasn't reached yet as unchecked
+        self.db.with_transaction(|c| -> QueryResult<()> {
+            delete(permission_checks::table).execute(c)?;
+            for chunk in checks.chunks(1000) {
+                let ins = chunk
+                    .iter()
+                    .map(|it| it.as_insert())
+                    .collect::<Vec<InsertPermissionCheck>>();
+                insert_into(permission_checks::table)
+                    .values(ins.as_slice())
+                    .execute(c)?;
+            }
+            Ok(())
+        })?;
+
+        self.monitor.on_event(Evt::SystemServicesEnded);
+        Ok(())
+    }
+
+    /// Find all permission checks reached by the given method in the given
+    /// implementation
+    fn find_checks(
+        &self,
+        imp: &SystemServiceImpl,
+        method: &SystemServiceMethod,
+        into: &mut Vec<FoundCheck>,
+    ) -> PermissionCheckResult<()> {
+        let param = match method.signature.as_ref() {
+            Some(sig) => MethodSearchParams::ByFullSpec {
+                class: &imp.class_name,
+                name: &method.name,
+                signature: sig,
+            },
+            None => MethodSearchParams::ByClassAndName {
+                class: &imp.class_name,
+                name: &method.name,
+            },
+        };
+        let search = MethodSearch::new(param, Some(&imp.source));
+
+        let paths = self.graph.find_outgoing_calls(&search, self.depth)?;
+
+        // The same check can be reached by a lot of paths, only keep the
+        // closest one for each check and permission
+        let mut found: HashMap<(ClassName, String, Option<String>), i32> = HashMap::new();
+
+        for p in paths {
+            let callee = p.must_get_dst_method();
+            if !is_permission_check(callee) {
+                continue;
+            }
+            let depth = (p.path.len() - 1) as i32;
+
+            // The permission string is expected to be loaded by the method
+            // calling the check
+            let caller = &p.path[p.path.len() - 2];
+            let perms = self
+                .graph
+                .get_strings_for_method(caller.id)?
+                .into_iter()
+                .filter(|it| self.is_permission_string(it))
+                .collect::<Vec<String>>();
+
+            let keys = if perms.is_empty() {
+                vec![None]
+            } else {
+                perms.into_iter().map(Some).collect()
+            };
+
+            for perm in keys {
+                let key = (callee.class.clone(), callee.name.clone(), perm);
+                let entry = found.entry(key).or_insert(depth);
+                if depth < *entry {
+                    *entry = depth;
+                }
+            }
+        }
+
+        into.extend(
+            found.into_iter().map(
+                |((check_class, check_method, permission), depth)| FoundCheck {
+                    method: method.id,
+                    system_service_impl: imp.id,
+                    check_class,
+                    check_method,
+                    permission,
+                    depth,
+                },
+            ),
+        );
+
+        Ok(())
+    }
+
+    fn is_permission_string(&self, s: &str) -> bool {
+        self.known_permissions.contains(s) || s.contains(".permission.")
+    }
+
+    #[inline]
+    fn cancel_check(&self) -> PermissionCheckResult<()> {
+        self.cancel.check(PermissionCheckError::Cancelled)
+    }
+}
+
+struct FoundCheck {
+    method: i32,
+    system_service_impl: i32,
+    check_class: ClassName,
+    check_method: String,
+    permission: Option<String>,
+    depth: i32,
+}
+
+impl FoundCheck {
+    fn as_insert(&self) -> InsertPermissionCheck<'_> {
+        InsertPermissionCheck::new(
+            self.method,
+            self.system_service_impl,
+            self.check_class.clone(),
+            &self.check_method,
+            self.depth,
+        )
+        .set_permission(self.permission.as_ref().map(|it| it.as_str()))
+    }
+}
+
+/// Determine whether the given method is a known permission check
+pub fn is_permission_check(method: &MethodSpec) -> bool {
+    if PERMISSION_CHECKER_CLASSES
+        .iter()
+        .any(|it| method.class == *it)
+    {
+        return true;
+    }
+    CONTEXT_CHECK_METHODS.contains(&method.name.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::device::db::test::db_test;
+    use crate::db::graph::db::test::db_test as graph_db_test;
+    use crate::tasks::{NoopMonitor, TaskCanceller};
+    use crate::testing::{tmp_context, TestContext};
+    use diesel::connection::SimpleConnection;
+    use rstest::*;
+    use smalisa::AccessFlag;
+
+    fn method(class: &str, name: &str) -> MethodSpec {
+        MethodSpec {
+            id: 1,
+            class: ClassName::from(class),
+            name: name.into(),
+            signature: "Ljava/lang/String;Ljava/lang/String;".into(),
+            ret: "V".into(),
+            source: "framework".into(),
+            access_flags: AccessFlag::PUBLIC,
+        }
+    }
+
+    #[rstest]
+    #[case("Landroid/content/Context;", "enforceCallingOrSelfPermission", true)]
+    #[case("Lcom/android/server/FooService;", "checkCallingPermission", true)]
+    #[case(
+        "Landroid/content/PermissionChecker;",
+        "checkCallingOrSelfPermissionForDataDelivery",
+        true
+    )]
+    #[case("Landroid/content/Context;", "getSystemService", false)]
+    #[case("Lcom/android/server/FooService;", "enforceSomething", false)]
+    fn test_is_permission_check(#[case] class: &str, #[case] name: &str, #[case] expected: bool) {
+        assert_eq!(is_permission_check(&method(class, name)), expected);
+    }
+
+    #[rstest]
+    fn test_run(tmp_context: TestContext) {
+        graph_db_test(&tmp_context, |graph| {
+            // TestCanService.checked loads the permission in a helper that
+            // calls the check, TestCanService.unchecked doesn't call anything
+            graph
+                .with_connection(|c| {
+                    c.batch_execute(
+                        r#"
+INSERT INTO classes(id,name,access_flags,source) VALUES
+    (320,'Lcom/android/server/TestCanService;',2,1),
+    (321,'Landroid/content/Context;',2,1);
+
+INSERT INTO methods(id,class,name,args,ret,access_flags,source) VALUES
+    (1300,320,'checked','I','V',2,1),
+    (1301,320,'unchecked','','V',2,1),
+    (1302,320,'enforceDump','','V',2,1),
+    (1303,321,'enforceCallingOrSelfPermission','Ljava/lang/String;Ljava/lang/String;','V',2,1);
+
+INSERT INTO calls(caller,callee,source) VALUES
+    (1300,1302,1),
+    (1302,1303,1);
+
+INSERT INTO strings(id,string,source) VALUES
+    (200,'android.permission.DUMP',1),
+    (201,'not a permission',1);
+
+INSERT INTO method_strings(string,method) VALUES
+    (200,1302),
+    (201,1302);
+"#,
+                    )
+                })
+                .unwrap();
+
+            db_test(&tmp_context, |db| {
+                let (_canceller, cancel) = TaskCanceller::new();
+                let mut task = PermissionCheckTask::new(&db, &graph, cancel, &NoopMonitor);
+                task.run().unwrap();
+
+                let checks = db.get_permission_checks().unwrap();
+                assert_eq!(checks.len(), 1);
+                let check = &checks[0];
+                assert_eq!(check.method, 0);
+                assert_eq!(check.system_service_impl, 0);
+                assert_eq!(
+                    check.check_class,
+                    ClassName::from("Landroid/content/Context;")
+                );
+                assert_eq!(check.check_method, "enforceCallingOrSelfPermission");
+                assert_eq!(check.permission.as_deref(), Some("android.permission.DUMP"));
+                assert_eq!(check.depth, 2);
+
+                let unchecked = db
+                    .get_unchecked_system_service_methods()
+                    .unwrap()
+                    .into_iter()
+                    .map(|it| it.name)
+                    .collect::<Vec<String>>();
+                assert_eq!(unchecked, vec![String::from("unchecked")]);
+            });
+        });
+    }
+}
@@ -24,6 +24,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    apk_certificates (id) {
+        id -> Integer,
+        apk_id -> Integer,
+        scheme -> Text,
+        sha256 -> Text,
+        subject -> Text,
+    }
+}
+
 diesel::table! {
     apk_diffs (id) {
         id -> Integer,
@@ -49,6 +59,19 @@ diesel::table! {
         is_debuggable -> Bool,
         is_priv -> Bool,
         device_path -> Text,
+        shared_user_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    binder_fuzz_results (id) {
+        id -> Integer,
+        method -> Integer,
+        description -> Text,
+        parcel -> Text,
+        outcome -> Text,
+        exception -> Nullable<Text>,
+        message -> Nullable<Text>,
     }
 }
 
@@ -60,10 +83,50 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    device_property_diffs (id) {
+        id -> Integer,
+        device_property -> Integer,
+        diff_source -> Integer,
+        exists_in_diff -> Bool,
+        value_matches_diff -> Bool,
+        diff_value -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     diff_sources (id) {
         id -> Integer,
         name -> Text,
+        smali_dir -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    fs_survey_entries (id) {
+        id -> Integer,
+        path -> Text,
+        mode -> Integer,
+        owner -> Text,
+        group_name -> Text,
+        selinux_label -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    fs_survey_entry_diffs (id) {
+        id -> Integer,
+        fs_survey_entry -> Integer,
+        diff_source -> Integer,
+        exists_in_diff -> Bool,
+        mode_matches_diff -> Bool,
+        diff_mode -> Nullable<Integer>,
+        owner_matches_diff -> Bool,
+        diff_owner -> Nullable<Text>,
+        group_matches_diff -> Bool,
+        diff_group -> Nullable<Text>,
+        label_matches_diff -> Bool,
+        diff_label -> Nullable<Text>,
     }
 }
 
@@ -77,6 +140,141 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    hal_service_diffs (id) {
+        id -> Integer,
+        hal_service -> Integer,
+        diff_source -> Integer,
+        exists_in_diff -> Bool,
+        transport_matches_diff -> Bool,
+        diff_transport -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    hal_services (id) {
+        id -> Integer,
+        interface -> Text,
+        instance -> Text,
+        kind -> Text,
+        transport -> Text,
+        server_pid -> Nullable<Integer>,
+        server_binary -> Nullable<Text>,
+        selinux_type -> Nullable<Text>,
+        reachable -> Integer,
+    }
+}
+
+diesel::table! {
+    intent_filter_actions (id) {
+        id -> Integer,
+        filter_id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    intent_filter_categories (id) {
+        id -> Integer,
+        filter_id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    intent_filter_data (id) {
+        id -> Integer,
+        filter_id -> Integer,
+        scheme -> Nullable<Text>,
+        host -> Nullable<Text>,
+        port -> Nullable<Text>,
+        path -> Nullable<Text>,
+        path_pattern -> Nullable<Text>,
+        path_prefix -> Nullable<Text>,
+        path_suffix -> Nullable<Text>,
+        path_advanced_pattern -> Nullable<Text>,
+        mime_type -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    intent_filters (id) {
+        id -> Integer,
+        activity_id -> Nullable<Integer>,
+        receiver_id -> Nullable<Integer>,
+        service_id -> Nullable<Integer>,
+        priority -> Integer,
+    }
+}
+
+diesel::table! {
+    native_service_diffs (id) {
+        id -> Integer,
+        native_service -> Integer,
+        diff_source -> Integer,
+        exists_in_diff -> Bool,
+        path_matches_diff -> Bool,
+        diff_path -> Nullable<Text>,
+        user_matches_diff -> Bool,
+        diff_user -> Nullable<Text>,
+        capabilities_matches_diff -> Bool,
+        diff_capabilities -> Nullable<Text>,
+        seclabel_matches_diff -> Bool,
+        diff_seclabel -> Nullable<Text>,
+        sockets_match_diff -> Bool,
+    }
+}
+
+diesel::table! {
+    native_service_sockets (id) {
+        id -> Integer,
+        service_id -> Integer,
+        name -> Text,
+        socket_type -> Text,
+        perm -> Text,
+        user -> Nullable<Text>,
+        group_name -> Nullable<Text>,
+        seclabel -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    native_service_triggers (id) {
+        id -> Integer,
+        service_id -> Integer,
+        on_trigger -> Text,
+    }
+}
+
+diesel::table! {
+    native_services (id) {
+        id -> Integer,
+        name -> Text,
+        path -> Text,
+        args -> Text,
+        user -> Text,
+        groups -> Text,
+        capabilities -> Nullable<Text>,
+        seclabel -> Nullable<Text>,
+        classes -> Text,
+        disabled -> Bool,
+        oneshot -> Bool,
+        rc_file -> Text,
+    }
+}
+
+diesel::table! {
+    permission_checks (id) {
+        id -> Integer,
+        method -> Integer,
+        system_service_impl -> Integer,
+        check_class -> Text,
+        check_method -> Text,
+        permission -> Nullable<Text>,
+        depth -> Integer,
+    }
+}
+
 diesel::table! {
     permission_diffs (id) {
         id -> Integer,
@@ -97,6 +295,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    property_findings (id) {
+        id -> Integer,
+        rule -> Text,
+        device_property -> Integer,
+        severity -> Text,
+        description -> Text,
+    }
+}
+
 diesel::table! {
     protected_broadcasts (id) {
         id -> Integer,
@@ -117,6 +325,35 @@ diesel::table! {
         diff_write_permission -> Nullable<Text>,
         read_permission_matches_diff -> Bool,
         diff_read_permission -> Nullable<Text>,
+        path_permissions_match_diff -> Bool,
+        grant_uri_permissions_match_diff -> Bool,
+    }
+}
+
+diesel::table! {
+    provider_grant_uri_permissions (id) {
+        id -> Integer,
+        provider_id -> Integer,
+        path -> Nullable<Text>,
+        path_prefix -> Nullable<Text>,
+        path_pattern -> Nullable<Text>,
+        path_advanced_pattern -> Nullable<Text>,
+        path_suffix -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    provider_path_permissions (id) {
+        id -> Integer,
+        provider_id -> Integer,
+        path -> Nullable<Text>,
+        path_prefix -> Nullable<Text>,
+        path_pattern -> Nullable<Text>,
+        path_advanced_pattern -> Nullable<Text>,
+        path_suffix -> Nullable<Text>,
+        permission -> Nullable<Text>,
+        read_permission -> Nullable<Text>,
+        write_permission -> Nullable<Text>,
     }
 }
 
@@ -244,14 +481,37 @@ diesel::table! {
 diesel::joinable!(activities -> apks (apk_id));
 diesel::joinable!(activity_diffs -> activities (activity));
 diesel::joinable!(activity_diffs -> diff_sources (diff_source));
+diesel::joinable!(apk_certificates -> apks (apk_id));
 diesel::joinable!(apk_diffs -> apks (apk));
 diesel::joinable!(apk_diffs -> diff_sources (diff_source));
 diesel::joinable!(apk_permissions -> apks (apk_id));
+diesel::joinable!(binder_fuzz_results -> system_service_methods (method));
+diesel::joinable!(device_property_diffs -> device_properties (device_property));
+diesel::joinable!(device_property_diffs -> diff_sources (diff_source));
+diesel::joinable!(fs_survey_entry_diffs -> diff_sources (diff_source));
+diesel::joinable!(fs_survey_entry_diffs -> fs_survey_entries (fs_survey_entry));
+diesel::joinable!(hal_service_diffs -> diff_sources (diff_source));
+diesel::joinable!(hal_service_diffs -> hal_services (hal_service));
+diesel::joinable!(intent_filter_actions -> intent_filters (filter_id));
+diesel::joinable!(intent_filter_categories -> intent_filters (filter_id));
+diesel::joinable!(intent_filter_data -> intent_filters (filter_id));
+diesel::joinable!(intent_filters -> activities (activity_id));
+diesel::joinable!(intent_filters -> receivers (receiver_id));
+diesel::joinable!(intent_filters -> services (service_id));
+diesel::joinable!(native_service_diffs -> diff_sources (diff_source));
+diesel::joinable!(native_service_diffs -> native_services (native_service));
+diesel::joinable!(native_service_sockets -> native_services (service_id));
+diesel::joinable!(native_service_triggers -> native_services (service_id));
+diesel::joinable!(permission_checks -> system_service_impls (system_service_impl));
+diesel::joinable!(permission_checks -> system_service_methods (method));
 diesel::joinable!(permission_diffs -> diff_sources (diff_source));
 diesel::joinable!(permission_diffs -> permissions (permission));
 diesel::joinable!(permissions -> apks (source_apk_id));
+diesel::joinable!(property_findings -> device_properties (device_property));
 diesel::joinable!(provider_diffs -> diff_sources (diff_source));
 diesel::joinable!(provider_diffs -> providers (provider));
+diesel::joinable!(provider_grant_uri_permissions -> providers (provider_id));
+diesel::joinable!(provider_path_permissions -> providers (provider_id));
 diesel::joinable!(providers -> apks (apk_id));
 diesel::joinable!(receiver_diffs -> diff_sources (diff_source));
 diesel::joinable!(receiver_diffs -> receivers (receiver));
@@ -270,16 +530,35 @@ diesel::joinable!(unprotected_broadcasts -> diff_sources (diff_source));
 diesel::allow_tables_to_appear_in_same_query!(
     activities,
     activity_diffs,
+    apk_certificates,
     apk_diffs,
     apk_permissions,
     apks,
+    binder_fuzz_results,
     device_properties,
+    device_property_diffs,
     diff_sources,
+    fs_survey_entries,
+    fs_survey_entry_diffs,
     fuzz_results,
+    hal_service_diffs,
+    hal_services,
+    intent_filter_actions,
+    intent_filter_categories,
+    intent_filter_data,
+    intent_filters,
+    native_service_diffs,
+    native_service_sockets,
+    native_service_triggers,
+    native_services,
+    permission_checks,
     permission_diffs,
     permissions,
+    property_findings,
     protected_broadcasts,
     provider_diffs,
+    provider_grant_uri_permissions,
+    provider_path_permissions,
     providers,
     receiver_diffs,
     receivers,
@@ -5,7 +5,7 @@ use std::ops::Deref;
 use std::path::PathBuf;
 
 use base64::Engine;
-use diesel::{insert_into, prelude::*, update};
+use diesel::{insert_into, insert_or_ignore_into, prelude::*, update};
 use regex::Regex;
 use sha2::{Digest, Sha256};
 use smalisa::instructions::{InvArgs, Invocation};
@@ -17,7 +17,7 @@ use smalisa::{
 use dtu_proc_macro::{define_setters, wraps_base_error};
 
 use crate::adb::{Adb, ExecAdb};
-use crate::command::err_on_status;
+use crate::command::{err_on_status, CmdOutput};
 use crate::config::DeviceAccessConfig;
 use crate::context::Context;
 use crate::db::common::Error;
@@ -28,16 +28,20 @@ use crate::db::graph::models::{ClassSearch, ClassSpec};
 use crate::db::graph::{GraphDatabase, FRAMEWORK_SOURCE};
 use crate::db::MetaDatabase;
 use crate::fsdump::FSDumpAccess;
+use crate::init_rc::{InitRcParser, InitService};
 use crate::manifest::{self, ApktoolManifestResolver, IPC};
 use crate::prereqs::Prereq;
 use crate::tasks::task::{EventMonitor, TaskCancelCheck};
 use crate::unknownbool::UnknownBool;
+use crate::utils::apk_signing::get_signing_certs;
 use crate::utils::class_name::ClassName;
 use crate::utils::device_path::DevicePath;
 use crate::utils::fs::{
     find_files_for_class, find_smali_file_for_class, path_has_ext, path_must_str,
 };
 use crate::utils::open_file;
+use crate::utils::sepolicy::{ServiceAccess, ServiceManagerKind};
+use crate::vintf::HalFormat;
 use crate::Manifest;
 
 #[wraps_base_error]
@@ -202,6 +206,7 @@ pub trait DatabaseSetupHelper {
     fn get_props(&self) -> crate::Result<HashMap<String, String>>;
     fn list_services(&self) -> crate::Result<Vec<ServiceMeta>>;
     fn list_packages(&self, on_pkg: &mut PackageCallback) -> crate::Result<()>;
+    fn list_hal_services(&self) -> crate::Result<Vec<HalServiceMeta>>;
 }
 
 macro_rules! on_event {
@@ -343,8 +348,11 @@ impl<'a> AddManifestTask<'a> {
             enabled,
             pkg: pkg_name,
         };
-        insert_into(receivers::table).values(&ins).execute(conn)?;
-        Ok(())
+        let id: i32 = insert_into(receivers::table)
+            .values(&ins)
+            .returning(receivers::id)
+            .get_result(conn)?;
+        self.add_intent_filters(conn, &rcv.intent_filters, |f| f.set_receiver_id(Some(id)))
     }
 
     fn add_manifest_provider(
@@ -383,7 +391,59 @@ impl<'a> AddManifestTask<'a> {
             read_permission: read_perm.as_ref().map(|it| it.as_ref()),
             write_permission: write_perm.as_ref().map(|it| it.as_ref()),
         };
-        insert_into(providers::table).values(&ins).execute(conn)?;
+        let id: i32 = insert_into(providers::table)
+            .values(&ins)
+            .returning(providers::id)
+            .get_result(conn)?;
+
+        self.add_provider_paths(conn, id, prov)
+    }
+
+    fn add_provider_paths(
+        &self,
+        conn: &mut SqlConnection,
+        provider_id: i32,
+        prov: &manifest::Provider,
+    ) -> SetupResult<()> {
+        for pp in &prov.path_permissions {
+            let path = pp.path(self.resolver);
+            let path_prefix = pp.path_prefix(self.resolver);
+            let path_pattern = pp.path_pattern(self.resolver);
+            let path_advanced_pattern = pp.path_advanced_pattern(self.resolver);
+            let path_suffix = pp.path_suffix(self.resolver);
+            let permission = pp.permission(self.resolver);
+            let read_permission = pp.read_permission(self.resolver);
+            let write_permission = pp.write_permission(self.resolver);
+            let ins = InsertProviderPathPermission::new(provider_id)
+                .set_path(path.as_deref())
+                .set_path_prefix(path_prefix.as_deref())
+                .set_path_pattern(path_pattern.as_deref())
+                .set_path_advanced_pattern(path_advanced_pattern.as_deref())
+                .set_path_suffix(path_suffix.as_deref())
+                .set_permission(permission.as_deref())
+                .set_read_permission(read_permission.as_deref())
+                .set_write_permission(write_permission.as_deref());
+            insert_into(provider_path_permissions::table)
+                .values(&ins)
+                .execute(conn)?;
+        }
+
+        for grant in &prov.grant_uri_patterns {
+            let path = grant.path(self.resolver);
+            let path_prefix = grant.path_prefix(self.resolver);
+            let path_pattern = grant.path_pattern(self.resolver);
+            let path_advanced_pattern = grant.path_advanced_pattern(self.resolver);
+            let path_suffix = grant.path_suffix(self.resolver);
+            let ins = InsertProviderGrantUriPermission::new(provider_id)
+                .set_path(path.as_deref())
+                .set_path_prefix(path_prefix.as_deref())
+                .set_path_pattern(path_pattern.as_deref())
+                .set_path_advanced_pattern(path_advanced_pattern.as_deref())
+                .set_path_suffix(path_suffix.as_deref());
+            insert_into(provider_grant_uri_permissions::table)
+                .values(&ins)
+                .execute(conn)?;
+        }
 
         Ok(())
     }
@@ -439,8 +499,11 @@ impl<'a> AddManifestTask<'a> {
             apk_id: self.apk_id,
             permission: common.permission(),
         };
-        insert_into(activities::table).values(&ins).execute(conn)?;
-        Ok(())
+        let id: i32 = insert_into(activities::table)
+            .values(&ins)
+            .returning(activities::id)
+            .get_result(conn)?;
+        self.add_intent_filters(conn, &act.intent_filters, |f| f.set_activity_id(Some(id)))
     }
 
     fn add_manifest_services(
@@ -486,8 +549,85 @@ impl<'a> AddManifestTask<'a> {
             permission,
             returns_binder,
         };
-        insert_into(services::table).values(&ins).execute(conn)?;
+        let id: i32 = insert_into(services::table)
+            .values(&ins)
+            .returning(services::id)
+            .get_result(conn)?;
+        self.add_intent_filters(conn, &svc.intent_filters, |f| f.set_service_id(Some(id)))
+    }
 
+    /// Add the given intent filters, `owner` sets the component they belong to
+    fn add_intent_filters<F>(
+        &self,
+        conn: &mut SqlConnection,
+        filters: &[manifest::IntentFilter],
+        owner: F,
+    ) -> SetupResult<()>
+    where
+        F: Fn(InsertIntentFilter) -> InsertIntentFilter,
+    {
+        for filter in filters {
+            let ins = owner(InsertIntentFilter::new(filter.priority(self.resolver)));
+            let filter_id: i32 = insert_into(intent_filters::table)
+                .values(&ins)
+                .returning(intent_filters::id)
+                .get_result(conn)?;
+
+            let actions = filter
+                .get_actions()
+                .iter()
+                .map(|it| it.name(self.resolver))
+                .collect::<Vec<Cow<'_, str>>>();
+            if !actions.is_empty() {
+                let ins = actions
+                    .iter()
+                    .map(|it| InsertIntentFilterAction::new(filter_id, it))
+                    .collect::<Vec<InsertIntentFilterAction>>();
+                insert_into(intent_filter_actions::table)
+                    .values(ins.as_slice())
+                    .execute(conn)?;
+            }
+
+            let categories = filter
+                .get_categories()
+                .iter()
+                .map(|it| it.name(self.resolver))
+                .collect::<Vec<Cow<'_, str>>>();
+            if !categories.is_empty() {
+                let ins = categories
+                    .iter()
+                    .map(|it| InsertIntentFilterCategory::new(filter_id, it))
+                    .collect::<Vec<InsertIntentFilterCategory>>();
+                insert_into(intent_filter_categories::table)
+                    .values(ins.as_slice())
+                    .execute(conn)?;
+            }
+
+            for data in filter.get_data() {
+                let scheme = data.scheme(self.resolver);
+                let host = data.host(self.resolver);
+                let port = data.port(self.resolver);
+                let path = data.path(self.resolver);
+                let path_pattern = data.path_pattern(self.resolver);
+                let path_prefix = data.path_prefix(self.resolver);
+                let path_suffix = data.path_suffix(self.resolver);
+                let path_advanced_pattern = data.path_advanced_pattern(self.resolver);
+                let mime_type = data.mime_type(self.resolver);
+                let ins = InsertIntentFilterData::new(filter_id)
+                    .set_scheme(scheme.as_deref())
+                    .set_host(host.as_deref())
+                    .set_port(port.as_deref())
+                    .set_path(path.as_deref())
+                    .set_path_pattern(path_pattern.as_deref())
+                    .set_path_prefix(path_prefix.as_deref())
+                    .set_path_suffix(path_suffix.as_deref())
+                    .set_path_advanced_pattern(path_advanced_pattern.as_deref())
+                    .set_mime_type(mime_type.as_deref());
+                insert_into(intent_filter_data::table)
+                    .values(&ins)
+                    .execute(conn)?;
+            }
+        }
         Ok(())
     }
 
@@ -1318,7 +1458,7 @@ impl<'a> AddApkTask<'a> {
         }
     }
 
-    pub fn run(self) -> SetupResult<()> {
+    pub fn run(mut self) -> SetupResult<()> {
         let manifest_path = self.apktool_out_dir.join("AndroidManifest.xml");
         if !manifest_path.exists() {
             log::warn!("apk {} has no manifest", self.device_path);
@@ -1342,13 +1482,16 @@ impl<'a> AddApkTask<'a> {
         // makes more sense to just default to false when resolution fails.
         let is_debug = manifest.debuggable(&resolver).unwrap_or(false);
 
+        let shared_user_id = manifest.shared_user_id(&resolver);
+
         let new_apk = InsertApk::new(
             &pkg,
             self.device_path.device_file_name(),
             is_debug,
             is_priv,
             self.device_path.clone(),
-        );
+        )
+        .set_shared_user_id(shared_user_id.as_deref());
 
         let apk_id = insert_into(apks::table)
             .values(&new_apk)
@@ -1356,6 +1499,8 @@ impl<'a> AddApkTask<'a> {
             .get_result(self.conn)?;
         self.cancel_check()?;
 
+        self.add_certificates(apk_id)?;
+
         let manifest_task = AddManifestTask {
             apk_id,
             ctx: self.ctx,
@@ -1371,6 +1516,41 @@ impl<'a> AddApkTask<'a> {
         manifest_task.run(self.conn)
     }
 
+    /// Add the signing certificates for the APK from the originally pulled file
+    ///
+    /// Failing to read the certificates is not fatal, the APK just won't show
+    /// up when searching by certificate.
+    fn add_certificates(&mut self, apk_id: i32) -> SetupResult<()> {
+        let path = self.ctx.get_apks_dir()?.join(self.device_path);
+        let certs = match get_signing_certs(&path) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!(
+                    "failed to get signing certificates for {}: {}",
+                    self.device_path,
+                    e
+                );
+                return Ok(());
+            }
+        };
+        if certs.is_empty() {
+            log::debug!("no signing certificates for {}", self.device_path);
+            return Ok(());
+        }
+
+        let values = certs
+            .iter()
+            .map(|it| {
+                InsertApkCertificate::new(apk_id, it.scheme.as_str(), &it.sha256, &it.subject)
+            })
+            .collect::<Vec<InsertApkCertificate>>();
+
+        insert_or_ignore_into(apk_certificates::table)
+            .values(values.as_slice())
+            .execute(self.conn)?;
+        Ok(())
+    }
+
     #[inline]
     fn cancel_check(&self) -> SetupResult<()> {
         self.cancel.check(SetupError::Cancelled)
@@ -1498,6 +1678,54 @@ fn list_priv_apps(helper: &dyn DatabaseSetupHelper) -> SetupResult<HashSet<Strin
     Ok(apps)
 }
 
+/// The domain used when determining whether a HAL service is reachable
+const HAL_CALLER_DOMAIN: &'static str = "untrusted_app";
+
+fn add_native_service(conn: &mut SqlConnection, svc: &InitService) -> SetupResult<()> {
+    let args = svc.args.join(" ");
+    let groups = svc.groups.join(" ");
+    let classes = svc.classes.join(" ");
+    let capabilities = svc.capabilities.as_ref().map(|it| it.join(" "));
+    let ins = InsertNativeService::new(
+        &svc.name,
+        &svc.path,
+        &args,
+        &svc.user,
+        &groups,
+        &classes,
+        svc.disabled,
+        svc.oneshot,
+        &svc.rc_file,
+    )
+    .set_capabilities(capabilities.as_deref())
+    .set_seclabel(svc.seclabel.as_deref());
+
+    let service_id: i32 = insert_into(native_services::table)
+        .values(&ins)
+        .returning(native_services::id)
+        .get_result(conn)?;
+
+    for sock in svc.sockets.iter() {
+        let ins =
+            InsertNativeServiceSocket::new(service_id, &sock.name, &sock.socket_type, &sock.perm)
+                .set_user(sock.user.as_deref())
+                .set_group_name(sock.group.as_deref())
+                .set_seclabel(sock.seclabel.as_deref());
+        insert_into(native_service_sockets::table)
+            .values(&ins)
+            .execute(conn)?;
+    }
+
+    for trigger in svc.triggers.iter() {
+        let ins = InsertNativeServiceTrigger::new(service_id, trigger);
+        insert_or_ignore_into(native_service_triggers::table)
+            .values(&ins)
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
 impl<'a> DBSetupTask<'a> {
     pub fn new(
         ctx: &'a dyn Context,
@@ -1532,9 +1760,93 @@ impl<'a> DBSetupTask<'a> {
         self.add_services(&entries)?;
         log::debug!("adding device properties");
         self.add_device_props()?;
+        log::debug!("adding native services");
+        self.add_native_services()?;
+        log::debug!("adding hal services");
+        self.add_hal_services()?;
         Ok(())
     }
 
+    /// Add the HIDL and AIDL HAL services, using the pulled SELinux policy to
+    /// determine whether an untrusted app can reach them
+    fn add_hal_services(&self) -> SetupResult<()> {
+        let hals = self.helper.list_hal_services()?;
+
+        let selinux_dir = self.ctx.get_selinux_dir()?;
+        let access =
+            match ServiceAccess::load(&selinux_dir.join("cils"), &selinux_dir.join("contexts")) {
+                Ok(Some(v)) => Some(v),
+                Ok(None) => {
+                    log::warn!("no SELinux policy found, HAL service reachability will be unknown");
+                    None
+                }
+                Err(e) => {
+                    log::warn!("failed to load SELinux policy: {}", e);
+                    None
+                }
+            };
+
+        self.db.with_transaction(|c| {
+            for hal in hals.iter() {
+                self.cancel_check()?;
+                let contexts_name = hal.contexts_name();
+                let (selinux_type, reachable) = match (access.as_ref(), hal.service_manager()) {
+                    (Some(access), Some(kind)) => (
+                        access.service_type(kind, &contexts_name),
+                        UnknownBool::from(access.can_find(HAL_CALLER_DOMAIN, kind, &contexts_name)),
+                    ),
+                    _ => (None, UnknownBool::Unknown),
+                };
+                let ins = InsertHalService::new(
+                    &hal.interface,
+                    &hal.instance,
+                    hal.format.as_str(),
+                    &hal.transport,
+                    reachable,
+                )
+                .set_server_pid(hal.server_pid)
+                .set_server_binary(hal.server_binary.as_deref())
+                .set_selinux_type(selinux_type);
+                insert_or_ignore_into(hal_services::table)
+                    .values(&ins)
+                    .execute(c)?;
+            }
+            Ok::<(), SetupError>(())
+        })
+    }
+
+    /// Parse the init `.rc` files pulled from the device and add the native
+    /// services they define
+    fn add_native_services(&self) -> SetupResult<()> {
+        let dir = self.ctx.get_init_rc_dir()?;
+        if !dir.exists() {
+            log::warn!("no init rc files found in {:?}", dir);
+            return Ok(());
+        }
+
+        let mut parser = InitRcParser::new();
+        for ent in std::fs::read_dir(&dir)? {
+            self.cancel_check()?;
+            let path = ent?.path();
+            if !(path.is_file() && path_has_ext(&path, "rc")) {
+                continue;
+            }
+            let device_path = DevicePath::from_path(&path)?;
+            match std::fs::read_to_string(&path) {
+                Ok(content) => parser.parse(&content, device_path.as_device_str()),
+                Err(e) => log::warn!("failed to read {:?}: {}", path, e),
+            }
+        }
+
+        let services = parser.finish();
+        self.db.with_transaction(|c| {
+            for svc in services.iter() {
+                add_native_service(c, svc)?;
+            }
+            Ok::<(), SetupError>(())
+        })
+    }
+
     fn add_device_props(&self) -> SetupResult<()> {
         let props = self.helper.get_props()?;
 
@@ -1775,6 +2087,102 @@ impl ServiceMeta {
     }
 }
 
+/// A HIDL or AIDL HAL service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HalServiceMeta {
+    /// Fully qualified interface name, `package@version::IName` for HIDL and
+    /// `package.IName` for AIDL
+    pub interface: String,
+    /// The instance name, for services registered with vndservicemanager this
+    /// is the full service name
+    pub instance: String,
+    pub format: HalFormat,
+    /// `hwbinder` or `passthrough` for HIDL, `binder` or `vndbinder` for AIDL
+    pub transport: String,
+    pub server_pid: Option<i32>,
+    pub server_binary: Option<String>,
+}
+
+impl HalServiceMeta {
+    /// Parse a [HalServiceMeta] from the output of `lshal list --neat -itp`
+    pub fn from_lshal_line(line: &str) -> Option<Self> {
+        let mut split = line.split_ascii_whitespace();
+        let (interface, instance) = split.next()?.split_once('/')?;
+        if !interface.contains("::") {
+            return None;
+        }
+        let transport = split.next()?;
+        let server_pid = split.next().and_then(|it| it.parse::<i32>().ok());
+        Some(Self {
+            interface: String::from(interface),
+            instance: String::from(instance),
+            format: HalFormat::Hidl,
+            transport: String::from(transport),
+            server_pid,
+            server_binary: None,
+        })
+    }
+
+    /// Create a [HalServiceMeta] from an AIDL HAL's `package.IName/instance`
+    /// service name
+    pub fn from_aidl_service(service_name: &str) -> Option<Self> {
+        let (interface, instance) = service_name.split_once('/')?;
+        Some(Self {
+            interface: String::from(interface),
+            instance: String::from(instance),
+            format: HalFormat::Aidl,
+            transport: String::from("binder"),
+            server_pid: None,
+            server_binary: None,
+        })
+    }
+
+    /// Create a [HalServiceMeta] from a service registered with
+    /// vndservicemanager
+    pub fn from_vnd_service(svc: &ServiceMeta) -> Self {
+        let interface = svc
+            .iface
+            .as_ref()
+            .map(|it| it.get_java_name().into_owned())
+            .unwrap_or_else(|| svc.service_name.clone());
+        Self {
+            interface,
+            instance: svc.service_name.clone(),
+            format: HalFormat::Aidl,
+            transport: String::from("vndbinder"),
+            server_pid: None,
+            server_binary: None,
+        }
+    }
+
+    /// The service manager the HAL is registered with, `None` for passthrough
+    /// HALs
+    pub fn service_manager(&self) -> Option<ServiceManagerKind> {
+        match self.transport.as_str() {
+            "hwbinder" => Some(ServiceManagerKind::HwService),
+            "binder" => Some(ServiceManagerKind::Service),
+            "vndbinder" => Some(ServiceManagerKind::VndService),
+            _ => None,
+        }
+    }
+
+    /// The name used for the HAL in the service contexts files
+    pub fn contexts_name(&self) -> String {
+        match (self.format, self.transport.as_str()) {
+            // HIDL contexts don't include the version: package::IName
+            (HalFormat::Hidl, _) => match self.interface.split_once('@') {
+                Some((pkg, rest)) => match rest.split_once("::") {
+                    Some((_, iface)) => format!("{}::{}", pkg, iface),
+                    None => self.interface.clone(),
+                },
+                None => self.interface.clone(),
+            },
+            (HalFormat::Aidl, "vndbinder") => self.instance.clone(),
+            (HalFormat::Aidl, _) => format!("{}/{}", self.interface, self.instance),
+        }
+    }
+}
+
 fn parse_device_props_output(output: &str) -> HashMap<String, String> {
     let mut props = HashMap::new();
     let mut multiline = String::new();
@@ -1884,6 +2292,10 @@ where
     fn list_packages(&self, on_pkg: &mut PackageCallback) -> crate::Result<()> {
         self.as_ref().list_packages(on_pkg)
     }
+
+    fn list_hal_services(&self) -> crate::Result<Vec<HalServiceMeta>> {
+        self.as_ref().list_hal_services()
+    }
 }
 
 impl<T> DatabaseSetupHelper for AdbDatabaseSetupHelper<T>
@@ -1957,6 +2369,95 @@ where
 
         Ok(())
     }
+
+    fn list_hal_services(&self) -> crate::Result<Vec<HalServiceMeta>> {
+        // lshal is missing on devices without HIDL support
+        let mut hals = match self.checked_shell("lshal list --neat -itp") {
+            Ok(cmdout) => cmdout
+                .stdout_utf8_lossy()
+                .lines()
+                .filter_map(HalServiceMeta::from_lshal_line)
+                .collect::<Vec<HalServiceMeta>>(),
+            Err(e) => {
+                log::warn!("failed to list HIDL services: {}", e);
+                Vec::new()
+            }
+        };
+
+        // AIDL HALs are registered with servicemanager as package.IName/instance
+        for svc in self.list_services()? {
+            if let Some(mut hal) = HalServiceMeta::from_aidl_service(&svc.service_name) {
+                hal.server_pid = self.get_service_pid(&svc.service_name);
+                hals.push(hal);
+            }
+        }
+
+        match self.checked_shell("vndservice list") {
+            Ok(cmdout) => {
+                for svc in cmdout
+                    .stdout_utf8_lossy()
+                    .lines()
+                    .filter_map(ServiceMeta::from_line)
+                {
+                    hals.push(HalServiceMeta::from_vnd_service(&svc));
+                }
+            }
+            Err(e) => log::warn!("failed to list vndservicemanager services: {}", e),
+        }
+
+        let binaries = self.get_process_binaries();
+        for hal in hals.iter_mut() {
+            if let Some(pid) = hal.server_pid {
+                hal.server_binary = binaries.get(&pid).cloned();
+            }
+        }
+
+        Ok(hals)
+    }
+}
+
+impl<T> AdbDatabaseSetupHelper<T>
+where
+    T: Adb,
+{
+    fn checked_shell(&self, cmd: &str) -> crate::Result<CmdOutput> {
+        Ok(self.shell(cmd)?.err_on_status()?)
+    }
+
+    fn get_service_pid(&self, service_name: &str) -> Option<i32> {
+        let cmd = format!("dumpsys --pid {}", service_name);
+        let cmdout = match self.checked_shell(&cmd) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("failed to get pid for {}: {}", service_name, e);
+                return None;
+            }
+        };
+        cmdout.stdout_utf8_lossy().trim().parse::<i32>().ok()
+    }
+
+    fn get_process_binaries(&self) -> HashMap<i32, String> {
+        let cmdout = match self.checked_shell("ps -A -o PID,ARGS") {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("failed to list processes: {}", e);
+                return HashMap::new();
+            }
+        };
+        parse_ps_binaries(&cmdout.stdout_utf8_lossy())
+    }
+}
+
+fn parse_ps_binaries(output: &str) -> HashMap<i32, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut split = line.split_ascii_whitespace();
+            let pid = split.next()?.parse::<i32>().ok()?;
+            let binary = split.next()?;
+            Some((pid, String::from(binary)))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -2159,6 +2660,103 @@ reboot,1753122820]
         assert_eq!(pkgs, expected);
     }
 
+    #[rstest]
+    fn test_hal_service_from_lshal_line() {
+        let parsed = HalServiceMeta::from_lshal_line(
+            "android.hardware.audio@6.0::IDevicesFactory/default hwbinder 1234",
+        )
+        .expect("should parse lshal line");
+        assert_eq!(
+            parsed.interface,
+            "android.hardware.audio@6.0::IDevicesFactory"
+        );
+        assert_eq!(parsed.instance, "default");
+        assert_eq!(parsed.transport, "hwbinder");
+        assert_eq!(parsed.server_pid, Some(1234));
+        assert_eq!(
+            parsed.contexts_name(),
+            "android.hardware.audio::IDevicesFactory"
+        );
+
+        let parsed = HalServiceMeta::from_lshal_line(
+            "android.hardware.graphics.mapper@4.0::IMapper/default passthrough N/A",
+        )
+        .expect("should parse passthrough lshal line");
+        assert_eq!(parsed.server_pid, None);
+        assert_eq!(parsed.service_manager(), None);
+
+        // Header lines
+        assert!(HalServiceMeta::from_lshal_line("Interface Transport Server").is_none());
+
+        let parsed = HalServiceMeta::from_aidl_service(
+            "android.hardware.camera.provider.ICameraProvider/internal/0",
+        )
+        .expect("should parse AIDL service name");
+        assert_eq!(
+            parsed.interface,
+            "android.hardware.camera.provider.ICameraProvider"
+        );
+        assert_eq!(parsed.instance, "internal/0");
+        assert_eq!(
+            parsed.contexts_name(),
+            "android.hardware.camera.provider.ICameraProvider/internal/0"
+        );
+        assert!(HalServiceMeta::from_aidl_service("activity").is_none());
+    }
+
+    #[rstest]
+    fn test_adb_list_hal_services(mut mock_adb: MockAdb) {
+        mock_adb.expect_shell().returning(|cmd| {
+            let stdout = match cmd {
+                "lshal list --neat -itp" => {
+                    "android.hardware.audio@6.0::IDevicesFactory/default hwbinder 512\n"
+                }
+                "vndservice list" => "0\tvendor.foo: [vendor.foo.IFoo]\n",
+                "dumpsys --pid android.hardware.power.IPower/default" => "600\n",
+                "ps -A -o PID,ARGS" => concat!(
+                    "  PID ARGS\n",
+                    "  512 /vendor/bin/hw/android.hardware.audio.service\n",
+                    "  600 /vendor/bin/hw/android.hardware.power-service --flag\n",
+                ),
+                _ => "",
+            };
+            Ok(CmdOutput {
+                status: ExitStatus::default(),
+                stdout: stdout.as_bytes().to_vec(),
+                stderr: Vec::new(),
+            })
+        });
+        mock_adb
+            .expect_shell_split_streamed()
+            .returning(move |_, _, on_stdout_line, _| {
+                on_stdout_line("0\tactivity: [android.app.IActivityManager]").unwrap();
+                on_stdout_line("1\tandroid.hardware.power.IPower/default: []").unwrap();
+                Ok(ExitStatus::default())
+            });
+
+        let helper = AdbDatabaseSetupHelper::new(mock_adb);
+        let hals = helper.list_hal_services().unwrap();
+        assert_eq!(hals.len(), 3);
+
+        assert_eq!(
+            hals[0].server_binary.as_deref(),
+            Some("/vendor/bin/hw/android.hardware.audio.service")
+        );
+
+        assert_eq!(hals[1].interface, "android.hardware.power.IPower");
+        assert_eq!(hals[1].format, HalFormat::Aidl);
+        assert_eq!(hals[1].server_pid, Some(600));
+        assert_eq!(
+            hals[1].server_binary.as_deref(),
+            Some("/vendor/bin/hw/android.hardware.power-service")
+        );
+
+        assert_eq!(hals[2].interface, "vendor.foo.IFoo");
+        assert_eq!(hals[2].instance, "vendor.foo");
+        assert_eq!(hals[2].transport, "vndbinder");
+        assert_eq!(hals[2].contexts_name(), "vendor.foo");
+    }
+
     #[rstest]
     fn test_list_privapps_adb(mut mock_adb: MockAdb) {
         let emulator_output = r#"package:/product/overlay/SystemUIEmulationPixel3XL/SystemUIEmulationPixel3XLOverlay.apk=com.android.systemui.emulation.pixel_3_xl
@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::db::device::db::DeviceDatabase;
+use crate::db::device::models::{DiffSource, SystemServiceImpl, SystemServiceMethod};
+use crate::utils::smali_diff::{find_smali_method, normalize_smali_method, SmaliDiff};
+use crate::utils::{
+    find_smali_file_for_class, path_must_str, search_smali_file_for_class_in, DevicePath,
+};
+use crate::{Context, Error, Result};
+
+/// The diff of a system service method's body against a diff source
+pub struct MethodSmaliDiff {
+    pub method: SystemServiceMethod,
+    pub imp: SystemServiceImpl,
+    /// Smali file the device's version of the method came from
+    pub device_file: PathBuf,
+    /// Smali file the diff source's version came from, None if the class
+    /// couldn't be found in the diff source's smali
+    pub diff_file: Option<PathBuf>,
+    /// Whether the method was found in the diff source's smali
+    pub in_diff_source: bool,
+    /// Diff with the diff source as the old version and the device as the new
+    pub diff: SmaliDiff,
+}
+
+impl MethodSmaliDiff {
+    /// Render the diff in unified format
+    pub fn unified(&self, context: usize) -> String {
+        let old = self
+            .diff_file
+            .as_ref()
+            .map_or("/dev/null", |it| path_must_str(it));
+        self.diff
+            .unified(old, path_must_str(&self.device_file), context)
+    }
+}
+
+/// Diff the body of the given system service method on the device against
+/// the version in the diff source.
+///
+/// The diff source must have a smali directory set, see
+/// [DeviceDatabase::set_diff_source_smali_dir]. Method bodies are normalized
+/// the same way the stored method hashes are, so register renumbering
+/// doesn't show up in the diff.
+pub fn diff_system_service_method_smali(
+    ctx: &dyn Context,
+    db: &DeviceDatabase,
+    diff_source: &DiffSource,
+    method: &SystemServiceMethod,
+) -> Result<MethodSmaliDiff> {
+    let diff_smali_dir = diff_source.smali_dir.as_ref().ok_or_else(|| {
+        Error::Generic(format!(
+            "diff source {} doesn't have a smali directory",
+            diff_source.name
+        ))
+    })?;
+    let diff_smali_dir = Path::new(diff_smali_dir);
+    let args = method.signature.as_deref();
+
+    let impls = db.get_system_service_impls(method.system_service_id)?;
+    if impls.is_empty() {
+        return Err(Error::Generic(format!(
+            "no implementations found for {}",
+            method.name
+        )));
+    }
+
+    for imp in impls {
+        let apk = if imp.is_from_framework() {
+            None
+        } else {
+            Some(imp.apk_path())
+        };
+        let Some(device_file) = find_smali_file_for_class(ctx, &imp.class_name, apk.as_ref())
+        else {
+            continue;
+        };
+        let Ok(device_smali) = fs::read_to_string(&device_file) else {
+            continue;
+        };
+        let Some(device_body) = find_smali_method(&device_smali, &method.name, args) else {
+            continue;
+        };
+        let new = normalize_smali_method(device_body);
+
+        let (diff_file, old) = diff_source_method(diff_smali_dir, &imp, apk.as_ref(), method)?;
+        let in_diff_source = old.is_some();
+        let old = old.unwrap_or_default();
+
+        return Ok(MethodSmaliDiff {
+            method: method.clone(),
+            imp,
+            device_file,
+            diff_file,
+            in_diff_source,
+            diff: SmaliDiff::new(&old, &new),
+        });
+    }
+
+    Err(Error::Generic(format!(
+        "failed to find smali for {} in any implementation",
+        method.name
+    )))
+}
+
+fn diff_source_method(
+    smali_dir: &Path,
+    imp: &SystemServiceImpl,
+    apk: Option<&DevicePath>,
+    method: &SystemServiceMethod,
+) -> Result<(Option<PathBuf>, Option<Vec<String>>)> {
+    let Some(path) = search_smali_file_for_class_in(smali_dir, &imp.class_name, apk) else {
+        return Ok((None, None));
+    };
+    let smali = fs::read_to_string(&path)?;
+    let body = find_smali_method(&smali, &method.name, method.signature.as_deref())
+        .map(normalize_smali_method);
+    Ok((Some(path), body))
+}
@@ -0,0 +1,466 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::db::device::db::{DeviceDatabase, PLATFORM_APP_NAME};
+use crate::db::device::models::{Apk, Permission, ProviderWithPaths};
+use crate::db::{ApkIPC, ApkIPCKind, Error, PermissionMode, PermissionProtected, Result};
+
+/// Shared user id of the system server
+const SYSTEM_UID: &str = "android.uid.system";
+
+/// Permission modes checked on each component and the attribute setting them
+const PERMISSION_ATTRIBUTES: [(PermissionMode, &str); 3] = [
+    (PermissionMode::Generic, "permission"),
+    (PermissionMode::Read, "readPermission"),
+    (PermissionMode::Write, "writePermission"),
+];
+
+/// Why a permission was reported by [find_weak_permissions]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeakPermissionKind {
+    /// A component requires a permission that no APK defines, so any app can
+    /// define it and grant it to itself
+    Undefined,
+    /// A custom `normal` or `dangerous` permission guards a component in a
+    /// privileged APK
+    Weak,
+    /// The permission's protection level differs from the diff source
+    ChangedFromDiff,
+}
+
+impl WeakPermissionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Undefined => "undefined",
+            Self::Weak => "weak",
+            Self::ChangedFromDiff => "changed-from-diff",
+        }
+    }
+}
+
+impl FromStr for WeakPermissionKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "undefined" => Self::Undefined,
+            "weak" => Self::Weak,
+            "changed-from-diff" => Self::ChangedFromDiff,
+            _ => return Err("valid values are 'undefined', 'weak', and 'changed-from-diff'"),
+        })
+    }
+}
+
+impl Display for WeakPermissionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// An exported component requiring a permission
+#[derive(Debug, Clone, Serialize)]
+pub struct GuardedComponent {
+    pub kind: ApkIPCKind,
+    /// `package/class` for most components, the name for providers followed
+    /// by the path for `<path-permission>` entries
+    pub name: String,
+    pub apk: String,
+    /// Which permission attribute is set: `permission`, `readPermission`, or
+    /// `writePermission`
+    pub attribute: &'static str,
+    /// Whether the APK is privileged, platform signed, or runs as system
+    pub privileged: bool,
+}
+
+/// A permission reported by [find_weak_permissions]
+#[derive(Debug, Clone, Serialize)]
+pub struct WeakPermission {
+    pub kind: WeakPermissionKind,
+    pub name: String,
+    /// The protection level, None if the permission isn't defined
+    pub protection_level: Option<String>,
+    /// APKs defining the permission
+    pub defined_by: Vec<String>,
+    /// The protection level in the diff source if it differs
+    pub diff_protection_level: Option<String>,
+    /// APKs requesting the permission
+    pub requested_by: Vec<String>,
+    /// Exported and enabled components requiring the permission
+    pub components: Vec<GuardedComponent>,
+}
+
+/// Whether the given protection level can be granted to third party apps
+/// without any user interaction beyond a runtime prompt
+pub fn is_weak_protection_level(level: &str) -> bool {
+    let base = level.split('|').next().unwrap_or(level).trim();
+    match base {
+        "normal" | "dangerous" => true,
+        _ => match base.strip_prefix("0x") {
+            // The base level is the low nibble: 0 is normal and 1 dangerous
+            Some(hex) => i64::from_str_radix(hex, 16).is_ok_and(|it| (it & 0xf) <= 1),
+            None => false,
+        },
+    }
+}
+
+/// Find custom permissions that don't actually protect the components they
+/// guard
+///
+/// This reports permissions required by exported components that no APK
+/// defines, custom `normal` or `dangerous` permissions guarding components
+/// of privileged APKs, and, if a diff source is given, permissions with a
+/// different protection level than in the diff source.
+pub fn find_weak_permissions(
+    db: &DeviceDatabase,
+    diff_source: Option<i32>,
+) -> Result<Vec<WeakPermission>> {
+    let apks = db
+        .get_apks()?
+        .into_iter()
+        .map(|it| (it.id, it))
+        .collect::<HashMap<i32, Apk>>();
+
+    let platform_signed = match db.get_platform_signed_apks() {
+        Ok(it) => it.into_iter().map(|it| it.id).collect::<HashSet<i32>>(),
+        Err(Error::NotFound) => HashSet::new(),
+        Err(e) => return Err(e),
+    };
+    let is_privileged = |apk: &Apk| {
+        apk.is_priv
+            || platform_signed.contains(&apk.id)
+            || apk.shared_user_id.as_deref() == Some(SYSTEM_UID)
+    };
+
+    let mut definitions: HashMap<String, Vec<Permission>> = HashMap::new();
+    for perm in db.get_permissions()? {
+        definitions.entry(perm.name.clone()).or_default().push(perm);
+    }
+
+    let mut requests: HashMap<String, Vec<String>> = HashMap::new();
+    for it in db.get_all_apks_with_permsissions()? {
+        for perm in it.permissions {
+            requests.entry(perm).or_default().push(it.apk.name.clone());
+        }
+    }
+
+    let mut guarded: BTreeMap<String, Vec<GuardedComponent>> = BTreeMap::new();
+    add_guarded(&mut guarded, &apks, &is_privileged, &db.get_activities()?);
+    add_guarded(&mut guarded, &apks, &is_privileged, &db.get_receivers()?);
+    add_guarded(&mut guarded, &apks, &is_privileged, &db.get_services()?);
+    let providers = db.get_provider_paths(db.get_providers()?)?;
+    let plain = providers
+        .iter()
+        .map(|it| it.provider.clone())
+        .collect::<Vec<_>>();
+    add_guarded(&mut guarded, &apks, &is_privileged, &plain);
+    add_path_guarded(&mut guarded, &apks, &is_privileged, &providers);
+
+    let apk_name = |id: i32| {
+        apks.get(&id)
+            .map(|it| it.name.clone())
+            .unwrap_or_else(|| format!("<apk {}>", id))
+    };
+    let is_custom = |defs: &[Permission]| {
+        defs.iter().all(|it| {
+            !apks
+                .get(&it.source_apk_id)
+                .is_some_and(|apk| apk.app_name == PLATFORM_APP_NAME)
+        })
+    };
+
+    let mut found: Vec<WeakPermission> = Vec::new();
+
+    for (name, components) in guarded.iter() {
+        let kind = match definitions.get(name) {
+            None => WeakPermissionKind::Undefined,
+            Some(defs)
+                if is_custom(defs)
+                    && is_weak_protection_level(&defs[0].protection_level)
+                    && components.iter().any(|it| it.privileged) =>
+            {
+                WeakPermissionKind::Weak
+            }
+            Some(_) => continue,
+        };
+        let defs = definitions.get(name).map(Vec::as_slice).unwrap_or(&[]);
+        found.push(WeakPermission {
+            kind,
+            name: name.clone(),
+            protection_level: defs.first().map(|it| it.protection_level.clone()),
+            defined_by: defs.iter().map(|it| apk_name(it.source_apk_id)).collect(),
+            diff_protection_level: None,
+            requested_by: requests.get(name).cloned().unwrap_or_default(),
+            components: components.clone(),
+        });
+    }
+
+    if let Some(diff_id) = diff_source {
+        let mut changed = BTreeMap::new();
+        for it in db.get_permission_diffs_by_diff_id(diff_id)? {
+            if it.exists_in_diff && !it.protection_level_matches_diff {
+                changed.insert(it.permission.name.clone(), it);
+            }
+        }
+        for (name, it) in changed {
+            let defs = definitions.get(&name).map(Vec::as_slice).unwrap_or(&[]);
+            // Already reported as weak, just note the difference
+            if let Some(perm) = found.iter_mut().find(|p| p.name == name) {
+                perm.diff_protection_level = it.diff_protection_level.clone();
+                continue;
+            }
+            found.push(WeakPermission {
+                kind: WeakPermissionKind::ChangedFromDiff,
+                name: name.clone(),
+                protection_level: Some(it.permission.protection_level.clone()),
+                defined_by: defs.iter().map(|it| apk_name(it.source_apk_id)).collect(),
+                diff_protection_level: it.diff_protection_level.clone(),
+                requested_by: requests.get(&name).cloned().unwrap_or_default(),
+                components: guarded.get(&name).cloned().unwrap_or_default(),
+            });
+        }
+    }
+
+    found.sort_by(|lhs, rhs| {
+        lhs.kind
+            .cmp(&rhs.kind)
+            .then_with(|| lhs.name.cmp(&rhs.name))
+    });
+    Ok(found)
+}
+
+fn add_guarded<T, F>(
+    into: &mut BTreeMap<String, Vec<GuardedComponent>>,
+    apks: &HashMap<i32, Apk>,
+    is_privileged: &F,
+    components: &[T],
+) where
+    T: ApkIPC,
+    F: Fn(&Apk) -> bool,
+{
+    for comp in components.iter() {
+        if !comp.is_exported() || !comp.is_enabled() {
+            continue;
+        }
+        let apk = apks.get(&comp.get_apk_id());
+        let kind = comp.get_kind();
+        let name = match kind {
+            ApkIPCKind::Provider => comp.get_class_name().to_string(),
+            _ => format!("{}/{}", comp.get_package(), comp.get_class_name()),
+        };
+        for (mode, attribute) in PERMISSION_ATTRIBUTES {
+            let Some(perm) = comp.get_permission_for_mode(mode) else {
+                continue;
+            };
+            into.entry(perm.to_string())
+                .or_default()
+                .push(GuardedComponent {
+                    kind,
+                    name: name.clone(),
+                    apk: apk.map(|it| it.name.clone()).unwrap_or_default(),
+                    attribute,
+                    privileged: apk.is_some_and(is_privileged),
+                });
+        }
+    }
+}
+
+/// Add the `<path-permission>` entries of exported and enabled providers
+fn add_path_guarded<F>(
+    into: &mut BTreeMap<String, Vec<GuardedComponent>>,
+    apks: &HashMap<i32, Apk>,
+    is_privileged: &F,
+    providers: &[ProviderWithPaths],
+) where
+    F: Fn(&Apk) -> bool,
+{
+    for it in providers.iter() {
+        let provider = &it.provider;
+        if !provider.exported || !provider.enabled {
+            continue;
+        }
+        let apk = apks.get(&provider.apk_id);
+        for path in it.path_permissions.iter() {
+            let name = match path.uri_pattern() {
+                Some(pattern) => format!("{} {}", provider.name, pattern),
+                None => provider.name.clone(),
+            };
+            for (mode, attribute) in PERMISSION_ATTRIBUTES {
+                let Some(perm) = path.get_permission_for_mode(mode) else {
+                    continue;
+                };
+                into.entry(perm.to_string())
+                    .or_default()
+                    .push(GuardedComponent {
+                        kind: ApkIPCKind::Provider,
+                        name: name.clone(),
+                        apk: apk.map(|it| it.name.clone()).unwrap_or_default(),
+                        attribute,
+                        privileged: apk.is_some_and(is_privileged),
+                    });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::device::db::test::db_test;
+    use crate::db::device::models::{InsertPermission, InsertPermissionDiff};
+    use crate::db::device::schema::{activities, apk_permissions, permissions, receivers};
+    use crate::testing::{tmp_context, TestContext};
+    use diesel::insert_into;
+    use diesel::prelude::*;
+    use rstest::*;
+
+    #[test]
+    fn test_is_weak_protection_level() {
+        assert!(is_weak_protection_level("normal"));
+        assert!(is_weak_protection_level("dangerous|instant"));
+        assert!(is_weak_protection_level("0x0"));
+        assert!(is_weak_protection_level("0x1"));
+        assert!(!is_weak_protection_level("signature"));
+        assert!(!is_weak_protection_level("signature|privileged"));
+        assert!(!is_weak_protection_level("0x12"));
+    }
+
+    #[rstest]
+    fn test_find_weak_permissions(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            db.with_connection(|c| {
+                // just.an.app (0) is platform signed, is.debuggable (1) isn't
+                for (name, level, apk) in [
+                    ("just.an.app.permission.WEAK", "normal", 0),
+                    ("just.an.app.permission.STRONG", "signature", 0),
+                    ("is.debuggable.permission.WEAK", "normal", 1),
+                    // Guards the /public path of the exact provider
+                    ("exact.WRITE", "normal", 0),
+                ] {
+                    insert_into(permissions::table)
+                        .values(&InsertPermission::new(name, level, apk))
+                        .execute(c)?;
+                }
+                insert_into(activities::table)
+                    .values((
+                        activities::id.eq(10),
+                        activities::class_name.eq("just.an.app.AdminActivity"),
+                        activities::permission.eq("just.an.app.permission.WEAK"),
+                        activities::exported.eq(true),
+                        activities::enabled.eq(true),
+                        activities::pkg.eq("just.an.app"),
+                        activities::apk_id.eq(0),
+                    ))
+                    .execute(c)?;
+                insert_into(activities::table)
+                    .values((
+                        activities::id.eq(11),
+                        activities::class_name.eq("just.an.app.StrongActivity"),
+                        activities::permission.eq("just.an.app.permission.STRONG"),
+                        activities::exported.eq(true),
+                        activities::enabled.eq(true),
+                        activities::pkg.eq("just.an.app"),
+                        activities::apk_id.eq(0),
+                    ))
+                    .execute(c)?;
+                insert_into(receivers::table)
+                    .values((
+                        receivers::id.eq(10),
+                        receivers::class_name.eq("is.debuggable.WeakReceiver"),
+                        receivers::permission.eq("is.debuggable.permission.WEAK"),
+                        receivers::exported.eq(true),
+                        receivers::enabled.eq(true),
+                        receivers::pkg.eq("is.debuggable"),
+                        receivers::apk_id.eq(1),
+                    ))
+                    .execute(c)?;
+                insert_into(receivers::table)
+                    .values((
+                        receivers::id.eq(11),
+                        receivers::class_name.eq("is.debuggable.SquatReceiver"),
+                        receivers::permission.eq("com.vendor.permission.UNDEFINED"),
+                        receivers::exported.eq(true),
+                        receivers::enabled.eq(true),
+                        receivers::pkg.eq("is.debuggable"),
+                        receivers::apk_id.eq(1),
+                    ))
+                    .execute(c)?;
+                insert_into(apk_permissions::table)
+                    .values((
+                        apk_permissions::name.eq("com.vendor.permission.UNDEFINED"),
+                        apk_permissions::apk_id.eq(1),
+                    ))
+                    .execute(c)?;
+                Ok::<(), diesel::result::Error>(())
+            })
+            .unwrap();
+
+            let found = find_weak_permissions(&db, None).unwrap();
+            let summary = found
+                .iter()
+                .map(|it| (it.kind, it.name.as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                summary,
+                vec![
+                    (
+                        WeakPermissionKind::Undefined,
+                        "com.vendor.permission.UNDEFINED"
+                    ),
+                    (WeakPermissionKind::Undefined, "exact.SECRET"),
+                    (WeakPermissionKind::Weak, "exact.WRITE"),
+                    (WeakPermissionKind::Weak, "just.an.app.permission.WEAK"),
+                ]
+            );
+            assert_eq!(found[0].requested_by, vec![String::from("Debuggable.apk")]);
+            assert!(found[0].defined_by.is_empty());
+
+            // The /secret path sets all three attributes
+            let attributes = found[1]
+                .components
+                .iter()
+                .map(|it| it.attribute)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                attributes,
+                vec!["permission", "readPermission", "writePermission"]
+            );
+
+            let path = &found[2].components;
+            assert_eq!(path.len(), 1);
+            assert_eq!(path[0].kind, ApkIPCKind::Provider);
+            assert_eq!(path[0].name, "exact pathPrefix=/public");
+            assert_eq!(path[0].attribute, "writePermission");
+            assert!(path[0].privileged);
+
+            assert_eq!(found[3].components.len(), 1);
+            assert_eq!(found[3].components[0].kind, ApkIPCKind::Activity);
+            assert!(found[3].components[0].privileged);
+            assert_eq!(found[3].defined_by, vec![String::from("JustAnApp.apk")]);
+
+            let strong = db
+                .get_permission_by_name("just.an.app.permission.STRONG")
+                .unwrap();
+            db.add_permission_diff(&InsertPermissionDiff {
+                permission: strong.id,
+                diff_source: 1,
+                exists_in_diff: true,
+                protection_level_matches_diff: false,
+                diff_protection_level: Some("normal"),
+            })
+            .unwrap();
+
+            let found = find_weak_permissions(&db, Some(1)).unwrap();
+            assert_eq!(found.len(), 5);
+            let changed = &found[4];
+            assert_eq!(changed.kind, WeakPermissionKind::ChangedFromDiff);
+            assert_eq!(changed.name, "just.an.app.permission.STRONG");
+            assert_eq!(changed.protection_level.as_deref(), Some("signature"));
+            assert_eq!(changed.diff_protection_level.as_deref(), Some("normal"));
+            assert_eq!(changed.components.len(), 1);
+        });
+    }
+}
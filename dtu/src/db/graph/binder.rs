@@ -0,0 +1,380 @@
+//! Linking `Stub.Proxy` methods to the system service methods they call
+//!
+//! The device database knows which interface each system service exposes,
+//! which classes implement it, and the transaction id for every method. Each
+//! `<iface>$Stub$Proxy` method in the graph is matched to a system service
+//! method by the transaction id it passes to `IBinder.transact` and linked to
+//! the method with that name and the proxy's signature in every
+//! implementation of the service. The result is stored in the `binder_calls`
+//! table. Walking the call graph with [DispatchMode::binder](super::DispatchMode)
+//! follows these links.
+
+use std::collections::HashMap;
+use std::fs;
+
+use diesel::prelude::*;
+use diesel::{delete, insert_or_ignore_into};
+
+use super::models::{InsertBinderCall, MethodSearch, MethodSearchParams, MethodSpec};
+use super::schema::binder_calls;
+use super::{GraphDatabase, GraphSqliteDatabase, FRAMEWORK_SOURCE};
+use crate::db::device::models::SystemServiceMethod;
+use crate::db::{DeviceDatabase, Result};
+use crate::utils::smali_diff::find_smali_method;
+use crate::utils::{find_smali_file_for_class, ClassName, DevicePath};
+use crate::Context;
+
+/// Summary of a [link_binder_calls] run
+#[derive(Default)]
+pub struct BinderLinkSummary {
+    /// Number of system services with at least one linked method
+    pub services: usize,
+    /// Number of proxy to implementation links found
+    pub linked: usize,
+}
+
+/// Get the `Stub.Proxy` class for the given AIDL interface
+pub fn proxy_class_for(iface: &ClassName) -> ClassName {
+    ClassName::from(format!("{}$Stub$Proxy", iface.get_java_name()))
+}
+
+/// Find the transaction id a `Stub.Proxy` method passes to `IBinder.transact`
+///
+/// The `TRANSACTION_*` constants are inlined by the compiler, so this is the
+/// last constant loaded into the register holding the code.
+pub fn proxy_transaction_id(body: &[&str]) -> Option<i32> {
+    let mut consts: HashMap<&str, i32> = HashMap::new();
+    let mut found = None;
+    for line in body.iter().map(|it| it.trim()) {
+        let Some((op, rest)) = line.split_once(' ') else {
+            continue;
+        };
+        if op == "const" || op == "const/4" || op == "const/16" {
+            if let Some((reg, value)) = rest.split_once(", ") {
+                if let Some(value) = parse_smali_int(value) {
+                    consts.insert(reg, value);
+                }
+            }
+            continue;
+        }
+        if !op.starts_with("invoke-interface")
+            || !rest.ends_with(
+                "Landroid/os/IBinder;->transact(ILandroid/os/Parcel;Landroid/os/Parcel;I)Z",
+            )
+        {
+            continue;
+        }
+        let regs = rest.strip_prefix('{')?.split_once('}')?.0;
+        // The code is the argument after the IBinder
+        let code_reg = match regs.split_once(" .. ") {
+            Some((first, _)) => {
+                let (prefix, num) = first.split_at(1);
+                format!("{}{}", prefix, num.parse::<u32>().ok()? + 1)
+            }
+            None => String::from(regs.split(", ").nth(1)?),
+        };
+        let txn = *consts.get(code_reg.as_str())?;
+        // Proxies only ever make one transaction
+        if found.is_some_and(|it| it != txn) {
+            return None;
+        }
+        found = Some(txn);
+    }
+    found
+}
+
+fn parse_smali_int(value: &str) -> Option<i32> {
+    let (neg, value) = match value.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, value),
+    };
+    let value = match value.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => value.parse::<i64>().ok()?,
+    };
+    i32::try_from(if neg { -value } else { value }).ok()
+}
+
+/// Pick the system service method a proxy method calls
+///
+/// With the transaction id, the method is the one with the same id as long as
+/// the signatures agree. Without it, which happens when the proxy's smali
+/// can't be read, the method has to be the only one with the proxy's name.
+fn match_proxy_method<'a>(
+    proxy: &MethodSpec,
+    txn: Option<i32>,
+    methods: &'a [SystemServiceMethod],
+) -> Option<&'a SystemServiceMethod> {
+    let signature_agrees = |m: &SystemServiceMethod| {
+        m.signature
+            .as_ref()
+            .map_or(true, |it| *it == proxy.signature)
+    };
+    match txn {
+        Some(txn) => {
+            let Some(m) = methods.iter().find(|it| it.transaction_id == txn) else {
+                log::debug!(
+                    "{} uses transaction {} which the service doesn't have",
+                    proxy,
+                    txn
+                );
+                return None;
+            };
+            if !signature_agrees(m) {
+                log::debug!(
+                    "{} uses transaction {} but the signature doesn't match {}",
+                    proxy,
+                    txn,
+                    m
+                );
+                return None;
+            }
+            Some(m)
+        }
+        None => {
+            let mut named = methods
+                .iter()
+                .filter(|it| it.name == proxy.name && signature_agrees(it));
+            let m = named.next()?;
+            if named.next().is_some() {
+                log::debug!("{} matches more than one method by name", proxy);
+                return None;
+            }
+            Some(m)
+        }
+    }
+}
+
+/// Read the transaction ids of every method of the proxy class in the given
+/// source, keyed by the method's name and signature
+fn read_proxy_transactions(
+    ctx: &dyn Context,
+    proxy: &ClassName,
+    source: &str,
+    methods: &[&MethodSpec],
+) -> HashMap<(String, String), i32> {
+    let mut txns = HashMap::new();
+    let apk = if source == FRAMEWORK_SOURCE {
+        None
+    } else {
+        Some(DevicePath::from_squashed(source))
+    };
+    let Some(smali) = find_smali_file_for_class(ctx, proxy, apk.as_ref())
+        .and_then(|it| fs::read_to_string(it).ok())
+    else {
+        log::debug!("couldn't read the smali for {} in {}", proxy, source);
+        return txns;
+    };
+    for m in methods {
+        let Some(body) = find_smali_method(&smali, &m.name, Some(m.signature.as_str())) else {
+            continue;
+        };
+        if let Some(txn) = proxy_transaction_id(&body) {
+            txns.insert((m.name.clone(), m.signature.clone()), txn);
+        }
+    }
+    txns
+}
+
+/// Rebuild the `binder_calls` table from the system services in the device
+/// database
+///
+/// Proxies are linked from every source since apps commonly bundle their own
+/// copy of framework AIDL interfaces. A proxy whose transaction id doesn't
+/// match the service's, such as one from an older copy of the interface, isn't
+/// linked.
+pub fn link_binder_calls(
+    db: &GraphSqliteDatabase,
+    device: &DeviceDatabase,
+    ctx: &dyn Context,
+) -> Result<BinderLinkSummary> {
+    let services = device.get_system_services()?;
+    let impls = device.get_all_system_service_impls()?;
+
+    let mut summary = BinderLinkSummary::default();
+    let mut found: Vec<(i32, i32, i32)> = Vec::new();
+
+    for service in services {
+        let iface = match service.iface.as_ref() {
+            Some(v) => v,
+            None => continue,
+        };
+        let service_impls = match impls.get(&service.name) {
+            Some(v) => v,
+            None => {
+                log::debug!("no implementations for {}", service.name);
+                continue;
+            }
+        };
+
+        let proxy = proxy_class_for(iface);
+        let proxy_methods = db.get_methods(&MethodSearch::new(
+            MethodSearchParams::ByClass { class: &proxy },
+            None,
+        ))?;
+        if proxy_methods.is_empty() {
+            log::trace!("no proxy methods for {}", proxy);
+            continue;
+        }
+
+        let mut by_source: HashMap<&str, Vec<&MethodSpec>> = HashMap::new();
+        for m in proxy_methods.iter() {
+            by_source.entry(m.source.as_str()).or_default().push(m);
+        }
+
+        let methods = device.get_system_service_methods_by_service_id(service.id)?;
+        let before = found.len();
+
+        for (source, source_methods) in by_source {
+            let txns = read_proxy_transactions(ctx, &proxy, source, &source_methods);
+            for pm in source_methods {
+                let txn = txns.get(&(pm.name.clone(), pm.signature.clone())).copied();
+                let Some(m) = match_proxy_method(pm, txn, &methods) else {
+                    continue;
+                };
+                for imp in service_impls {
+                    let search = MethodSearch::new(
+                        MethodSearchParams::ByFullSpec {
+                            class: &imp.class_name,
+                            name: &m.name,
+                            signature: &pm.signature,
+                        },
+                        Some(&imp.source),
+                    );
+                    for target in db.get_method_ids(&search)? {
+                        found.push((pm.id, target, m.transaction_id));
+                    }
+                }
+            }
+        }
+
+        if found.len() > before {
+            summary.services += 1;
+        }
+    }
+
+    found.sort_unstable();
+    found.dedup();
+    summary.linked = found.len();
+
+    db.transaction(|c| -> Result<()> {
+        delete(binder_calls::table).execute(c)?;
+        for chunk in found.chunks(1000) {
+            let values = chunk
+                .iter()
+                .map(|(proxy, imp, txn)| InsertBinderCall::new(*proxy, *imp, *txn))
+                .collect::<Vec<_>>();
+            insert_or_ignore_into(binder_calls::table)
+                .values(values.as_slice())
+                .execute(c)?;
+        }
+        Ok(())
+    })?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use smalisa::AccessFlag;
+
+    fn proxy_method(name: &str, signature: &str) -> MethodSpec {
+        MethodSpec {
+            id: 1,
+            class: ClassName::from("Landroid/os/IFooService$Stub$Proxy;"),
+            name: name.into(),
+            signature: signature.into(),
+            ret: "V".into(),
+            source: FRAMEWORK_SOURCE.into(),
+            access_flags: AccessFlag::PUBLIC,
+        }
+    }
+
+    fn service_method(txn: i32, name: &str, signature: Option<&str>) -> SystemServiceMethod {
+        SystemServiceMethod {
+            id: txn,
+            system_service_id: 1,
+            transaction_id: txn,
+            name: name.into(),
+            signature: signature.map(String::from),
+            return_type: None,
+            smalisa_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_proxy_transaction_id() {
+        let smali = r#"
+.method public doThing(I)V
+    .registers 6
+    invoke-static {}, Landroid/os/Parcel;->obtain()Landroid/os/Parcel;
+    move-result-object v0
+    const/4 v2, 0x0
+    :try_start_0
+    iget-object v1, p0, Landroid/os/IFooService$Stub$Proxy;->mRemote:Landroid/os/IBinder;
+    const/16 v3, 0x1d
+    invoke-interface {v1, v3, v0, v2, v2}, Landroid/os/IBinder;->transact(ILandroid/os/Parcel;Landroid/os/Parcel;I)Z
+    return-void
+.end method
+
+.method public doOther()V
+    .registers 8
+    const v3, 0x10
+    invoke-interface/range {v2 .. v6}, Landroid/os/IBinder;->transact(ILandroid/os/Parcel;Landroid/os/Parcel;I)Z
+    return-void
+.end method
+
+.method public asBinder()Landroid/os/IBinder;
+    .registers 2
+    iget-object v0, p0, Landroid/os/IFooService$Stub$Proxy;->mRemote:Landroid/os/IBinder;
+    return-object v0
+.end method
+"#;
+        let txn = |name: &str, args: &str| {
+            proxy_transaction_id(&find_smali_method(smali, name, Some(args)).unwrap())
+        };
+        assert_eq!(txn("doThing", "I"), Some(0x1d));
+        assert_eq!(txn("doOther", ""), Some(0x10));
+        assert_eq!(txn("asBinder", ""), None);
+    }
+
+    #[test]
+    fn test_match_proxy_method_overloaded() {
+        let methods = vec![
+            service_method(1, "doThing", Some("I")),
+            service_method(2, "doThing", Some("Ljava/lang/String;")),
+            service_method(3, "renamed", None),
+        ];
+        let matched = |proxy: &MethodSpec, txn: Option<i32>| {
+            match_proxy_method(proxy, txn, &methods).map(|it| it.transaction_id)
+        };
+
+        // Overloads are told apart by their transaction ids
+        assert_eq!(matched(&proxy_method("doThing", "I"), Some(1)), Some(1));
+        assert_eq!(
+            matched(&proxy_method("doThing", "Ljava/lang/String;"), Some(2)),
+            Some(2)
+        );
+        // Ids that disagree with the signature or that the service doesn't
+        // have aren't linked
+        assert_eq!(matched(&proxy_method("doThing", "I"), Some(2)), None);
+        assert_eq!(matched(&proxy_method("doThing", "I"), Some(9)), None);
+        // The transaction id wins over the name
+        assert_eq!(matched(&proxy_method("obfuscated", "J"), Some(3)), Some(3));
+        // Without the id only an unambiguous name can be used
+        assert_eq!(matched(&proxy_method("doThing", "I"), None), Some(1));
+        assert_eq!(matched(&proxy_method("doThing", "Z"), None), None);
+        assert_eq!(matched(&proxy_method("renamed", "Z"), None), Some(3));
+        assert_eq!(matched(&proxy_method("obfuscated", "J"), None), None);
+    }
+
+    #[test]
+    fn test_proxy_class_for() {
+        let iface = ClassName::from("Landroid/os/IFooService;");
+        assert_eq!(
+            proxy_class_for(&iface),
+            ClassName::from("android.os.IFooService$Stub$Proxy")
+        );
+    }
+}
@@ -1,11 +1,13 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fs;
 use std::iter::repeat;
 
 use diesel::backend::Backend;
 use diesel::connection::SimpleConnection;
+use diesel::dsl;
 use diesel::sql_query;
-use diesel::sql_types::{BigInt, Integer, Text};
+use diesel::sql_types::{BigInt, Bool, Integer, Text};
 use diesel::SqliteConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 use smalisa::AccessFlag;
@@ -14,8 +16,8 @@ use super::schema::*;
 use crate::db::common::DBThread;
 use crate::db::common::*;
 use crate::db::graph::models::{
-    ClassSearch, FieldRef, FieldSearchParams, MethodCallPath, MethodSearch, MethodSearchParams,
-    MethodSpec, SourcedString,
+    ClassSearch, DispatchMode, FieldRef, FieldSearchParams, MethodCallPath, MethodSearch,
+    MethodSearchParams, MethodSpec, NativeImpl, NativeMethod, NativeMethodSpec, SourcedString,
 };
 use crate::db::graph::models::{FieldAccessOp, FieldSearch, FieldSpec, Source};
 use crate::db::graph::{ClassSpec, GraphDatabase, StringSearch};
@@ -104,7 +106,7 @@ impl GraphSqliteDatabase {
         })?)
     }
 
-    impl_get_all!(get_sources, Source, sources);
+    impl_get_all!(pub(super) get_sources, Source, sources);
     impl_delete_by!(delete_source_by_name, &str, sources, name.eq);
 
     #[inline]
@@ -218,12 +220,78 @@ impl GraphSqliteDatabase {
         method: &MethodSearch,
         call_source: Option<&str>,
         depth: usize,
+        dispatch: DispatchMode,
     ) -> Result<Vec<MethodCallPath>> {
         let (src, dst) = match dir {
             CallDirection::Into => ("caller", "callee"),
             CallDirection::From => ("callee", "caller"),
         };
 
+        // With class hierarchy analysis we also step over `method_overrides`. Going into a
+        // method, anything calling a method it overrides is a potential caller. Going out of a
+        // method, anything overriding a callee is a potential callee.
+        let cha_step = match (dispatch.is_class_hierarchy(), &dir) {
+            (false, _) => "",
+            (true, CallDirection::Into) => {
+                r#"
+        UNION
+        SELECT
+            c.caller,
+            ct.distance + 1,
+            json_insert(ct.path, '$[#]', c.caller)
+        FROM method_overrides AS o
+        JOIN calls_to AS ct
+            ON ct.methodid = o.method
+        JOIN calls AS c
+            ON c.callee = o.parent
+        WHERE ct.distance < ?"#
+            }
+            (true, CallDirection::From) => {
+                r#"
+        UNION
+        SELECT
+            o.method,
+            ct.distance + 1,
+            json_insert(ct.path, '$[#]', o.method)
+        FROM calls AS c
+        JOIN calls_to AS ct
+            ON ct.methodid = c.caller
+        JOIN method_overrides AS o
+            ON o.parent = c.callee
+        WHERE ct.distance < ?"#
+            }
+        };
+
+        // Following binder calls treats the `Stub.Proxy` method as calling the system service
+        // method it transacts with, so the proxy stays in the path.
+        let binder_step = match (dispatch.is_binder(), &dir) {
+            (false, _) => "",
+            (true, CallDirection::Into) => {
+                r#"
+        UNION
+        SELECT
+            b.proxy,
+            ct.distance + 1,
+            json_insert(ct.path, '$[#]', b.proxy)
+        FROM binder_calls AS b
+        JOIN calls_to AS ct
+            ON ct.methodid = b.service_impl
+        WHERE ct.distance < ?"#
+            }
+            (true, CallDirection::From) => {
+                r#"
+        UNION
+        SELECT
+            b.service_impl,
+            ct.distance + 1,
+            json_insert(ct.path, '$[#]', b.service_impl)
+        FROM binder_calls AS b
+        JOIN calls_to AS ct
+            ON ct.methodid = b.proxy
+        WHERE ct.distance < ?"#
+            }
+        };
+
         Ok(self.with_connection(|c| -> Result<Vec<MethodCallPath>> {
             let method_ids = Self::get_method_ids_with_conn(c, method)?;
 
@@ -253,7 +321,7 @@ impl GraphSqliteDatabase {
         FROM calls AS c
         JOIN calls_to AS ct
             ON ct.methodid = c.{dst}
-        WHERE ct.distance < ?
+        WHERE ct.distance < ?{cha_step}{binder_step}
         ORDER BY 2 DESC
     ),
     method_calls(source, class, id, name, args, ret, access_flags, idx) AS (
@@ -282,6 +350,14 @@ SELECT * from method_calls;"#,
 
             q = q.bind::<Integer, _>(int_depth);
 
+            if dispatch.is_class_hierarchy() {
+                q = q.bind::<Integer, _>(int_depth);
+            }
+
+            if dispatch.is_binder() {
+                q = q.bind::<Integer, _>(int_depth);
+            }
+
             let rows: Vec<MethodCallRow> = query!(q).get_results(c)?;
             let it = PathRowIterator::new(rows.into_iter());
 
@@ -305,6 +381,250 @@ SELECT * from method_calls;"#,
             })
         })?)
     }
+
+    fn get_call_paths(
+        &self,
+        from: &MethodSearch,
+        to: &MethodSearch,
+        max_depth: usize,
+        limit: CallPathLimit,
+    ) -> Result<Vec<MethodCallPath>> {
+        let k = match limit {
+            CallPathLimit::Shortest => {
+                return self.get_shortest_call_paths(from, to, max_depth);
+            }
+            CallPathLimit::K(k) => k,
+        };
+
+        self.with_connection(|c| -> Result<Vec<MethodCallPath>> {
+            let from_ids = Self::get_method_ids_with_conn(c, from)?;
+            let targets = Self::get_method_ids_with_conn(c, to)?
+                .into_iter()
+                .collect::<HashSet<i32>>();
+
+            // Search backwards from the targets level by level, the same way
+            // `get_shortest_call_paths` searches forward, to find how far each method is from
+            // the closest target. Paths stop at the first target, so targets are never passed
+            // through. Only the calls into methods that can still reach a target are kept.
+            let mut dist: HashMap<i32, usize> = targets.iter().map(|it| (*it, 0)).collect();
+            let mut callees: HashMap<i32, Vec<i32>> = HashMap::new();
+            let mut frontier = targets.iter().copied().collect::<Vec<i32>>();
+            for level in 1..=max_depth {
+                if frontier.is_empty() {
+                    break;
+                }
+                let mut next = Vec::new();
+                for chunk in frontier.chunks(1000) {
+                    let edges = query!(calls::table
+                        .filter(calls::callee.eq_any(chunk))
+                        .select((calls::caller, calls::callee)))
+                    .load::<(i32, i32)>(c)?;
+                    for (caller, callee) in edges {
+                        callees.entry(caller).or_default().push(callee);
+                        if !dist.contains_key(&caller) {
+                            dist.insert(caller, level);
+                            next.push(caller);
+                        }
+                    }
+                }
+                frontier = next;
+            }
+            for it in callees.values_mut() {
+                it.sort();
+                it.dedup();
+            }
+
+            // Extend the partial path with the shortest possible length first. The distances
+            // never overestimate, so complete paths come out shortest first and the search
+            // stops after `k` of them instead of building every path up to `max_depth`.
+            let mut queue = BinaryHeap::new();
+            let mut starts = from_ids
+                .into_iter()
+                .filter(|it| !targets.contains(it))
+                .collect::<Vec<i32>>();
+            starts.sort();
+            starts.dedup();
+            for mid in starts {
+                if let Some(d) = dist.get(&mid) {
+                    queue.push(Reverse((*d, path_key(&[mid]), vec![mid])));
+                }
+            }
+
+            let mut found = Vec::new();
+            while found.len() < k {
+                let Some(Reverse((_, _, path))) = queue.pop() else {
+                    break;
+                };
+                let last = *path.last().expect("paths are never empty");
+                if targets.contains(&last) {
+                    found.push(path);
+                    continue;
+                }
+                let Some(next) = callees.get(&last) else {
+                    continue;
+                };
+                for callee in next {
+                    if path.contains(callee) {
+                        continue;
+                    }
+                    let Some(d) = dist.get(callee) else {
+                        continue;
+                    };
+                    let length = path.len() + d;
+                    if length > max_depth {
+                        continue;
+                    }
+                    let mut it = path.clone();
+                    it.push(*callee);
+                    queue.push(Reverse((length, path_key(&it), it)));
+                }
+            }
+
+            Self::load_call_paths(c, found)
+        })
+    }
+
+    /// Find every shortest path between the methods
+    ///
+    /// This is a breadth first search that records, for each method, the callers one level
+    /// closer to the `from` methods and stops at the first level that reaches a `to` method.
+    /// Only the paths through those callers are built, so the cost doesn't grow with the number
+    /// of longer paths.
+    fn get_shortest_call_paths(
+        &self,
+        from: &MethodSearch,
+        to: &MethodSearch,
+        max_depth: usize,
+    ) -> Result<Vec<MethodCallPath>> {
+        self.with_connection(|c| -> Result<Vec<MethodCallPath>> {
+            let from_ids = Self::get_method_ids_with_conn(c, from)?;
+            let targets = Self::get_method_ids_with_conn(c, to)?
+                .into_iter()
+                .collect::<HashSet<i32>>();
+
+            // Paths stop at the first target, so targets are never expanded, not even when
+            // they are also one of the `from` methods
+            let mut frontier = from_ids
+                .into_iter()
+                .filter(|it| !targets.contains(it))
+                .collect::<HashSet<i32>>()
+                .into_iter()
+                .collect::<Vec<i32>>();
+            let mut preds: HashMap<i32, Vec<i32>> =
+                frontier.iter().map(|it| (*it, Vec::new())).collect();
+            let mut found = Vec::new();
+
+            for _ in 0..max_depth {
+                if frontier.is_empty() || targets.is_empty() {
+                    break;
+                }
+
+                let mut next: HashMap<i32, Vec<i32>> = HashMap::new();
+                for chunk in frontier.chunks(1000) {
+                    let edges = query!(calls::table
+                        .filter(calls::caller.eq_any(chunk))
+                        .select((calls::caller, calls::callee)))
+                    .load::<(i32, i32)>(c)?;
+                    for (caller, callee) in edges {
+                        // Already reached on an earlier level
+                        if preds.contains_key(&callee) {
+                            continue;
+                        }
+                        next.entry(callee).or_default().push(caller);
+                    }
+                }
+
+                frontier.clear();
+                for (mid, callers) in next.iter_mut() {
+                    callers.sort();
+                    callers.dedup();
+                    if targets.contains(mid) {
+                        found.push(*mid);
+                    } else {
+                        frontier.push(*mid);
+                    }
+                }
+                preds.extend(next);
+
+                if !found.is_empty() {
+                    break;
+                }
+            }
+
+            // Walk back from the targets to the `from` methods, which have no callers recorded
+            let mut paths = found.into_iter().map(|it| vec![it]).collect::<Vec<_>>();
+            loop {
+                let mut done = true;
+                let mut extended = Vec::with_capacity(paths.len());
+                for path in paths {
+                    let last = *path.last().expect("paths are never empty");
+                    match preds.get(&last) {
+                        Some(callers) if !callers.is_empty() => {
+                            done = false;
+                            for caller in callers {
+                                let mut it = path.clone();
+                                it.push(*caller);
+                                extended.push(it);
+                            }
+                        }
+                        _ => extended.push(path),
+                    }
+                }
+                paths = extended;
+                if done {
+                    break;
+                }
+            }
+            for path in paths.iter_mut() {
+                path.reverse();
+            }
+            // Same order as the paths from `get_call_paths`
+            paths.sort_by_cached_key(|it| path_key(it));
+
+            Self::load_call_paths(c, paths)
+        })
+    }
+
+    /// Turn paths of method IDs into [MethodCallPath]s
+    fn load_call_paths(
+        c: &mut SqliteConnection,
+        paths: Vec<Vec<i32>>,
+    ) -> Result<Vec<MethodCallPath>> {
+        let mut ids = paths.iter().flatten().copied().collect::<Vec<i32>>();
+        ids.sort();
+        ids.dedup();
+        let mut specs = HashMap::with_capacity(ids.len());
+        for chunk in ids.chunks(1000) {
+            let rows = query!(methods::table
+                .inner_join(classes::table.on(classes::id.eq(methods::class)))
+                .inner_join(sources::table.on(sources::id.eq(methods::source)))
+                .filter(methods::id.eq_any(chunk))
+                .select(MethodSpecRow::as_select()))
+            .load::<MethodSpecRow>(c)?;
+            for row in rows {
+                let spec = MethodSpec::from(row);
+                specs.insert(spec.id, spec);
+            }
+        }
+
+        Ok(paths
+            .into_iter()
+            .filter_map(|path| {
+                path.iter()
+                    .map(|mid| specs.get(mid).cloned())
+                    .collect::<Option<Vec<MethodSpec>>>()
+            })
+            .map(MethodCallPath::from)
+            .collect())
+    }
+}
+
+/// Key used to order paths of the same length
+fn path_key(path: &[i32]) -> String {
+    path.iter()
+        .map(|mid| mid.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
 }
 
 enum CallDirection {
@@ -312,6 +632,13 @@ enum CallDirection {
     Into,
 }
 
+enum CallPathLimit {
+    /// Every path with the shortest length
+    Shortest,
+    /// At most this many paths, shortest first
+    K(usize),
+}
+
 impl<'a> FieldSearchParams<'a> {
     fn get_spec_sql(
         &self,
@@ -833,13 +1160,14 @@ fn conn_find_classes_with_method(
 }
 
 impl GraphDatabase for GraphSqliteDatabase {
-    fn find_callers(
+    fn find_callers_with_dispatch(
         &self,
         method: &MethodSearch,
         call_source: Option<&str>,
         depth: usize,
+        dispatch: DispatchMode,
     ) -> Result<Vec<MethodCallPath>> {
-        self.get_calls(CallDirection::Into, method, call_source, depth)
+        self.get_calls(CallDirection::Into, method, call_source, depth, dispatch)
     }
     fn wipe(&self, ctx: &dyn Context) -> Result<()> {
         let path = ctx.get_sqlite_dir()?;
@@ -1001,12 +1329,82 @@ impl GraphDatabase for GraphSqliteDatabase {
         })
     }
 
-    fn find_outgoing_calls(
+    fn get_native_methods(&self, search: Option<&MethodSearch>) -> Result<Vec<NativeMethodSpec>> {
+        let native = dsl::sql::<Bool>(&format!(
+            "(methods.access_flags & {}) != 0",
+            AccessFlag::NATIVE.bits()
+        ));
+
+        self.with_connection(|c| -> Result<Vec<NativeMethodSpec>> {
+            let rows = match search {
+                Some(search) => {
+                    let ids = Self::get_method_ids_with_conn(c, search)?;
+                    query!(methods::table
+                        .inner_join(sources::table)
+                        .inner_join(classes::table)
+                        .filter(methods::id.eq_any(ids))
+                        .filter(native)
+                        .select(MethodSpecRow::as_select()))
+                    .load::<MethodSpecRow>(c)?
+                }
+                None => query!(methods::table
+                    .inner_join(sources::table)
+                    .inner_join(classes::table)
+                    .filter(native)
+                    .select(MethodSpecRow::as_select()))
+                .load::<MethodSpecRow>(c)?,
+            };
+
+            let ids = rows.iter().map(|it| it.id).collect::<Vec<i32>>();
+            let mut impls: HashMap<i32, Vec<NativeImpl>> = HashMap::new();
+            for chunk in ids.chunks(1000) {
+                let found =
+                    query!(native_methods::table.filter(native_methods::method.eq_any(chunk)))
+                        .load::<NativeMethod>(c)?;
+                for it in found {
+                    impls.entry(it.method).or_default().push(it.into());
+                }
+            }
+
+            Ok(rows
+                .into_iter()
+                .map(|it| {
+                    let implementations = impls.remove(&it.id).unwrap_or_default();
+                    NativeMethodSpec {
+                        method: MethodSpec::from(it),
+                        implementations,
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn find_outgoing_calls_with_dispatch(
         &self,
         from: &MethodSearch,
         depth: usize,
+        dispatch: DispatchMode,
+    ) -> Result<Vec<MethodCallPath>> {
+        self.get_calls(CallDirection::From, from, None, depth, dispatch)
+    }
+
+    fn find_call_paths(
+        &self,
+        from: &MethodSearch,
+        to: &MethodSearch,
+        max_depth: usize,
+    ) -> Result<Vec<MethodCallPath>> {
+        self.get_call_paths(from, to, max_depth, CallPathLimit::Shortest)
+    }
+
+    fn find_k_shortest_call_paths(
+        &self,
+        from: &MethodSearch,
+        to: &MethodSearch,
+        max_depth: usize,
+        k: usize,
     ) -> Result<Vec<MethodCallPath>> {
-        self.get_calls(CallDirection::From, from, None, depth)
+        self.get_call_paths(from, to, max_depth, CallPathLimit::K(k))
     }
 
     fn find_parent_classes_of(&self, child: &ClassName, source: &str) -> Result<Vec<ClassSpec>> {
@@ -1448,13 +1846,14 @@ where
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
     use rstest::*;
     use std::panic;
     use std::panic::AssertUnwindSafe;
 
     use super::super::common::cleanup_database;
+    use super::super::export;
     use crate::testing::{tmp_context, TestContext};
     use crate::utils::ensure_dir_exists;
 
@@ -1467,7 +1866,7 @@ mod test {
         )
     }
 
-    fn db_test(context: &dyn Context, func: impl FnOnce(GraphSqliteDatabase)) {
+    pub(crate) fn db_test(context: &dyn Context, func: impl FnOnce(GraphSqliteDatabase)) {
         let url = get_db_url(&context);
         let db = GraphSqliteDatabase::new_from_url(&url).expect("failed to get database");
         let res = panic::catch_unwind(AssertUnwindSafe(|| func(db)));
@@ -1658,6 +2057,548 @@ mod test {
         });
     }
 
+    fn cha_method(id: i32, class: &str, name: &str, signature: &str) -> MethodSpec {
+        MethodSpec {
+            id,
+            class: class.into(),
+            name: name.into(),
+            signature: signature.into(),
+            ret: "V".into(),
+            source: "F".into(),
+            access_flags: AccessFlag::PUBLIC,
+        }
+    }
+
+    #[rstest]
+    fn test_get_callers_class_hierarchy(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let class = ClassName::from("Lcha/Impl;");
+            let method = MethodSearch::new(
+                MethodSearchParams::ByFullSpec {
+                    class: &class,
+                    name: "run",
+                    signature: "I",
+                },
+                None,
+            );
+
+            let callers = db
+                .find_callers_with_dispatch(&method, None, 2, DispatchMode::default())
+                .expect("find_callers");
+            assert!(callers.is_empty());
+
+            let mut callers = db
+                .find_callers_with_dispatch(&method, None, 2, DispatchMode::new(true))
+                .expect("find_callers");
+            callers.sort();
+
+            let target = cha_method(1002, "cha.Impl", "run", "I");
+            let mut expected = vec![
+                MethodCallPath::from(vec![
+                    cha_method(1003, "cha.Caller", "viaIface", ""),
+                    target.clone(),
+                ]),
+                MethodCallPath::from(vec![cha_method(1004, "cha.Caller", "viaBase", ""), target]),
+            ];
+            expected.sort();
+            assert_eq!(callers, expected);
+        });
+    }
+
+    #[rstest]
+    fn test_get_outgoing_calls_class_hierarchy(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let class = ClassName::from("Lcha/Caller;");
+            let method = MethodSearch::new(
+                MethodSearchParams::ByClassAndName {
+                    class: &class,
+                    name: "viaIface",
+                },
+                None,
+            );
+
+            let caller = cha_method(1003, "cha.Caller", "viaIface", "");
+            let iface = cha_method(1000, "cha.Iface", "run", "I");
+            let base = cha_method(1001, "cha.Base", "run", "I");
+            let imp = cha_method(1002, "cha.Impl", "run", "I");
+            let helper = cha_method(1005, "cha.Impl", "helper", "");
+
+            let calls = db
+                .find_outgoing_calls_with_dispatch(&method, 2, DispatchMode::default())
+                .expect("find_outgoing_calls");
+            assert_eq!(
+                calls,
+                vec![MethodCallPath::from(vec![caller.clone(), iface.clone()])]
+            );
+
+            let mut calls = db
+                .find_outgoing_calls_with_dispatch(&method, 2, DispatchMode::new(true))
+                .expect("find_outgoing_calls");
+            calls.sort();
+
+            let mut expected = vec![
+                MethodCallPath::from(vec![caller.clone(), iface]),
+                MethodCallPath::from(vec![caller.clone(), base]),
+                MethodCallPath::from(vec![caller.clone(), imp.clone()]),
+                MethodCallPath::from(vec![caller, imp, helper]),
+            ];
+            expected.sort();
+            assert_eq!(calls, expected);
+        });
+    }
+
+    #[rstest]
+    fn test_get_calls_binder(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let app = cha_method(1203, "binder.App", "onCreate", "");
+            let proxy = cha_method(1200, "binder.IFoo$Stub$Proxy", "doThing", "I");
+            let service = cha_method(1201, "binder.FooService", "doThing", "I");
+            let helper = cha_method(1202, "binder.FooService", "helper", "");
+            let binder = DispatchMode::default().with_binder(true);
+
+            let class = ClassName::from("Lbinder/App;");
+            let method = MethodSearch::new(
+                MethodSearchParams::ByClassAndName {
+                    class: &class,
+                    name: "onCreate",
+                },
+                None,
+            );
+
+            let calls = db
+                .find_outgoing_calls_with_dispatch(&method, 3, DispatchMode::default())
+                .expect("find_outgoing_calls");
+            assert_eq!(
+                calls,
+                vec![MethodCallPath::from(vec![app.clone(), proxy.clone()])]
+            );
+
+            let mut calls = db
+                .find_outgoing_calls_with_dispatch(&method, 3, binder)
+                .expect("find_outgoing_calls");
+            calls.sort();
+            let mut expected = vec![
+                MethodCallPath::from(vec![app.clone(), proxy.clone()]),
+                MethodCallPath::from(vec![app.clone(), proxy.clone(), service.clone()]),
+                MethodCallPath::from(vec![
+                    app.clone(),
+                    proxy.clone(),
+                    service.clone(),
+                    helper.clone(),
+                ]),
+            ];
+            expected.sort();
+            assert_eq!(calls, expected);
+
+            let class = ClassName::from("Lbinder/FooService;");
+            let method = MethodSearch::new(
+                MethodSearchParams::ByClassAndName {
+                    class: &class,
+                    name: "helper",
+                },
+                None,
+            );
+            let mut callers = db
+                .find_callers_with_dispatch(&method, None, 3, binder)
+                .expect("find_callers");
+            callers.sort();
+            let mut expected = vec![
+                MethodCallPath::from(vec![service.clone(), helper.clone()]),
+                MethodCallPath::from(vec![proxy.clone(), service.clone(), helper.clone()]),
+                MethodCallPath::from(vec![app, proxy, service, helper]),
+            ];
+            expected.sort();
+            assert_eq!(callers, expected);
+        });
+    }
+
+    #[rstest]
+    fn test_find_call_paths(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let from_class = ClassName::from("Lbj/bj;");
+            let to_class = ClassName::from("Lca/ca;");
+            let from = MethodSearch::new(
+                MethodSearchParams::ByClassAndName {
+                    class: &from_class,
+                    name: "ai",
+                },
+                None,
+            );
+            let to = MethodSearch::new(
+                MethodSearchParams::ByClassAndName {
+                    class: &to_class,
+                    name: "ad",
+                },
+                None,
+            );
+
+            let method =
+                |id: i32, class: &str, name: &str, signature: &str, ret: &str, source: &str| {
+                    MethodSpec {
+                        id,
+                        class: class.into(),
+                        name: name.into(),
+                        signature: signature.into(),
+                        ret: ret.into(),
+                        source: source.into(),
+                        access_flags: AccessFlag::PUBLIC,
+                    }
+                };
+
+            let bj = method(17, "bj.bj", "ai", "ZFF", "I", "D");
+            let ay = method(13, "ay.ay", "ag", "", "Landroid/os/IBinder;", "E");
+            let ax = method(11, "ax.ax", "af", "", "Landroid/os/IBinder;", "D");
+            let cb = method(8, "cb.cb", "ad", "CLandroid/os/IBinder;", "D", "B");
+            let ca = method(7, "ca.ca", "ad", "CLandroid/os/IBinder;", "D", "framework");
+
+            let paths = db.find_call_paths(&from, &to, 5).expect("find_call_paths");
+            assert_eq!(
+                paths,
+                vec![
+                    MethodCallPath::from(vec![bj.clone(), ay.clone(), ca.clone()]),
+                    MethodCallPath::from(vec![bj.clone(), cb.clone(), ca.clone()]),
+                ]
+            );
+
+            let paths = db
+                .find_k_shortest_call_paths(&from, &to, 5, 3)
+                .expect("find_k_shortest_call_paths");
+            assert_eq!(
+                paths,
+                vec![
+                    MethodCallPath::from(vec![bj.clone(), ay.clone(), ca.clone()]),
+                    MethodCallPath::from(vec![bj.clone(), cb, ca.clone()]),
+                    MethodCallPath::from(vec![bj, ay, ax, ca]),
+                ]
+            );
+
+            let paths = db.find_call_paths(&from, &to, 1).expect("find_call_paths");
+            assert!(paths.is_empty());
+
+            let paths = db
+                .find_call_paths(&from, &to.with_source("B"), 5)
+                .expect("find_call_paths");
+            assert!(paths.is_empty());
+        });
+    }
+
+    #[rstest]
+    fn test_find_call_paths_parallel_branches(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            // `start` calls into a ladder of 20 levels with two methods each where every method
+            // calls both methods on the next level, so there are 2^20 paths through it to
+            // `target`. `start` also reaches `target` through `shortcut`.
+            let levels = 20;
+            let ladder = |level: i32, side: i32| 1310 + 2 * level + side;
+            let mut methods = vec![
+                String::from("(1300,320,'start','','V',2,6)"),
+                String::from("(1301,320,'shortcut','','V',2,6)"),
+                String::from("(1302,320,'target','','V',2,6)"),
+            ];
+            let mut calls = vec![(1300, 1301), (1301, 1302)];
+            for level in 0..levels {
+                for side in 0..2 {
+                    let mid = ladder(level, side);
+                    methods.push(format!("({},320,'m{}_{}','','V',2,6)", mid, level, side));
+                    if level == 0 {
+                        calls.push((1300, mid));
+                    }
+                    if level + 1 == levels {
+                        calls.push((mid, 1302));
+                    } else {
+                        calls.push((mid, ladder(level + 1, 0)));
+                        calls.push((mid, ladder(level + 1, 1)));
+                    }
+                }
+            }
+            let sql = format!(
+                "INSERT INTO classes(id,name,access_flags,source) VALUES (320,'Lbranch/Branch;',2,6);
+                INSERT INTO methods(id,class,name,args,ret,access_flags,source) VALUES {};
+                INSERT INTO calls(caller,callee,source) VALUES {};",
+                methods.join(","),
+                calls
+                    .iter()
+                    .map(|(caller, callee)| format!("({},{},6)", caller, callee))
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+            db.with_connection(|c| c.batch_execute(&sql))
+                .expect("adding branches");
+
+            let class = ClassName::from("Lbranch/Branch;");
+            let search = |name: &'static str| {
+                MethodSearch::new(
+                    MethodSearchParams::ByClassAndName {
+                        class: &class,
+                        name,
+                    },
+                    None,
+                )
+            };
+            let names = |paths: &[MethodCallPath]| {
+                paths
+                    .iter()
+                    .map(|it| {
+                        it.path
+                            .iter()
+                            .map(|m| m.name.clone())
+                            .collect::<Vec<String>>()
+                            .join(" -> ")
+                    })
+                    .collect::<Vec<String>>()
+            };
+
+            let paths = db
+                .find_call_paths(&search("start"), &search("target"), 30)
+                .expect("find_call_paths");
+            assert_eq!(names(&paths), vec!["start -> shortcut -> target"]);
+
+            // Every shortest path is found, even when they branch
+            let paths = db
+                .find_call_paths(&search("start"), &search("m3_0"), 30)
+                .expect("find_call_paths");
+            assert_eq!(paths.len(), 8);
+            assert!(names(&paths).iter().all(|it| it.starts_with("start -> m0_")
+                && it.ends_with(" -> m3_0")
+                && it.matches(" -> ").count() == 4));
+
+            // Only the first `k` paths are built, the shortcut then two of the ladder's paths
+            let paths = db
+                .find_k_shortest_call_paths(&search("start"), &search("target"), 30, 3)
+                .expect("find_k_shortest_call_paths");
+            let found = names(&paths);
+            assert_eq!(found.len(), 3);
+            assert_eq!(found[0], "start -> shortcut -> target");
+            assert_ne!(found[1], found[2]);
+            assert!(found[1..].iter().all(|it| it.starts_with("start -> m0_")
+                && it.ends_with(" -> target")
+                && it.matches(" -> ").count() == levels as usize + 1));
+        });
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        nodes: Vec<String>,
+        edges: Vec<String>,
+    }
+
+    impl export::GraphExportSink for RecordingSink {
+        fn class(&mut self, class: &export::ExportClass) -> std::io::Result<()> {
+            self.nodes.push(format!(
+                "{} {}",
+                export::class_node_id(class.id),
+                class.name
+            ));
+            Ok(())
+        }
+
+        fn method(&mut self, method: &export::ExportMethod) -> std::io::Result<()> {
+            self.nodes.push(format!(
+                "{} {}",
+                export::method_node_id(method.id),
+                method.label()
+            ));
+            Ok(())
+        }
+
+        fn field(&mut self, field: &export::ExportField) -> std::io::Result<()> {
+            self.nodes.push(format!(
+                "{} {}",
+                export::field_node_id(field.id),
+                field.label()
+            ));
+            Ok(())
+        }
+
+        fn edge(&mut self, edge: &export::ExportEdge) -> std::io::Result<()> {
+            self.edges.push(format!(
+                "{} -{}-> {}",
+                edge.src_id(),
+                edge.kind.as_str(),
+                edge.dst_id()
+            ));
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    fn test_export_graph(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let class = ClassName::from("Lbj/bj;");
+            let root = || {
+                Some(MethodSearch::new(
+                    MethodSearchParams::ByClassAndName {
+                        class: &class,
+                        name: "ai",
+                    },
+                    None,
+                ))
+            };
+
+            let filter = export::GraphExportFilter {
+                sources: Vec::new(),
+                root: root(),
+                depth: 1,
+            };
+            let mut sink = RecordingSink::default();
+            export::export_graph(&db, &filter, &mut sink).expect("export_graph");
+            assert_eq!(
+                sink.nodes,
+                vec![
+                    "c42 Lay/ay;",
+                    "c56 Lbf/bf;",
+                    "c62 Lbj/bj;",
+                    "c80 Lcb/cb;",
+                    "m8 Lcb/cb;->ad(CLandroid/os/IBinder;)D",
+                    "m13 Lay/ay;->ag()Landroid/os/IBinder;",
+                    "m15 Lbf/bf;->ah()F",
+                    "m17 Lbj/bj;->ai(ZFF)I",
+                ]
+            );
+            sink.edges.sort();
+            assert_eq!(
+                sink.edges,
+                vec![
+                    "c42 -HAS_METHOD-> m13",
+                    "c56 -HAS_METHOD-> m15",
+                    "c62 -HAS_METHOD-> m17",
+                    "c80 -HAS_METHOD-> m8",
+                    "m15 -CALLS-> m8",
+                    "m17 -CALLS-> m13",
+                    "m17 -CALLS-> m15",
+                    "m17 -CALLS-> m8",
+                ]
+            );
+
+            let filter = export::GraphExportFilter {
+                sources: vec!["C"],
+                root: root(),
+                depth: 1,
+            };
+            let mut sink = RecordingSink::default();
+            export::export_graph(&db, &filter, &mut sink).expect("export_graph");
+            assert_eq!(sink.nodes, vec!["c56 Lbf/bf;", "m15 Lbf/bf;->ah()F"]);
+            assert_eq!(sink.edges, vec!["c56 -HAS_METHOD-> m15"]);
+
+            let filter = export::GraphExportFilter {
+                sources: vec!["nope"],
+                ..Default::default()
+            };
+            let mut sink = RecordingSink::default();
+            assert!(export::export_graph(&db, &filter, &mut sink).is_err());
+        });
+    }
+
+    #[cfg(feature = "setup")]
+    #[rstest]
+    fn test_update_method_overrides(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            db.update_method_overrides()
+                .expect("update_method_overrides");
+            let overrides: Vec<(i32, i32)> = db
+                .with_connection(|c| {
+                    method_overrides::table
+                        .filter(method_overrides::method.ge(1000))
+                        .select((method_overrides::method, method_overrides::parent))
+                        .order_by((method_overrides::method, method_overrides::parent))
+                        .load::<(i32, i32)>(c)
+                })
+                .expect("loading overrides");
+            assert_eq!(overrides, vec![(1001, 1000), (1002, 1000), (1002, 1001)]);
+        });
+    }
+
+    #[cfg(feature = "setup")]
+    #[rstest]
+    fn test_update_reflective_calls(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            db.update_reflective_calls()
+                .expect("update_reflective_calls");
+            let reflective: Vec<(i32, i32)> = db
+                .with_connection(|c| {
+                    calls::table
+                        .filter(calls::reflective.eq(true))
+                        .select((calls::caller, calls::callee))
+                        .order_by((calls::caller, calls::callee))
+                        .load::<(i32, i32)>(c)
+                })
+                .expect("loading reflective calls");
+            assert_eq!(reflective, vec![(1108, 1104), (1108, 1105), (1109, 1107)]);
+
+            let class = ClassName::from("Lrefl/Target;");
+            let method = MethodSearch::new(
+                MethodSearchParams::ByFullSpec {
+                    class: &class,
+                    name: "hidden",
+                    signature: "",
+                },
+                None,
+            );
+            let callers = db.find_callers(&method, None, 2).expect("find_callers");
+            assert_eq!(callers.len(), 1);
+            assert_eq!(callers[0].path[0].name, "callHidden");
+        });
+    }
+
+    #[cfg(feature = "setup")]
+    #[rstest]
+    fn test_restore_cross_source_edges(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let class = ClassName::from("Lbj/bj;");
+            let search = MethodSearch::new(
+                MethodSearchParams::ByClassAndName {
+                    class: &class,
+                    name: "hq",
+                },
+                Some("D"),
+            );
+
+            // The return type isn't known for calls into missing methods, so leave it out
+            let callees = || -> Vec<String> {
+                let mut res = db
+                    .find_outgoing_calls(&search, 1)
+                    .expect("find_outgoing_calls")
+                    .into_iter()
+                    .map(|it| {
+                        let m = it.must_get_dst_method();
+                        format!("{}->{}({})", m.class.get_smali_name(), m.name, m.signature)
+                    })
+                    .collect::<Vec<String>>();
+                res.sort();
+                res
+            };
+
+            let before = callees();
+            assert_eq!(
+                before,
+                vec![
+                    "Lai/ai;->dq(Landroid/os/IBinder;C)",
+                    "Lam/am;->ab()",
+                    "Lax/ax;->ds(FIJ)",
+                ]
+            );
+
+            let mut affected = db
+                .transaction(|c| db.stash_cross_source_edges_with_conn(c, 1))
+                .expect("stash_cross_source_edges");
+            affected.sort();
+            assert_eq!(affected, vec![2, 3, 4, 5]);
+
+            // Removing the framework loses every edge into it
+            db.with_connection(|c| {
+                c.batch_execute(
+                    "PRAGMA foreign_keys=ON; DELETE FROM sources WHERE id = 1; INSERT INTO sources(id, name) VALUES (1, 'framework');",
+                )
+            })
+            .expect("removing framework");
+            assert!(callees().is_empty());
+
+            db.restore_cross_source_edges(&tmp_context, &affected)
+                .expect("restore_cross_source_edges");
+            assert_eq!(callees(), before);
+        });
+    }
+
     #[rstest]
     fn test_get_method_ids(tmp_context: TestContext) {
         db_test(&tmp_context, |db| {
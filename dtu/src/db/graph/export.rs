@@ -0,0 +1,934 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::iter::repeat;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use diesel::connection::{DefaultLoadingMode, SimpleConnection};
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+use diesel::SqliteConnection;
+
+use super::db::GraphSqliteDatabase;
+use super::models::{FieldAccessOp, MethodSearch};
+use super::GraphDatabase;
+use crate::db::common::{Error, Result};
+use crate::utils::ensure_dir_exists;
+
+/// Supported graph export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// GraphML, which can be opened by Gephi, yEd, networkx, etc.
+    GraphML,
+    /// Graphviz DOT
+    Dot,
+    /// A directory of CSV files suitable for `neo4j-admin database import`
+    Neo4jCsv,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GraphML => "graphml",
+            Self::Dot => "dot",
+            Self::Neo4jCsv => "neo4j",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "graphml" => Self::GraphML,
+            "dot" => Self::Dot,
+            "neo4j" | "neo4j-csv" => Self::Neo4jCsv,
+            _ => return Err("valid values are 'graphml', 'dot', and 'neo4j'"),
+        })
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Determines which part of the graph database is exported
+pub struct GraphExportFilter<'a> {
+    /// Only export nodes and edges from these sources, an empty list exports
+    /// every source
+    pub sources: Vec<&'a str>,
+
+    /// Only export the subgraph reachable by outgoing calls from the methods
+    /// matching this search. Note that the call graph is walked regardless of
+    /// `sources`, the source filter is only applied to the results.
+    pub root: Option<MethodSearch<'a>>,
+
+    /// The call depth to walk from `root`
+    pub depth: usize,
+}
+
+impl<'a> Default for GraphExportFilter<'a> {
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            root: None,
+            depth: DEFAULT_EXPORT_DEPTH,
+        }
+    }
+}
+
+/// Default call depth for a subgraph export
+pub const DEFAULT_EXPORT_DEPTH: usize = 3;
+
+#[derive(QueryableByName, Debug)]
+pub struct ExportClass {
+    #[diesel(sql_type = Integer)]
+    pub id: i32,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = BigInt)]
+    pub access_flags: i64,
+    #[diesel(sql_type = Text)]
+    pub source: String,
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct ExportMethod {
+    #[diesel(sql_type = Integer)]
+    pub id: i32,
+    #[diesel(sql_type = Text)]
+    pub class: String,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub args: String,
+    #[diesel(sql_type = Text)]
+    pub ret: String,
+    #[diesel(sql_type = BigInt)]
+    pub access_flags: i64,
+    #[diesel(sql_type = Text)]
+    pub source: String,
+}
+
+impl ExportMethod {
+    pub fn label(&self) -> String {
+        format!("{}->{}({}){}", self.class, self.name, self.args, self.ret)
+    }
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct ExportField {
+    #[diesel(sql_type = Integer)]
+    pub id: i32,
+    #[diesel(sql_type = Text)]
+    pub class: String,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub ty: String,
+    #[diesel(sql_type = BigInt)]
+    pub access_flags: i64,
+    #[diesel(sql_type = Text)]
+    pub source: String,
+}
+
+impl ExportField {
+    pub fn label(&self) -> String {
+        format!("{}->{}:{}", self.class, self.name, self.ty)
+    }
+}
+
+/// The kinds of edges in an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportEdgeKind {
+    /// Class to one of its methods
+    HasMethod,
+    /// Class to one of its fields
+    HasField,
+    /// Caller method to callee method
+    Calls,
+    /// Class to an interface it implements
+    Implements,
+    /// Class to its parent class
+    Extends,
+    /// Method to a field it reads
+    Reads,
+    /// Method to a field it writes
+    Writes,
+}
+
+impl ExportEdgeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::HasMethod => "HAS_METHOD",
+            Self::HasField => "HAS_FIELD",
+            Self::Calls => "CALLS",
+            Self::Implements => "IMPLEMENTS",
+            Self::Extends => "EXTENDS",
+            Self::Reads => "READS",
+            Self::Writes => "WRITES",
+        }
+    }
+
+    fn src_prefix(&self) -> char {
+        match self {
+            Self::HasMethod | Self::HasField | Self::Implements | Self::Extends => 'c',
+            Self::Calls | Self::Reads | Self::Writes => 'm',
+        }
+    }
+
+    fn dst_prefix(&self) -> char {
+        match self {
+            Self::HasMethod | Self::Calls => 'm',
+            Self::HasField | Self::Reads | Self::Writes => 'f',
+            Self::Implements | Self::Extends => 'c',
+        }
+    }
+}
+
+/// An edge between two exported nodes
+///
+/// Node ids are the database id prefixed with `c` for classes, `m` for methods
+/// and `f` for fields, see [ExportEdge::src_id] and [ExportEdge::dst_id].
+#[derive(Debug)]
+pub struct ExportEdge {
+    pub kind: ExportEdgeKind,
+    pub src: i32,
+    pub dst: i32,
+    /// The source the relation was discovered in, if the database tracks it
+    pub source: Option<String>,
+}
+
+impl ExportEdge {
+    pub fn src_id(&self) -> String {
+        format!("{}{}", self.kind.src_prefix(), self.src)
+    }
+
+    pub fn dst_id(&self) -> String {
+        format!("{}{}", self.kind.dst_prefix(), self.dst)
+    }
+}
+
+pub fn class_node_id(id: i32) -> String {
+    format!("c{}", id)
+}
+
+pub fn method_node_id(id: i32) -> String {
+    format!("m{}", id)
+}
+
+pub fn field_node_id(id: i32) -> String {
+    format!("f{}", id)
+}
+
+/// Receives the nodes and edges of an export
+///
+/// All nodes are written before any edges, in the order classes, methods,
+/// fields.
+pub trait GraphExportSink {
+    fn begin(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+    fn class(&mut self, class: &ExportClass) -> io::Result<()>;
+    fn method(&mut self, method: &ExportMethod) -> io::Result<()>;
+    fn field(&mut self, field: &ExportField) -> io::Result<()>;
+    fn edge(&mut self, edge: &ExportEdge) -> io::Result<()>;
+    fn end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Export the `classes`, `methods`, `class_fields`, `calls`, `interfaces`,
+/// `supers` and `method_field_access` tables to the given sink
+///
+/// Rows are streamed out of the database, so this is suitable for exporting
+/// the entire graph. Edges are only exported if both of their nodes are.
+pub fn export_graph<S: GraphExportSink + Send>(
+    db: &GraphSqliteDatabase,
+    filter: &GraphExportFilter,
+    sink: &mut S,
+) -> Result<()> {
+    // This needs to happen before we grab the connection
+    let root_ids = match &filter.root {
+        Some(search) => {
+            let ids = db.get_method_ids(search)?;
+            if ids.is_empty() {
+                return Err(Error::NotFound);
+            }
+            Some(ids)
+        }
+        None => None,
+    };
+
+    let depth: i32 = filter
+        .depth
+        .try_into()
+        .map_err(|_| Error::Generic(format!("invalid depth")))?;
+
+    db.with_connection(|c| -> Result<()> {
+        create_export_tables(c, &filter.sources, root_ids.as_ref(), depth)?;
+        let res = stream_export(c, sink);
+        c.batch_execute(DROP_EXPORT_TABLES)?;
+        res
+    })
+}
+
+/// Export the graph to the given file in the given format
+///
+/// For [ExportFormat::Neo4jCsv] the path is a directory that the CSV files
+/// are written into.
+pub fn export_graph_to_path(
+    db: &GraphSqliteDatabase,
+    filter: &GraphExportFilter,
+    format: ExportFormat,
+    path: &Path,
+) -> Result<()> {
+    match format {
+        ExportFormat::GraphML => {
+            let mut sink = GraphMLWriter::new(BufWriter::new(File::create(path)?));
+            export_graph(db, filter, &mut sink)
+        }
+        ExportFormat::Dot => {
+            let mut sink = DotWriter::new(BufWriter::new(File::create(path)?));
+            export_graph(db, filter, &mut sink)
+        }
+        ExportFormat::Neo4jCsv => {
+            let mut sink = Neo4jCsvWriter::new(path)?;
+            export_graph(db, filter, &mut sink)
+        }
+    }
+}
+
+const DROP_EXPORT_TABLES: &'static str = r#"
+DROP TABLE IF EXISTS temp._export_sources;
+DROP TABLE IF EXISTS temp._export_classes;
+DROP TABLE IF EXISTS temp._export_methods;
+DROP TABLE IF EXISTS temp._export_fields;
+"#;
+
+fn create_export_tables(
+    c: &mut SqliteConnection,
+    sources: &[&str],
+    root_ids: Option<&Vec<i32>>,
+    depth: i32,
+) -> Result<()> {
+    c.batch_execute(DROP_EXPORT_TABLES)?;
+    c.batch_execute(
+        r#"
+CREATE TEMP TABLE _export_sources(id INTEGER PRIMARY KEY);
+CREATE TEMP TABLE _export_classes(id INTEGER PRIMARY KEY);
+CREATE TEMP TABLE _export_methods(id INTEGER PRIMARY KEY);
+CREATE TEMP TABLE _export_fields(id INTEGER PRIMARY KEY);
+"#,
+    )?;
+
+    if sources.is_empty() {
+        c.batch_execute("INSERT INTO _export_sources SELECT id FROM sources;")?;
+    } else {
+        let binds = repeat("?")
+            .take(sources.len())
+            .collect::<Vec<&str>>()
+            .join(",");
+        let mut q = sql_query(format!(
+            "INSERT INTO _export_sources SELECT id FROM sources WHERE name IN ({binds})"
+        ))
+        .into_boxed();
+        for s in sources {
+            q = q.bind::<Text, _>(*s);
+        }
+        let inserted = query!(q).execute(c)?;
+        if inserted != sources.iter().collect::<HashSet<_>>().len() {
+            return Err(Error::Generic(format!(
+                "unknown source in {}",
+                sources.join(", ")
+            )));
+        }
+    }
+
+    let ids = match root_ids {
+        None => {
+            c.batch_execute(
+                r#"
+INSERT INTO _export_classes
+    SELECT c.id FROM classes AS c JOIN _export_sources AS s ON s.id = c.source;
+INSERT INTO _export_methods
+    SELECT m.id FROM methods AS m JOIN _export_sources AS s ON s.id = m.source;
+INSERT INTO _export_fields
+    SELECT f.id FROM class_fields AS f JOIN _export_classes AS c ON c.id = f.class;
+"#,
+            )?;
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    let binds = repeat("(?)")
+        .take(ids.len())
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    let mut q = sql_query(format!(
+        r#"INSERT INTO _export_methods
+WITH RECURSIVE
+    roots(id) AS (VALUES {binds}),
+    reachable(id, distance) AS (
+        SELECT id, 0 FROM roots
+        UNION
+        SELECT c.callee, r.distance + 1
+        FROM calls AS c
+        JOIN reachable AS r
+            ON r.id = c.caller
+        WHERE r.distance < ?
+    )
+SELECT DISTINCT m.id
+FROM reachable AS r
+JOIN methods AS m
+    ON m.id = r.id
+JOIN _export_sources AS s
+    ON s.id = m.source;"#
+    ))
+    .into_boxed();
+
+    for id in ids {
+        q = q.bind::<Integer, _>(*id);
+    }
+    q = q.bind::<Integer, _>(depth);
+    query!(q).execute(c)?;
+
+    // Fields are included if they are accessed by an exported method and
+    // classes if they own an exported method or field
+    c.batch_execute(
+        r#"
+INSERT INTO _export_fields
+    SELECT DISTINCT a.field
+    FROM method_field_access AS a
+    JOIN _export_methods AS m
+        ON m.id = a.method
+    JOIN class_fields AS f
+        ON f.id = a.field
+    JOIN classes AS c
+        ON c.id = f.class
+    JOIN _export_sources AS s
+        ON s.id = c.source;
+INSERT OR IGNORE INTO _export_classes
+    SELECT m.class FROM methods AS m JOIN _export_methods AS e ON e.id = m.id;
+INSERT OR IGNORE INTO _export_classes
+    SELECT f.class FROM class_fields AS f JOIN _export_fields AS e ON e.id = f.id;
+"#,
+    )?;
+
+    Ok(())
+}
+
+#[derive(QueryableByName)]
+struct EdgeRow {
+    #[diesel(sql_type = Integer)]
+    src: i32,
+    #[diesel(sql_type = Integer)]
+    dst: i32,
+    #[diesel(sql_type = Nullable<Text>)]
+    source: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct FieldAccessRow {
+    #[diesel(sql_type = Integer)]
+    method: i32,
+    #[diesel(sql_type = Integer)]
+    field: i32,
+    #[diesel(sql_type = Integer)]
+    action: i32,
+}
+
+const CLASSES_QUERY: &'static str = r#"
+SELECT c.id, c.name, c.access_flags, s.name AS source
+FROM classes AS c
+JOIN _export_classes AS e
+    ON e.id = c.id
+JOIN sources AS s
+    ON s.id = c.source
+ORDER BY c.id"#;
+
+const METHODS_QUERY: &'static str = r#"
+SELECT m.id, c.name AS class, m.name, m.args, m.ret, m.access_flags, s.name AS source
+FROM methods AS m
+JOIN _export_methods AS e
+    ON e.id = m.id
+JOIN classes AS c
+    ON c.id = m.class
+JOIN sources AS s
+    ON s.id = m.source
+ORDER BY m.id"#;
+
+const FIELDS_QUERY: &'static str = r#"
+SELECT f.id, c.name AS class, f.name, f.ty, f.access_flags, s.name AS source
+FROM class_fields AS f
+JOIN _export_fields AS e
+    ON e.id = f.id
+JOIN classes AS c
+    ON c.id = f.class
+JOIN sources AS s
+    ON s.id = c.source
+ORDER BY f.id"#;
+
+const EDGE_QUERIES: &'static [(ExportEdgeKind, &'static str)] = &[
+    (
+        ExportEdgeKind::HasMethod,
+        r#"
+SELECT m.class AS src, m.id AS dst, NULL AS source
+FROM methods AS m
+JOIN _export_methods AS e
+    ON e.id = m.id
+JOIN _export_classes AS c
+    ON c.id = m.class"#,
+    ),
+    (
+        ExportEdgeKind::HasField,
+        r#"
+SELECT f.class AS src, f.id AS dst, NULL AS source
+FROM class_fields AS f
+JOIN _export_fields AS e
+    ON e.id = f.id
+JOIN _export_classes AS c
+    ON c.id = f.class"#,
+    ),
+    (
+        ExportEdgeKind::Calls,
+        r#"
+SELECT DISTINCT c.caller AS src, c.callee AS dst, s.name AS source
+FROM calls AS c
+JOIN _export_methods AS a
+    ON a.id = c.caller
+JOIN _export_methods AS b
+    ON b.id = c.callee
+JOIN sources AS s
+    ON s.id = c.source"#,
+    ),
+    (
+        ExportEdgeKind::Implements,
+        r#"
+SELECT DISTINCT i.class AS src, i.interface AS dst, s.name AS source
+FROM interfaces AS i
+JOIN _export_classes AS a
+    ON a.id = i.class
+JOIN _export_classes AS b
+    ON b.id = i.interface
+JOIN sources AS s
+    ON s.id = i.source"#,
+    ),
+    (
+        ExportEdgeKind::Extends,
+        r#"
+SELECT DISTINCT p.child AS src, p.parent AS dst, s.name AS source
+FROM supers AS p
+JOIN _export_classes AS a
+    ON a.id = p.child
+JOIN _export_classes AS b
+    ON b.id = p.parent
+JOIN sources AS s
+    ON s.id = p.source"#,
+    ),
+];
+
+const FIELD_ACCESS_QUERY: &'static str = r#"
+SELECT a.method, a.field, a.action
+FROM method_field_access AS a
+JOIN _export_methods AS m
+    ON m.id = a.method
+JOIN _export_fields AS f
+    ON f.id = a.field"#;
+
+fn stream_export<S: GraphExportSink>(c: &mut SqliteConnection, sink: &mut S) -> Result<()> {
+    sink.begin()?;
+
+    for row in query!(sql_query(CLASSES_QUERY)).load_iter::<ExportClass, DefaultLoadingMode>(c)? {
+        sink.class(&row?)?;
+    }
+
+    for row in query!(sql_query(METHODS_QUERY)).load_iter::<ExportMethod, DefaultLoadingMode>(c)? {
+        sink.method(&row?)?;
+    }
+
+    for row in query!(sql_query(FIELDS_QUERY)).load_iter::<ExportField, DefaultLoadingMode>(c)? {
+        sink.field(&row?)?;
+    }
+
+    for (kind, q) in EDGE_QUERIES {
+        for row in query!(sql_query(*q)).load_iter::<EdgeRow, DefaultLoadingMode>(c)? {
+            let row = row?;
+            sink.edge(&ExportEdge {
+                kind: *kind,
+                src: row.src,
+                dst: row.dst,
+                source: row.source,
+            })?;
+        }
+    }
+
+    for row in
+        query!(sql_query(FIELD_ACCESS_QUERY)).load_iter::<FieldAccessRow, DefaultLoadingMode>(c)?
+    {
+        let row = row?;
+        let kind = match FieldAccessOp::maybe_from_literal(row.action as u8) {
+            Some(FieldAccessOp::Read) => ExportEdgeKind::Reads,
+            Some(FieldAccessOp::Write) => ExportEdgeKind::Writes,
+            None => {
+                log::warn!("invalid field access action {}", row.action);
+                continue;
+            }
+        };
+        sink.edge(&ExportEdge {
+            kind,
+            src: row.method,
+            dst: row.field,
+            source: None,
+        })?;
+    }
+
+    sink.end()?;
+    Ok(())
+}
+
+/// Writes the export as GraphML
+pub struct GraphMLWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> GraphMLWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn data(&mut self, key: &str, value: &str) -> io::Result<()> {
+        write!(
+            self.out,
+            "<data key=\"{}\">{}</data>",
+            key,
+            quick_xml::escape::escape(value)
+        )
+    }
+}
+
+impl<W: Write> GraphExportSink for GraphMLWriter<W> {
+    fn begin(&mut self) -> io::Result<()> {
+        write!(
+            self.out,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="kind" for="all" attr.name="kind" attr.type="string"/>
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="name" for="node" attr.name="name" attr.type="string"/>
+  <key id="class" for="node" attr.name="class" attr.type="string"/>
+  <key id="signature" for="node" attr.name="signature" attr.type="string"/>
+  <key id="ret" for="node" attr.name="ret" attr.type="string"/>
+  <key id="type" for="node" attr.name="type" attr.type="string"/>
+  <key id="access_flags" for="node" attr.name="access_flags" attr.type="long"/>
+  <key id="source" for="all" attr.name="source" attr.type="string"/>
+  <graph id="dtu" edgedefault="directed">
+"#
+        )
+    }
+
+    fn class(&mut self, class: &ExportClass) -> io::Result<()> {
+        write!(self.out, "    <node id=\"{}\">", class_node_id(class.id))?;
+        self.data("kind", "class")?;
+        self.data("label", &class.name)?;
+        self.data("name", &class.name)?;
+        self.data("access_flags", &class.access_flags.to_string())?;
+        self.data("source", &class.source)?;
+        writeln!(self.out, "</node>")
+    }
+
+    fn method(&mut self, method: &ExportMethod) -> io::Result<()> {
+        write!(self.out, "    <node id=\"{}\">", method_node_id(method.id))?;
+        self.data("kind", "method")?;
+        self.data("label", &method.label())?;
+        self.data("name", &method.name)?;
+        self.data("class", &method.class)?;
+        self.data("signature", &method.args)?;
+        self.data("ret", &method.ret)?;
+        self.data("access_flags", &method.access_flags.to_string())?;
+        self.data("source", &method.source)?;
+        writeln!(self.out, "</node>")
+    }
+
+    fn field(&mut self, field: &ExportField) -> io::Result<()> {
+        write!(self.out, "    <node id=\"{}\">", field_node_id(field.id))?;
+        self.data("kind", "field")?;
+        self.data("label", &field.label())?;
+        self.data("name", &field.name)?;
+        self.data("class", &field.class)?;
+        self.data("type", &field.ty)?;
+        self.data("access_flags", &field.access_flags.to_string())?;
+        self.data("source", &field.source)?;
+        writeln!(self.out, "</node>")
+    }
+
+    fn edge(&mut self, edge: &ExportEdge) -> io::Result<()> {
+        write!(
+            self.out,
+            "    <edge source=\"{}\" target=\"{}\">",
+            edge.src_id(),
+            edge.dst_id()
+        )?;
+        self.data("kind", edge.kind.as_str())?;
+        if let Some(src) = edge.source.as_ref() {
+            self.data("source", src)?;
+        }
+        writeln!(self.out, "</edge>")
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        write!(self.out, "  </graph>\n</graphml>\n")?;
+        self.out.flush()
+    }
+}
+
+/// Writes the export as a Graphviz DOT digraph
+pub struct DotWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> DotWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn node(&mut self, id: &str, kind: &str, label: &str, source: &str) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "  \"{}\" [kind=\"{}\", label=\"{}\", source=\"{}\"];",
+            id,
+            kind,
+            dot_escape(label),
+            dot_escape(source)
+        )
+    }
+}
+
+fn dot_escape(s: &str) -> Cow<'_, str> {
+    if s.contains(['"', '\\']) {
+        Cow::Owned(s.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+impl<W: Write> GraphExportSink for DotWriter<W> {
+    fn begin(&mut self) -> io::Result<()> {
+        writeln!(self.out, "digraph dtu {{")
+    }
+
+    fn class(&mut self, class: &ExportClass) -> io::Result<()> {
+        self.node(
+            &class_node_id(class.id),
+            "class",
+            &class.name,
+            &class.source,
+        )
+    }
+
+    fn method(&mut self, method: &ExportMethod) -> io::Result<()> {
+        self.node(
+            &method_node_id(method.id),
+            "method",
+            &method.label(),
+            &method.source,
+        )
+    }
+
+    fn field(&mut self, field: &ExportField) -> io::Result<()> {
+        self.node(
+            &field_node_id(field.id),
+            "field",
+            &field.label(),
+            &field.source,
+        )
+    }
+
+    fn edge(&mut self, edge: &ExportEdge) -> io::Result<()> {
+        write!(
+            self.out,
+            "  \"{}\" -> \"{}\" [kind=\"{}\"",
+            edge.src_id(),
+            edge.dst_id(),
+            edge.kind.as_str()
+        )?;
+        if let Some(src) = edge.source.as_ref() {
+            write!(self.out, ", source=\"{}\"", dot_escape(src))?;
+        }
+        writeln!(self.out, "];")
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        writeln!(self.out, "}}")?;
+        self.out.flush()
+    }
+}
+
+type CsvWriter = csv::Writer<BufWriter<File>>;
+
+/// Writes the export as a directory of CSV files for the Neo4j bulk importer
+///
+/// The nodes are written to `classes.csv`, `methods.csv` and `fields.csv` and
+/// the relationships to `calls.csv`, `members.csv`, `interfaces.csv`,
+/// `supers.csv` and `field_access.csv`. The output can be loaded with:
+///
+/// ```text
+/// neo4j-admin database import full \
+///     --nodes=classes.csv --nodes=methods.csv --nodes=fields.csv \
+///     --relationships=calls.csv --relationships=members.csv \
+///     --relationships=interfaces.csv --relationships=supers.csv \
+///     --relationships=field_access.csv
+/// ```
+pub struct Neo4jCsvWriter {
+    classes: CsvWriter,
+    methods: CsvWriter,
+    fields: CsvWriter,
+    calls: CsvWriter,
+    members: CsvWriter,
+    interfaces: CsvWriter,
+    supers: CsvWriter,
+    field_access: CsvWriter,
+}
+
+fn csv_err(e: csv::Error) -> io::Error {
+    io::Error::from(e)
+}
+
+impl Neo4jCsvWriter {
+    pub fn new(dir: &Path) -> Result<Self> {
+        ensure_dir_exists(dir)?;
+
+        let open = |name: &str, header: &[&str]| -> io::Result<CsvWriter> {
+            let path: PathBuf = dir.join(name);
+            let mut wr = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(BufWriter::new(File::create(&path)?));
+            wr.write_record(header).map_err(csv_err)?;
+            Ok(wr)
+        };
+
+        let rel_header = &[":START_ID", ":END_ID", ":TYPE", "source"];
+
+        Ok(Self {
+            classes: open(
+                "classes.csv",
+                &["id:ID", "name", "access_flags:long", "source", ":LABEL"],
+            )?,
+            methods: open(
+                "methods.csv",
+                &[
+                    "id:ID",
+                    "class",
+                    "name",
+                    "signature",
+                    "ret",
+                    "access_flags:long",
+                    "source",
+                    ":LABEL",
+                ],
+            )?,
+            fields: open(
+                "fields.csv",
+                &[
+                    "id:ID",
+                    "class",
+                    "name",
+                    "type",
+                    "access_flags:long",
+                    "source",
+                    ":LABEL",
+                ],
+            )?,
+            calls: open("calls.csv", rel_header)?,
+            members: open("members.csv", rel_header)?,
+            interfaces: open("interfaces.csv", rel_header)?,
+            supers: open("supers.csv", rel_header)?,
+            field_access: open("field_access.csv", rel_header)?,
+        })
+    }
+}
+
+impl GraphExportSink for Neo4jCsvWriter {
+    fn class(&mut self, class: &ExportClass) -> io::Result<()> {
+        self.classes
+            .write_record(&[
+                class_node_id(class.id).as_str(),
+                &class.name,
+                &class.access_flags.to_string(),
+                &class.source,
+                "Class",
+            ])
+            .map_err(csv_err)
+    }
+
+    fn method(&mut self, method: &ExportMethod) -> io::Result<()> {
+        self.methods
+            .write_record(&[
+                method_node_id(method.id).as_str(),
+                &method.class,
+                &method.name,
+                &method.args,
+                &method.ret,
+                &method.access_flags.to_string(),
+                &method.source,
+                "Method",
+            ])
+            .map_err(csv_err)
+    }
+
+    fn field(&mut self, field: &ExportField) -> io::Result<()> {
+        self.fields
+            .write_record(&[
+                field_node_id(field.id).as_str(),
+                &field.class,
+                &field.name,
+                &field.ty,
+                &field.access_flags.to_string(),
+                &field.source,
+                "Field",
+            ])
+            .map_err(csv_err)
+    }
+
+    fn edge(&mut self, edge: &ExportEdge) -> io::Result<()> {
+        let wr = match edge.kind {
+            ExportEdgeKind::Calls => &mut self.calls,
+            ExportEdgeKind::HasMethod | ExportEdgeKind::HasField => &mut self.members,
+            ExportEdgeKind::Implements => &mut self.interfaces,
+            ExportEdgeKind::Extends => &mut self.supers,
+            ExportEdgeKind::Reads | ExportEdgeKind::Writes => &mut self.field_access,
+        };
+        wr.write_record(&[
+            edge.src_id().as_str(),
+            &edge.dst_id(),
+            edge.kind.as_str(),
+            edge.source.as_ref().map(|it| it.as_str()).unwrap_or(""),
+        ])
+        .map_err(csv_err)
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        for wr in [
+            &mut self.classes,
+            &mut self.methods,
+            &mut self.fields,
+            &mut self.calls,
+            &mut self.members,
+            &mut self.interfaces,
+            &mut self.supers,
+            &mut self.field_access,
+        ] {
+            wr.flush()?;
+        }
+        Ok(())
+    }
+}
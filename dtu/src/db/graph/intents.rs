@@ -0,0 +1,464 @@
+//! Matching intent senders to the components that can receive them
+//!
+//! Call sites of `startActivity`, `startService`, `sendBroadcast` and friends
+//! are found in the graph database. The constant strings in each calling method
+//! are then compared against the actions in the intent filters stored in the
+//! device database, and against component class names for explicit intents,
+//! giving the components the intent can be delivered to.
+//!
+//! This only works for intents built in the same method that sends them, and
+//! intent filter categories other than `DEFAULT` and data elements are not
+//! considered.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::models::{MethodSearch, MethodSearchParams, MethodSpec};
+use super::GraphDatabase;
+use crate::db::device::models::IntentFilterDetails;
+use crate::db::{
+    ApkComponent, ApkIPC, DeviceDatabase, Enablable, Exportable, Idable, PermissionProtected,
+    Result,
+};
+use crate::utils::{ClassName, DevicePath};
+
+/// Implicit intents passed to `startActivity` only resolve to activities with
+/// this category in their filter
+pub const CATEGORY_DEFAULT: &str = "android.intent.category.DEFAULT";
+
+/// The kind of component an intent is delivered to
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum IntentTargetKind {
+    Activity,
+    Service,
+    Receiver,
+}
+
+impl Display for IntentTargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Activity => "activity",
+            Self::Service => "service",
+            Self::Receiver => "receiver",
+        })
+    }
+}
+
+impl FromStr for IntentTargetKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "activity" | "activities" => Self::Activity,
+            "service" | "services" => Self::Service,
+            "receiver" | "receivers" | "broadcast" => Self::Receiver,
+            _ => return Err("invalid intent target, expected activity, service, or receiver"),
+        })
+    }
+}
+
+/// Methods that send an intent and the kind of component receiving it
+pub const INTENT_SEND_METHODS: &[(&str, IntentTargetKind)] = &[
+    ("startActivity", IntentTargetKind::Activity),
+    ("startActivityAsUser", IntentTargetKind::Activity),
+    ("startActivityForResult", IntentTargetKind::Activity),
+    ("startActivities", IntentTargetKind::Activity),
+    ("startService", IntentTargetKind::Service),
+    ("startServiceAsUser", IntentTargetKind::Service),
+    ("startForegroundService", IntentTargetKind::Service),
+    ("bindService", IntentTargetKind::Service),
+    ("bindServiceAsUser", IntentTargetKind::Service),
+    ("sendBroadcast", IntentTargetKind::Receiver),
+    ("sendBroadcastAsUser", IntentTargetKind::Receiver),
+    (
+        "sendBroadcastWithMultiplePermissions",
+        IntentTargetKind::Receiver,
+    ),
+    ("sendOrderedBroadcast", IntentTargetKind::Receiver),
+    ("sendOrderedBroadcastAsUser", IntentTargetKind::Receiver),
+    ("sendStickyBroadcast", IntentTargetKind::Receiver),
+    ("sendStickyBroadcastAsUser", IntentTargetKind::Receiver),
+];
+
+/// The parts of an intent filter used for matching
+#[derive(Clone, Default)]
+pub struct ComponentIntentFilter {
+    pub actions: Vec<String>,
+    pub categories: Vec<String>,
+}
+
+impl From<IntentFilterDetails> for ComponentIntentFilter {
+    fn from(value: IntentFilterDetails) -> Self {
+        Self {
+            actions: value.actions,
+            categories: value.categories,
+        }
+    }
+}
+
+/// A component that intents can be delivered to
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct IntentTarget {
+    pub kind: IntentTargetKind,
+    pub apk: DevicePath,
+    pub class_name: ClassName,
+    pub exported: bool,
+    pub enabled: bool,
+    pub permission: Option<String>,
+}
+
+/// An [IntentTarget] along with its intent filters
+#[derive(Clone)]
+pub struct IntentComponent {
+    pub target: IntentTarget,
+    pub filters: Vec<ComponentIntentFilter>,
+}
+
+impl IntentComponent {
+    /// Whether an implicit intent with the given action can be delivered to
+    /// this component
+    pub fn handles_action(&self, action: &str) -> bool {
+        let needs_default = self.target.kind == IntentTargetKind::Activity;
+        self.filters.iter().any(|f| {
+            f.actions.iter().any(|it| it == action)
+                && (!needs_default || f.categories.iter().any(|it| it == CATEGORY_DEFAULT))
+        })
+    }
+}
+
+fn add_components<T: ApkIPC>(
+    into: &mut Vec<IntentComponent>,
+    kind: IntentTargetKind,
+    apks: &HashMap<i32, DevicePath>,
+    items: Vec<T>,
+    filters: &mut HashMap<i32, Vec<ComponentIntentFilter>>,
+) {
+    for it in items {
+        let apk = match apks.get(&it.get_apk_id()) {
+            Some(v) => v.clone(),
+            None => continue,
+        };
+        into.push(IntentComponent {
+            target: IntentTarget {
+                kind,
+                apk,
+                class_name: it.get_class_name(),
+                exported: it.is_exported(),
+                enabled: it.is_enabled(),
+                permission: it.get_generic_permission().map(String::from),
+            },
+            filters: filters.remove(&it.get_id()).unwrap_or_default(),
+        });
+    }
+}
+
+/// Load every activity, service, and receiver along with its intent filters
+/// from the device database
+pub fn load_intent_components(device: &DeviceDatabase) -> Result<Vec<IntentComponent>> {
+    let apks = device
+        .get_apks()?
+        .into_iter()
+        .map(|it| (it.id, it.device_path))
+        .collect::<HashMap<i32, DevicePath>>();
+
+    let mut activity_filters: HashMap<i32, Vec<ComponentIntentFilter>> = HashMap::new();
+    let mut receiver_filters: HashMap<i32, Vec<ComponentIntentFilter>> = HashMap::new();
+    let mut service_filters: HashMap<i32, Vec<ComponentIntentFilter>> = HashMap::new();
+
+    for details in device.get_all_intent_filter_details()? {
+        let into = match (details.activity_id, details.receiver_id, details.service_id) {
+            (Some(id), _, _) => activity_filters.entry(id),
+            (_, Some(id), _) => receiver_filters.entry(id),
+            (_, _, Some(id)) => service_filters.entry(id),
+            _ => continue,
+        };
+        into.or_default().push(ComponentIntentFilter::from(details));
+    }
+
+    let mut components = Vec::new();
+    add_components(
+        &mut components,
+        IntentTargetKind::Activity,
+        &apks,
+        device.get_activities()?,
+        &mut activity_filters,
+    );
+    add_components(
+        &mut components,
+        IntentTargetKind::Receiver,
+        &apks,
+        device.get_receivers()?,
+        &mut receiver_filters,
+    );
+    add_components(
+        &mut components,
+        IntentTargetKind::Service,
+        &apks,
+        device.get_services()?,
+        &mut service_filters,
+    );
+
+    Ok(components)
+}
+
+/// An intent sent from a method that can be delivered to the target
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IntentEdge {
+    /// The method sending the intent
+    pub sender: MethodSpec,
+    /// The name of the method used to send the intent, such as `sendBroadcast`
+    pub via: String,
+    /// The matched action for implicit intents, explicit intents don't have one
+    pub action: Option<String>,
+    pub target: IntentTarget,
+}
+
+impl IntentEdge {
+    pub fn is_explicit(&self) -> bool {
+        self.action.is_none()
+    }
+}
+
+/// Limits the edges returned by [find_intent_targets]
+#[derive(Default)]
+pub struct IntentTargetSearch<'a> {
+    /// Only consider senders in this graph source
+    pub source: Option<&'a str>,
+    /// Only consider intents with this action
+    pub action: Option<&'a str>,
+    /// Only consider intents delivered to this kind of component
+    pub kind: Option<IntentTargetKind>,
+    /// Only consider intents delivered to this component
+    pub target: Option<&'a ClassName>,
+    /// Only consider exported and enabled components
+    pub only_exported: bool,
+}
+
+impl<'a> IntentTargetSearch<'a> {
+    fn allows(&self, target: &IntentTarget) -> bool {
+        if self.kind.map_or(false, |it| it != target.kind) {
+            return false;
+        }
+        if self.target.map_or(false, |it| *it != target.class_name) {
+            return false;
+        }
+        !self.only_exported || (target.exported && target.enabled)
+    }
+}
+
+/// Index of components by the actions they handle and by class name
+struct ComponentIndex<'a> {
+    by_action: HashMap<&'a str, Vec<&'a IntentComponent>>,
+    by_class: HashMap<String, Vec<&'a IntentComponent>>,
+}
+
+impl<'a> ComponentIndex<'a> {
+    fn new(components: &'a [IntentComponent], search: &IntentTargetSearch) -> Self {
+        let mut by_action: HashMap<&'a str, Vec<&'a IntentComponent>> = HashMap::new();
+        let mut by_class: HashMap<String, Vec<&'a IntentComponent>> = HashMap::new();
+
+        for comp in components.iter().filter(|it| search.allows(&it.target)) {
+            let actions = comp
+                .filters
+                .iter()
+                .flat_map(|f| f.actions.iter())
+                .filter(|it| search.action.map_or(true, |a| a == it.as_str()))
+                .map(|it| it.as_str())
+                .collect::<HashSet<&str>>();
+            for action in actions {
+                if comp.handles_action(action) {
+                    by_action.entry(action).or_default().push(comp);
+                }
+            }
+            by_class
+                .entry(comp.target.class_name.get_java_name().into_owned())
+                .or_default()
+                .push(comp);
+        }
+
+        Self {
+            by_action,
+            by_class,
+        }
+    }
+
+    /// Find the components a method holding the given constant strings can
+    /// send an intent of the given kind to
+    fn matches<'s>(
+        &self,
+        kind: IntentTargetKind,
+        strings: &'s [String],
+        explicit: bool,
+    ) -> Vec<(Option<&'s str>, &'a IntentComponent)> {
+        let mut found = Vec::new();
+        for s in strings {
+            if let Some(comps) = self.by_action.get(s.as_str()) {
+                found.extend(
+                    comps
+                        .iter()
+                        .filter(|it| it.target.kind == kind)
+                        .map(|it| (Some(s.as_str()), *it)),
+                );
+            }
+            if !explicit {
+                continue;
+            }
+            if let Some(comps) = self.by_class.get(s) {
+                found.extend(
+                    comps
+                        .iter()
+                        .filter(|it| it.target.kind == kind)
+                        .map(|it| (None, *it)),
+                );
+            }
+        }
+        found
+    }
+}
+
+/// Find intents sent from methods in the graph database to the given
+/// components
+///
+/// See [load_intent_components] to get the components from the device.
+pub fn find_intent_targets(
+    graph: &dyn GraphDatabase,
+    components: &[IntentComponent],
+    search: &IntentTargetSearch,
+) -> Result<Vec<IntentEdge>> {
+    let index = ComponentIndex::new(components, search);
+    // Explicit intents don't have an action to match
+    let explicit = search.action.is_none();
+
+    let mut seen = HashSet::new();
+    let mut edges = Vec::new();
+    let mut strings: HashMap<i32, Vec<String>> = HashMap::new();
+
+    for &(name, kind) in INTENT_SEND_METHODS {
+        if search.kind.map_or(false, |it| it != kind) {
+            continue;
+        }
+
+        let send = MethodSearch::new(MethodSearchParams::ByName { name }, None);
+        for path in graph.find_callers(&send, search.source, 1)? {
+            let sender = match path.path.first() {
+                Some(v) => v,
+                None => continue,
+            };
+
+            if !strings.contains_key(&sender.id) {
+                let found = graph.get_strings_for_method(sender.id)?;
+                strings.insert(sender.id, found);
+            }
+            let sender_strings = strings.get(&sender.id).unwrap();
+
+            for (action, comp) in index.matches(kind, sender_strings, explicit) {
+                let edge = IntentEdge {
+                    sender: sender.clone(),
+                    via: String::from(name),
+                    action: action.map(String::from),
+                    target: comp.target.clone(),
+                };
+                if seen.insert(edge.clone()) {
+                    edges.push(edge);
+                }
+            }
+        }
+    }
+
+    Ok(edges)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    fn component(
+        kind: IntentTargetKind,
+        class: &str,
+        actions: &[&str],
+        categories: &[&str],
+    ) -> IntentComponent {
+        IntentComponent {
+            target: IntentTarget {
+                kind,
+                apk: DevicePath::new("/system/app/Foo/Foo.apk"),
+                class_name: ClassName::from(class),
+                exported: true,
+                enabled: true,
+                permission: None,
+            },
+            filters: vec![ComponentIntentFilter {
+                actions: actions.iter().map(|it| String::from(*it)).collect(),
+                categories: categories.iter().map(|it| String::from(*it)).collect(),
+            }],
+        }
+    }
+
+    #[rstest]
+    #[case(IntentTargetKind::Receiver, &[], true)]
+    #[case(IntentTargetKind::Service, &[], true)]
+    #[case(IntentTargetKind::Activity, &[], false)]
+    #[case(IntentTargetKind::Activity, &[CATEGORY_DEFAULT], true)]
+    fn test_handles_action(
+        #[case] kind: IntentTargetKind,
+        #[case] categories: &[&str],
+        #[case] expected: bool,
+    ) {
+        let comp = component(kind, "foo.Bar", &["foo.ACTION"], categories);
+        assert_eq!(comp.handles_action("foo.ACTION"), expected);
+        assert!(!comp.handles_action("foo.OTHER"));
+    }
+
+    #[test]
+    fn test_index_matches() {
+        let components = vec![
+            component(
+                IntentTargetKind::Receiver,
+                "foo.Receiver",
+                &["foo.ACTION"],
+                &[],
+            ),
+            component(
+                IntentTargetKind::Service,
+                "foo.Service",
+                &["foo.ACTION"],
+                &[],
+            ),
+            component(IntentTargetKind::Activity, "foo.Activity", &[], &[]),
+        ];
+        let search = IntentTargetSearch::default();
+        let index = ComponentIndex::new(&components, &search);
+        let strings = vec![
+            String::from("foo.ACTION"),
+            String::from("foo.Activity"),
+            String::from("unrelated"),
+        ];
+
+        let found = index.matches(IntentTargetKind::Receiver, &strings, true);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, Some("foo.ACTION"));
+        assert_eq!(found[0].1.target.class_name, "foo.Receiver");
+
+        let found = index.matches(IntentTargetKind::Activity, &strings, true);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, None);
+        assert_eq!(found[0].1.target.class_name, "foo.Activity");
+
+        assert!(index
+            .matches(IntentTargetKind::Activity, &strings, false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_intent_target_kind_from_str() {
+        assert_eq!(
+            "Receivers".parse::<IntentTargetKind>(),
+            Ok(IntentTargetKind::Receiver)
+        );
+        assert!("provider".parse::<IntentTargetKind>().is_err());
+    }
+}
@@ -7,8 +7,16 @@ use super::common;
 mod traitdef;
 pub use traitdef::*;
 
+pub mod binder;
+pub mod export;
+pub mod intents;
+
+#[cfg(feature = "setup")]
+pub mod native;
+
 pub use models::{
-    ClassSearch, ClassSpec, MethodCallPath, MethodSearch, MethodSearchParams, MethodSpec,
+    ClassSearch, ClassSpec, DispatchMode, MethodCallPath, MethodSearch, MethodSearchParams,
+    MethodSpec, NativeImpl, NativeMethodSpec,
 };
 
 #[cfg(feature = "setup")]
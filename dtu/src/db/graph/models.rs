@@ -63,6 +63,7 @@ impl AsRef<str> for FieldAccessOp {
 pub struct Call {
     pub caller: i32,
     pub callee: i32,
+    pub reflective: bool,
 }
 
 #[sql_db_row]
@@ -117,6 +118,21 @@ pub struct MethodString {
     pub method: i32,
 }
 
+#[sql_db_row]
+#[diesel(table_name = binder_calls)]
+pub struct BinderCall {
+    pub proxy: i32,
+    pub service_impl: i32,
+    pub transaction_id: i32,
+}
+
+#[sql_db_row]
+#[diesel(table_name = method_overrides)]
+pub struct MethodOverride {
+    pub method: i32,
+    pub parent: i32,
+}
+
 #[sql_db_row]
 #[diesel(table_name = strings)]
 pub struct DiscoveredString {
@@ -141,6 +157,22 @@ pub struct Source {
     pub name: String,
 }
 
+#[sql_db_row]
+#[diesel(table_name = native_methods)]
+pub struct NativeMethod {
+    pub method: i32,
+    pub library: String,
+    pub symbol: String,
+    pub registered: bool,
+}
+
+#[sql_db_row]
+#[diesel(table_name = source_hashes)]
+pub struct SourceHash {
+    pub source: i32,
+    pub hash: String,
+}
+
 #[sql_db_row]
 #[diesel(table_name = _load_status)]
 pub struct LoadStatus {
@@ -148,6 +180,43 @@ pub struct LoadStatus {
     pub kind: i32,
 }
 
+/// Controls how call edges are followed when walking the call graph
+///
+/// The default only follows the literal `calls` edges found in the smali.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, Debug, Default, serde::Serialize, serde::Deserialize,
+)]
+pub struct DispatchMode {
+    /// Class hierarchy analysis: a call to a method is also treated as a call to
+    /// every method overriding or implementing it
+    pub class_hierarchy: bool,
+    /// Follow binder IPC from `Stub.Proxy` methods into the system service
+    /// methods they transact with
+    pub binder: bool,
+}
+
+impl DispatchMode {
+    pub fn new(class_hierarchy: bool) -> Self {
+        Self {
+            class_hierarchy,
+            binder: false,
+        }
+    }
+
+    pub fn with_binder(mut self, binder: bool) -> Self {
+        self.binder = binder;
+        self
+    }
+
+    pub fn is_class_hierarchy(self) -> bool {
+        self.class_hierarchy
+    }
+
+    pub fn is_binder(self) -> bool {
+        self.binder
+    }
+}
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(test, derive(PartialEq, Eq, Debug, PartialOrd, Ord))]
 pub struct ClassSpec {
@@ -175,6 +244,38 @@ pub struct SourcedString {
     pub source: String,
 }
 
+/// A library symbol implementing a native method
+#[derive(PartialEq, Eq, Hash, Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(test, derive(Debug))]
+pub struct NativeImpl {
+    /// Device path of the library
+    pub library: String,
+    /// Symbol implementing the method, this is an address for stripped
+    /// functions passed to `RegisterNatives`
+    pub symbol: String,
+    /// Whether the method is bound with `RegisterNatives` rather than by
+    /// its `Java_...` symbol name
+    pub registered: bool,
+}
+
+impl From<NativeMethod> for NativeImpl {
+    fn from(value: NativeMethod) -> Self {
+        Self {
+            library: value.library,
+            symbol: value.symbol,
+            registered: value.registered,
+        }
+    }
+}
+
+/// A native method and the library symbols known to implement it
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(test, derive(Debug))]
+pub struct NativeMethodSpec {
+    pub method: MethodSpec,
+    pub implementations: Vec<NativeImpl>,
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(test, derive(Debug, PartialOrd, Ord))]
 pub struct MethodCallPath {
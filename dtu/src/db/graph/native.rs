@@ -0,0 +1,526 @@
+//! Linking `native` methods to the shared libraries implementing them
+//!
+//! Native libraries are collected into [Context::get_native_libs_dir] by the
+//! pull step. Every library there is searched for `Java_...` exports and for
+//! tables that look like they are passed to `RegisterNatives`, and the matching
+//! native methods are recorded in the `native_methods` table.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use diesel::prelude::*;
+use diesel::{delete, insert_or_ignore_into};
+
+use super::models::{InsertNativeMethod, MethodSpec};
+use super::schema::native_methods;
+use super::{GraphDatabase, GraphSqliteDatabase, SetupError, SetupResult};
+use crate::tasks::TaskCancelCheck;
+use crate::utils::elf::Elf;
+use crate::utils::{path_must_name, ClassName, DevicePath};
+use crate::Context;
+
+/// Separates an APK's device path from the path of a file inside of it
+pub const APK_ENTRY_SEP: &str = "!/";
+
+/// A native library pulled from the device
+pub struct NativeLibrary {
+    /// The device path of the library, libraries extracted from APKs use
+    /// `<apk>!/<entry>`
+    pub device_path: String,
+    /// The local path to the library
+    pub path: PathBuf,
+}
+
+impl NativeLibrary {
+    /// Get the graph source of the APK this library was extracted from, if any
+    pub fn apk_source(&self) -> Option<String> {
+        let (apk, _) = self.device_path.split_once(APK_ENTRY_SEP)?;
+        Some(DevicePath::new(apk).get_squashed_string())
+    }
+}
+
+/// Find all native libraries pulled from the device
+pub fn find_native_libraries(ctx: &dyn Context) -> crate::Result<Vec<NativeLibrary>> {
+    let dir = ctx.get_native_libs_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut libs = Vec::new();
+    for ent in fs::read_dir(&dir)? {
+        let path = ent?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let device_path = DevicePath::from_squashed(path_must_name(&path));
+        libs.push(NativeLibrary {
+            device_path: device_path.as_device_str().to_string(),
+            path,
+        });
+    }
+    Ok(libs)
+}
+
+/// Mangle a string for use in a JNI symbol name
+pub fn jni_mangle(s: &str) -> String {
+    let mut mangled = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '/' => mangled.push('_'),
+            '_' => mangled.push_str("_1"),
+            ';' => mangled.push_str("_2"),
+            '[' => mangled.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => mangled.push(c),
+            c => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    mangled.push_str(&format!("_0{:04x}", unit));
+                }
+            }
+        }
+    }
+    mangled
+}
+
+fn jni_class_part(class: &ClassName) -> String {
+    let smali = class.get_smali_name();
+    let trimmed = smali
+        .strip_prefix('L')
+        .and_then(|it| it.strip_suffix(';'))
+        .unwrap_or(&smali);
+    jni_mangle(trimmed)
+}
+
+/// Get the short JNI symbol name for a native method
+pub fn jni_short_name(class: &ClassName, name: &str) -> String {
+    format!("Java_{}_{}", jni_class_part(class), jni_mangle(name))
+}
+
+/// Get the long JNI symbol name for a native method, which is used to
+/// disambiguate overloaded methods
+pub fn jni_long_name(class: &ClassName, name: &str, args: &str) -> String {
+    format!("{}__{}", jni_short_name(class, name), jni_mangle(args))
+}
+
+/// An entry in a `JNINativeMethod` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredNative {
+    pub name: String,
+    pub signature: String,
+    /// The symbol of the implementing function, or its address if the
+    /// library is stripped
+    pub symbol: String,
+}
+
+impl RegisteredNative {
+    /// Split the signature into the args and return type
+    pub fn args_and_ret(&self) -> Option<(&str, &str)> {
+        let rest = self.signature.strip_prefix('(')?;
+        rest.split_once(')')
+    }
+}
+
+/// Recover `JNINativeMethod` tables from the library's data sections
+///
+/// The tables are arrays of `{ name, signature, fnPtr }` so any run of three
+/// pointers where the first two point to a method name and a method
+/// signature and the last points to a function is treated as an entry.
+pub fn find_registered_natives(elf: &Elf) -> Vec<RegisteredNative> {
+    let ptr_size = elf.ptr_size();
+    let mut found = Vec::new();
+
+    let functions = elf
+        .symbols()
+        .into_iter()
+        .filter(|it| it.is_defined() && it.is_function() && !it.name.is_empty())
+        .map(|it| (it.value, it.name))
+        .collect::<HashMap<u64, String>>();
+
+    for section in elf
+        .sections()
+        .iter()
+        .filter(|it| it.is_alloc() && !it.is_executable() && it.has_data())
+    {
+        // Only look at what is actually in the file, the header's size can't
+        // be trusted
+        let len = elf.section_data(section).len() as u64;
+        let Some(end) = section.addr.checked_add(len) else {
+            continue;
+        };
+        let Some(mut addr) = section.addr.checked_next_multiple_of(ptr_size) else {
+            continue;
+        };
+
+        while addr.checked_add(3 * ptr_size).is_some_and(|it| it <= end) {
+            match read_native_method(elf, addr, ptr_size, &functions) {
+                Some(v) => {
+                    found.push(v);
+                    addr += 3 * ptr_size;
+                }
+                None => addr += ptr_size,
+            }
+        }
+    }
+
+    found
+}
+
+fn read_native_method(
+    elf: &Elf,
+    addr: u64,
+    ptr_size: u64,
+    functions: &HashMap<u64, String>,
+) -> Option<RegisteredNative> {
+    let name = elf.read_ptr(addr).and_then(|it| elf.read_cstr(it))?;
+    if !is_java_identifier(name) {
+        return None;
+    }
+    let signature = elf
+        .read_ptr(addr + ptr_size)
+        .and_then(|it| elf.read_cstr(it))?;
+    if !is_jni_method_signature(signature) {
+        return None;
+    }
+
+    let fn_addr = addr + 2 * ptr_size;
+    // Pointers to imported functions can only be named through their relocation
+    let symbol = match elf.ptr_symbol(fn_addr) {
+        Some(v) => v.to_string(),
+        None => {
+            let fn_ptr = elf.read_ptr(fn_addr)?;
+            if !elf.is_code_addr(fn_ptr) {
+                return None;
+            }
+            functions
+                .get(&fn_ptr)
+                .or_else(|| functions.get(&(fn_ptr & !1)))
+                .cloned()
+                .unwrap_or_else(|| format!("0x{:x}", fn_ptr))
+        }
+    };
+
+    Some(RegisteredNative {
+        name: name.to_string(),
+        signature: signature.to_string(),
+        symbol,
+    })
+}
+
+/// Find every string in the library that looks like a JNI class name, these
+/// are the names passed to `FindClass` before calling `RegisterNatives`
+pub fn find_jni_class_names(elf: &Elf) -> HashSet<ClassName> {
+    let mut names = HashSet::new();
+    for section in elf
+        .sections()
+        .iter()
+        .filter(|it| it.is_alloc() && !it.is_executable())
+    {
+        for raw in elf.section_data(section).split(|it| *it == 0) {
+            let s = match std::str::from_utf8(raw) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            if is_jni_class_name(s) {
+                names.insert(ClassName::from(format!("L{};", s)));
+            }
+        }
+    }
+    names
+}
+
+fn is_java_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn is_jni_method_signature(s: &str) -> bool {
+    let (args, ret) = match s.strip_prefix('(').and_then(|it| it.split_once(')')) {
+        Some(v) => v,
+        None => return false,
+    };
+    !ret.is_empty()
+        && args
+            .chars()
+            .chain(ret.chars())
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ';' | '[' | '_' | '$'))
+}
+
+fn is_jni_class_name(s: &str) -> bool {
+    s.contains('/')
+        && !s.starts_with('/')
+        && !s.ends_with('/')
+        && !s.contains("//")
+        && s.split('/').all(is_java_identifier)
+}
+
+/// Result of [link_native_methods]
+pub struct NativeLinkSummary {
+    /// Number of libraries that were searched
+    pub libraries: usize,
+    /// Number of native methods in the graph database
+    pub native_methods: usize,
+    /// Number of native methods that were linked to at least one library
+    pub linked: usize,
+}
+
+struct FoundNative {
+    method: i32,
+    library: String,
+    symbol: String,
+    registered: bool,
+}
+
+/// Lookup tables for native methods in the graph database
+struct NativeIndex<'a> {
+    methods: &'a [MethodSpec],
+    by_symbol: HashMap<String, Vec<usize>>,
+    by_signature: HashMap<(&'a str, &'a str, &'a str), Vec<usize>>,
+}
+
+impl<'a> NativeIndex<'a> {
+    fn new(methods: &'a [MethodSpec]) -> Self {
+        let mut by_symbol: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_signature: HashMap<(&str, &str, &str), Vec<usize>> = HashMap::new();
+
+        for (idx, m) in methods.iter().enumerate() {
+            by_symbol
+                .entry(jni_short_name(&m.class, &m.name))
+                .or_default()
+                .push(idx);
+            by_symbol
+                .entry(jni_long_name(&m.class, &m.name, &m.signature))
+                .or_default()
+                .push(idx);
+            by_signature
+                .entry((&m.name, &m.signature, &m.ret))
+                .or_default()
+                .push(idx);
+        }
+
+        Self {
+            methods,
+            by_symbol,
+            by_signature,
+        }
+    }
+
+    fn link(&self, lib: &NativeLibrary, elf: &Elf, into: &mut Vec<FoundNative>) {
+        // Libraries shipped in an APK can only implement that APK's methods
+        let owner = lib.apk_source();
+        let allowed = |m: &MethodSpec| owner.as_ref().map_or(true, |it| m.source == *it);
+
+        for sym in elf
+            .dynamic_symbols()
+            .into_iter()
+            .filter(|it| it.is_defined() && it.name.starts_with("Java_"))
+        {
+            let idxs = match self.by_symbol.get(&sym.name) {
+                Some(v) => v,
+                None => continue,
+            };
+            for m in idxs
+                .iter()
+                .map(|it| &self.methods[*it])
+                .filter(|it| allowed(it))
+            {
+                into.push(FoundNative {
+                    method: m.id,
+                    library: lib.device_path.clone(),
+                    symbol: sym.name.clone(),
+                    registered: false,
+                });
+            }
+        }
+
+        let registered = find_registered_natives(elf);
+        if registered.is_empty() {
+            return;
+        }
+        let classes = find_jni_class_names(elf);
+
+        for reg in registered {
+            let (args, ret) = match reg.args_and_ret() {
+                Some(v) => v,
+                None => continue,
+            };
+            let candidates = match self.by_signature.get(&(reg.name.as_str(), args, ret)) {
+                Some(v) => v
+                    .iter()
+                    .map(|it| &self.methods[*it])
+                    .filter(|it| allowed(it))
+                    .collect::<Vec<&MethodSpec>>(),
+                None => continue,
+            };
+
+            // Prefer methods in classes the library names, but fall back to a
+            // unique match since class names can be built at runtime
+            let in_lib = candidates
+                .iter()
+                .filter(|it| classes.contains(&it.class))
+                .copied()
+                .collect::<Vec<&MethodSpec>>();
+            let chosen = if !in_lib.is_empty() {
+                in_lib
+            } else if candidates.len() == 1 {
+                candidates
+            } else {
+                log::debug!(
+                    "ambiguous registered native {}{} in {}",
+                    reg.name,
+                    reg.signature,
+                    lib.device_path
+                );
+                continue;
+            };
+
+            for m in chosen {
+                into.push(FoundNative {
+                    method: m.id,
+                    library: lib.device_path.clone(),
+                    symbol: reg.symbol.clone(),
+                    registered: true,
+                });
+            }
+        }
+    }
+}
+
+/// Link every native method in the graph database to the libraries
+/// implementing it, replacing any previous results
+pub fn link_native_methods(
+    db: &GraphSqliteDatabase,
+    ctx: &dyn Context,
+    cancel: &TaskCancelCheck,
+) -> SetupResult<NativeLinkSummary> {
+    let natives = db
+        .get_native_methods(None)?
+        .into_iter()
+        .map(|it| it.method)
+        .collect::<Vec<MethodSpec>>();
+    let index = NativeIndex::new(&natives);
+
+    let libs = find_native_libraries(ctx)?;
+    let mut found = Vec::new();
+
+    for lib in libs.iter() {
+        cancel.check(SetupError::Cancelled)?;
+        let data = match fs::read(&lib.path) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("failed to read {}: {}", lib.path.display(), e);
+                continue;
+            }
+        };
+        let elf = match Elf::parse(&data) {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("skipping {}: {}", lib.device_path, e);
+                continue;
+            }
+        };
+        index.link(lib, &elf, &mut found);
+    }
+
+    let linked = found
+        .iter()
+        .map(|it| it.method)
+        .collect::<HashSet<i32>>()
+        .len();
+
+    db.transaction(|c| -> crate::db::Result<()> {
+        delete(native_methods::table).execute(c)?;
+        for chunk in found.chunks(1000) {
+            let values = chunk
+                .iter()
+                .map(|it| {
+                    InsertNativeMethod::new(it.method, &it.library, &it.symbol, it.registered)
+                })
+                .collect::<Vec<_>>();
+            insert_or_ignore_into(native_methods::table)
+                .values(values.as_slice())
+                .execute(c)?;
+        }
+        Ok(())
+    })?;
+
+    Ok(NativeLinkSummary {
+        libraries: libs.len(),
+        native_methods: natives.len(),
+        linked,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Lcom/example/Foo;", "nativeInit", "Java_com_example_Foo_nativeInit")]
+    #[case(
+        "Lcom/example/Foo$Bar;",
+        "native_init",
+        "Java_com_example_Foo_00024Bar_native_1init"
+    )]
+    #[case("com.example.Foo", "nativeInit", "Java_com_example_Foo_nativeInit")]
+    fn test_jni_short_name(#[case] class: &str, #[case] name: &str, #[case] expected: &str) {
+        assert_eq!(jni_short_name(&ClassName::from(class), name), expected);
+    }
+
+    #[test]
+    fn test_jni_long_name() {
+        assert_eq!(
+            jni_long_name(
+                &ClassName::from("Lcom/example/Foo;"),
+                "write",
+                "[BLjava/lang/String;I"
+            ),
+            "Java_com_example_Foo_write___3BLjava_lang_String_2I"
+        );
+    }
+
+    #[rstest]
+    #[case("(I)V", true)]
+    #[case("()Ljava/lang/String;", true)]
+    #[case("([BLandroid/os/Parcel;)[J", true)]
+    #[case("()", false)]
+    #[case("I)V", false)]
+    #[case("(I V", false)]
+    #[case("(some words)V", false)]
+    fn test_is_jni_method_signature(#[case] sig: &str, #[case] expected: bool) {
+        assert_eq!(is_jni_method_signature(sig), expected);
+    }
+
+    #[rstest]
+    #[case("android/media/MediaPlayer", true)]
+    #[case("com/android/server/Foo$Bar", true)]
+    #[case("/system/lib64", false)]
+    #[case("lib/", false)]
+    #[case("MediaPlayer", false)]
+    #[case("not a/class", false)]
+    fn test_is_jni_class_name(#[case] s: &str, #[case] expected: bool) {
+        assert_eq!(is_jni_class_name(s), expected);
+    }
+
+    #[test]
+    fn test_apk_source() {
+        let lib = NativeLibrary {
+            device_path: "/system/app/Foo/Foo.apk!/lib/arm64-v8a/libfoo.so".into(),
+            path: PathBuf::new(),
+        };
+        assert_eq!(
+            lib.apk_source(),
+            Some(DevicePath::new("/system/app/Foo/Foo.apk").get_squashed_string())
+        );
+
+        let lib = NativeLibrary {
+            device_path: "/system/lib64/libandroid_runtime.so".into(),
+            path: PathBuf::new(),
+        };
+        assert_eq!(lib.apk_source(), None);
+    }
+}
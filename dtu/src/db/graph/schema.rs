@@ -8,12 +8,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    binder_calls (proxy, service_impl) {
+        proxy -> Integer,
+        service_impl -> Integer,
+        transaction_id -> Integer,
+    }
+}
+
 diesel::table! {
     calls (rowid) {
         rowid -> Integer,
         caller -> Integer,
         callee -> Integer,
         source -> Integer,
+        reflective -> Bool,
     }
 }
 
@@ -53,6 +62,13 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    method_overrides (method, parent) {
+        method -> Integer,
+        parent -> Integer,
+    }
+}
+
 diesel::table! {
     method_strings (string, method) {
         string -> Integer,
@@ -72,6 +88,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    native_methods (method, library, symbol) {
+        method -> Integer,
+        library -> Text,
+        symbol -> Text,
+        registered -> Bool,
+    }
+}
+
+diesel::table! {
+    source_hashes (source) {
+        source -> Integer,
+        hash -> Text,
+    }
+}
+
 diesel::table! {
     sources (id) {
         id -> Integer,
@@ -107,18 +139,24 @@ diesel::joinable!(method_strings -> methods (method));
 diesel::joinable!(method_strings -> strings (string));
 diesel::joinable!(methods -> classes (class));
 diesel::joinable!(methods -> sources (source));
+diesel::joinable!(native_methods -> methods (method));
+diesel::joinable!(source_hashes -> sources (source));
 diesel::joinable!(strings -> sources (source));
 diesel::joinable!(supers -> sources (source));
 
 diesel::allow_tables_to_appear_in_same_query!(
     _load_status,
+    binder_calls,
     calls,
     class_fields,
     classes,
     interfaces,
     method_field_access,
+    method_overrides,
     method_strings,
     methods,
+    native_methods,
+    source_hashes,
     sources,
     strings,
     supers,
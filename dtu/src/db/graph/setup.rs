@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::str::FromStr;
 
@@ -9,8 +10,8 @@ use itertools::Itertools;
 use smalisa::AccessFlag;
 
 use super::common::*;
-use super::models::{InsertClass, InsertLoadStatus, InsertSource};
-use super::schema::{_load_status, classes, sources};
+use super::models::{InsertClass, InsertLoadStatus, InsertSource, InsertSourceHash};
+use super::schema::{_load_status, classes, source_hashes, sources};
 use super::setup_task::{AddDirTask, GraphDatabaseSetup, InitialImportOptions};
 use super::FRAMEWORK_SOURCE;
 use super::{setup::SetupResult, AddDirectoryOptions, SetupEvent};
@@ -26,6 +27,7 @@ use crate::{
 use super::common::Error;
 use super::db::GraphSqliteDatabase;
 use super::setup_task::*;
+use super::GraphDatabase;
 
 impl CSV {
     fn to_kind(self) -> i32 {
@@ -234,6 +236,23 @@ impl<'a> SetupContext<'a> {
     }
 }
 
+const CLEAR_STAGED_SQL: &'static str = r#"
+DELETE FROM named_method_field_access;
+DELETE FROM named_class_fields;
+DELETE FROM named_method_strings;
+DELETE FROM named_calls;
+DELETE FROM named_methods;
+DELETE FROM named_supers;
+DELETE FROM named_interfaces;
+"#;
+
+const DROP_FIXUP_TABLES_SQL: &'static str = r#"
+DROP TABLE IF EXISTS temp._fixup_calls;
+DROP TABLE IF EXISTS temp._fixup_interfaces;
+DROP TABLE IF EXISTS temp._fixup_supers;
+DROP TABLE IF EXISTS temp._fixup_method_field_access;
+"#;
+
 impl GraphSqliteDatabase {
     fn finalize(&self, _ctx: &dyn Context) -> Result<()> {
         Ok(self.with_connection(|c| -> Result<()> {
@@ -494,6 +513,181 @@ WHERE dst.id != src.id"#
         Ok(())
     }
 
+    fn load_method_overrides_with_conn(
+        &self,
+        conn: &mut SqliteConnection,
+        src: Option<i32>,
+    ) -> Result<()> {
+        // Constructors, static methods, and private methods are never virtually dispatched so
+        // they can't override anything.
+        let no_dispatch = AccessFlag::CONSTRUCTOR | AccessFlag::STATIC | AccessFlag::PRIVATE;
+        let raw_flags: i64 = no_dispatch.bits() as i64;
+
+        // Relationships are always recorded in the child's source, so when loading a single
+        // source we only need to walk up from that source's classes.
+        let (seed_where, delete_sql) = match src {
+            Some(_) => (
+                "WHERE c.source = ?2",
+                "DELETE FROM method_overrides WHERE method IN (SELECT id FROM methods WHERE source = ?)",
+            ),
+            None => ("", "DELETE FROM method_overrides"),
+        };
+
+        let mut delete = sql_query(delete_sql).into_boxed();
+        if let Some(v) = src {
+            delete = delete.bind::<Integer, _>(v);
+        }
+        query!(delete).execute(conn)?;
+
+        // Walk every (class, ancestor) pair through both `supers` and `interfaces` and match
+        // methods by name and args.
+        let mut q = sql_query(format!(
+            r#"INSERT OR IGNORE INTO method_overrides(method, parent)
+WITH RECURSIVE
+    hierarchy(child, parent) AS (
+        SELECT child, parent FROM supers
+        UNION ALL
+        SELECT class, interface FROM interfaces
+    ),
+    ancestors(class, ancestor) AS (
+        SELECT h.child, h.parent
+        FROM hierarchy AS h
+        JOIN classes AS c
+            ON c.id = h.child
+        {seed_where}
+        UNION
+        SELECT a.class, h.parent
+        FROM ancestors AS a
+        JOIN hierarchy AS h
+            ON h.child = a.ancestor
+    )
+SELECT DISTINCT m.id, pm.id
+FROM ancestors AS a
+JOIN methods AS m
+    ON m.class = a.class
+JOIN methods AS pm
+    ON pm.class = a.ancestor AND pm.name = m.name AND pm.args = m.args
+WHERE (m.access_flags & ?1) = 0 AND (pm.access_flags & ?1) = 0"#
+        ))
+        .into_boxed()
+        .bind::<BigInt, _>(raw_flags);
+
+        if let Some(v) = src {
+            q = q.bind::<Integer, _>(v);
+        }
+
+        let found = query!(q).execute(conn)?;
+        log::debug!("Found {found} method overrides");
+        Ok(())
+    }
+
+    fn load_method_overrides(&self, src: i32) -> Result<()> {
+        Ok(self.transaction(|c| self.load_method_overrides_with_conn(c, Some(src)))?)
+    }
+
+    /// Rebuild the `method_overrides` table used for class hierarchy analysis
+    ///
+    /// This is done automatically for each source as it is added, but can be
+    /// used to populate databases created before the table existed.
+    pub fn update_method_overrides(&self) -> Result<()> {
+        Ok(self.transaction(|c| self.load_method_overrides_with_conn(c, None))?)
+    }
+
+    fn load_reflective_calls_with_conn(
+        &self,
+        conn: &mut SqliteConnection,
+        src: Option<i32>,
+    ) -> Result<()> {
+        let (seed_where, delete_sql) = match src {
+            Some(_) => (
+                "AND src.source = ?1",
+                "DELETE FROM calls WHERE reflective AND source = ?",
+            ),
+            None => ("", "DELETE FROM calls WHERE reflective"),
+        };
+
+        let mut delete = sql_query(delete_sql).into_boxed();
+        if let Some(v) = src {
+            delete = delete.bind::<Integer, _>(v);
+        }
+        query!(delete).execute(conn)?;
+
+        // Some notes on this insert:
+        //
+        // 1. Only methods that look up a method or constructor on a `Class` are considered,
+        //    anything else using the strings we'd match on isn't reflection.
+        // 2. Constant strings are resolved to classes the same way callees are when loading
+        //    `calls`: the caller's source first and then the framework. Method names are then
+        //    matched against the other constant strings in the same method. The signature isn't
+        //    known, so every overload is linked.
+        // 3. Edges that already exist in the smali aren't duplicated.
+        let mut q = sql_query(format!(
+            r#"INSERT INTO calls(caller, callee, source, reflective)
+WITH
+    lookups(method, source, ctor) AS (
+        SELECT DISTINCT c.caller, src.source, m.name NOT IN ('getMethod', 'getDeclaredMethod')
+        FROM calls AS c
+        JOIN methods AS src
+            ON src.id = c.caller
+        JOIN methods AS m
+            ON m.id = c.callee
+        JOIN classes AS k
+            ON k.id = m.class
+        WHERE k.name = 'Ljava/lang/Class;'
+            AND m.name IN ('getMethod', 'getDeclaredMethod', 'getConstructor', 'getDeclaredConstructor', 'newInstance')
+            AND NOT c.reflective
+            {seed_where}
+    ),
+    consts(method, string) AS (
+        SELECT ms.method, s.string
+        FROM method_strings AS ms
+        JOIN strings AS s
+            ON s.id = ms.string
+        WHERE ms.method IN (SELECT method FROM lookups)
+    ),
+    targets(method, source, ctor, class) AS (
+        SELECT l.method, l.source, l.ctor, COALESCE(
+            (SELECT id FROM classes WHERE name = 'L' || replace(cs.string, '.', '/') || ';' AND source = l.source),
+            (SELECT id FROM classes WHERE name = 'L' || replace(cs.string, '.', '/') || ';' AND source = 1)
+        )
+        FROM lookups AS l
+        JOIN consts AS cs
+            ON cs.method = l.method
+    )
+SELECT DISTINCT t.method, dst.id, t.source, TRUE
+FROM targets AS t
+JOIN methods AS dst
+    ON dst.class = t.class
+WHERE dst.id != t.method
+    AND (
+        (t.ctor AND dst.name = '<init>')
+        OR (NOT t.ctor AND dst.name IN (SELECT string FROM consts WHERE method = t.method))
+    )
+    AND NOT EXISTS (SELECT 1 FROM calls AS e WHERE e.caller = t.method AND e.callee = dst.id)"#
+        ))
+        .into_boxed();
+
+        if let Some(v) = src {
+            q = q.bind::<Integer, _>(v);
+        }
+
+        let found = query!(q).execute(conn)?;
+        log::debug!("Found {found} reflective calls");
+        Ok(())
+    }
+
+    fn load_reflective_calls(&self, src: i32) -> Result<()> {
+        Ok(self.transaction(|c| self.load_reflective_calls_with_conn(c, Some(src)))?)
+    }
+
+    /// Rebuild the synthetic `calls` edges for reflective method and constructor lookups
+    ///
+    /// Like [Self::update_method_overrides], this is done automatically for each
+    /// source as it is added.
+    pub fn update_reflective_calls(&self) -> Result<()> {
+        Ok(self.transaction(|c| self.load_reflective_calls_with_conn(c, None))?)
+    }
+
     fn load_staged_impls(&self, src: i32) -> Result<()> {
         Ok(self.transaction(|c| self.load_staged_impls_with_conn(c, src))?)
     }
@@ -552,6 +746,285 @@ WHERE dst.id != src.id"#
         )?)
     }
 
+    fn get_import_hashes(&self) -> Result<HashMap<String, String>> {
+        let rows = self.with_connection(|c| {
+            query!(source_hashes::table
+                .inner_join(sources::table)
+                .select((sources::name, source_hashes::hash)))
+            .load::<(String, String)>(c)
+        })?;
+        Ok(rows.into_iter().collect())
+    }
+
+    fn set_import_hash(&self, src: i32, hash: &str) -> Result<()> {
+        let ins = InsertSourceHash::new(src, hash);
+        self.with_connection(|c| {
+            query!(diesel::replace_into(source_hashes::table).values(&ins)).execute(c)
+        })?;
+        Ok(())
+    }
+
+    /// Save every edge from another source that points into the given source by name
+    ///
+    /// These edges are lost when the source is removed, but they are only ever discovered while
+    /// loading the source they originate from, so they need to be restored with
+    /// [Self::restore_cross_source_edges] once the source has been imported again. The ids of all
+    /// sources that had edges saved, or that have reflective calls into the source, are returned.
+    pub(super) fn stash_cross_source_edges_with_conn(
+        &self,
+        conn: &mut SqliteConnection,
+        src: i32,
+    ) -> Result<Vec<i32>> {
+        conn.batch_execute(DROP_FIXUP_TABLES_SQL)?;
+        conn.batch_execute(
+            r#"
+CREATE TEMPORARY TABLE _fixup_calls(
+    source INTEGER NOT NULL,
+    caller_class TEXT NOT NULL,
+    caller_method TEXT NOT NULL,
+    caller_args TEXT NOT NULL,
+    callee_class TEXT NOT NULL,
+    callee_method TEXT NOT NULL,
+    callee_args TEXT NOT NULL
+);
+
+CREATE TEMPORARY TABLE _fixup_interfaces(
+    source INTEGER NOT NULL,
+    interface TEXT NOT NULL,
+    class TEXT NOT NULL
+);
+
+CREATE TEMPORARY TABLE _fixup_supers(
+    source INTEGER NOT NULL,
+    parent TEXT NOT NULL,
+    child TEXT NOT NULL
+);
+
+CREATE TEMPORARY TABLE _fixup_method_field_access(
+    source INTEGER NOT NULL,
+    field_class TEXT NOT NULL,
+    field_name TEXT NOT NULL,
+    field_ty TEXT NOT NULL,
+    method_class TEXT NOT NULL,
+    method_name TEXT NOT NULL,
+    method_args TEXT NOT NULL,
+    action INTEGER NOT NULL
+);
+"#,
+        )?;
+
+        query!(sql_query(
+            r#"INSERT INTO _fixup_calls
+SELECT DISTINCT c.source, sc.name, src.name, src.args, dc.name, dst.name, dst.args
+FROM calls AS c
+JOIN methods AS dst
+    ON dst.id = c.callee
+JOIN classes AS dc
+    ON dc.id = dst.class
+JOIN methods AS src
+    ON src.id = c.caller
+JOIN classes AS sc
+    ON sc.id = src.class
+WHERE dc.source = ?1 AND c.source != ?1 AND NOT c.reflective"#
+        )
+        .bind::<Integer, _>(src))
+        .execute(conn)?;
+
+        query!(sql_query(
+            r#"INSERT INTO _fixup_interfaces
+SELECT DISTINCT i.source, iface.name, cls.name
+FROM interfaces AS i
+JOIN classes AS iface
+    ON iface.id = i.interface
+JOIN classes AS cls
+    ON cls.id = i.class
+WHERE iface.source = ?1 AND i.source != ?1"#
+        )
+        .bind::<Integer, _>(src))
+        .execute(conn)?;
+
+        query!(sql_query(
+            r#"INSERT INTO _fixup_supers
+SELECT DISTINCT s.source, parent.name, child.name
+FROM supers AS s
+JOIN classes AS parent
+    ON parent.id = s.parent
+JOIN classes AS child
+    ON child.id = s.child
+WHERE parent.source = ?1 AND s.source != ?1"#
+        )
+        .bind::<Integer, _>(src))
+        .execute(conn)?;
+
+        query!(sql_query(
+            r#"INSERT INTO _fixup_method_field_access
+SELECT DISTINCT mc.source, fc.name, f.name, f.ty, mc.name, m.name, m.args, a.action
+FROM method_field_access AS a
+JOIN class_fields AS f
+    ON f.id = a.field
+JOIN classes AS fc
+    ON fc.id = f.class
+JOIN methods AS m
+    ON m.id = a.method
+JOIN classes AS mc
+    ON mc.id = m.class
+WHERE fc.source = ?1 AND mc.source != ?1"#
+        )
+        .bind::<Integer, _>(src))
+        .execute(conn)?;
+
+        #[derive(QueryableByName)]
+        struct SourceRow {
+            #[diesel(sql_type = Integer)]
+            source: i32,
+        }
+
+        let rows = query!(sql_query(
+            r#"SELECT source FROM _fixup_calls
+UNION SELECT source FROM _fixup_interfaces
+UNION SELECT source FROM _fixup_supers
+UNION SELECT source FROM _fixup_method_field_access
+UNION SELECT c.source FROM calls AS c
+    JOIN methods AS dst
+        ON dst.id = c.callee
+    WHERE c.reflective AND dst.source = ?1 AND c.source != ?1"#
+        )
+        .bind::<Integer, _>(src))
+        .load::<SourceRow>(conn)?;
+
+        Ok(rows.into_iter().map(|it| it.source).collect())
+    }
+
+    fn restore_cross_source_edges_with_conn(
+        &self,
+        conn: &mut SqliteConnection,
+        src: i32,
+    ) -> Result<()> {
+        conn.batch_execute(CLEAR_STAGED_SQL)?;
+
+        // Stage the saved edges exactly as they would be staged from the source's CSVs and reuse
+        // the normal loading queries to resolve them against the newly imported source.
+        for sql in [
+            "INSERT INTO named_interfaces(interface, class) SELECT interface, class FROM _fixup_interfaces WHERE source = ?",
+            "INSERT INTO named_supers(parent, child) SELECT parent, child FROM _fixup_supers WHERE source = ?",
+            r#"INSERT INTO named_calls(caller_class, caller_method, caller_args, callee_class, callee_method, callee_args)
+SELECT caller_class, caller_method, caller_args, callee_class, callee_method, callee_args FROM _fixup_calls WHERE source = ?"#,
+            r#"INSERT INTO named_method_field_access(field_class, field_name, field_ty, method_class, method_name, method_args, action)
+SELECT field_class, field_name, field_ty, method_class, method_name, method_args, action FROM _fixup_method_field_access WHERE source = ?"#,
+        ] {
+            query!(sql_query(sql).bind::<Integer, _>(src)).execute(conn)?;
+        }
+
+        self.load_staged_impls_with_conn(conn, src)?;
+        self.load_staged_supers_with_conn(conn, src)?;
+        self.load_staged_calls_with_conn(conn, src)?;
+        self.load_staged_method_field_access_with_conn(conn, src)?;
+
+        conn.batch_execute(CLEAR_STAGED_SQL)?;
+        Ok(())
+    }
+
+    pub(super) fn restore_cross_source_edges(
+        &self,
+        ctx: &dyn Context,
+        sources: &[i32],
+    ) -> Result<()> {
+        self.load_begin(ctx)?;
+
+        let mut res = Ok(());
+        for src in sources {
+            res = self
+                .transaction(|c| self.restore_cross_source_edges_with_conn(c, *src))
+                .and_then(|_| self.load_method_overrides(*src))
+                .and_then(|_| self.load_reflective_calls(*src));
+            if res.is_err() {
+                break;
+            }
+        }
+
+        _ = self.load_complete(ctx, res.is_ok());
+        self.with_connection(|c| c.batch_execute(DROP_FIXUP_TABLES_SQL))?;
+        res
+    }
+
+    /// Remove the given source and import it again from its smalisa CSVs
+    fn reimport_directory(
+        &self,
+        ctx: &dyn Context,
+        opts: AddDirectoryOptions,
+        hash: String,
+        monitor: &dyn EventMonitor<SetupEvent>,
+        cancel: &TaskCancelCheck,
+    ) -> SetupResult<()> {
+        let src = self.get_source_id(&opts.name)?;
+        let name = opts.name.clone();
+
+        let affected = self.transaction(|c| self.stash_cross_source_edges_with_conn(c, src))?;
+
+        // Removing the source relies on cascading deletes, but loading turns foreign keys off. The
+        // source keeps the same id since the framework is always expected to be 1.
+        self.with_connection(|c| -> Result<()> {
+            c.batch_execute("PRAGMA foreign_keys=ON;")?;
+            c.transaction(|c| -> Result<()> {
+                query!(diesel::delete(sources::table.filter(sources::id.eq(src)))).execute(c)?;
+                query!(insert_into(sources::table)
+                    .values((sources::id.eq(src), sources::name.eq(&name))))
+                .execute(c)?;
+                Ok(())
+            })
+        })?;
+
+        self.add_directory_with_hash(ctx, opts, Some(hash), monitor, cancel)?;
+
+        if !affected.is_empty() {
+            monitor.on_event(SetupEvent::FixingEdges { source: name });
+            self.restore_cross_source_edges(ctx, &affected)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_directory_with_hash(
+        &self,
+        ctx: &dyn Context,
+        opts: AddDirectoryOptions,
+        hash: Option<String>,
+        monitor: &dyn EventMonitor<SetupEvent>,
+        cancel: &TaskCancelCheck,
+    ) -> SetupResult<()> {
+        self.with_connection(|c| -> std::result::Result<(), Error> {
+            let ins = InsertSource::new(&opts.name);
+            _ = insert_into(sources::table)
+                .values(&ins)
+                .on_conflict_do_nothing()
+                .execute(c);
+            Ok(())
+        })?;
+
+        let name = opts.name.clone();
+
+        let task = AddDirTask {
+            ctx,
+            cancel,
+            opts,
+            monitor,
+            graph: self,
+        };
+
+        task.run()?;
+
+        let src = self.get_source_id(&name)?;
+        self.load_method_overrides(src)?;
+        self.load_reflective_calls(src)?;
+
+        let hash = match hash {
+            Some(v) => v,
+            None => hash_import_dir(&ctx.get_graph_import_dir()?.join(&name))?,
+        };
+        self.set_import_hash(src, &hash)?;
+        Ok(())
+    }
+
     fn update_load_status(conn: &mut SqliteConnection, src: i32, status: CSV) -> Result<()> {
         let ls = InsertLoadStatus::new(src, status.to_kind());
         _ = query!(insert_into(_load_status::table).values(&ls)).execute(conn)?;
@@ -598,24 +1071,84 @@ impl GraphDatabaseSetup for GraphSqliteDatabase {
         monitor: &dyn EventMonitor<SetupEvent>,
         cancel: &TaskCancelCheck,
     ) -> SetupResult<()> {
-        self.with_connection(|c| -> std::result::Result<(), Error> {
-            let ins = InsertSource::new(&opts.name);
-            _ = insert_into(sources::table)
-                .values(&ins)
-                .on_conflict_do_nothing()
-                .execute(c);
-            Ok(())
-        })?;
+        self.add_directory_with_hash(ctx, opts, None, monitor, cancel)
+    }
 
-        let task = AddDirTask {
-            ctx,
-            cancel,
-            opts,
-            monitor,
-            graph: self,
-        };
+    fn run_incremental_update(
+        &self,
+        ctx: &dyn Context,
+        opts: InitialImportOptions,
+        monitor: &dyn EventMonitor<SetupEvent>,
+        cancel: &TaskCancelCheck,
+    ) -> SetupResult<Vec<String>> {
+        let import_dir = ctx.get_graph_import_dir()?;
+        let existing = self.get_all_sources()?;
+        let hashes = self.get_import_hashes()?;
 
-        task.run()?;
+        // The framework has to go first since edges from APKs are resolved against it
+        let mut sources = vec![String::from(FRAMEWORK_SOURCE)];
+        sources.extend(opts.get_apk_sources(ctx)?);
+
+        let mut updated = Vec::new();
+
+        for name in sources.iter() {
+            cancel.check(SetupError::Cancelled)?;
+
+            let dir = import_dir.join(name);
+            if !dir.exists() {
+                log::warn!("no smalisa output for {}, skipping", name);
+                continue;
+            }
+
+            let hash = hash_import_dir(&dir)?;
+            let add_opts = AddDirectoryOptions::new(name.clone(), &dir);
+
+            if !existing.contains(name) {
+                log::info!("adding new source {}", name);
+                self.add_directory_with_hash(ctx, add_opts, Some(hash), monitor, cancel)?;
+            } else if hashes.get(name) != Some(&hash) {
+                log::info!("smalisa output for {} changed, importing it again", name);
+                self.reimport_directory(ctx, add_opts, hash, monitor, cancel)?;
+            } else {
+                monitor.on_event(SetupEvent::SourceUnchanged {
+                    source: name.clone(),
+                });
+                continue;
+            }
+
+            updated.push(name.clone());
+        }
+
+        let known = sources.iter().collect::<HashSet<&String>>();
+        for name in existing.iter().filter(|it| !known.contains(it)) {
+            log::warn!(
+                "source {} is no longer available, use `dtu graph remove-source` to remove it",
+                name
+            );
+        }
+
+        if !updated.is_empty() {
+            monitor.on_event(SetupEvent::Finalizing);
+            self.finalize(ctx)?;
+        }
+
+        Ok(updated)
+    }
+
+    fn record_import_hashes(&self, ctx: &dyn Context) -> SetupResult<()> {
+        let import_dir = ctx.get_graph_import_dir()?;
+        for source in self.get_sources()? {
+            let dir = import_dir.join(&source.name);
+            if !dir.exists() {
+                log::warn!(
+                    "no smalisa output for {}, not recording a hash",
+                    source.name
+                );
+                continue;
+            }
+            let hash = hash_import_dir(&dir)?;
+            self.set_import_hash(source.id, &hash)?;
+        }
         Ok(())
     }
 
@@ -742,17 +1275,7 @@ CREATE TEMPORARY TABLE IF NOT EXISTS named_interfaces(
 
     fn load_complete(&self, _ctx: &dyn Context, _success: bool) -> Result<()> {
         self.with_connection(|c| {
-            c.batch_execute(
-                r#"
-            DELETE FROM named_method_field_access;
-            DELETE FROM named_class_fields;
-            DELETE FROM named_method_strings;
-            DELETE FROM named_calls;
-            DELETE FROM named_methods;
-            DELETE FROM named_supers;
-            DELETE FROM named_interfaces;
-                "#,
-            )?;
+            c.batch_execute(CLEAR_STAGED_SQL)?;
             Ok(())
         })
     }
@@ -1,10 +1,14 @@
 use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 
+use sha2::{Digest, Sha256};
+
 use crate::db;
 use crate::smalisa_wrapper::CSV;
 use crate::tasks::{EventMonitor, TaskCancelCheck};
-use crate::utils::{opt_deny, path_must_name, path_must_str, DevicePath, OptDenylist};
+use crate::utils::{hex, opt_deny, path_must_name, path_must_str, DevicePath, OptDenylist};
 use crate::Context;
 use dtu_proc_macro::wraps_base_error;
 
@@ -31,6 +35,18 @@ pub enum SetupEvent {
         path: String,
     },
 
+    /// Fired during an incremental update when a source's CSVs haven't
+    /// changed since it was imported
+    SourceUnchanged {
+        source: String,
+    },
+
+    /// Fired during an incremental update when edges from other sources into
+    /// a re-imported source are being restored
+    FixingEdges {
+        source: String,
+    },
+
     Finalizing,
 }
 
@@ -70,6 +86,14 @@ impl InitialImportOptions {
         !opt_deny(&self.apk_denylist, path)
     }
 
+    /// Get the graph source name for each APK respecting the denylist
+    pub fn get_apk_sources(&self, ctx: &dyn Context) -> crate::Result<Vec<String>> {
+        self.get_apk_smalisa_dirs(ctx)?
+            .iter()
+            .map(|it| Ok(DevicePath::from_path(it)?.get_squashed_string()))
+            .collect()
+    }
+
     /// Get the smalisa directories for each APK respecting the denylist
     pub fn get_apk_smalisa_dirs(&self, ctx: &dyn Context) -> crate::Result<Vec<PathBuf>> {
         let smali_apks = ctx.get_smali_dir()?.join("apks");
@@ -120,6 +144,45 @@ impl From<db::Error> for SetupError {
 
 pub type SetupResult<T> = std::result::Result<T, SetupError>;
 
+/// Hash the contents of the smalisa CSVs in the given import directory
+///
+/// Smalisa writes rows from multiple threads so the order of the rows isn't
+/// stable between runs. To account for that, each row is hashed separately and
+/// the row hashes are summed, so the result only changes if the set of rows
+/// changes.
+pub fn hash_import_dir(dir: &Path) -> SetupResult<String> {
+    let mut hasher = Sha256::new();
+
+    for csv in CSV::all() {
+        hasher.update(csv.file_name().as_bytes());
+
+        let path = csv.in_path(dir);
+        if !path.exists() {
+            hasher.update(b"missing");
+            continue;
+        }
+
+        let mut sum: u128 = 0;
+        let mut count: u64 = 0;
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut line = Vec::new();
+
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            let digest = Sha256::digest(&line);
+            let mut bytes = [0u8; 16];
+            bytes.copy_from_slice(&digest[..16]);
+            sum = sum.wrapping_add(u128::from_le_bytes(bytes));
+            count += 1;
+            line.clear();
+        }
+
+        hasher.update(count.to_le_bytes());
+        hasher.update(sum.to_le_bytes());
+    }
+
+    Ok(hex::bytes_to_hex(&hasher.finalize()))
+}
+
 pub struct AddDirTask<'a, M, G>
 where
     M: EventMonitor<SetupEvent> + ?Sized,
@@ -237,6 +300,26 @@ pub trait GraphDatabaseSetup: Sync + Send + GraphDatabase {
         cancel: &TaskCancelCheck,
     ) -> SetupResult<()>;
 
+    /// Bring an existing graph database up to date with the smalisa CSVs
+    ///
+    /// Sources whose CSVs have changed since they were imported are removed and imported again,
+    /// and any edges from other sources into them are restored. Sources that aren't in the
+    /// database yet are added. The names of all sources that were imported are returned.
+    fn run_incremental_update(
+        &self,
+        ctx: &dyn Context,
+        opts: InitialImportOptions,
+        monitor: &dyn EventMonitor<SetupEvent>,
+        cancel: &TaskCancelCheck,
+    ) -> SetupResult<Vec<String>>;
+
+    /// Record the hashes of the current smalisa CSVs for every source in the database without
+    /// importing anything
+    ///
+    /// This is useful for databases that were created before hashes were recorded, since every
+    /// source without a hash is treated as changed by [run_incremental_update].
+    fn record_import_hashes(&self, ctx: &dyn Context) -> SetupResult<()>;
+
     /// Adds the contents of a given directory to the graph.
     ///
     /// Use this only for adding a single directory at a time, the graph database must be setup up
@@ -275,3 +358,34 @@ pub trait GraphDatabaseSetup: Sync + Send + GraphDatabase {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_hash_import_dir() {
+        let a = tempfile::tempdir().expect("tempdir");
+        let b = tempfile::tempdir().expect("tempdir");
+
+        let classes = CSV::Classes.file_name();
+        let methods = CSV::Methods.file_name();
+
+        fs::write(a.path().join(classes), "La;,1\nLb;,1\n").unwrap();
+        fs::write(a.path().join(methods), "La;,foo,,V,1\n").unwrap();
+        fs::write(b.path().join(classes), "Lb;,1\nLa;,1\n").unwrap();
+        fs::write(b.path().join(methods), "La;,foo,,V,1\n").unwrap();
+
+        let hash = hash_import_dir(a.path()).expect("hash");
+        assert_eq!(hash, hash_import_dir(b.path()).expect("hash"));
+
+        fs::write(b.path().join(methods), "La;,bar,,V,1\n").unwrap();
+        assert_ne!(hash, hash_import_dir(b.path()).expect("hash"));
+
+        // Moving a row between files is a change too
+        fs::write(b.path().join(classes), "Lb;,1\n").unwrap();
+        fs::write(b.path().join(methods), "La;,foo,,V,1\nLa;,1\n").unwrap();
+        assert_ne!(hash, hash_import_dir(b.path()).expect("hash"));
+    }
+}
@@ -102,11 +102,74 @@ pub trait GraphDatabase: Sync + Send {
         method: &MethodSearch,
         call_source: Option<&str>,
         depth: usize,
+    ) -> Result<Vec<MethodCallPath>> {
+        self.find_callers_with_dispatch(method, call_source, depth, DispatchMode::default())
+    }
+
+    /// Find all callers of the given method resolving calls with the given
+    /// [DispatchMode]
+    ///
+    /// With [DispatchMode::class_hierarchy], a call to a method in a parent
+    /// class or interface is treated as a call to every overriding method, so
+    /// callers that only ever see the interface are found as well. With
+    /// [DispatchMode::binder], callers of a system service method also include
+    /// the `Stub.Proxy` methods transacting with it.
+    fn find_callers_with_dispatch(
+        &self,
+        method: &MethodSearch,
+        call_source: Option<&str>,
+        depth: usize,
+        dispatch: DispatchMode,
     ) -> Result<Vec<MethodCallPath>>;
 
     /// Find all calls leaving the given method up to a given depth.
-    fn find_outgoing_calls(&self, from: &MethodSearch, depth: usize)
-        -> Result<Vec<MethodCallPath>>;
+    fn find_outgoing_calls(
+        &self,
+        from: &MethodSearch,
+        depth: usize,
+    ) -> Result<Vec<MethodCallPath>> {
+        self.find_outgoing_calls_with_dispatch(from, depth, DispatchMode::default())
+    }
+
+    /// Find all calls leaving the given method up to a given depth resolving
+    /// calls with the given [DispatchMode]
+    ///
+    /// With [DispatchMode::class_hierarchy], every method overriding a called
+    /// method is also considered a callee. With [DispatchMode::binder], calls
+    /// to a `Stub.Proxy` method continue into the system service implementing
+    /// it.
+    fn find_outgoing_calls_with_dispatch(
+        &self,
+        from: &MethodSearch,
+        depth: usize,
+        dispatch: DispatchMode,
+    ) -> Result<Vec<MethodCallPath>>;
+
+    /// Find the shortest call paths from `from` to `to`
+    ///
+    /// Every path tied for the shortest length within `max_depth` calls is
+    /// returned. Each path starts with a method matching `from` and ends with
+    /// a method matching `to`, and the sources on the searches restrict which
+    /// methods the path may start and end in. Paths never visit the same
+    /// method twice.
+    fn find_call_paths(
+        &self,
+        from: &MethodSearch,
+        to: &MethodSearch,
+        max_depth: usize,
+    ) -> Result<Vec<MethodCallPath>>;
+
+    /// Find at most `k` call paths from `from` to `to` ordered by length
+    ///
+    /// See [GraphDatabase::find_call_paths] for details on how paths are
+    /// found.
+    fn find_k_shortest_call_paths(
+        &self,
+        from: &MethodSearch,
+        to: &MethodSearch,
+        max_depth: usize,
+        k: usize,
+    ) -> Result<Vec<MethodCallPath>>;
 
     /// Find all classes with the given method
     fn find_classes_with_method(
@@ -142,6 +205,13 @@ pub trait GraphDatabase: Sync + Send {
     /// Get all methods that contain a string matching the provided search params
     fn get_methods_for_string(&self, string: StringSearch) -> Result<Vec<MethodSpec>>;
 
+    /// Get native methods and the libraries implementing them
+    ///
+    /// If a search is given only matching native methods are returned,
+    /// otherwise all native methods are. Methods that haven't been linked to
+    /// a library are included with no implementations.
+    fn get_native_methods(&self, search: Option<&MethodSearch>) -> Result<Vec<NativeMethodSpec>>;
+
     /// Get all classes defined by the given source
     fn get_classes_for(&self, source: &str) -> Result<Vec<ClassName>>;
 
@@ -1,5 +1,5 @@
 use diesel::prelude::*;
-use diesel::{delete, insert_into, update};
+use diesel::{delete, insert_into, insert_or_ignore_into, update};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 
 use super::schema::*;
@@ -52,6 +52,18 @@ pub trait Database: Sync + Sync {
 
     fn app_activity_name_taken(&self, name: &str) -> Result<bool>;
 
+    /// Add a crash if no crash with the same stack hash exists, returning the
+    /// ID of the stored crash either way
+    fn add_crash(&self, crash: &InsertCrash) -> Result<i32>;
+    def_get_multi!(get_crashes, Crash);
+    def_get_one_by!(get_crash_by_id, i32, Crash);
+    /// Add an occurrence of a crash, returning false if the occurrence was
+    /// already stored
+    fn add_crash_occurrence(&self, occurrence: &InsertCrashOccurrence) -> Result<bool>;
+    def_get_multi!(get_crash_occurrences, CrashOccurrence);
+    def_get_multi_by!(get_crash_occurrences_by_crash, i32, CrashOccurrence);
+    fn wipe_crashes(&self) -> Result<()>;
+
     fn get_key_value(&self, key: &str) -> Result<String>;
     fn add_key_value(&self, key: &str, value: &str) -> Result<()>;
     fn update_key_value(&self, key: &str, value: &str) -> Result<()>;
@@ -229,6 +241,44 @@ impl Database for MetaSqliteDatabase {
         })
     }
 
+    fn add_crash(&self, crash: &InsertCrash) -> Result<i32> {
+        self.with_connection(|conn| {
+            query!(insert_or_ignore_into(crashes::table).values(crash)).execute(conn)?;
+            Ok(query!(crashes::table
+                .filter(crashes::stack_hash.eq(crash.stack_hash))
+                .select(crashes::id))
+            .get_result(conn)?)
+        })
+    }
+    impl_get_all!(get_crashes, Crash, crashes);
+    impl_get_one_by!(get_crash_by_id, i32, Crash, crashes, id.eq);
+
+    fn add_crash_occurrence(&self, occurrence: &InsertCrashOccurrence) -> Result<bool> {
+        self.with_connection(|conn| {
+            let count = query!(insert_or_ignore_into(crash_occurrences::table).values(occurrence))
+                .execute(conn)?;
+            Ok(count > 0)
+        })
+    }
+    impl_get_all!(get_crash_occurrences, CrashOccurrence, crash_occurrences);
+    impl_get_multi_by!(
+        get_crash_occurrences_by_crash,
+        i32,
+        CrashOccurrence,
+        crash_occurrences,
+        crash.eq
+    );
+
+    fn wipe_crashes(&self) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.transaction(|txn| {
+                delete(crash_occurrences::table).execute(txn)?;
+                delete(crashes::table).execute(txn)?;
+                Ok(())
+            })
+        })
+    }
+
     fn wipe_app_data(&self) -> Result<()> {
         self.with_connection(|conn| {
             conn.transaction(|txn| {
@@ -270,3 +320,59 @@ impl Database for MetaSqliteDatabase {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+    use std::panic;
+    use std::panic::AssertUnwindSafe;
+
+    use super::super::common::cleanup_database;
+    use crate::testing::{tmp_context, TestContext};
+    use crate::utils::ensure_dir_exists;
+
+    fn get_db_url(context: &dyn Context) -> String {
+        let dir = context.get_sqlite_dir().expect("failed to get sqlite dir");
+        ensure_dir_exists(&dir).expect("failed to make dir");
+        format!(
+            "sqlite://{}",
+            dir.join(META_DATABASE_FILE_NAME).to_string_lossy()
+        )
+    }
+
+    fn db_test(context: &dyn Context, func: impl FnOnce(MetaSqliteDatabase)) {
+        let url = get_db_url(&context);
+        let db = MetaSqliteDatabase::new_from_url(&url).expect("failed to get database");
+        let res = panic::catch_unwind(AssertUnwindSafe(|| func(db)));
+        cleanup_database(&url);
+        match res {
+            Err(e) => panic::resume_unwind(e),
+            _ => {}
+        }
+    }
+
+    #[rstest]
+    fn test_add_crash_dedup(tmp_context: TestContext) {
+        db_test(&tmp_context, |db| {
+            let crash =
+                InsertCrash::new("java", "system_server", "NullPointerException", "abc", "");
+            let id = db.add_crash(&crash).expect("failed to add crash");
+            assert_eq!(db.add_crash(&crash).expect("failed to add crash"), id);
+
+            let first = InsertCrashOccurrence::new(id, "logcat.txt", 10, "raw");
+            assert!(db.add_crash_occurrence(&first).unwrap());
+            assert!(!db.add_crash_occurrence(&first).unwrap());
+            let other = InsertCrashOccurrence::new(id, "other.txt", 10, "raw");
+            assert!(db.add_crash_occurrence(&other).unwrap());
+
+            let crashes = db.get_crashes().expect("failed to get crashes");
+            assert_eq!(crashes.len(), 1);
+            assert_eq!(crashes[0].id, id);
+            let occurrences = db
+                .get_crash_occurrences_by_crash(id)
+                .expect("failed to get occurrences");
+            assert_eq!(occurrences.len(), 2);
+        });
+    }
+}
@@ -10,6 +10,7 @@ use diesel::serialize::{Output, ToSql};
 use diesel::sql_types::Integer;
 use diesel::FromSqlRow;
 use dtu_proc_macro::sql_db_row;
+use serde::Serialize;
 
 use super::schema::*;
 use crate::prereqs::Prereq;
@@ -78,6 +79,40 @@ impl DecompileStatus {
     }
 }
 
+/// A unique crash found in logcat output, deduplicated by its stack hash
+#[sql_db_row]
+#[diesel(table_name = crashes)]
+#[derive(Serialize)]
+pub struct Crash {
+    pub id: i32,
+    /// One of `java`, `system-server`, `anr` or `native`
+    pub kind: String,
+    pub process: String,
+    /// Short description, such as the exception class or signal
+    pub signature: String,
+    pub stack_hash: String,
+    /// The normalized stack, one frame per line
+    pub stack: String,
+}
+
+/// A single time a [Crash] was seen
+#[sql_db_row]
+#[derive(Serialize)]
+pub struct CrashOccurrence {
+    pub id: i32,
+    pub crash: i32,
+    /// The logcat file the crash was found in
+    pub source: String,
+    /// The line in `source` the crash started on
+    pub line: i32,
+    /// The logcat timestamp, if the format had one
+    pub time: Option<String>,
+    /// The last app server command logged before the crash
+    pub command: Option<String>,
+    /// The logcat lines making up the crash
+    pub raw: String,
+}
+
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, PartialEq, AsExpression, FromSqlRow)]
 #[diesel(sql_type = Integer)]
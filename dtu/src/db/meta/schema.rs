@@ -18,6 +18,29 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    crash_occurrences (id) {
+        id -> Integer,
+        crash -> Integer,
+        source -> Text,
+        line -> Integer,
+        time -> Nullable<Text>,
+        command -> Nullable<Text>,
+        raw -> Text,
+    }
+}
+
+diesel::table! {
+    crashes (id) {
+        id -> Integer,
+        kind -> Text,
+        process -> Text,
+        signature -> Text,
+        stack_hash -> Text,
+        stack -> Text,
+    }
+}
+
 diesel::table! {
     decompile_status (id) {
         id -> Integer,
@@ -44,9 +67,13 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(crash_occurrences -> crashes (crash));
+
 diesel::allow_tables_to_appear_in_same_query!(
     app_activities,
     app_permissions,
+    crash_occurrences,
+    crashes,
     decompile_status,
     key_values,
     progress,
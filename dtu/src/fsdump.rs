@@ -14,12 +14,14 @@ use walkdir::WalkDir;
 use crate::command::LineCallback;
 use crate::config::DumpConfig;
 #[cfg(feature = "setup")]
-use crate::db::device::{DatabaseSetupHelper, PackageCallback, ServiceMeta};
+use crate::db::device::{DatabaseSetupHelper, HalServiceMeta, PackageCallback, ServiceMeta};
 
 use crate::devicefs::{FindLimits, FindName, FindType};
 #[cfg(feature = "setup")]
 use crate::utils::open_file;
 use crate::utils::path_must_str;
+#[cfg(feature = "setup")]
+use crate::vintf::parse_vintf_manifest;
 use crate::{
     devicefs::DeviceFSHelper,
     utils::{maybe_link, DevicePath},
@@ -287,6 +289,64 @@ impl DatabaseSetupHelper for FSDumpAccess {
     fn list_packages(&self, on_pkg: &mut PackageCallback) -> crate::Result<()> {
         self.find_apks(on_pkg)
     }
+
+    fn list_hal_services(&self) -> crate::Result<Vec<HalServiceMeta>> {
+        // Without a running device the best we can do is the HALs declared in
+        // the VINTF manifests, there is no way to get the server processes.
+        let mut hals = Vec::new();
+
+        let mut on_found = |filename: &str| -> anyhow::Result<()> {
+            let path = Path::new(filename);
+            let in_vintf_dir = path
+                .parent()
+                .and_then(|it| it.file_name())
+                .map_or(false, |it| it == "vintf" || it == "manifest");
+            let is_matrix = path.file_name().map_or(false, |it| {
+                it.to_string_lossy().contains("compatibility_matrix")
+            });
+            if !in_vintf_dir || is_matrix {
+                return Ok(());
+            }
+
+            log::trace!("Found VINTF manifest: {}", filename);
+            let content = match fs::read_to_string(path) {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("failed to read VINTF manifest {}: {}", filename, e);
+                    return Ok(());
+                }
+            };
+            match parse_vintf_manifest(&content) {
+                Ok(parsed) => {
+                    for hal in parsed {
+                        let meta = HalServiceMeta {
+                            interface: hal.interface,
+                            instance: hal.instance,
+                            format: hal.format,
+                            transport: hal.transport,
+                            server_pid: None,
+                            server_binary: None,
+                        };
+                        if !hals.contains(&meta) {
+                            hals.push(meta);
+                        }
+                    }
+                }
+                Err(e) => log::error!("failed to parse VINTF manifest {}: {}", filename, e),
+            }
+            Ok(())
+        };
+
+        self.find(
+            "/",
+            FindType::File,
+            None,
+            Some(FindName::Suffix(".xml")),
+            &mut on_found,
+        )?;
+
+        Ok(hals)
+    }
 }
 
 #[cfg(test)]
@@ -332,6 +392,27 @@ window                                    u:object_r:window_service:s0
         const PRODUCT_SERVICE_CONTEXTS: &'static str = r#"#line 1 "device/google/sunfish-sepolicy/private/service_contexts"
 qchook                                               u:object_r:qchook_service:s0
 cneservice                                           u:object_r:cne_service:s0"#;
+        const VENDOR_VINTF_MANIFEST: &'static str = r#"<manifest version="1.0" type="device">
+    <hal format="hidl">
+        <name>vendor.foo.bar</name>
+        <transport>hwbinder</transport>
+        <fqname>@1.0::IBar/default</fqname>
+    </hal>
+    <hal format="aidl">
+        <name>android.hardware.power</name>
+        <fqname>IPower/default</fqname>
+    </hal>
+</manifest>"#;
+        const VENDOR_COMPATIBILITY_MATRIX: &'static str = r#"<compatibility-matrix version="1.0" type="device">
+    <hal format="hidl" optional="false">
+        <name>android.hidl.manager</name>
+        <version>1.0</version>
+        <interface>
+            <name>IServiceManager</name>
+            <instance>default</instance>
+        </interface>
+    </hal>
+</compatibility-matrix>"#;
 
         tmp_context
             .create_tree(&[
@@ -360,6 +441,14 @@ cneservice                                           u:object_r:cne_service:s0"#
                     "product/etc/selinux/product_service_contexts",
                     TreeEntry::TxtFile(PRODUCT_SERVICE_CONTEXTS),
                 ),
+                (
+                    "vendor/etc/vintf/manifest.xml",
+                    TreeEntry::TxtFile(VENDOR_VINTF_MANIFEST),
+                ),
+                (
+                    "vendor/etc/vintf/compatibility_matrix.xml",
+                    TreeEntry::TxtFile(VENDOR_COMPATIBILITY_MATRIX),
+                ),
             ])
             .expect("creating base tree");
 
@@ -473,4 +562,32 @@ cneservice                                           u:object_r:cne_service:s0"#
         got.extend(services.into_iter());
         assert_eq!(got, expected);
     }
+
+    #[rstest]
+    fn test_fsdump_list_hal_services(devicefs_context: &TestContext) {
+        let base = devicefs_context.get_base_dir();
+        let fsd = FSDumpAccess::new(base.into(), false);
+
+        let mut hals = fsd.list_hal_services().unwrap();
+        hals.sort_by(|a, b| a.interface.cmp(&b.interface));
+
+        let names = hals
+            .iter()
+            .map(|it| {
+                (
+                    it.interface.as_str(),
+                    it.instance.as_str(),
+                    it.transport.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("android.hardware.power.IPower", "default", "binder"),
+                ("vendor.foo.bar@1.0::IBar", "default", "hwbinder"),
+            ]
+        );
+        assert!(hals.iter().all(|it| it.server_pid.is_none()));
+    }
 }
@@ -0,0 +1,463 @@
+//! Parsing for the init `.rc` files that start native daemons
+//!
+//! Only the parts of the init language needed to build an inventory of
+//! `service` blocks are understood. Actions (`on <trigger>`) are only inspected
+//! for the commands that start services so that each service can be tied to
+//! the triggers that cause it to run.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The user a service runs as when no `user` option is given
+pub const DEFAULT_SERVICE_USER: &'static str = "root";
+
+/// A `socket` option on a service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitSocket {
+    /// Name of the socket, it is created as `/dev/socket/<name>`
+    pub name: String,
+    /// `stream`, `dgram`, or `seqpacket`, possibly with a `+passcred` or
+    /// `+listen` suffix
+    pub socket_type: String,
+    /// Octal permissions of the socket
+    pub perm: String,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub seclabel: Option<String>,
+}
+
+/// A `service` block from an init `.rc` file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitService {
+    pub name: String,
+    /// Path to the binary being executed
+    pub path: String,
+    pub args: Vec<String>,
+    /// The user the service runs as, [DEFAULT_SERVICE_USER] if not set
+    pub user: String,
+    /// The primary group followed by any supplementary groups
+    pub groups: Vec<String>,
+    /// Capabilities the service keeps
+    ///
+    /// `None` if the option wasn't present, in which case a service running
+    /// as root keeps all capabilities. An empty list means all capabilities
+    /// are dropped.
+    pub capabilities: Option<Vec<String>>,
+    pub seclabel: Option<String>,
+    pub classes: Vec<String>,
+    pub disabled: bool,
+    pub oneshot: bool,
+    pub sockets: Vec<InitSocket>,
+    /// The `on` triggers of all actions that start this service, either
+    /// directly or by starting one of its classes
+    pub triggers: Vec<String>,
+    /// The `.rc` file the service was defined in
+    pub rc_file: String,
+}
+
+impl InitService {
+    fn new(name: &str, path: &str, args: Vec<String>, rc_file: &str) -> Self {
+        Self {
+            name: String::from(name),
+            path: String::from(path),
+            args,
+            user: String::from(DEFAULT_SERVICE_USER),
+            groups: Vec::new(),
+            capabilities: None,
+            seclabel: None,
+            // Services without a class are in the default class
+            classes: vec![String::from("default")],
+            disabled: false,
+            oneshot: false,
+            sockets: Vec::new(),
+            triggers: Vec::new(),
+            rc_file: String::from(rc_file),
+        }
+    }
+
+    pub fn runs_as_root(&self) -> bool {
+        self.user == "root" || self.user == "0"
+    }
+
+    fn add_trigger(&mut self, trigger: &str) {
+        if !self.triggers.iter().any(|it| it == trigger) {
+            self.triggers.push(String::from(trigger));
+        }
+    }
+
+    fn apply_option(&mut self, option: &str, args: &[String]) {
+        match option {
+            "user" => {
+                if let Some(user) = args.first() {
+                    self.user = user.clone();
+                }
+            }
+            "group" => self.groups = args.to_vec(),
+            "capabilities" => self.capabilities = Some(args.to_vec()),
+            "seclabel" => self.seclabel = args.first().cloned(),
+            "class" => self.classes = args.to_vec(),
+            "disabled" => self.disabled = true,
+            "oneshot" => self.oneshot = true,
+            "socket" => {
+                if args.len() < 3 {
+                    log::warn!("invalid socket option for {}: {:?}", self.name, args);
+                    return;
+                }
+                self.sockets.push(InitSocket {
+                    name: args[0].clone(),
+                    socket_type: args[1].clone(),
+                    perm: args[2].clone(),
+                    user: args.get(3).cloned(),
+                    group: args.get(4).cloned(),
+                    seclabel: args.get(5).cloned(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An `on <trigger>` action, only the commands that start services are kept
+struct InitAction {
+    trigger: String,
+    /// Names of the services started by `start`, `restart`, or `enable`
+    starts: Vec<String>,
+    /// Classes started by `class_start`
+    class_starts: Vec<String>,
+}
+
+enum Section {
+    None,
+    Service(usize),
+    Action(usize),
+}
+
+/// Parser for a set of init `.rc` files
+///
+/// Triggers are resolved across all parsed files, so every file should be
+/// given to [InitRcParser::parse] before calling [InitRcParser::finish].
+pub struct InitRcParser {
+    services: Vec<InitService>,
+    /// Whether the service at the same index used the `override` option
+    overrides: Vec<bool>,
+    actions: Vec<InitAction>,
+}
+
+impl Default for InitRcParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InitRcParser {
+    pub fn new() -> Self {
+        Self {
+            services: Vec::new(),
+            overrides: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Parse the contents of an `.rc` file, `rc_file` is only used to record
+    /// where each service was defined
+    pub fn parse(&mut self, content: &str, rc_file: &str) {
+        let mut section = Section::None;
+
+        for line in logical_lines(content) {
+            let tokens = tokenize(&line);
+            let (keyword, args) = match tokens.split_first() {
+                Some(v) => v,
+                None => continue,
+            };
+
+            match keyword.as_str() {
+                "service" => {
+                    section = self.start_service(args, rc_file);
+                    continue;
+                }
+                "on" => {
+                    self.actions.push(InitAction {
+                        trigger: args.join(" "),
+                        starts: Vec::new(),
+                        class_starts: Vec::new(),
+                    });
+                    section = Section::Action(self.actions.len() - 1);
+                    continue;
+                }
+                "import" | "subsystem" => {
+                    section = Section::None;
+                    continue;
+                }
+                _ => {}
+            }
+
+            match section {
+                Section::None => {}
+                Section::Service(idx) if keyword == "override" => self.overrides[idx] = true,
+                Section::Service(idx) => self.services[idx].apply_option(keyword, args),
+                Section::Action(idx) => {
+                    let action = &mut self.actions[idx];
+                    match (keyword.as_str(), args.first()) {
+                        ("start" | "restart" | "enable", Some(name)) => {
+                            action.starts.push(name.clone())
+                        }
+                        ("class_start", Some(class)) => action.class_starts.push(class.clone()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn start_service(&mut self, args: &[String], rc_file: &str) -> Section {
+        if args.len() < 2 {
+            log::warn!("invalid service definition in {}: {:?}", rc_file, args);
+            return Section::None;
+        }
+        self.services.push(InitService::new(
+            &args[0],
+            &args[1],
+            args[2..].to_vec(),
+            rc_file,
+        ));
+        self.overrides.push(false);
+        Section::Service(self.services.len() - 1)
+    }
+
+    /// Resolve the triggers for every service and return them
+    pub fn finish(self) -> Vec<InitService> {
+        let mut services = dedup_services(self.services, &self.overrides);
+
+        for action in self.actions.iter() {
+            for svc in services.iter_mut() {
+                let started = action.starts.iter().any(|it| *it == svc.name)
+                    || (!svc.disabled
+                        && action
+                            .class_starts
+                            .iter()
+                            .any(|class| svc.classes.contains(class)));
+                if started {
+                    svc.add_trigger(&action.trigger);
+                }
+            }
+        }
+
+        services
+    }
+}
+
+/// Parse a single `.rc` file into its services
+pub fn parse_init_rc(content: &str, rc_file: &str) -> Vec<InitService> {
+    let mut parser = InitRcParser::new();
+    parser.parse(content, rc_file);
+    parser.finish()
+}
+
+/// Collapse services with the same name into the first definition unless a
+/// later one uses the `override` option, matching init's behavior
+fn dedup_services(services: Vec<InitService>, overrides: &[bool]) -> Vec<InitService> {
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    let mut out: Vec<InitService> = Vec::with_capacity(services.len());
+    for (svc, is_override) in services.into_iter().zip(overrides.iter()) {
+        match by_name.get(&svc.name) {
+            Some(&idx) => {
+                if *is_override {
+                    out[idx] = svc;
+                } else {
+                    log::debug!("ignoring duplicate service {} in {}", svc.name, svc.rc_file);
+                }
+            }
+            None => {
+                by_name.insert(svc.name.clone(), out.len());
+                out.push(svc);
+            }
+        }
+    }
+    out
+}
+
+/// Split the content into logical lines, joining lines ending with `\` and
+/// dropping comments
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for raw in content.lines() {
+        let line = raw.trim();
+        if current.is_empty() && line.starts_with('#') {
+            continue;
+        }
+        match line.strip_suffix('\\') {
+            Some(cont) => {
+                current.push_str(cont);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Split a logical line into tokens, handling double quotes and trailing
+/// comments
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_token = true;
+            }
+            '#' if !in_quotes && !in_token => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const VENDOR_RC: &'static str = r#"
+# A vendor daemon
+service vendor.thing /vendor/bin/hw/thingd --verbose \
+        --mode "fast path"
+    class hal
+    user root
+    group system inet # trailing comment
+    capabilities NET_ADMIN NET_RAW
+    seclabel u:r:vendor_thing:s0
+    socket thing stream 0666 root system
+    socket thing_dgram dgram+passcred 0660 system system u:object_r:thing_socket:s0
+
+service vendor.lazy /vendor/bin/lazyd
+    class hal
+    user nobody
+    capabilities
+    disabled
+    oneshot
+
+on property:vendor.lazy.enable=1
+    start vendor.lazy
+"#;
+
+    const SYSTEM_RC: &'static str = r#"
+import /system/etc/init/other.rc
+
+on late-init && property:ro.debuggable=1
+    class_start hal
+
+service vendor.lazy /system/bin/not_used
+    user root
+
+service vendor.thing /system/bin/overridden
+    override
+    class hal
+"#;
+
+    #[test]
+    fn test_parse_init_rc() {
+        let services = parse_init_rc(VENDOR_RC, "/vendor/etc/init/thing.rc");
+        assert_eq!(services.len(), 2);
+
+        let thing = &services[0];
+        assert_eq!(thing.name, "vendor.thing");
+        assert_eq!(thing.path, "/vendor/bin/hw/thingd");
+        assert_eq!(thing.args, vec!["--verbose", "--mode", "fast path"]);
+        assert_eq!(thing.user, "root");
+        assert!(thing.runs_as_root());
+        assert_eq!(thing.groups, vec!["system", "inet"]);
+        assert_eq!(
+            thing.capabilities,
+            Some(vec![String::from("NET_ADMIN"), String::from("NET_RAW")])
+        );
+        assert_eq!(thing.seclabel.as_deref(), Some("u:r:vendor_thing:s0"));
+        assert_eq!(thing.classes, vec!["hal"]);
+        assert!(!thing.disabled);
+        assert_eq!(thing.rc_file, "/vendor/etc/init/thing.rc");
+        assert_eq!(
+            thing.sockets,
+            vec![
+                InitSocket {
+                    name: String::from("thing"),
+                    socket_type: String::from("stream"),
+                    perm: String::from("0666"),
+                    user: Some(String::from("root")),
+                    group: Some(String::from("system")),
+                    seclabel: None,
+                },
+                InitSocket {
+                    name: String::from("thing_dgram"),
+                    socket_type: String::from("dgram+passcred"),
+                    perm: String::from("0660"),
+                    user: Some(String::from("system")),
+                    group: Some(String::from("system")),
+                    seclabel: Some(String::from("u:object_r:thing_socket:s0")),
+                },
+            ]
+        );
+        assert!(thing.triggers.is_empty());
+
+        let lazy = &services[1];
+        assert_eq!(lazy.user, "nobody");
+        assert!(!lazy.runs_as_root());
+        assert_eq!(lazy.capabilities, Some(vec![]));
+        assert!(lazy.disabled);
+        assert!(lazy.oneshot);
+        assert_eq!(lazy.triggers, vec!["property:vendor.lazy.enable=1"]);
+    }
+
+    #[test]
+    fn test_triggers_across_files() {
+        let mut parser = InitRcParser::new();
+        parser.parse(VENDOR_RC, "/vendor/etc/init/thing.rc");
+        parser.parse(SYSTEM_RC, "/system/etc/init/thing.rc");
+        let services = parser.finish();
+        assert_eq!(services.len(), 2);
+
+        let thing = &services[0];
+        assert_eq!(thing.path, "/system/bin/overridden");
+        assert_eq!(
+            thing.triggers,
+            vec!["late-init && property:ro.debuggable=1"]
+        );
+
+        // Disabled services aren't started by class_start and the
+        // redefinition without override is ignored
+        let lazy = &services[1];
+        assert_eq!(lazy.path, "/vendor/bin/lazyd");
+        assert_eq!(lazy.triggers, vec!["property:vendor.lazy.enable=1"]);
+    }
+}
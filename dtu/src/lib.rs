@@ -1,6 +1,12 @@
 pub mod manifest;
 pub use manifest::Manifest;
 
+pub mod init_rc;
+
+pub mod vintf;
+
+pub mod prop_audit;
+
 pub mod devicefs;
 
 pub mod fsdump;
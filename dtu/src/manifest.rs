@@ -576,6 +576,9 @@ pub struct IntentFilter {
 
     #[serde(default = "Vec::new")]
     data: Vec<Data>,
+
+    #[serde(rename = "@priority")]
+    priority: Option<String>,
 }
 
 impl IntentFilter {
@@ -589,6 +592,83 @@ impl IntentFilter {
     pub fn get_data(&self) -> &[Data] {
         self.data.as_slice()
     }
+
+    /// Retrieve the priority of the filter, this is 0 if it is missing or invalid
+    pub fn priority(&self, resolver: &dyn ManifestResolver) -> i32 {
+        self.priority
+            .as_ref()
+            .and_then(|it| resolver.resolve_string(it).parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+/// A `<path-permission>` on a `<provider>`
+#[derive(Deserialize)]
+pub struct PathPermission {
+    #[serde(rename = "@path")]
+    path: Option<String>,
+    #[serde(rename = "@pathPrefix")]
+    path_prefix: Option<String>,
+    #[serde(rename = "@pathPattern")]
+    path_pattern: Option<String>,
+    #[serde(rename = "@pathAdvancedPattern")]
+    path_advanced_pattern: Option<String>,
+    #[serde(rename = "@pathSuffix")]
+    path_suffix: Option<String>,
+    #[serde(rename = "@permission")]
+    permission: Option<String>,
+    #[serde(rename = "@readPermission")]
+    read_permission: Option<String>,
+    #[serde(rename = "@writePermission")]
+    write_permission: Option<String>,
+}
+
+impl PathPermission {
+    maybe_cow_getter!(path);
+    maybe_cow_getter!(path_prefix);
+    maybe_cow_getter!(path_pattern);
+    maybe_cow_getter!(path_advanced_pattern);
+    maybe_cow_getter!(path_suffix);
+    maybe_cow_getter!(permission);
+
+    /// Retrieve the read permission, falling back to `android:permission`
+    pub fn read_permission<'s>(&'s self, resolver: &dyn ManifestResolver) -> Option<Cow<'s, str>> {
+        match self.read_permission {
+            None => self.permission(resolver),
+            Some(ref v) => Some(resolver.resolve_string(v)),
+        }
+    }
+
+    /// Retrieve the write permission, falling back to `android:permission`
+    pub fn write_permission<'s>(&'s self, resolver: &dyn ManifestResolver) -> Option<Cow<'s, str>> {
+        match self.write_permission {
+            None => self.permission(resolver),
+            Some(ref v) => Some(resolver.resolve_string(v)),
+        }
+    }
+}
+
+/// A `<grant-uri-permission>` on a `<provider>`
+#[derive(Deserialize)]
+pub struct GrantUriPermission {
+    #[serde(rename = "@path")]
+    path: Option<String>,
+    #[serde(rename = "@pathPrefix")]
+    path_prefix: Option<String>,
+    #[serde(rename = "@pathPattern")]
+    path_pattern: Option<String>,
+    #[serde(rename = "@pathAdvancedPattern")]
+    path_advanced_pattern: Option<String>,
+    #[serde(rename = "@pathSuffix")]
+    path_suffix: Option<String>,
+}
+
+impl GrantUriPermission {
+    maybe_cow_getter!(path);
+    maybe_cow_getter!(path_prefix);
+    maybe_cow_getter!(path_pattern);
+    maybe_cow_getter!(path_advanced_pattern);
+    maybe_cow_getter!(path_suffix);
 }
 
 pub trait IPC {
@@ -683,8 +763,14 @@ def_ipc!(Provider {
     #[serde(rename = "@writePermission")]
     write_permission: Option<String>,
 
-    #[serde(rename = "grantUriPermissions")]
+    #[serde(rename = "@grantUriPermissions")]
     grant_uri_permissions: Option<String>,
+
+    #[serde(rename = "path-permission", default = "Vec::new")]
+    pub path_permissions: Vec<PathPermission>,
+
+    #[serde(rename = "grant-uri-permission", default = "Vec::new")]
+    pub grant_uri_patterns: Vec<GrantUriPermission>,
 });
 
 impl Provider {
@@ -829,6 +915,8 @@ where
 pub struct Manifest {
     #[serde(rename = "@package")]
     package: String,
+    #[serde(rename = "@sharedUserId")]
+    shared_user_id: Option<String>,
     #[serde(rename = "uses-permission", default = "Vec::new")]
     pub uses_permissions: Vec<UsesPermission>,
     #[serde(rename = "permission", default = "Vec::new")]
@@ -904,6 +992,13 @@ impl Manifest {
         resolver.resolve_string(&self.package)
     }
 
+    /// Get the `android:sharedUserId` this package runs under, if any
+    pub fn shared_user_id<'s>(&'s self, resolver: &dyn ManifestResolver) -> Option<Cow<'s, str>> {
+        self.shared_user_id
+            .as_ref()
+            .map(|it| resolver.resolve_string(it))
+    }
+
     pub fn debuggable(&self, resolver: &dyn ManifestResolver) -> Option<bool> {
         match &self.application.debuggable {
             None => Some(false),
@@ -932,7 +1027,8 @@ mod test {
         let as_str = r#"<?xml version="1.0" encoding="utf-8"?>
 <manifest xmlns:android="http://schemas.android.com/apk/res/android"
     xmlns:tools="http://schemas.android.com/tools"
-    package="t.s.t">
+    package="t.s.t"
+    android:sharedUserId="android.uid.system">
 
     <uses-permission android:name="android.permission.INTERNET" />
     <uses-permission android:name="android.permission.ACCESS_COARSE_LOCATION" />
@@ -968,7 +1064,7 @@ mod test {
         <receiver
             android:name=".MyReceiver2"
             android:enabled="true">
-            <intent-filter>
+            <intent-filter android:priority="10">
                 <action android:name="t.s.t.RECEIVER2_ACTION" />
                 <category android:name="t.s.t.RECEIVER2_CATEGORY" />
                 <data
@@ -991,6 +1087,18 @@ mod test {
         <receiver
             android:name=".MyReceiver3" />
 
+        <provider
+            android:name=".MyProvider"
+            android:authorities="t.s.t.provider"
+            android:exported="true"
+            android:grantUriPermissions="false"
+            android:permission="t.s.t.PERMISSIONB">
+            <path-permission
+                android:pathPrefix="/public"
+                android:readPermission="t.s.t.PERMISSIONA" />
+            <grant-uri-permission android:pathPattern="/shared/.*" />
+        </provider>
+
     </application>
 
 </manifest>
@@ -1000,6 +1108,7 @@ mod test {
         let resolve = NoopManifestResolver::default();
 
         assert_eq!(man.package, "t.s.t");
+        assert_eq!(man.shared_user_id, Some("android.uid.system".into()));
         assert_eq!(man.application.allow_backup, Some("true".into()));
         assert_eq!(
             man.uses_permissions,
@@ -1029,6 +1138,20 @@ mod test {
             Some(true),
             "intent-filter should make exported default to true"
         );
+        let filter = &rcvers[0].intent_filters[0];
+        assert_eq!(filter.priority(&resolve), 10);
+        assert_eq!(
+            filter.get_actions()[0].name(&resolve),
+            "t.s.t.RECEIVER2_ACTION"
+        );
+        assert_eq!(
+            filter.get_data()[0].scheme(&resolve).as_deref(),
+            Some("neato")
+        );
+        assert_eq!(
+            filter.get_data()[0].host(&resolve).as_deref(),
+            Some("t.s.t")
+        );
         assert_eq!(
             rcvers[1].exported(&resolve),
             Some(true),
@@ -1039,6 +1162,20 @@ mod test {
             Some(false),
             "default value for export"
         );
+
+        let prov = &man.get_providers()[0];
+        assert_eq!(prov.grant_uri_permissions(&resolve), Some(false));
+        let path_perm = &prov.path_permissions[0];
+        assert_eq!(path_perm.path_prefix(&resolve).as_deref(), Some("/public"));
+        assert_eq!(
+            path_perm.read_permission(&resolve).as_deref(),
+            Some("t.s.t.PERMISSIONA")
+        );
+        assert_eq!(path_perm.write_permission(&resolve), None);
+        assert_eq!(
+            prov.grant_uri_patterns[0].path_pattern(&resolve).as_deref(),
+            Some("/shared/.*")
+        );
     }
 
     macro_rules! resource_test {
@@ -0,0 +1,485 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::PropertyAuditConfig;
+use crate::Context;
+
+#[cfg(feature = "sql")]
+use std::collections::HashMap;
+
+#[cfg(feature = "sql")]
+use crate::db::device::models::{
+    DeviceProperty, DiffedDeviceProperty, InsertPropertyFinding, PropertyFinding,
+};
+#[cfg(feature = "sql")]
+use crate::db::DeviceDatabase;
+#[cfg(feature = "sql")]
+use crate::UnknownBool;
+
+/// The rules shipped with dtu
+const DEFAULT_RULES: &str = include_str!("../rules/property_audit.toml");
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "info" => Self::Info,
+            "low" => Self::Low,
+            "medium" => Self::Medium,
+            "high" => Self::High,
+            _ => return Err("valid values are 'info', 'low', 'medium', and 'high'"),
+        })
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A single property audit rule
+///
+/// The rule only applies when the property is set, and every condition that
+/// is given has to match.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct PropertyRule {
+    pub id: String,
+    pub property: String,
+    #[serde(default)]
+    pub severity: Severity,
+    pub description: String,
+    /// The value is exactly this
+    pub equals: Option<String>,
+    /// The value is anything but this
+    pub not_equals: Option<String>,
+    /// The value contains this
+    pub contains: Option<String>,
+    /// The value is a `YYYY-MM-DD` date more than this many days ago
+    pub older_than_days: Option<u32>,
+}
+
+impl PropertyRule {
+    fn has_condition(&self) -> bool {
+        self.equals.is_some()
+            || self.not_equals.is_some()
+            || self.contains.is_some()
+            || self.older_than_days.is_some()
+    }
+
+    /// Whether the rule flags the given value
+    pub fn matches(&self, value: &str) -> bool {
+        self.matches_on(value, days_since_epoch())
+    }
+
+    /// Whether the rule flags the given value, with `today` given in days
+    /// since the Unix epoch
+    pub fn matches_on(&self, value: &str, today: i64) -> bool {
+        if self.equals.as_ref().is_some_and(|it| it != value) {
+            return false;
+        }
+        if self.not_equals.as_ref().is_some_and(|it| it == value) {
+            return false;
+        }
+        if self
+            .contains
+            .as_ref()
+            .is_some_and(|it| !value.contains(it.as_str()))
+        {
+            return false;
+        }
+        if let Some(days) = self.older_than_days {
+            match parse_date(value) {
+                Some(date) if today - date > i64::from(days) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+    #[serde(rename = "rule", default)]
+    rules: Vec<PropertyRule>,
+}
+
+/// A set of [PropertyRule]s
+#[derive(Clone, Debug)]
+pub struct PropertyRules {
+    rules: Vec<PropertyRule>,
+}
+
+impl PropertyRules {
+    /// Parse rules from a TOML file with a `[[rule]]` table per rule
+    pub fn parse(content: &str) -> crate::Result<Self> {
+        let file: RuleFile = toml::from_str(content)
+            .map_err(|e| crate::Error::Generic(format!("invalid property rules: {}", e)))?;
+        Self::new(file.rules)
+    }
+
+    pub fn new(rules: Vec<PropertyRule>) -> crate::Result<Self> {
+        let mut ids = HashSet::new();
+        for rule in rules.iter() {
+            if !ids.insert(rule.id.as_str()) {
+                return Err(crate::Error::Generic(format!(
+                    "duplicate property rule {}",
+                    rule.id
+                )));
+            }
+            if !rule.has_condition() {
+                return Err(crate::Error::Generic(format!(
+                    "property rule {} has no conditions",
+                    rule.id
+                )));
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    /// The rules shipped with dtu
+    pub fn default_rules() -> Self {
+        Self::parse(DEFAULT_RULES).expect("default property rules should be valid")
+    }
+
+    /// Apply the `[property-audit]` section of the project configuration to
+    /// the default rules
+    ///
+    /// Rules in the configuration replace default rules with the same id.
+    pub fn from_config(cfg: &PropertyAuditConfig) -> crate::Result<Self> {
+        let mut rules = if cfg.no_default_rules {
+            Vec::new()
+        } else {
+            Self::default_rules().rules
+        };
+        rules.retain(|it| {
+            !cfg.disable.contains(&it.id) && !cfg.rules.iter().any(|rule| rule.id == it.id)
+        });
+        rules.extend(cfg.rules.iter().cloned());
+        Self::new(rules)
+    }
+
+    /// Get the rules for the current project
+    pub fn for_project(ctx: &dyn Context) -> crate::Result<Self> {
+        Self::from_config(&ctx.get_project_config()?.property_audit)
+    }
+
+    pub fn rules(&self) -> &[PropertyRule] {
+        &self.rules
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PropertyRule> {
+        self.rules.iter().find(|it| it.id == id)
+    }
+
+    /// Get all rules that flag the given property
+    pub fn matching<'a>(
+        &'a self,
+        name: &'a str,
+        value: &'a str,
+    ) -> impl Iterator<Item = &'a PropertyRule> + 'a {
+        let today = days_since_epoch();
+        self.rules
+            .iter()
+            .filter(move |it| it.property == name && it.matches_on(value, today))
+    }
+}
+
+/// Check every device property against the rules and store the findings in
+/// the device database, replacing any previous findings
+#[cfg(feature = "sql")]
+pub fn audit_device_properties(
+    db: &DeviceDatabase,
+    rules: &PropertyRules,
+) -> crate::Result<Vec<PropertyFinding>> {
+    let props = db.get_device_properties()?;
+    let mut findings = Vec::new();
+    for prop in props.iter() {
+        for rule in rules.matching(&prop.name, &prop.value) {
+            findings.push(InsertPropertyFinding::new(
+                &rule.id,
+                prop.id,
+                rule.severity.as_str(),
+                &rule.description,
+            ));
+        }
+    }
+    db.replace_property_findings(&findings)?;
+    Ok(db.get_property_findings()?)
+}
+
+/// A [PropertyFinding] compared against a diff source
+#[cfg(feature = "sql")]
+#[derive(Serialize)]
+pub struct ComparedPropertyFinding {
+    pub finding: PropertyFinding,
+    pub property: DeviceProperty,
+    /// The property diffed against the diff source, None if it hasn't been
+    pub diff: Option<DiffedDeviceProperty>,
+    /// Whether the rule also flags the diff source's value. This is unknown if
+    /// the property hasn't been diffed or the rule no longer exists.
+    pub in_diff: UnknownBool,
+}
+
+#[cfg(feature = "sql")]
+impl ComparedPropertyFinding {
+    /// The property's value in the diff source, if it is set there
+    pub fn diff_value(&self) -> Option<&str> {
+        self.diff.as_ref()?.diff_value.as_deref()
+    }
+}
+
+/// Compare the stored findings against the values in the given diff source
+///
+/// Without a diff source the findings are returned without any diff.
+#[cfg(feature = "sql")]
+pub fn compare_property_findings(
+    db: &DeviceDatabase,
+    rules: &PropertyRules,
+    diff_source: Option<i32>,
+) -> crate::Result<Vec<ComparedPropertyFinding>> {
+    let props = db
+        .get_device_properties()?
+        .into_iter()
+        .map(|it| (it.id, it))
+        .collect::<HashMap<i32, DeviceProperty>>();
+    let diffs = match diff_source {
+        Some(id) => db
+            .get_device_property_diffs_by_diff_id(id)?
+            .into_iter()
+            .map(|it| (it.property.id, it))
+            .collect::<HashMap<i32, DiffedDeviceProperty>>(),
+        None => HashMap::new(),
+    };
+
+    let mut compared = Vec::new();
+    for finding in db.get_property_findings()? {
+        let Some(property) = props.get(&finding.device_property).cloned() else {
+            continue;
+        };
+        let diff = diffs.get(&finding.device_property).cloned();
+        let mut it = ComparedPropertyFinding {
+            finding,
+            property,
+            diff,
+            in_diff: UnknownBool::Unknown,
+        };
+        if let (Some(rule), Some(_)) = (rules.get(&it.finding.rule), it.diff.as_ref()) {
+            it.in_diff = UnknownBool::from(it.diff_value().is_some_and(|v| rule.matches(v)));
+        }
+        compared.push(it);
+    }
+    Ok(compared)
+}
+
+/// Parse a `YYYY-MM-DD` date into days since the Unix epoch
+fn parse_date(value: &str) -> Option<i64> {
+    let mut split = value.trim().splitn(3, '-');
+    let year = split.next()?;
+    // Longer years aren't real dates and would overflow below
+    if year.len() > 4 {
+        return None;
+    }
+    let year = year.parse::<i64>().ok()?;
+    let month = split.next()?.parse::<i64>().ok()?;
+    let day = split.next()?.parse::<i64>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146097 + doe - 719468)
+}
+
+fn days_since_epoch() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |it| (it.as_secs() / 86400) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(id: &str) -> PropertyRule {
+        PropertyRules::default_rules()
+            .get(id)
+            .expect("default rule should exist")
+            .clone()
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11017));
+        assert_eq!(parse_date("2024-02-29"), Some(19782));
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("unknown"), None);
+        assert_eq!(parse_date("99999999999999999-01-01"), None);
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = PropertyRules::default_rules();
+        assert!(!rules.rules().is_empty());
+
+        assert!(rule("debuggable").matches("1"));
+        assert!(!rule("debuggable").matches("0"));
+        assert_eq!(rule("debuggable").severity, Severity::High);
+
+        assert!(rule("verified-boot-not-green").matches("orange"));
+        assert!(!rule("verified-boot-not-green").matches("green"));
+
+        assert!(rule("test-keys").matches("release-keys,test-keys"));
+        assert!(!rule("test-keys").matches("release-keys"));
+
+        let today = parse_date("2026-10-17").unwrap();
+        let patch = rule("old-security-patch");
+        assert!(patch.matches_on("2019-01-05", today));
+        assert!(!patch.matches_on("2026-09-05", today));
+        assert!(!patch.matches_on("", today));
+
+        let matching = rules
+            .matching("ro.build.tags", "test-keys")
+            .map(|it| it.id.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(matching, vec!["test-keys"]);
+    }
+
+    #[test]
+    fn test_rules_from_config() {
+        let raw_config = r#"
+[property-audit]
+disable = ["debuggable"]
+
+[[property-audit.rule]]
+id = "test-keys"
+property = "ro.build.tags"
+contains = "test-keys"
+severity = "info"
+description = "Expected for this device"
+
+[[property-audit.rule]]
+id = "vendor-debug"
+property = "persist.vendor.debug"
+equals = "1"
+description = "Vendor debug mode is enabled"
+"#;
+        let config: crate::config::ProjectConfig =
+            toml::from_str(raw_config).expect("parse config");
+        let rules = PropertyRules::from_config(&config.property_audit).unwrap();
+
+        assert!(rules.get("debuggable").is_none());
+        assert!(rules.get("not-secure").is_some());
+        assert_eq!(rules.get("test-keys").unwrap().severity, Severity::Info);
+        let vendor = rules.get("vendor-debug").unwrap();
+        assert_eq!(vendor.severity, Severity::Medium);
+        assert!(vendor.matches("1"));
+
+        let missing = PropertyRules::parse(
+            r#"
+[[rule]]
+id = "nothing"
+property = "ro.debuggable"
+description = "No conditions"
+"#,
+        );
+        assert!(missing.is_err());
+    }
+
+    #[cfg(feature = "sql")]
+    #[test]
+    fn test_audit_device_properties() {
+        use crate::db::device::models::InsertDevicePropertyDiff;
+        use crate::utils::path_must_str;
+
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("device.db");
+        let db = DeviceDatabase::new_from_path(path_must_str(&path)).unwrap();
+
+        let rules = PropertyRules::default_rules();
+        let findings = audit_device_properties(&db, &rules).unwrap();
+        let mut flagged = findings
+            .iter()
+            .map(|it| it.rule.as_str())
+            .collect::<Vec<&str>>();
+        flagged.sort();
+        assert_eq!(
+            flagged,
+            vec!["debuggable", "old-security-patch", "test-keys"]
+        );
+
+        // Running it again replaces the old findings
+        assert_eq!(audit_device_properties(&db, &rules).unwrap().len(), 3);
+
+        let emulator = db.get_diff_source_by_name("emulator").unwrap();
+        // ro.debuggable is the same in the diff source, ro.build.tags isn't
+        db.add_device_property_diff(&InsertDevicePropertyDiff {
+            device_property: 0,
+            diff_source: emulator.id,
+            exists_in_diff: true,
+            value_matches_diff: true,
+            diff_value: Some("1"),
+        })
+        .unwrap();
+        db.add_device_property_diff(&InsertDevicePropertyDiff {
+            device_property: 2,
+            diff_source: emulator.id,
+            exists_in_diff: true,
+            value_matches_diff: false,
+            diff_value: Some("release-keys"),
+        })
+        .unwrap();
+
+        let compared = compare_property_findings(&db, &rules, Some(emulator.id)).unwrap();
+        let get = |rule: &str| {
+            compared
+                .iter()
+                .find(|it| it.finding.rule == rule)
+                .expect("finding should exist")
+        };
+        assert_eq!(get("debuggable").in_diff, UnknownBool::True);
+        let keys = get("test-keys");
+        assert_eq!(keys.in_diff, UnknownBool::False);
+        assert_eq!(keys.diff_value(), Some("release-keys"));
+        assert_eq!(get("old-security-patch").in_diff, UnknownBool::Unknown);
+
+        let compared = compare_property_findings(&db, &rules, None).unwrap();
+        assert_eq!(compared.len(), 3);
+        assert!(compared
+            .iter()
+            .all(|it| it.diff.is_none() && it.in_diff == UnknownBool::Unknown));
+    }
+}
@@ -0,0 +1,688 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::ErrorKind;
+use std::str::FromStr;
+use std::time::Duration;
+
+use dtu_proc_macro::wraps_base_error;
+use serde::Serialize;
+
+use crate::app_server::server::{self, AppServer, BinderReply, ConnectError, TcpAppServer};
+use crate::app_server::{decode_reply, MethodArgError, ParamType, ParcelString, ParcelStringElem};
+use crate::db::device::db::DeviceDatabase;
+use crate::db::device::models::{
+    InsertBinderFuzzResult, InsertFuzzResult, SystemService, SystemServiceMethod,
+};
+use crate::db::Error;
+use crate::tasks::{EventMonitor, TaskCancelCheck};
+use crate::utils::ClassName;
+use crate::Context;
+
+/// Default time to wait for a single call
+pub const DEFAULT_BINDER_FUZZ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of inputs sent to each method
+pub const DEFAULT_BINDER_FUZZ_MAX_CASES: usize = 64;
+
+/// Events fired by the BinderFuzzTask
+pub enum BinderFuzzEvent {
+    MethodStarted {
+        service: String,
+        method: String,
+        /// Number of inputs that will be sent
+        cases: usize,
+    },
+    MethodSkipped {
+        service: String,
+        method: String,
+        reason: String,
+    },
+    CaseDone {
+        description: String,
+        outcome: FuzzOutcome,
+        exception: Option<String>,
+    },
+    MethodEnded {
+        service: String,
+        method: String,
+    },
+}
+
+type Evt = BinderFuzzEvent;
+
+#[wraps_base_error]
+#[derive(Debug, thiserror::Error)]
+pub enum BinderFuzzError {
+    #[error("user cancelled")]
+    Cancelled,
+    #[error("database error {0}")]
+    DB(Error),
+    #[error("{service} {method} appears to have crashed the device")]
+    Crashed { service: String, method: String },
+}
+
+impl From<Error> for BinderFuzzError {
+    fn from(value: Error) -> Self {
+        Self::DB(value)
+    }
+}
+
+pub type BinderFuzzTaskResult<T> = Result<T, BinderFuzzError>;
+
+/// What happened when an input was sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FuzzOutcome {
+    Ok,
+    Exception,
+    SecurityException,
+    Timeout,
+    /// The service or the app server died
+    Crash,
+    /// The call couldn't be made, usually because the app couldn't build the
+    /// Parcel
+    Error,
+}
+
+impl FuzzOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Exception => "exception",
+            Self::SecurityException => "security-exception",
+            Self::Timeout => "timeout",
+            Self::Crash => "crash",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl FromStr for FuzzOutcome {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ok" => Self::Ok,
+            "exception" => Self::Exception,
+            "security-exception" => Self::SecurityException,
+            "timeout" => Self::Timeout,
+            "crash" => Self::Crash,
+            "error" => Self::Error,
+            _ => return Err("invalid fuzz outcome"),
+        })
+    }
+}
+
+impl Display for FuzzOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single input for a system service method
+pub struct FuzzCase {
+    /// What the input is testing, such as `arg 1 (String): long string`
+    pub description: String,
+    pub parcel: ParcelString<'static>,
+}
+
+impl FuzzCase {
+    fn new<S: Into<String>>(description: S, args: &[Vec<ParcelStringElem<'static>>]) -> Self {
+        let mut parcel = ParcelString::default();
+        for it in args.iter().flatten() {
+            parcel.push(it.clone());
+        }
+        Self {
+            description: description.into(),
+            parcel,
+        }
+    }
+}
+
+/// Generate inputs for a method from its smali signature
+///
+/// The first case is a baseline with a benign value for every argument and
+/// each following case replaces one argument with a boundary value, a null,
+/// an oversized value or a malformed object. Arguments take turns so the
+/// cases are spread across all of them when `max_cases` cuts them short.
+pub fn generate_fuzz_cases(
+    signature: &str,
+    max_cases: usize,
+) -> Result<Vec<FuzzCase>, MethodArgError> {
+    let types = ParamType::parse_signature(signature)?;
+    let baseline = types.iter().map(baseline_elems).collect::<Vec<_>>();
+    let mutations = types.iter().map(mutated_elems).collect::<Vec<_>>();
+
+    let mut cases = vec![FuzzCase::new("baseline", &baseline)];
+    let rounds = mutations.iter().map(Vec::len).max().unwrap_or(0);
+    for round in 0..rounds {
+        for (i, ty) in types.iter().enumerate() {
+            let Some((what, elems)) = mutations[i].get(round) else {
+                continue;
+            };
+            if cases.len() >= max_cases {
+                cases.truncate(max_cases);
+                return Ok(cases);
+            }
+            let mut args = baseline.clone();
+            args[i] = elems.clone();
+            cases.push(FuzzCase::new(
+                format!("arg {} ({}): {}", i, ty, what),
+                &args,
+            ));
+        }
+    }
+    cases.truncate(max_cases);
+    Ok(cases)
+}
+
+fn string_elem(s: &'static str) -> ParcelStringElem<'static> {
+    ParcelStringElem::String(Cow::Borrowed(s))
+}
+
+/// A value that should make it past argument validation
+fn baseline_elems(ty: &ParamType) -> Vec<ParcelStringElem<'static>> {
+    use ParcelStringElem as E;
+    match ty {
+        ParamType::Boolean => vec![E::Bool(false)],
+        ParamType::Byte | ParamType::Char | ParamType::Int => vec![E::Int(0)],
+        ParamType::Short => vec![E::Short(0)],
+        ParamType::Long => vec![E::Long(0)],
+        ParamType::Float => vec![E::Float(0.0)],
+        ParamType::Double => vec![E::Double(0.0)],
+        ParamType::String => vec![string_elem("dtu")],
+        ParamType::CharSequence => vec![E::Int(1), E::Int(1), string_elem("dtu")],
+        ParamType::Binder(_) => vec![E::Binder],
+        ParamType::Bundle => vec![E::Int(1), E::Bundle(HashMap::new())],
+        ParamType::ParcelFileDescriptor => vec![E::Int(1), E::ReadFd(Cow::Borrowed("/dev/null"))],
+        ParamType::ComponentName => {
+            vec![
+                E::Int(1),
+                string_elem("android"),
+                string_elem("android.Dtu"),
+            ]
+        }
+        // Empty
+        ParamType::Array(_) | ParamType::Collection(_) => vec![E::Int(0)],
+        // Null
+        ParamType::Object(_) => vec![E::Int(0)],
+    }
+}
+
+type Mutation = (&'static str, Vec<ParcelStringElem<'static>>);
+
+/// Values likely to upset the method, with a short description of each
+fn mutated_elems(ty: &ParamType) -> Vec<Mutation> {
+    use ParcelStringElem as E;
+    let ints = |values: &[(&'static str, i32)]| {
+        values
+            .iter()
+            .map(|(what, v)| (*what, vec![E::Int(*v)]))
+            .collect::<Vec<Mutation>>()
+    };
+    match ty {
+        ParamType::Boolean => vec![("true", vec![E::Bool(true)]), ("2", vec![E::Int(2)])],
+        ParamType::Byte => ints(&[("-1", -1), ("min", -128), ("max", 127), ("256", 256)]),
+        ParamType::Char => ints(&[("0xffff", 0xffff), ("lone surrogate", 0xd800)]),
+        ParamType::Short => vec![
+            ("-1", vec![E::Short(-1)]),
+            ("min", vec![E::Short(i16::MIN)]),
+            ("max", vec![E::Short(i16::MAX)]),
+        ],
+        ParamType::Int => ints(&[
+            ("-1", -1),
+            ("1", 1),
+            ("min", i32::MIN),
+            ("max", i32::MAX),
+            ("0x10000", 0x10000),
+        ]),
+        ParamType::Long => vec![
+            ("-1", vec![E::Long(-1)]),
+            ("min", vec![E::Long(i64::MIN)]),
+            ("max", vec![E::Long(i64::MAX)]),
+            ("1 << 32", vec![E::Long(1 << 32)]),
+        ],
+        ParamType::Float => vec![
+            ("-1", vec![E::Float(-1.0)]),
+            ("max", vec![E::Float(f32::MAX)]),
+            ("NaN", vec![E::Float(f32::NAN)]),
+        ],
+        ParamType::Double => vec![
+            ("-1", vec![E::Double(-1.0)]),
+            ("max", vec![E::Double(f64::MAX)]),
+            ("NaN", vec![E::Double(f64::NAN)]),
+        ],
+        ParamType::String => string_mutations(&[]),
+        ParamType::CharSequence => {
+            let mut muts = string_mutations(&[E::Int(1), E::Int(1)]);
+            muts[0] = ("null", vec![E::Int(0)]);
+            muts
+        }
+        ParamType::Binder(_) => vec![("not a binder", vec![E::Int(0)])],
+        ParamType::Bundle => vec![
+            ("null", vec![E::Int(0)]),
+            ("nested bundles", vec![E::Int(1), nested_bundle()]),
+            ("many keys", vec![E::Int(1), large_bundle()]),
+            ("bogus length", vec![E::Int(1), E::Int(i32::MAX)]),
+        ],
+        ParamType::ParcelFileDescriptor => vec![
+            ("null", vec![E::Int(0)]),
+            (
+                "directory fd",
+                vec![E::Int(1), E::ReadFd(Cow::Borrowed("/"))],
+            ),
+            (
+                "write fd",
+                vec![E::Int(1), E::WriteFd(Cow::Borrowed("/dev/null"))],
+            ),
+            ("missing fd", vec![E::Int(1), E::Int(-1)]),
+        ],
+        ParamType::ComponentName => vec![
+            ("null", vec![E::Int(0)]),
+            ("empty", vec![E::Int(1), string_elem(""), string_elem("")]),
+            ("null names", vec![E::Int(1), E::Null, E::Null]),
+        ],
+        ParamType::Array(elem) => {
+            let mut one = vec![E::Int(1)];
+            one.extend(baseline_elems(elem));
+            vec![
+                ("null", vec![E::Null]),
+                ("one element", one),
+                ("huge length", vec![E::Int(i32::MAX)]),
+                ("negative length", vec![E::Int(-2)]),
+            ]
+        }
+        ParamType::Collection(_) => vec![
+            ("null", vec![E::Null]),
+            ("huge length", vec![E::Int(i32::MAX)]),
+            ("negative length", vec![E::Int(-2)]),
+        ],
+        ParamType::Object(_) => vec![
+            ("non-null without data", vec![E::Int(1)]),
+            ("bogus data", vec![E::Int(1), E::Int(i32::MAX), E::Int(-1)]),
+        ],
+    }
+}
+
+fn string_mutations(prefix: &[ParcelStringElem<'static>]) -> Vec<Mutation> {
+    let with_prefix = |elem: ParcelStringElem<'static>| {
+        let mut elems = prefix.to_vec();
+        elems.push(elem);
+        elems
+    };
+    vec![
+        ("null", with_prefix(ParcelStringElem::Null)),
+        ("empty", with_prefix(string_elem(""))),
+        (
+            "long string",
+            with_prefix(ParcelStringElem::String(Cow::Owned("A".repeat(0x10000)))),
+        ),
+        ("format string", with_prefix(string_elem("%s%s%s%s%n"))),
+        (
+            "path traversal",
+            with_prefix(string_elem("../../../../../../data/system/packages.xml")),
+        ),
+    ]
+}
+
+/// A Bundle holding a Bundle, Parcel strings can't nest any deeper
+fn nested_bundle() -> ParcelStringElem<'static> {
+    let mut inner = HashMap::new();
+    inner.insert(String::from("dtu"), ParcelStringElem::Int(i32::MAX));
+    inner.insert(String::from("dtu_str"), string_elem("dtu"));
+    let mut outer = HashMap::new();
+    outer.insert(String::from("dtu"), ParcelStringElem::Bundle(inner));
+    ParcelStringElem::Bundle(outer)
+}
+
+fn large_bundle() -> ParcelStringElem<'static> {
+    let map = (0..512)
+        .map(|i| (format!("dtu{}", i), ParcelStringElem::Int(i)))
+        .collect::<HashMap<_, _>>();
+    ParcelStringElem::Bundle(map)
+}
+
+/// The result of sending a single input
+#[derive(Debug, PartialEq, Eq)]
+pub struct FuzzCaseResult {
+    pub outcome: FuzzOutcome,
+    pub exception: Option<String>,
+    pub message: Option<String>,
+}
+
+impl FuzzCaseResult {
+    fn new(outcome: FuzzOutcome, message: Option<String>) -> Self {
+        Self {
+            outcome,
+            exception: None,
+            message,
+        }
+    }
+
+    /// Classify the app server's response to a call
+    pub fn from_response(res: server::Result<BinderReply>) -> Self {
+        let err = match res {
+            Ok(reply) => {
+                return match decode_reply(&reply.reply, None) {
+                    Ok(decoded) => match decoded.exception {
+                        None => Self::new(FuzzOutcome::Ok, None),
+                        Some(ex) => Self {
+                            outcome: if ex.is_security_exception() {
+                                FuzzOutcome::SecurityException
+                            } else {
+                                FuzzOutcome::Exception
+                            },
+                            exception: Some(String::from(ex.name)),
+                            message: ex.message,
+                        },
+                    },
+                    Err(e) => Self::new(FuzzOutcome::Error, Some(e.to_string())),
+                }
+            }
+            Err(e) => e,
+        };
+
+        let outcome = match &err {
+            server::Error::IO(e) => match e.kind() {
+                ErrorKind::WouldBlock | ErrorKind::TimedOut => FuzzOutcome::Timeout,
+                ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof => FuzzOutcome::Crash,
+                _ => FuzzOutcome::Error,
+            },
+            // The remote process died during the call
+            server::Error::ServerError(msg) if msg.contains("DeadObjectException") => {
+                FuzzOutcome::Crash
+            }
+            _ => FuzzOutcome::Error,
+        };
+        Self::new(outcome, Some(err.to_string()))
+    }
+
+    /// Classify a failure to connect to the app server
+    ///
+    /// The server handles a single command per connection, so failing to
+    /// reconnect after a previous connection worked means the app died.
+    /// Otherwise the server was never reachable and it's just an error.
+    pub fn from_connect_error(err: ConnectError, connected: bool) -> Self {
+        let outcome = if connected {
+            FuzzOutcome::Crash
+        } else {
+            FuzzOutcome::Error
+        };
+        Self::new(outcome, Some(err.to_string()))
+    }
+}
+
+/// Fuzz system service methods through the application server.
+///
+/// Inputs are generated from each method's signature with
+/// [generate_fuzz_cases] and each is sent on a new connection to the app
+/// server with a timeout. Every call is stored in the `binder_fuzz_results`
+/// table and the exceptions each method threw are summarized in
+/// `fuzz_results`, replacing any previous results for the method. The task
+/// stops at the first crash since the device is usually restarting.
+pub struct BinderFuzzTask<'a> {
+    ctx: &'a dyn Context,
+    db: &'a DeviceDatabase,
+    monitor: &'a dyn EventMonitor<BinderFuzzEvent>,
+    cancel: TaskCancelCheck,
+    timeout: Duration,
+    max_cases: usize,
+    /// Whether any connection to the app server has succeeded
+    connected: Cell<bool>,
+}
+
+impl<'a> BinderFuzzTask<'a> {
+    pub fn new(
+        ctx: &'a dyn Context,
+        db: &'a DeviceDatabase,
+        cancel: TaskCancelCheck,
+        monitor: &'a dyn EventMonitor<BinderFuzzEvent>,
+    ) -> Self {
+        Self {
+            ctx,
+            db,
+            monitor,
+            cancel,
+            timeout: DEFAULT_BINDER_FUZZ_TIMEOUT,
+            max_cases: DEFAULT_BINDER_FUZZ_MAX_CASES,
+            connected: Cell::new(false),
+        }
+    }
+
+    /// Set how long to wait for each call
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of inputs sent to each method
+    pub fn set_max_cases(&mut self, max_cases: usize) -> &mut Self {
+        self.max_cases = max_cases;
+        self
+    }
+
+    /// Fuzz the given methods of each service
+    pub fn run(
+        &self,
+        services: &[(SystemService, Vec<SystemServiceMethod>)],
+    ) -> BinderFuzzTaskResult<()> {
+        for (service, methods) in services {
+            for method in methods {
+                self.cancel_check()?;
+                self.fuzz_method(service, method)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fuzz_method(
+        &self,
+        service: &SystemService,
+        method: &SystemServiceMethod,
+    ) -> BinderFuzzTaskResult<()> {
+        let skip = |reason: String| {
+            self.monitor.on_event(Evt::MethodSkipped {
+                service: service.name.clone(),
+                method: method.name.clone(),
+                reason,
+            })
+        };
+        let Some(iface) = service.iface.as_ref() else {
+            skip(String::from("unknown interface"));
+            return Ok(());
+        };
+        let Some(sig) = method.signature.as_ref() else {
+            skip(String::from("unknown signature"));
+            return Ok(());
+        };
+        let cases = match generate_fuzz_cases(sig, self.max_cases) {
+            Ok(v) => v,
+            Err(e) => {
+                skip(e.to_string());
+                return Ok(());
+            }
+        };
+
+        self.monitor.on_event(Evt::MethodStarted {
+            service: service.name.clone(),
+            method: method.name.clone(),
+            cases: cases.len(),
+        });
+
+        let mut results = Vec::with_capacity(cases.len());
+        let mut crashed = false;
+        for case in cases.iter() {
+            self.cancel_check()?;
+            let res = self.call(service, iface, method, case);
+            self.monitor.on_event(Evt::CaseDone {
+                description: case.description.clone(),
+                outcome: res.outcome,
+                exception: res.exception.clone(),
+            });
+            crashed = res.outcome == FuzzOutcome::Crash;
+            results.push((case, case.parcel.build(), res));
+            if crashed {
+                break;
+            }
+        }
+
+        self.store_results(service, method, &results)?;
+        self.monitor.on_event(Evt::MethodEnded {
+            service: service.name.clone(),
+            method: method.name.clone(),
+        });
+
+        if crashed {
+            return Err(BinderFuzzError::Crashed {
+                service: service.name.clone(),
+                method: method.name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn call(
+        &self,
+        service: &SystemService,
+        iface: &ClassName,
+        method: &SystemServiceMethod,
+        case: &FuzzCase,
+    ) -> FuzzCaseResult {
+        let mut srv = match TcpAppServer::from_ctx(self.ctx) {
+            Ok(v) => v,
+            Err(e) => return FuzzCaseResult::from_connect_error(e, self.connected.get()),
+        };
+        self.connected.set(true);
+        if let Err(e) = srv.set_timeout(Some(self.timeout)) {
+            return FuzzCaseResult::from_response(Err(e));
+        }
+        let parcel = if case.parcel == ParcelString::default() {
+            None
+        } else {
+            Some(&case.parcel)
+        };
+        FuzzCaseResult::from_response(srv.call_system_service(
+            &service.name,
+            method.transaction_id as u32,
+            Some(iface),
+            parcel,
+        ))
+    }
+
+    fn store_results(
+        &self,
+        service: &SystemService,
+        method: &SystemServiceMethod,
+        results: &[(&FuzzCase, String, FuzzCaseResult)],
+    ) -> BinderFuzzTaskResult<()> {
+        let inserts = results
+            .iter()
+            .map(|(case, parcel, res)| {
+                InsertBinderFuzzResult::new(
+                    method.id,
+                    &case.description,
+                    parcel,
+                    res.outcome.as_str(),
+                )
+                .set_exception(res.exception.as_deref())
+                .set_message(res.message.as_deref())
+            })
+            .collect::<Vec<InsertBinderFuzzResult>>();
+
+        let threw = |outcome: FuzzOutcome| results.iter().any(|(_, _, it)| it.outcome == outcome);
+        let security = threw(FuzzOutcome::SecurityException);
+        let summary = InsertFuzzResult::new(
+            &service.name,
+            &method.name,
+            security || threw(FuzzOutcome::Exception),
+            security,
+        );
+        self.db
+            .replace_binder_fuzz_results(method.id, &inserts, &summary)?;
+        Ok(())
+    }
+
+    fn cancel_check(&self) -> BinderFuzzTaskResult<()> {
+        self.cancel.check(BinderFuzzError::Cancelled)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn test_generate_fuzz_cases() {
+        let cases = generate_fuzz_cases("ILjava/lang/String;", 100).unwrap();
+        assert_eq!(cases[0].description, "baseline");
+        assert_eq!(cases[0].parcel.build(), "_INT0,_STRdtu");
+        // Arguments take turns
+        assert_eq!(cases[1].description, "arg 0 (int): -1");
+        assert_eq!(cases[1].parcel.build(), "_INT-1,_STRdtu");
+        assert_eq!(cases[2].description, "arg 1 (String): null");
+        assert_eq!(cases[2].parcel.build(), "_INT0,NULL");
+        // Baseline, 5 ints and 5 strings
+        assert_eq!(cases.len(), 11);
+
+        let cases = generate_fuzz_cases("ILjava/lang/String;", 4).unwrap();
+        assert_eq!(cases.len(), 4);
+
+        let cases = generate_fuzz_cases("", 10).unwrap();
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].parcel, ParcelString::default());
+
+        assert!(generate_fuzz_cases("L", 10).is_err());
+    }
+
+    #[test]
+    fn test_case_result_from_response() {
+        // Parcel.writeException(new SecurityException("denied"))
+        let reply = BinderReply {
+            response: String::new(),
+            reply: vec![
+                0xff, 0xff, 0xff, 0xff, 6, 0, 0, 0, b'd', 0, b'e', 0, b'n', 0, b'i', 0, b'e', 0,
+                b'd', 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+        };
+        let res = FuzzCaseResult::from_response(Ok(reply));
+        assert_eq!(res.outcome, FuzzOutcome::SecurityException);
+        assert_eq!(res.exception.as_deref(), Some("SecurityException"));
+        assert_eq!(res.message.as_deref(), Some("denied"));
+
+        let reply = BinderReply {
+            response: String::new(),
+            reply: vec![0, 0, 0, 0],
+        };
+        let res = FuzzCaseResult::from_response(Ok(reply));
+        assert_eq!(res.outcome, FuzzOutcome::Ok);
+
+        let timeout = io::Error::from(ErrorKind::WouldBlock);
+        let res = FuzzCaseResult::from_response(Err(server::Error::IO(timeout)));
+        assert_eq!(res.outcome, FuzzOutcome::Timeout);
+
+        let dead = String::from("android.os.DeadObjectException");
+        let res = FuzzCaseResult::from_response(Err(server::Error::ServerError(dead)));
+        assert_eq!(res.outcome, FuzzOutcome::Crash);
+    }
+
+    #[test]
+    fn test_case_result_from_connect_error() {
+        let res = FuzzCaseResult::from_connect_error(ConnectError::InvalidEnvPort, false);
+        assert_eq!(res.outcome, FuzzOutcome::Error);
+
+        let refused = ConnectError::ConnectFailed {
+            addr: String::from("127.0.0.1"),
+            port: 8080,
+            err: io::Error::from(ErrorKind::ConnectionRefused),
+        };
+        let res = FuzzCaseResult::from_connect_error(refused, true);
+        assert_eq!(res.outcome, FuzzOutcome::Crash);
+        assert!(res.message.is_some());
+    }
+}
@@ -0,0 +1,727 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::db::meta::models::{InsertCrash, InsertCrashOccurrence};
+use crate::db::{self, MetaDatabase};
+use crate::utils::bytes_to_hex;
+
+/// Tag the test application logs with
+pub const APP_LOG_TAG: &str = "DeviceTestApp";
+
+/// Prefix of the line the app server logs for every command it receives, see
+/// `Server.kt`
+pub const APP_SERVER_COMMAND_LOG_PREFIX: &str = "server command: ";
+
+/// Only the top of native stacks is kept, the bottom is the same thread
+/// entry boilerplate for every crash
+const MAX_NATIVE_FRAMES: usize = 16;
+
+/// Pending ANRs are given up on after this many lines without a reason
+const MAX_ANR_LINES: usize = 16;
+
+const SYSTEM_SERVER: &str = "system_server";
+
+lazy_static! {
+    static ref BRIEF_LINE: Regex =
+        Regex::new(r"^([VDIWEFA])/([^(]*?)\s*\(\s*(?:[^:)]*:\s*)?(\d+)\): ?(.*)$").unwrap();
+    static ref THREADTIME_LINE: Regex = Regex::new(
+        r"^(\d\d-\d\d \d\d:\d\d:\d\d\.\d+)\s+(?:\S+\s+)?(\d+)\s+\d+\s+([VDIWEFA])\s+(.*?)\s*: ?(.*)$"
+    )
+    .unwrap();
+    static ref NATIVE_FRAME: Regex =
+        Regex::new(r"^#\d+\s+pc\s+[0-9a-fA-F]+\s+(\S+)\s*(?:\(offset 0x[0-9a-fA-F]+\)\s*)?(.*)$")
+            .unwrap();
+    static ref SIGNAL: Regex = Regex::new(r"^signal \d+ \((\w+)\)").unwrap();
+    static ref LAMBDA: Regex = Regex::new(r"Lambda\$?\d+").unwrap();
+    static ref NUMBER: Regex = Regex::new(r"\b(?:0x)?[0-9a-fA-F]*[0-9][0-9a-fA-F]*").unwrap();
+}
+
+/// The kinds of crashes found in logcat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrashKind {
+    /// An uncaught exception in an app process
+    Java,
+    /// A system_server exception, watchdog kill or unexplained restart
+    SystemServer,
+    Anr,
+    /// A native crash tombstone
+    Native,
+}
+
+impl CrashKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Java => "java",
+            Self::SystemServer => "system-server",
+            Self::Anr => "anr",
+            Self::Native => "native",
+        }
+    }
+}
+
+impl FromStr for CrashKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "java" => Self::Java,
+            "system-server" => Self::SystemServer,
+            "anr" => Self::Anr,
+            "native" => Self::Native,
+            _ => return Err("valid values are 'java', 'system-server', 'anr', and 'native'"),
+        })
+    }
+}
+
+impl Display for CrashKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single parsed logcat line in either the `brief` or `threadtime` format
+#[derive(Debug, PartialEq, Eq)]
+pub struct LogcatLine<'a> {
+    /// Only available in the `threadtime` format
+    pub time: Option<&'a str>,
+    pub pid: u32,
+    pub level: char,
+    pub tag: &'a str,
+    pub message: &'a str,
+}
+
+pub fn parse_logcat_line(line: &str) -> Option<LogcatLine<'_>> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if let Some(caps) = BRIEF_LINE.captures(line) {
+        return Some(LogcatLine {
+            time: None,
+            pid: caps.get(3)?.as_str().parse().ok()?,
+            level: caps.get(1)?.as_str().chars().next()?,
+            tag: caps.get(2)?.as_str(),
+            message: caps.get(4)?.as_str(),
+        });
+    }
+    let caps = THREADTIME_LINE.captures(line)?;
+    Some(LogcatLine {
+        time: Some(caps.get(1)?.as_str()),
+        pid: caps.get(2)?.as_str().parse().ok()?,
+        level: caps.get(3)?.as_str().chars().next()?,
+        tag: caps.get(4)?.as_str(),
+        message: caps.get(5)?.as_str(),
+    })
+}
+
+/// A crash found by [CrashTriage]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriagedCrash {
+    pub kind: CrashKind,
+    pub process: String,
+    /// Short description, such as the exception class or signal
+    pub signature: String,
+    /// The normalized stack, without line numbers, addresses or messages
+    pub stack: Vec<String>,
+    /// The line number the crash started on
+    pub line: usize,
+    pub time: Option<String>,
+    /// The last app server command logged before the crash
+    pub command: Option<String>,
+    /// The logcat lines making up the crash
+    pub raw: Vec<String>,
+}
+
+impl TriagedCrash {
+    fn new(kind: CrashKind, process: &str, line: usize, logcat: &LogcatLine) -> Self {
+        Self {
+            kind,
+            process: String::from(process),
+            signature: String::new(),
+            stack: Vec::new(),
+            line,
+            time: logcat.time.map(String::from),
+            command: None,
+            raw: Vec::new(),
+        }
+    }
+
+    /// A hash of the kind, signature and normalized stack, crashes with the
+    /// same hash are considered duplicates
+    pub fn stack_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.kind.as_str().as_bytes());
+        hasher.update(b"\n");
+        hasher.update(self.signature.as_bytes());
+        for frame in self.stack.iter() {
+            hasher.update(b"\n");
+            hasher.update(frame.as_bytes());
+        }
+        bytes_to_hex(&hasher.finalize())
+    }
+}
+
+/// A crash that is still being read
+struct Pending {
+    crash: TriagedCrash,
+    pid: u32,
+    /// Set once stack frames have been seen, the first line that isn't part
+    /// of the stack after this ends the crash
+    in_stack: bool,
+}
+
+impl Pending {
+    fn push_raw(&mut self, raw: &str) {
+        self.crash
+            .raw
+            .push(String::from(raw.trim_end_matches(['\r', '\n'])));
+    }
+}
+
+/// Finds Java crashes, system_server deaths, ANRs and native tombstones in
+/// logcat output
+///
+/// Lines are fed one at a time with [CrashTriage::push_line] so this works
+/// for both saved logcat files and live streams. Each crash is tagged with
+/// the last command the app server logged before it, which requires the
+/// `DeviceTestApp` tag to be logged at the info level.
+#[derive(Default)]
+pub struct CrashTriage {
+    line: usize,
+    last_command: Option<String>,
+    java: Option<Pending>,
+    native: Option<Pending>,
+    anr: Option<Pending>,
+    watchdog: Option<Pending>,
+    /// Set when a crash took down system_server so the Zygote restart that
+    /// follows isn't reported on its own
+    system_server_crashed: bool,
+}
+
+impl CrashTriage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a line of logcat output, returning any crashes the line completed
+    pub fn push_line(&mut self, raw: &str) -> Vec<TriagedCrash> {
+        self.line += 1;
+        let mut done = Vec::new();
+        let Some(line) = parse_logcat_line(raw) else {
+            return done;
+        };
+
+        // The process logs that it is being killed right after the stack
+        let ends_java = self.java.as_ref().map_or(false, |it| {
+            it.in_stack && it.pid == line.pid && line.tag != "AndroidRuntime"
+        });
+        if ends_java {
+            self.finish_pending(PendingSlot::Java, &mut done);
+        }
+
+        match line.tag {
+            "AndroidRuntime" => self.on_android_runtime(&line, raw, &mut done),
+            "DEBUG" => self.on_debug(&line, raw, &mut done),
+            "ActivityManager" => self.on_activity_manager(&line, raw, &mut done),
+            "Watchdog" => self.on_watchdog(&line, raw, &mut done),
+            "Zygote" => self.on_zygote(&line, raw, &mut done),
+            APP_LOG_TAG => {
+                if let Some(cmd) = line.message.strip_prefix(APP_SERVER_COMMAND_LOG_PREFIX) {
+                    self.last_command = Some(String::from(cmd));
+                }
+            }
+            _ => {}
+        }
+        done
+    }
+
+    /// Finish any crashes still being read
+    pub fn finish(mut self) -> Vec<TriagedCrash> {
+        let mut done = Vec::new();
+        for slot in [
+            PendingSlot::Java,
+            PendingSlot::Native,
+            PendingSlot::Anr,
+            PendingSlot::Watchdog,
+        ] {
+            self.finish_pending(slot, &mut done);
+        }
+        done.sort_by_key(|it| it.line);
+        done
+    }
+
+    fn start(
+        &mut self,
+        slot: PendingSlot,
+        kind: CrashKind,
+        process: &str,
+        line: &LogcatLine,
+        raw: &str,
+        done: &mut Vec<TriagedCrash>,
+    ) -> &mut Pending {
+        self.finish_pending(slot, done);
+        let mut crash = TriagedCrash::new(kind, process, self.line, line);
+        crash.command = self.last_command.clone();
+        let mut pending = Pending {
+            crash,
+            pid: line.pid,
+            in_stack: false,
+        };
+        pending.push_raw(raw);
+        self.slot(slot).insert(pending)
+    }
+
+    fn slot(&mut self, slot: PendingSlot) -> &mut Option<Pending> {
+        match slot {
+            PendingSlot::Java => &mut self.java,
+            PendingSlot::Native => &mut self.native,
+            PendingSlot::Anr => &mut self.anr,
+            PendingSlot::Watchdog => &mut self.watchdog,
+        }
+    }
+
+    fn finish_pending(&mut self, slot: PendingSlot, done: &mut Vec<TriagedCrash>) {
+        let Some(pending) = self.slot(slot).take() else {
+            return;
+        };
+        let mut crash = pending.crash;
+        if crash.signature.is_empty() {
+            crash.signature = String::from(match crash.kind {
+                CrashKind::Anr => "ANR",
+                CrashKind::Native => "unknown signal",
+                _ => "unknown exception",
+            });
+        }
+        if crash.process == SYSTEM_SERVER {
+            self.system_server_crashed = true;
+        }
+        done.push(crash);
+    }
+
+    fn on_android_runtime(&mut self, line: &LogcatLine, raw: &str, done: &mut Vec<TriagedCrash>) {
+        let msg = line.message;
+        if msg.contains("FATAL EXCEPTION IN SYSTEM PROCESS") {
+            let slot = PendingSlot::Java;
+            self.start(
+                slot,
+                CrashKind::SystemServer,
+                SYSTEM_SERVER,
+                line,
+                raw,
+                done,
+            );
+            return;
+        }
+        if msg.starts_with("FATAL EXCEPTION:") {
+            self.start(PendingSlot::Java, CrashKind::Java, "", line, raw, done);
+            return;
+        }
+
+        let Some(pending) = self.java.as_mut() else {
+            return;
+        };
+        if pending.pid != line.pid {
+            return;
+        }
+        pending.push_raw(raw);
+        let crash = &mut pending.crash;
+        let trimmed = msg.trim();
+
+        if let Some(proc) = trimmed.strip_prefix("Process: ") {
+            let name = proc.split(',').next().unwrap_or(proc);
+            crash.process = String::from(name.trim());
+        } else if let Some(frame) = trimmed.strip_prefix("at ") {
+            crash
+                .stack
+                .push(format!("at {}", normalize_java_frame(frame)));
+            pending.in_stack = true;
+        } else if let Some(cause) = trimmed.strip_prefix("Caused by: ") {
+            crash
+                .stack
+                .push(format!("Caused by: {}", exception_class(cause)));
+        } else if trimmed.starts_with("... ") && trimmed.ends_with(" more") {
+            // Repeated frames of a cause
+        } else if crash.signature.is_empty() && !trimmed.is_empty() {
+            let class = exception_class(trimmed);
+            crash.signature = String::from(class);
+            crash.stack.push(String::from(class));
+        }
+        // Anything else is a multiline exception message
+    }
+
+    fn on_debug(&mut self, line: &LogcatLine, raw: &str, done: &mut Vec<TriagedCrash>) {
+        let msg = line.message.trim();
+        if msg.starts_with("*** *** *** *** ***") {
+            self.start(PendingSlot::Native, CrashKind::Native, "", line, raw, done);
+            return;
+        }
+
+        let Some(pending) = self.native.as_mut() else {
+            return;
+        };
+        if pending.pid != line.pid {
+            return;
+        }
+        let crash = &mut pending.crash;
+
+        if let Some(caps) = NATIVE_FRAME.captures(msg) {
+            if crash.stack.len() < MAX_NATIVE_FRAMES {
+                crash.stack.push(normalize_native_frame(&caps));
+            }
+            pending.in_stack = true;
+        } else if pending.in_stack {
+            self.finish_pending(PendingSlot::Native, done);
+            return;
+        } else if msg.starts_with("pid: ") {
+            if let Some((_, name)) = msg.split_once(">>> ") {
+                let name = name.split(" <<<").next().unwrap_or(name);
+                crash.process = String::from(name.trim());
+            }
+        } else if let Some(caps) = SIGNAL.captures(msg) {
+            crash.signature = String::from(&caps[1]);
+        }
+        pending.push_raw(raw);
+    }
+
+    fn on_activity_manager(&mut self, line: &LogcatLine, raw: &str, done: &mut Vec<TriagedCrash>) {
+        if let Some(rest) = line.message.strip_prefix("ANR in ") {
+            let process = rest.split([' ', '(']).next().unwrap_or(rest);
+            self.start(PendingSlot::Anr, CrashKind::Anr, process, line, raw, done);
+            return;
+        }
+
+        let Some(pending) = self.anr.as_mut() else {
+            return;
+        };
+        if pending.pid != line.pid {
+            return;
+        }
+        pending.push_raw(raw);
+        if let Some(reason) = line.message.strip_prefix("Reason: ") {
+            pending.crash.signature = format!("ANR: {}", normalize_message(reason));
+            self.finish_pending(PendingSlot::Anr, done);
+        } else if pending.crash.raw.len() >= MAX_ANR_LINES {
+            self.finish_pending(PendingSlot::Anr, done);
+        }
+    }
+
+    fn on_watchdog(&mut self, line: &LogcatLine, raw: &str, done: &mut Vec<TriagedCrash>) {
+        let msg = line.message.trim();
+        if let Some((_, reason)) = msg.split_once("WATCHDOG KILLING SYSTEM PROCESS:") {
+            let slot = PendingSlot::Watchdog;
+            let kind = CrashKind::SystemServer;
+            let pending = self.start(slot, kind, SYSTEM_SERVER, line, raw, done);
+            pending.crash.signature = format!("watchdog: {}", normalize_message(reason.trim()));
+            return;
+        }
+
+        let Some(pending) = self.watchdog.as_mut() else {
+            return;
+        };
+        if let Some(frame) = msg.strip_prefix("at ") {
+            pending.push_raw(raw);
+            pending
+                .crash
+                .stack
+                .push(format!("at {}", normalize_java_frame(frame)));
+            pending.in_stack = true;
+        } else if msg.contains("GOODBYE") {
+            pending.push_raw(raw);
+            self.finish_pending(PendingSlot::Watchdog, done);
+        } else if msg.ends_with("stack trace:") {
+            pending.push_raw(raw);
+        } else if pending.in_stack {
+            self.finish_pending(PendingSlot::Watchdog, done);
+        }
+    }
+
+    fn on_zygote(&mut self, line: &LogcatLine, raw: &str, done: &mut Vec<TriagedCrash>) {
+        let msg = line.message;
+        let died = msg.contains("because system server")
+            || (msg.contains("System server process") && msg.contains("has died"));
+        if !died {
+            return;
+        }
+        for slot in [
+            PendingSlot::Java,
+            PendingSlot::Native,
+            PendingSlot::Watchdog,
+        ] {
+            let is_system_server = self
+                .slot(slot)
+                .as_ref()
+                .map_or(false, |it| it.crash.process == SYSTEM_SERVER);
+            if is_system_server {
+                self.finish_pending(slot, done);
+            }
+        }
+        // Already reported as a Java, native, or watchdog crash
+        if self.system_server_crashed {
+            self.system_server_crashed = false;
+            return;
+        }
+        let mut crash = TriagedCrash::new(CrashKind::SystemServer, SYSTEM_SERVER, self.line, line);
+        crash.signature = String::from("system_server died");
+        crash.command = self.last_command.clone();
+        crash
+            .raw
+            .push(String::from(raw.trim_end_matches(['\r', '\n'])));
+        done.push(crash);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PendingSlot {
+    Java,
+    Native,
+    Anr,
+    Watchdog,
+}
+
+/// The class of an exception line such as `java.lang.Exception: message`
+fn exception_class(line: &str) -> &str {
+    line.split(':').next().unwrap_or(line).trim()
+}
+
+/// Drop the source file and line number and the numbering of synthetic
+/// lambda classes
+fn normalize_java_frame(frame: &str) -> String {
+    let method = frame.split('(').next().unwrap_or(frame).trim();
+    LAMBDA.replace_all(method, "Lambda").into_owned()
+}
+
+/// Keep the library and symbol of a native frame, without the pc, symbol
+/// offset or build ID
+fn normalize_native_frame(caps: &regex::Captures) -> String {
+    let lib = &caps[1];
+    let rest = &caps[2];
+    let rest = match rest.find("(BuildId:") {
+        Some(idx) => rest[..idx].trim(),
+        None => rest.trim(),
+    };
+    let symbol = rest
+        .strip_prefix('(')
+        .and_then(|it| it.strip_suffix(')'))
+        .map(|it| match it.rsplit_once('+') {
+            Some((sym, off)) if off.chars().all(|c| c.is_ascii_digit()) => sym,
+            _ => it,
+        });
+    match symbol {
+        Some(sym) => format!("{} ({})", lib, sym),
+        None => String::from(lib),
+    }
+}
+
+/// Replace numbers, addresses and hashes that change between runs
+fn normalize_message(msg: &str) -> String {
+    NUMBER.replace_all(msg, "N").into_owned()
+}
+
+/// Triage an entire logcat capture
+pub fn triage_logcat<R: BufRead>(reader: R) -> std::io::Result<Vec<TriagedCrash>> {
+    let mut triage = CrashTriage::new();
+    let mut crashes = Vec::new();
+    for line in reader.lines() {
+        crashes.extend(triage.push_line(&line?));
+    }
+    crashes.extend(triage.finish());
+    Ok(crashes)
+}
+
+/// Every file saved by `fuzz logcat` under the given directory
+pub fn get_logcat_captures(dir: &Path) -> Vec<PathBuf> {
+    let mut files = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|it| it.ok())
+        .filter(|it| it.file_type().is_file())
+        .map(|it| it.into_path())
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+    files
+}
+
+/// Store a crash found in `source` in the meta database
+///
+/// Returns false if the crash was already stored from the same line of the
+/// same source.
+pub fn store_crash(
+    meta: &dyn MetaDatabase,
+    source: &str,
+    crash: &TriagedCrash,
+) -> db::Result<bool> {
+    let hash = crash.stack_hash();
+    let stack = crash.stack.join("\n");
+    let ins = InsertCrash::new(
+        crash.kind.as_str(),
+        &crash.process,
+        &crash.signature,
+        &hash,
+        &stack,
+    );
+    let id = meta.add_crash(&ins)?;
+    let raw = crash.raw.join("\n");
+    let occurrence = InsertCrashOccurrence::new(id, source, crash.line as i32, &raw)
+        .set_time(crash.time.as_deref())
+        .set_command(crash.command.as_deref());
+    meta.add_crash_occurrence(&occurrence)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const JAVA_CRASH: &str = "\
+I/DeviceTestApp( 2345): server command: TransactSystemService {\"service\":\"foo\"}
+E/AndroidRuntime( 1234): FATAL EXCEPTION: main
+E/AndroidRuntime( 1234): Process: com.example.app, PID: 1234
+E/AndroidRuntime( 1234): java.lang.NullPointerException: Attempt to invoke virtual method on a null object reference
+E/AndroidRuntime( 1234): \tat com.example.app.Main.onCreate(Main.java:42)
+E/AndroidRuntime( 1234): \tat com.example.app.Main$$ExternalSyntheticLambda3.run(D8$$SyntheticClass:0)
+E/AndroidRuntime( 1234): Caused by: java.lang.IllegalStateException: bad
+E/AndroidRuntime( 1234): \t... 12 more
+I/Process ( 1234): Sending signal. PID: 1234 SIG: 9
+";
+
+    #[test]
+    fn test_parse_logcat_line() {
+        let line = parse_logcat_line("E/AndroidRuntime( 1234): FATAL EXCEPTION: main").unwrap();
+        assert_eq!(line.tag, "AndroidRuntime");
+        assert_eq!(line.pid, 1234);
+        assert_eq!(line.level, 'E');
+        assert_eq!(line.message, "FATAL EXCEPTION: main");
+
+        let line =
+            parse_logcat_line("F/DEBUG   (u0_a12:  567):     #00 pc 0004f6a8  /libc.so").unwrap();
+        assert_eq!(line.tag, "DEBUG");
+        assert_eq!(line.pid, 567);
+        assert_eq!(line.message, "    #00 pc 0004f6a8  /libc.so");
+
+        let line = parse_logcat_line(
+            "01-02 03:04:05.678  1000  1234  1240 E ActivityManager: ANR in com.example",
+        )
+        .unwrap();
+        assert_eq!(line.time, Some("01-02 03:04:05.678"));
+        assert_eq!(line.pid, 1234);
+        assert_eq!(line.tag, "ActivityManager");
+        assert_eq!(line.message, "ANR in com.example");
+
+        let line =
+            parse_logcat_line("01-02 03:04:05.678  1234  1240 W Watchdog: *** GOODBYE!").unwrap();
+        assert_eq!(line.pid, 1234);
+        assert_eq!(line.tag, "Watchdog");
+
+        assert!(parse_logcat_line("--------- beginning of crash").is_none());
+    }
+
+    #[test]
+    fn test_java_crash() {
+        let crashes = triage_logcat(JAVA_CRASH.as_bytes()).unwrap();
+        assert_eq!(crashes.len(), 1);
+        let crash = &crashes[0];
+        assert_eq!(crash.kind, CrashKind::Java);
+        assert_eq!(crash.process, "com.example.app");
+        assert_eq!(crash.signature, "java.lang.NullPointerException");
+        assert_eq!(
+            crash.stack,
+            vec![
+                "java.lang.NullPointerException",
+                "at com.example.app.Main.onCreate",
+                "at com.example.app.Main$$ExternalSyntheticLambda.run",
+                "Caused by: java.lang.IllegalStateException",
+            ]
+        );
+        assert_eq!(crash.line, 2);
+        assert_eq!(
+            crash.command.as_deref(),
+            Some("TransactSystemService {\"service\":\"foo\"}")
+        );
+        assert_eq!(crash.raw.len(), 7);
+
+        // Line numbers, pids and messages don't change the hash
+        let other = JAVA_CRASH
+            .replace("1234", "4321")
+            .replace("42", "50")
+            .replace("null object", "other");
+        let other = triage_logcat(other.as_bytes()).unwrap();
+        assert_eq!(other[0].stack_hash(), crash.stack_hash());
+    }
+
+    #[test]
+    fn test_system_server_crash() {
+        let log = "\
+E/AndroidRuntime(  600): *** FATAL EXCEPTION IN SYSTEM PROCESS: main
+E/AndroidRuntime(  600): java.lang.RuntimeException: boom
+E/AndroidRuntime(  600): \tat com.android.server.Foo.bar(Foo.java:1)
+E/Zygote  (  300): Exit zygote because system server (pid 600) has terminated
+E/Zygote  (  300): Exit zygote because system server (pid 900) has terminated
+";
+        let crashes = triage_logcat(log.as_bytes()).unwrap();
+        assert_eq!(crashes.len(), 2);
+        assert_eq!(crashes[0].kind, CrashKind::SystemServer);
+        assert_eq!(crashes[0].process, "system_server");
+        assert_eq!(crashes[0].signature, "java.lang.RuntimeException");
+        // The first restart is explained by the exception
+        assert_eq!(crashes[1].signature, "system_server died");
+        assert_eq!(crashes[1].line, 5);
+    }
+
+    #[test]
+    fn test_anr_and_watchdog() {
+        let log = "\
+E/ActivityManager(  600): ANR in com.example.app (com.example.app/.Main)
+E/ActivityManager(  600): PID: 1234
+E/ActivityManager(  600): Reason: Input dispatching timed out (Waited 5001ms for FocusEvent)
+W/Watchdog(  600): *** WATCHDOG KILLING SYSTEM PROCESS: Blocked in handler on main thread (main) for 60s
+W/Watchdog(  600): main thread stack trace:
+W/Watchdog(  600):     at com.android.server.Foo.lock(Foo.java:10)
+W/Watchdog(  600): *** GOODBYE!
+";
+        let crashes = triage_logcat(log.as_bytes()).unwrap();
+        assert_eq!(crashes.len(), 2);
+        assert_eq!(crashes[0].kind, CrashKind::Anr);
+        assert_eq!(crashes[0].process, "com.example.app");
+        assert_eq!(
+            crashes[0].signature,
+            "ANR: Input dispatching timed out (Waited Nms for FocusEvent)"
+        );
+        assert_eq!(crashes[1].kind, CrashKind::SystemServer);
+        assert_eq!(
+            crashes[1].signature,
+            "watchdog: Blocked in handler on main thread (main) for Ns"
+        );
+        assert_eq!(crashes[1].stack, vec!["at com.android.server.Foo.lock"]);
+    }
+
+    #[test]
+    fn test_native_crash() {
+        let log = "\
+F/DEBUG   ( 4321): *** *** *** *** *** *** *** *** *** *** *** *** *** *** *** ***
+F/DEBUG   ( 4321): pid: 1234, tid: 1240, name: Binder:1234_2  >>> /system/bin/mediaserver <<<
+F/DEBUG   ( 4321): signal 11 (SIGSEGV), code 1 (SEGV_MAPERR), fault addr 0x0
+F/DEBUG   ( 4321): backtrace:
+F/DEBUG   ( 4321):       #00 pc 000000000004f6a8  /system/lib64/libfoo.so (Foo::bar(int)+164) (BuildId: abcdef)
+F/DEBUG   ( 4321):       #01 pc 0000000000012345  /system/lib64/libbar.so (BuildId: 123456)
+F/DEBUG   ( 4321): memory near x0:
+";
+        let crashes = triage_logcat(log.as_bytes()).unwrap();
+        assert_eq!(crashes.len(), 1);
+        let crash = &crashes[0];
+        assert_eq!(crash.kind, CrashKind::Native);
+        assert_eq!(crash.process, "/system/bin/mediaserver");
+        assert_eq!(crash.signature, "SIGSEGV");
+        assert_eq!(
+            crash.stack,
+            vec![
+                "/system/lib64/libfoo.so (Foo::bar(int))",
+                "/system/lib64/libbar.so"
+            ]
+        );
+        assert_eq!(crash.command, None);
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "app-server")]
+pub mod binder_fuzz;
+pub mod crash_triage;
 pub mod fuzz;
 pub mod pull;
 pub mod selinux;
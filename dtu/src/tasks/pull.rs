@@ -3,20 +3,22 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::fs::{read_dir, DirEntry};
 use std::hash::{Hash, Hasher};
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 use crossbeam::channel::{bounded, Receiver, Sender};
 use crossbeam::thread::{self, ScopedJoinHandle};
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use walkdir::WalkDir;
+use zip::ZipArchive;
 
 use dtu_proc_macro::{wraps_base_error, wraps_decompile_error};
 
+use crate::db::graph::native::APK_ENTRY_SEP;
 use crate::db::meta::models::{DecompileStatus, InsertDecompileStatus, ProgressStep};
 use crate::db::{self, MetaDatabase};
 use crate::decompile::{decompile_file, ApexFile, ApkFile, Decompile, FrameworkFileType};
-use crate::devicefs::FindType;
+use crate::devicefs::{FindName, FindType};
 use crate::prereqs::Prereq;
 use crate::tasks::{cancelable_recv, cancelable_send, EventMonitor, TaskCancelCheck};
 use crate::utils::{
@@ -28,6 +30,28 @@ use crate::devicefs::DeviceFSHelper;
 
 const NUM_HELPER_THREADS: usize = 2;
 
+/// Directories searched for native libraries on the device
+const NATIVE_LIB_DIRS: &[&str] = &[
+    "/system/lib64",
+    "/system/lib",
+    "/system_ext/lib64",
+    "/system_ext/lib",
+    "/product/lib64",
+    "/product/lib",
+    "/vendor/lib64",
+    "/vendor/lib",
+    "/apex",
+];
+
+/// Directories searched for init `.rc` files on the device
+const INIT_RC_DIRS: &[&str] = &[
+    "/system/etc/init",
+    "/system_ext/etc/init",
+    "/product/etc/init",
+    "/vendor/etc/init",
+    "/odm/etc/init",
+];
+
 pub struct Options {
     /// The maximum number of worker threads to use for pulling
     pub worker_threads: usize,
@@ -46,6 +70,12 @@ pub struct Options {
 
     /// Retry failed items
     pub retry: bool,
+
+    /// Pull native libraries from the device and extract them from APKs
+    pub native_libs: bool,
+
+    /// Pull the init `.rc` files defining native services
+    pub init_rc: bool,
 }
 
 impl Default for Options {
@@ -55,6 +85,8 @@ impl Default for Options {
             try_vdex: false,
             force: false,
             retry: true,
+            native_libs: true,
+            init_rc: true,
         }
     }
 }
@@ -103,6 +135,10 @@ pub enum Event {
     FrameworkEnded,
     ApksStarted,
     ApksEnded,
+    NativeLibsStarted,
+    NativeLibsEnded,
+    InitRcStarted,
+    InitRcEnded,
 
     FindingDirectories,
     DirectoryFound { device: DevicePath },
@@ -268,6 +304,14 @@ impl<'a> Pull<'a> {
                         res = apk_handle.join().expect("failed to join handle");
                     }
 
+                    if res.is_ok() && self.opts.native_libs && !self.cancelled() {
+                        res = self.pull_native_libs();
+                    }
+
+                    if res.is_ok() && self.opts.init_rc && !self.cancelled() {
+                        res = self.pull_init_rc();
+                    }
+
                     res
                 })
                 .join()
@@ -339,6 +383,110 @@ impl<'a> Pull<'a> {
         Ok(())
     }
 
+    /// Pull all native libraries from the device and extract the libraries shipped in pulled
+    /// APKs into the native libraries directory
+    fn pull_native_libs(&self) -> Result<()> {
+        log::trace!("pulling native libraries");
+        self.send_event(Event::NativeLibsStarted);
+        let libs_dir = self.ctx.get_native_libs_dir()?;
+        ensure_dir_exists(&libs_dir)?;
+
+        let mut found = Vec::new();
+        for dir in NATIVE_LIB_DIRS {
+            if self.cancelled() {
+                return Err(BaseError::Cancelled.into());
+            }
+            let mut on_file = |line: &str| -> anyhow::Result<()> {
+                // Skip the versioned duplicates of each apex
+                if !line.is_empty() && !(line.starts_with("/apex/") && line.contains('@')) {
+                    found.push(DevicePath::new(line));
+                }
+                Ok(())
+            };
+            self.dfs.find(
+                dir,
+                FindType::File,
+                None,
+                Some(FindName::Suffix(".so")),
+                &mut on_file,
+            )?;
+        }
+
+        self.worker_pool.scope(|s| {
+            for device_path in found.iter() {
+                let local = libs_dir.join(device_path);
+                if local.exists() {
+                    continue;
+                }
+                s.spawn(move |_| {
+                    if self.cancelled() {
+                        return;
+                    }
+                    let _ = self.pull_file(device_path, path_must_str(&local));
+                });
+            }
+        });
+
+        for ent in read_dir(self.ctx.get_apks_dir()?)? {
+            if self.cancelled() {
+                return Err(BaseError::Cancelled.into());
+            }
+            let path = ent?.path();
+            if !(path.is_file() && path_has_ext(&path, "apk")) {
+                continue;
+            }
+            if let Err(e) = extract_apk_native_libs(&path, &libs_dir) {
+                log::error!("extracting native libraries from {:?}: {}", path, e);
+            }
+        }
+
+        self.send_event(Event::NativeLibsEnded);
+        Ok(())
+    }
+
+    /// Pull all of the init `.rc` files from the device
+    fn pull_init_rc(&self) -> Result<()> {
+        log::trace!("pulling init rc files");
+        self.send_event(Event::InitRcStarted);
+        let rc_dir = self.ctx.get_init_rc_dir()?;
+        ensure_dir_exists(&rc_dir)?;
+
+        for dir in INIT_RC_DIRS {
+            if self.cancelled() {
+                return Err(BaseError::Cancelled.into());
+            }
+            let mut found = Vec::new();
+            let mut on_file = |line: &str| -> anyhow::Result<()> {
+                if !line.is_empty() {
+                    found.push(DevicePath::new(line));
+                }
+                Ok(())
+            };
+            // Not every partition exists on every device
+            if let Err(e) = self.dfs.find(
+                dir,
+                FindType::File,
+                None,
+                Some(FindName::Suffix(".rc")),
+                &mut on_file,
+            ) {
+                log::debug!("searching {} for rc files: {}", dir, e);
+                continue;
+            }
+
+            for device_path in found.iter() {
+                let local = rc_dir.join(device_path);
+                if local.exists() {
+                    continue;
+                }
+                let _ = self.pull_file(device_path, path_must_str(&local));
+            }
+        }
+
+        self.send_event(Event::InitRcEnded);
+        Ok(())
+    }
+
     fn update_decompile_status(&self, status: &DecompileStatus) -> Result<()> {
         self.meta_db.update_decompile_status(status)?;
         Ok(())
@@ -919,6 +1067,50 @@ fn on_normed_file_received(
     }
 }
 
+/// Extract the native libraries in the given APK into the native libraries directory
+///
+/// The APK should be named by its squashed device path, and each library is named by the
+/// squashed `<apk>!/<entry>` path.
+pub fn extract_apk_native_libs(apk: &Path, libs_dir: &Path) -> Result<()> {
+    let apk_device_path = DevicePath::from_squashed(path_must_name(apk));
+    let opened = fs::File::open(apk)?;
+    let mut archive =
+        ZipArchive::new(&opened).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    for idx in 0..archive.len() {
+        let mut file = archive
+            .by_index(idx)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let name = file.name().to_string();
+        if !(name.starts_with("lib/") && name.ends_with(".so")) {
+            continue;
+        }
+        let device_path = DevicePath::new(format!(
+            "{}{}{}",
+            apk_device_path.as_device_str(),
+            APK_ENTRY_SEP,
+            name
+        ));
+        let local = libs_dir.join(&device_path);
+        if local.exists() {
+            continue;
+        }
+        // Copy to a temporary file first, a truncated library left behind by a
+        // failed copy would be skipped by the check above on every later run
+        let mut partial = local.clone().into_os_string();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+        let copied = fs::File::create(&partial)
+            .and_then(|mut out| io::copy(&mut file, &mut out))
+            .and_then(|_| fs::rename(&partial, &local));
+        if let Err(e) = copied {
+            let _ = fs::remove_file(&partial);
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
 /// Move all smali files from the given output directory to the smali_dir. This
 /// will handle all smali[_classesN] directories in the directory.
 ///
@@ -73,6 +73,7 @@ impl<'a> PullTask<'a> {
         let secilc = self.ctx.get_bin("secilc")?;
 
         let files = self.get_files()?;
+        self.pull_service_contexts()?;
 
         let policy_out = base_dir.join("policy.33");
 
@@ -116,6 +117,37 @@ impl<'a> PullTask<'a> {
         Ok(files)
     }
 
+    /// Pull the `*service_contexts` files used to label services in each of the
+    /// service managers
+    fn pull_service_contexts(&self) -> Result<()> {
+        let out_dir = self.ctx.get_selinux_dir()?.join("contexts");
+        ensure_dir_exists(&out_dir)?;
+
+        let mut paths = Vec::new();
+        let mut on_file = |line: &str| {
+            let line = line.trim();
+            if line.len() > 0 {
+                paths.push(DevicePath::new(line));
+            }
+            Ok(())
+        };
+
+        self.dfs.find(
+            "/",
+            FindType::File,
+            None,
+            Some(FindName::Suffix("service_contexts")),
+            &mut on_file,
+        )?;
+
+        for p in paths {
+            let out_path = out_dir.join(p.as_squashed_str());
+            self.emit(Event::PullingFile { file: p.clone() });
+            self.dfs.pull(&p, path_must_str(&out_path))?;
+        }
+        Ok(())
+    }
+
     fn find_files(&self) -> Result<Vec<DevicePath>> {
         let mut paths = Vec::new();
         let re = Regex::new(r"[0-9]+\.[0-9]\.cil").unwrap();
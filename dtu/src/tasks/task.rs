@@ -36,6 +36,7 @@ impl TaskCanceller {
     }
 }
 
+#[derive(Clone)]
 pub struct TaskCancelCheck {
     cancelled: Arc<AtomicBool>,
 }
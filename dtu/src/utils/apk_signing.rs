@@ -0,0 +1,565 @@
+//! Extracting signing certificates from APKs
+//!
+//! Certificates are pulled out of the JAR signature files in `META-INF` (v1)
+//! and the APK Signing Block (v2 and v3). Nothing is verified: system APKs are
+//! commonly stripped of their dex files after being signed, so only the
+//! certificates themselves are of interest here.
+
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::ZipArchive;
+
+use super::bytes_to_hex;
+
+const EOCD_MAGIC: u32 = 0x0605_4b50;
+const EOCD_MIN_SIZE: usize = 22;
+
+const APK_SIG_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const APK_SIG_BLOCK_FOOTER_SIZE: usize = 24;
+
+const APK_SIGNATURE_SCHEME_V2_ID: u32 = 0x7109_871a;
+const APK_SIGNATURE_SCHEME_V3_ID: u32 = 0xf053_68c0;
+const APK_SIGNATURE_SCHEME_V31_ID: u32 = 0x1b93_ad61;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OID: u8 = 0x06;
+const TAG_BMP_STRING: u8 = 0x1e;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SigningError {
+    #[error("{0}")]
+    IO(#[from] std::io::Error),
+    #[error("{0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("malformed APK: {0}")]
+    Malformed(&'static str),
+}
+
+pub type SigningResult<T> = Result<T, SigningError>;
+
+/// The APK signature scheme a certificate was found in
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum SigningScheme {
+    V1,
+    V2,
+    V3,
+}
+
+impl SigningScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+            Self::V3 => "v3",
+        }
+    }
+}
+
+impl Display for SigningScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SigningScheme {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "v1" => Self::V1,
+            "v2" => Self::V2,
+            "v3" => Self::V3,
+            _ => return Err("invalid signing scheme, expected v1, v2, or v3"),
+        })
+    }
+}
+
+/// A certificate an APK was signed with
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SigningCert {
+    pub scheme: SigningScheme,
+    /// Lowercase hex SHA-256 digest of the DER encoded certificate
+    pub sha256: String,
+    /// The certificate subject, formatted like `CN=Android, OU=Android, O=Android`
+    pub subject: String,
+}
+
+impl SigningCert {
+    fn from_der(scheme: SigningScheme, der: &[u8]) -> Self {
+        Self {
+            scheme,
+            sha256: bytes_to_hex(&Sha256::digest(der)),
+            subject: cert_subject(der).unwrap_or_default(),
+        }
+    }
+}
+
+/// Get all v1, v2, and v3 signing certificates from the given APK
+pub fn get_signing_certs(path: &Path) -> SigningResult<Vec<SigningCert>> {
+    let mut file = File::open(path)?;
+    let mut certs = get_v1_certs(&mut file)?;
+    certs.extend(get_signing_block_certs(&mut file)?);
+    // v3.1 blocks repeat the v3 certificates
+    certs.dedup();
+    Ok(certs)
+}
+
+fn is_v1_signature_file(name: &str) -> bool {
+    let file = match name.strip_prefix("META-INF/") {
+        Some(v) if !v.contains('/') => v.to_ascii_uppercase(),
+        _ => return false,
+    };
+    file.ends_with(".RSA") || file.ends_with(".DSA") || file.ends_with(".EC")
+}
+
+fn get_v1_certs<R: Read + Seek>(reader: R) -> SigningResult<Vec<SigningCert>> {
+    let mut zip = ZipArchive::new(reader)?;
+    let names = zip
+        .file_names()
+        .filter(|it| is_v1_signature_file(it))
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    let mut certs = Vec::new();
+    for name in names {
+        let mut data = Vec::new();
+        zip.by_name(&name)?.read_to_end(&mut data)?;
+        let found =
+            pkcs7_certificates(&data).ok_or(SigningError::Malformed("invalid PKCS #7 data"))?;
+        certs.extend(
+            found
+                .into_iter()
+                .map(|it| SigningCert::from_der(SigningScheme::V1, it)),
+        );
+    }
+    Ok(certs)
+}
+
+fn get_signing_block_certs<R: Read + Seek>(reader: &mut R) -> SigningResult<Vec<SigningCert>> {
+    match find_signing_block(reader)? {
+        Some(pairs) => parse_signing_block(&pairs),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[inline]
+fn le_u32(buf: &[u8]) -> u32 {
+    u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+#[inline]
+fn le_u64(buf: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Find the APK Signing Block and return the ID-value pairs in it
+///
+/// The block sits right before the ZIP central directory, which is found
+/// through the end of central directory record.
+fn find_signing_block<R: Read + Seek>(reader: &mut R) -> SigningResult<Option<Vec<u8>>> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = len.min((EOCD_MIN_SIZE + u16::MAX as usize) as u64);
+    if (tail_len as usize) < EOCD_MIN_SIZE {
+        return Err(SigningError::Malformed("too small to be a ZIP file"));
+    }
+
+    let mut tail = vec![0u8; tail_len as usize];
+    reader.seek(SeekFrom::Start(len - tail_len))?;
+    reader.read_exact(&mut tail)?;
+
+    let eocd = (0..=tail.len() - EOCD_MIN_SIZE)
+        .rev()
+        .find(|it| le_u32(&tail[*it..]) == EOCD_MAGIC)
+        .ok_or(SigningError::Malformed("no end of central directory"))?;
+    let cd_offset = le_u32(&tail[eocd + 16..]) as u64;
+
+    if cd_offset < APK_SIG_BLOCK_FOOTER_SIZE as u64 + 8 {
+        return Ok(None);
+    }
+
+    let mut footer = [0u8; APK_SIG_BLOCK_FOOTER_SIZE];
+    reader.seek(SeekFrom::Start(
+        cd_offset - APK_SIG_BLOCK_FOOTER_SIZE as u64,
+    ))?;
+    reader.read_exact(&mut footer)?;
+    if &footer[8..] != APK_SIG_BLOCK_MAGIC {
+        return Ok(None);
+    }
+
+    // The size excludes the leading size field but includes the footer
+    let size = le_u64(&footer);
+    if size < APK_SIG_BLOCK_FOOTER_SIZE as u64
+        || size.checked_add(8).map_or(true, |it| it > cd_offset)
+    {
+        return Err(SigningError::Malformed("invalid APK Signing Block size"));
+    }
+    let start = cd_offset - size - 8;
+
+    let mut pairs = vec![0u8; (size as usize) - APK_SIG_BLOCK_FOOTER_SIZE];
+    reader.seek(SeekFrom::Start(start + 8))?;
+    reader.read_exact(&mut pairs)?;
+    Ok(Some(pairs))
+}
+
+fn parse_signing_block(mut pairs: &[u8]) -> SigningResult<Vec<SigningCert>> {
+    let mut certs = Vec::new();
+
+    while !pairs.is_empty() {
+        if pairs.len() < 8 {
+            return Err(SigningError::Malformed("truncated APK Signing Block"));
+        }
+        let len = le_u64(pairs) as usize;
+        let rest = &pairs[8..];
+        if len < 4 || len > rest.len() {
+            return Err(SigningError::Malformed("invalid APK Signing Block entry"));
+        }
+        let (pair, rest) = rest.split_at(len);
+        pairs = rest;
+
+        let scheme = match le_u32(pair) {
+            APK_SIGNATURE_SCHEME_V2_ID => SigningScheme::V2,
+            APK_SIGNATURE_SCHEME_V3_ID | APK_SIGNATURE_SCHEME_V31_ID => SigningScheme::V3,
+            _ => continue,
+        };
+        let found = scheme_certificates(&pair[4..])
+            .ok_or(SigningError::Malformed("invalid signature scheme block"))?;
+        certs.extend(
+            found
+                .into_iter()
+                .map(|it| SigningCert::from_der(scheme, it)),
+        );
+    }
+
+    Ok(certs)
+}
+
+fn length_prefixed<'a>(buf: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if buf.len() < 4 {
+        return None;
+    }
+    let len = le_u32(buf) as usize;
+    let rest = &buf[4..];
+    if len > rest.len() {
+        return None;
+    }
+    let (value, rest) = rest.split_at(len);
+    *buf = rest;
+    Some(value)
+}
+
+/// Get the certificates out of a v2 or v3 signature scheme block
+///
+/// Both schemes start the same way: a sequence of signers, each starting with
+/// the signed data which holds the digests followed by the certificates.
+fn scheme_certificates(mut value: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut certs = Vec::new();
+    let mut signers = length_prefixed(&mut value)?;
+    while !signers.is_empty() {
+        let mut signer = length_prefixed(&mut signers)?;
+        let mut signed_data = length_prefixed(&mut signer)?;
+        let _digests = length_prefixed(&mut signed_data)?;
+        let mut encoded = length_prefixed(&mut signed_data)?;
+        while !encoded.is_empty() {
+            certs.push(length_prefixed(&mut encoded)?);
+        }
+    }
+    Some(certs)
+}
+
+struct DerElement<'a> {
+    tag: u8,
+    contents: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// Read a single DER element from the front of the buffer
+fn der_read<'a>(buf: &mut &'a [u8]) -> Option<DerElement<'a>> {
+    let tag = *buf.first()?;
+    let first = *buf.get(1)? as usize;
+    let (len, header) = if first & 0x80 == 0 {
+        (first, 2)
+    } else {
+        let count = first & 0x7f;
+        // Indefinite lengths aren't DER and anything over 4 bytes is nonsense here
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let mut len = 0usize;
+        for i in 0..count {
+            len = (len << 8) | *buf.get(2 + i)? as usize;
+        }
+        (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    if end > buf.len() {
+        return None;
+    }
+    let element = DerElement {
+        tag,
+        contents: &buf[header..end],
+        raw: &buf[..end],
+    };
+    *buf = &buf[end..];
+    Some(element)
+}
+
+/// Read a DER element with the given tag and return its contents
+fn der_expect<'a>(buf: &mut &'a [u8], tag: u8) -> Option<&'a [u8]> {
+    let element = der_read(buf)?;
+    if element.tag != tag {
+        return None;
+    }
+    Some(element.contents)
+}
+
+/// Get the raw certificates from a PKCS #7 `SignedData` structure
+fn pkcs7_certificates(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut buf = data;
+    let mut content_info = der_expect(&mut buf, TAG_SEQUENCE)?;
+    der_expect(&mut content_info, TAG_OID)?;
+    let mut content = der_expect(&mut content_info, TAG_CONTEXT_0)?;
+    let mut signed_data = der_expect(&mut content, TAG_SEQUENCE)?;
+    // version, digestAlgorithms, and contentInfo
+    der_expect(&mut signed_data, TAG_INTEGER)?;
+    der_expect(&mut signed_data, TAG_SET)?;
+    der_expect(&mut signed_data, TAG_SEQUENCE)?;
+
+    let mut certs = Vec::new();
+    if signed_data.first() == Some(&TAG_CONTEXT_0) {
+        let mut encoded = der_expect(&mut signed_data, TAG_CONTEXT_0)?;
+        while !encoded.is_empty() {
+            let element = der_read(&mut encoded)?;
+            if element.tag == TAG_SEQUENCE {
+                certs.push(element.raw);
+            }
+        }
+    }
+    Some(certs)
+}
+
+/// Format the subject of the given DER encoded X.509 certificate
+fn cert_subject(der: &[u8]) -> Option<String> {
+    let mut buf = der;
+    let mut cert = der_expect(&mut buf, TAG_SEQUENCE)?;
+    let mut tbs = der_expect(&mut cert, TAG_SEQUENCE)?;
+    if tbs.first() == Some(&TAG_CONTEXT_0) {
+        der_read(&mut tbs)?;
+    }
+    // serialNumber, signature, issuer, and validity come before the subject
+    der_expect(&mut tbs, TAG_INTEGER)?;
+    der_expect(&mut tbs, TAG_SEQUENCE)?;
+    der_expect(&mut tbs, TAG_SEQUENCE)?;
+    der_expect(&mut tbs, TAG_SEQUENCE)?;
+    let name = der_expect(&mut tbs, TAG_SEQUENCE)?;
+    format_name(name)
+}
+
+/// Format an X.501 `Name` most significant attribute first, the same way
+/// `apksigner` and `keytool` do
+fn format_name(mut name: &[u8]) -> Option<String> {
+    let mut parts = Vec::new();
+    while !name.is_empty() {
+        let mut rdn = der_expect(&mut name, TAG_SET)?;
+        while !rdn.is_empty() {
+            let mut attr = der_expect(&mut rdn, TAG_SEQUENCE)?;
+            let oid = der_expect(&mut attr, TAG_OID)?;
+            let value = der_read(&mut attr)?;
+            let value = if value.tag == TAG_BMP_STRING {
+                let units = value
+                    .contents
+                    .chunks_exact(2)
+                    .map(|it| u16::from_be_bytes([it[0], it[1]]))
+                    .collect::<Vec<u16>>();
+                String::from_utf16_lossy(&units)
+            } else {
+                String::from_utf8_lossy(value.contents).into_owned()
+            };
+            parts.push(format!("{}={}", attribute_name(oid), value));
+        }
+    }
+    parts.reverse();
+    Some(parts.join(", "))
+}
+
+fn attribute_name(oid: &[u8]) -> String {
+    let name = match oid {
+        [0x55, 0x04, 0x03] => "CN",
+        [0x55, 0x04, 0x05] => "SERIALNUMBER",
+        [0x55, 0x04, 0x06] => "C",
+        [0x55, 0x04, 0x07] => "L",
+        [0x55, 0x04, 0x08] => "ST",
+        [0x55, 0x04, 0x0a] => "O",
+        [0x55, 0x04, 0x0b] => "OU",
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01] => "EMAILADDRESS",
+        _ => return oid_to_string(oid),
+    };
+    String::from(name)
+}
+
+fn oid_to_string(oid: &[u8]) -> String {
+    let mut parts: Vec<u64> = Vec::new();
+    let mut value: u64 = 0;
+    for b in oid {
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 != 0 {
+            continue;
+        }
+        if parts.is_empty() {
+            let first = (value / 40).min(2);
+            parts.push(first);
+            parts.push(value - first * 40);
+        } else {
+            parts.push(value);
+        }
+        value = 0;
+    }
+    parts
+        .iter()
+        .map(|it| it.to_string())
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+    use std::io::Cursor;
+
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if contents.len() < 0x80 {
+            out.push(contents.len() as u8);
+        } else {
+            out.push(0x82);
+            out.extend_from_slice(&(contents.len() as u16).to_be_bytes());
+        }
+        out.extend_from_slice(contents);
+        out
+    }
+
+    fn name_attr(oid: &[u8], value: &str) -> Vec<u8> {
+        let attr = [tlv(TAG_OID, oid), tlv(0x0c, value.as_bytes())].concat();
+        tlv(TAG_SET, &tlv(TAG_SEQUENCE, &attr))
+    }
+
+    fn fake_cert() -> Vec<u8> {
+        let name = [
+            name_attr(&[0x55, 0x04, 0x06], "US"),
+            name_attr(&[0x55, 0x04, 0x0a], "Android"),
+            name_attr(&[0x55, 0x04, 0x03], "Android"),
+        ]
+        .concat();
+        let tbs = [
+            tlv(TAG_CONTEXT_0, &tlv(TAG_INTEGER, &[2])),
+            tlv(TAG_INTEGER, &[1]),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_SEQUENCE, &name),
+            tlv(TAG_SEQUENCE, &[]),
+            tlv(TAG_SEQUENCE, &name),
+        ]
+        .concat();
+        tlv(TAG_SEQUENCE, &tlv(TAG_SEQUENCE, &tbs))
+    }
+
+    fn prefixed(data: &[u8]) -> Vec<u8> {
+        [&(data.len() as u32).to_le_bytes()[..], data].concat()
+    }
+
+    #[rstest]
+    #[case("META-INF/CERT.RSA", true)]
+    #[case("META-INF/platform.ec", true)]
+    #[case("META-INF/CERT.SF", false)]
+    #[case("META-INF/nested/CERT.RSA", false)]
+    #[case("res/CERT.RSA", false)]
+    fn test_is_v1_signature_file(#[case] name: &str, #[case] expected: bool) {
+        assert_eq!(is_v1_signature_file(name), expected);
+    }
+
+    #[test]
+    fn test_cert_subject() {
+        assert_eq!(
+            cert_subject(&fake_cert()).as_deref(),
+            Some("CN=Android, O=Android, C=US")
+        );
+    }
+
+    #[test]
+    fn test_oid_to_string() {
+        assert_eq!(
+            oid_to_string(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]),
+            "1.2.840.113549.1.1.11"
+        );
+    }
+
+    #[test]
+    fn test_pkcs7_certificates() {
+        let cert = fake_cert();
+        let signed_data = [
+            tlv(TAG_INTEGER, &[1]),
+            tlv(TAG_SET, &[]),
+            tlv(TAG_SEQUENCE, &tlv(TAG_OID, &[0x2a])),
+            tlv(TAG_CONTEXT_0, &cert),
+        ]
+        .concat();
+        let content_info = [
+            tlv(TAG_OID, &[0x2a]),
+            tlv(TAG_CONTEXT_0, &tlv(TAG_SEQUENCE, &signed_data)),
+        ]
+        .concat();
+        let pkcs7 = tlv(TAG_SEQUENCE, &content_info);
+        assert_eq!(pkcs7_certificates(&pkcs7), Some(vec![cert.as_slice()]));
+    }
+
+    #[test]
+    fn test_signing_block_certs() {
+        let cert = fake_cert();
+        let signed_data = [prefixed(&[]), prefixed(&prefixed(&cert))].concat();
+        let signer = prefixed(&[prefixed(&signed_data), prefixed(&[]), prefixed(&[])].concat());
+        let scheme = prefixed(&prefixed(&signer));
+
+        let mut pairs = Vec::new();
+        for (id, value) in [
+            (0x4242_4242u32, vec![0u8; 4]),
+            (APK_SIGNATURE_SCHEME_V2_ID, scheme),
+        ] {
+            pairs.extend_from_slice(&((value.len() + 4) as u64).to_le_bytes());
+            pairs.extend_from_slice(&id.to_le_bytes());
+            pairs.extend_from_slice(&value);
+        }
+        let size = (pairs.len() + APK_SIG_BLOCK_FOOTER_SIZE) as u64;
+
+        // Nothing but the signing block before the (empty) central directory
+        let mut apk = Vec::new();
+        apk.extend_from_slice(&size.to_le_bytes());
+        apk.extend_from_slice(&pairs);
+        apk.extend_from_slice(&size.to_le_bytes());
+        apk.extend_from_slice(APK_SIG_BLOCK_MAGIC);
+        let cd_offset = apk.len() as u32;
+        apk.extend_from_slice(&EOCD_MAGIC.to_le_bytes());
+        apk.extend_from_slice(&[0u8; 12]);
+        apk.extend_from_slice(&cd_offset.to_le_bytes());
+        apk.extend_from_slice(&[0u8; 2]);
+
+        let certs = get_signing_block_certs(&mut Cursor::new(apk)).expect("valid block");
+        assert_eq!(
+            certs,
+            vec![SigningCert {
+                scheme: SigningScheme::V2,
+                sha256: bytes_to_hex(&Sha256::digest(&cert)),
+                subject: String::from("CN=Android, O=Android, C=US"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,787 @@
+//! A small ELF reader for native libraries pulled from the device
+//!
+//! This only supports what dtu needs: section headers, symbol tables, reading
+//! strings and pointers out of the loaded image, and the relocations needed to
+//! make sense of pointers in position independent shared objects. Only little
+//! endian files are supported, which covers every Android ABI.
+
+use std::collections::HashMap;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_RELA: u32 = 4;
+const SHT_NOBITS: u32 = 8;
+const SHT_REL: u32 = 9;
+const SHT_DYNSYM: u32 = 11;
+const SHT_ANDROID_REL: u32 = 0x6000_0001;
+const SHT_ANDROID_RELA: u32 = 0x6000_0002;
+
+const SHF_WRITE: u64 = 0x1;
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+
+const STT_FUNC: u8 = 2;
+
+pub const EM_386: u16 = 3;
+pub const EM_ARM: u16 = 40;
+pub const EM_X86_64: u16 = 62;
+pub const EM_AARCH64: u16 = 183;
+pub const EM_RISCV: u16 = 243;
+
+// Flags for Android's packed (APS2) relocation groups
+const RELOCATION_GROUPED_BY_INFO_FLAG: i64 = 1;
+const RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG: i64 = 2;
+const RELOCATION_GROUPED_BY_ADDEND_FLAG: i64 = 4;
+const RELOCATION_GROUP_HAS_ADDEND_FLAG: i64 = 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ElfError {
+    #[error("not an ELF file")]
+    NotElf,
+    #[error("unsupported ELF file: {0}")]
+    Unsupported(&'static str),
+    #[error("malformed ELF file: {0}")]
+    Malformed(&'static str),
+}
+
+pub type ElfResult<T> = Result<T, ElfError>;
+
+#[derive(Debug, Clone)]
+pub struct ElfSection {
+    pub name: String,
+    pub ty: u32,
+    pub flags: u64,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub entsize: u64,
+}
+
+impl ElfSection {
+    /// Whether the section is part of the loaded image
+    pub fn is_alloc(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.flags & SHF_EXECINSTR != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.flags & SHF_WRITE != 0
+    }
+
+    /// Whether the section has contents in the file
+    pub fn has_data(&self) -> bool {
+        self.ty != SHT_NOBITS
+    }
+
+    pub fn contains_addr(&self, addr: u64) -> bool {
+        addr >= self.addr && addr - self.addr < self.size
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ElfSymbol {
+    pub name: String,
+    pub value: u64,
+    pub size: u64,
+    pub kind: u8,
+    pub bind: u8,
+    pub shndx: u16,
+}
+
+impl ElfSymbol {
+    /// Whether the symbol is defined in this file rather than imported
+    pub fn is_defined(&self) -> bool {
+        self.shndx != 0
+    }
+
+    pub fn is_function(&self) -> bool {
+        self.kind == STT_FUNC
+    }
+}
+
+pub struct Elf<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    machine: u16,
+    sections: Vec<ElfSection>,
+    /// Pointer values that are only known after relocation, keyed by the
+    /// address they are written to
+    relocated: HashMap<u64, u64>,
+    /// Names of the symbols pointers are relocated against, keyed by the
+    /// address they are written to
+    relocated_symbols: HashMap<u64, String>,
+}
+
+impl<'a> Elf<'a> {
+    pub fn parse(data: &'a [u8]) -> ElfResult<Self> {
+        if data.len() < 0x34 || &data[..4] != ELF_MAGIC {
+            return Err(ElfError::NotElf);
+        }
+        let is_64 = match data[4] {
+            ELFCLASS32 => false,
+            ELFCLASS64 => true,
+            _ => return Err(ElfError::Unsupported("unknown class")),
+        };
+        if data[5] != ELFDATA2LSB {
+            return Err(ElfError::Unsupported("big endian"));
+        }
+
+        let mut elf = Self {
+            data,
+            is_64,
+            machine: 0,
+            sections: Vec::new(),
+            relocated: HashMap::new(),
+            relocated_symbols: HashMap::new(),
+        };
+
+        elf.machine = elf.u16_at(18)?;
+        let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+            (
+                elf.u64_at(0x28)?,
+                elf.u16_at(0x3a)?,
+                elf.u16_at(0x3c)?,
+                elf.u16_at(0x3e)?,
+            )
+        } else {
+            (
+                elf.u32_at(0x20)? as u64,
+                elf.u16_at(0x2e)?,
+                elf.u16_at(0x30)?,
+                elf.u16_at(0x32)?,
+            )
+        };
+
+        let mut raw_names = Vec::with_capacity(shnum as usize);
+        for i in 0..shnum as u64 {
+            let off = (i * shentsize as u64)
+                .checked_add(shoff)
+                .and_then(|it| usize::try_from(it).ok())
+                .ok_or(ElfError::Malformed("section header offset"))?;
+            let (name, section) = elf.section_at(off)?;
+            raw_names.push(name);
+            elf.sections.push(section);
+        }
+
+        if let Some(strtab) = elf.sections.get(shstrndx as usize).cloned() {
+            for (section, name) in elf.sections.iter_mut().zip(raw_names) {
+                section.name = strtab
+                    .offset
+                    .checked_add(name as u64)
+                    .and_then(|it| read_cstr(data, it))
+                    .unwrap_or("")
+                    .to_string();
+            }
+        }
+
+        elf.load_relocations()?;
+
+        Ok(elf)
+    }
+
+    pub fn is_64(&self) -> bool {
+        self.is_64
+    }
+
+    pub fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    pub fn ptr_size(&self) -> u64 {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    pub fn sections(&self) -> &[ElfSection] {
+        &self.sections
+    }
+
+    /// Get the raw contents of the given section
+    pub fn section_data(&self, section: &ElfSection) -> &'a [u8] {
+        if !section.has_data() {
+            return &[];
+        }
+        let start = section.offset as usize;
+        let end = start.saturating_add(section.size as usize);
+        self.data.get(start..end).unwrap_or(&[])
+    }
+
+    /// Symbols from the dynamic symbol table
+    pub fn dynamic_symbols(&self) -> Vec<ElfSymbol> {
+        self.symbols_of_type(SHT_DYNSYM)
+    }
+
+    /// Symbols from both the static and dynamic symbol tables
+    ///
+    /// Most libraries on a device are stripped, so this will usually be the
+    /// same as [Elf::dynamic_symbols].
+    pub fn symbols(&self) -> Vec<ElfSymbol> {
+        let mut syms = self.symbols_of_type(SHT_SYMTAB);
+        syms.extend(self.symbols_of_type(SHT_DYNSYM));
+        syms
+    }
+
+    /// Whether the given address is in an executable section
+    pub fn is_code_addr(&self, addr: u64) -> bool {
+        // Thumb function pointers have the low bit set
+        let addr = if self.machine == EM_ARM {
+            addr & !1
+        } else {
+            addr
+        };
+        self.sections
+            .iter()
+            .any(|it| it.is_alloc() && it.is_executable() && it.contains_addr(addr))
+    }
+
+    /// Read a NUL terminated UTF-8 string at the given virtual address
+    pub fn read_cstr(&self, addr: u64) -> Option<&'a str> {
+        let offset = self.addr_to_offset(addr)?;
+        read_cstr(self.data, offset)
+    }
+
+    /// Read a pointer at the given virtual address, taking relocations into
+    /// account
+    pub fn read_ptr(&self, addr: u64) -> Option<u64> {
+        if let Some(v) = self.relocated.get(&addr) {
+            return Some(*v);
+        }
+        let offset = self.addr_to_offset(addr)? as usize;
+        if self.is_64 {
+            read_u64(self.data, offset)
+        } else {
+            read_u32(self.data, offset).map(|it| it as u64)
+        }
+    }
+
+    /// Get the name of the symbol the pointer at the given address is
+    /// relocated against, if any
+    ///
+    /// This is the only way to know where pointers to imported functions go.
+    pub fn ptr_symbol(&self, addr: u64) -> Option<&str> {
+        self.relocated_symbols.get(&addr).map(|it| it.as_str())
+    }
+
+    fn addr_to_offset(&self, addr: u64) -> Option<u64> {
+        self.sections
+            .iter()
+            .find(|it| it.is_alloc() && it.has_data() && it.contains_addr(addr))
+            .and_then(|it| it.offset.checked_add(addr - it.addr))
+    }
+
+    fn section_at(&self, off: usize) -> ElfResult<(u32, ElfSection)> {
+        let name = self.u32_at(off)?;
+        let section = if self.is_64 {
+            ElfSection {
+                name: String::new(),
+                ty: self.u32_at(off + 4)?,
+                flags: self.u64_at(off + 8)?,
+                addr: self.u64_at(off + 16)?,
+                offset: self.u64_at(off + 24)?,
+                size: self.u64_at(off + 32)?,
+                link: self.u32_at(off + 40)?,
+                entsize: self.u64_at(off + 56)?,
+            }
+        } else {
+            ElfSection {
+                name: String::new(),
+                ty: self.u32_at(off + 4)?,
+                flags: self.u32_at(off + 8)? as u64,
+                addr: self.u32_at(off + 12)? as u64,
+                offset: self.u32_at(off + 16)? as u64,
+                size: self.u32_at(off + 20)? as u64,
+                link: self.u32_at(off + 24)?,
+                entsize: self.u32_at(off + 36)? as u64,
+            }
+        };
+        Ok((name, section))
+    }
+
+    fn symbols_of_type(&self, ty: u32) -> Vec<ElfSymbol> {
+        let mut syms = Vec::new();
+        for section in self.sections.iter().filter(|it| it.ty == ty) {
+            let strtab = match self.sections.get(section.link as usize) {
+                Some(v) if v.ty == SHT_STRTAB => v,
+                _ => continue,
+            };
+            let entsize = if section.entsize > 0 {
+                section.entsize
+            } else if self.is_64 {
+                24
+            } else {
+                16
+            };
+            let count = section.size / entsize;
+            for i in 0..count {
+                let Some(off) = self.entry_offset(section, i, entsize) else {
+                    break;
+                };
+                if let Some(sym) = self.symbol_at(off, strtab) {
+                    syms.push(sym);
+                }
+            }
+        }
+        syms
+    }
+
+    fn symbol_at(&self, off: usize, strtab: &ElfSection) -> Option<ElfSymbol> {
+        let name_off = read_u32(self.data, off)?;
+        let (value, size, info, shndx) = if self.is_64 {
+            (
+                read_u64(self.data, off + 8)?,
+                read_u64(self.data, off + 16)?,
+                *self.data.get(off + 4)?,
+                read_u16(self.data, off + 6)?,
+            )
+        } else {
+            (
+                read_u32(self.data, off + 4)? as u64,
+                read_u32(self.data, off + 8)? as u64,
+                *self.data.get(off + 12)?,
+                read_u16(self.data, off + 14)?,
+            )
+        };
+        let name = strtab
+            .offset
+            .checked_add(name_off as u64)
+            .and_then(|it| read_cstr(self.data, it))
+            .unwrap_or("");
+        Some(ElfSymbol {
+            name: name.to_string(),
+            value,
+            size,
+            kind: info & 0xf,
+            bind: info >> 4,
+            shndx,
+        })
+    }
+
+    /// Record the value of every relocated pointer we know how to compute
+    fn load_relocations(&mut self) -> ElfResult<()> {
+        let mut relocs = Vec::new();
+        for section in self.sections.iter() {
+            let data = self.section_data(section);
+            match section.ty {
+                SHT_RELA => self.decode_rel(data, true, section.link, &mut relocs),
+                SHT_REL => self.decode_rel(data, false, section.link, &mut relocs),
+                SHT_ANDROID_RELA => self.decode_packed(data, true, section.link, &mut relocs)?,
+                SHT_ANDROID_REL => self.decode_packed(data, false, section.link, &mut relocs)?,
+                _ => {}
+            }
+        }
+
+        let mut symtabs: HashMap<u32, Vec<ElfSymbol>> = HashMap::new();
+
+        for reloc in relocs {
+            let kind = match reloc_kind(self.machine, reloc.ty) {
+                Some(v) => v,
+                None => continue,
+            };
+            // REL relocations keep the addend in place
+            let addend = match reloc.addend {
+                Some(v) => v as u64,
+                None => match self.read_ptr(reloc.offset) {
+                    Some(v) => v,
+                    None => continue,
+                },
+            };
+            let value = match kind {
+                RelocKind::Relative => addend,
+                RelocKind::Symbol => {
+                    let syms = symtabs
+                        .entry(reloc.symtab)
+                        .or_insert_with(|| self.symtab_at(reloc.symtab));
+                    let sym = match syms.get(reloc.sym as usize) {
+                        Some(v) => v,
+                        None => continue,
+                    };
+                    if !sym.name.is_empty() {
+                        self.relocated_symbols
+                            .insert(reloc.offset, sym.name.clone());
+                    }
+                    if !sym.is_defined() {
+                        continue;
+                    }
+                    sym.value.wrapping_add(addend)
+                }
+            };
+            self.relocated.insert(reloc.offset, value);
+        }
+        Ok(())
+    }
+
+    fn symtab_at(&self, idx: u32) -> Vec<ElfSymbol> {
+        let section = match self.sections.get(idx as usize) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+        let strtab = match self.sections.get(section.link as usize) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+        let entsize = if self.is_64 { 24 } else { 16 };
+        (0..section.size / entsize)
+            .map_while(|i| self.entry_offset(section, i, entsize))
+            .filter_map(|off| self.symbol_at(off, strtab))
+            .collect()
+    }
+
+    /// File offset of the `i`th entry of a table section, as long as it is
+    /// inside the file
+    fn entry_offset(&self, section: &ElfSection, i: u64, entsize: u64) -> Option<usize> {
+        let off = i.checked_mul(entsize)?.checked_add(section.offset)?;
+        let off = usize::try_from(off).ok()?;
+        (off < self.data.len()).then_some(off)
+    }
+
+    fn decode_packed(
+        &self,
+        data: &[u8],
+        has_addend: bool,
+        symtab: u32,
+        into: &mut Vec<Reloc>,
+    ) -> ElfResult<()> {
+        // Every relocation writes a pointer somewhere in the file's contents,
+        // so there can't be more of them than pointers in the file
+        let max_count = self.data.len() as u64 / self.ptr_size();
+        decode_packed(data, has_addend, self.is_64, max_count, symtab, into)
+    }
+
+    fn decode_rel(&self, data: &[u8], has_addend: bool, symtab: u32, into: &mut Vec<Reloc>) {
+        let entsize = match (self.is_64, has_addend) {
+            (true, true) => 24,
+            (true, false) => 16,
+            (false, true) => 12,
+            (false, false) => 8,
+        };
+        for ent in data.chunks_exact(entsize) {
+            let reloc = if self.is_64 {
+                let info = read_u64(ent, 8).unwrap_or(0);
+                Reloc {
+                    offset: read_u64(ent, 0).unwrap_or(0),
+                    ty: (info & 0xffff_ffff) as u32,
+                    sym: (info >> 32) as u32,
+                    addend: has_addend.then(|| read_u64(ent, 16).unwrap_or(0) as i64),
+                    symtab,
+                }
+            } else {
+                let info = read_u32(ent, 4).unwrap_or(0);
+                Reloc {
+                    offset: read_u32(ent, 0).unwrap_or(0) as u64,
+                    ty: info & 0xff,
+                    sym: info >> 8,
+                    addend: has_addend.then(|| read_u32(ent, 8).unwrap_or(0) as i32 as i64),
+                    symtab,
+                }
+            };
+            into.push(reloc);
+        }
+    }
+
+    fn u16_at(&self, off: usize) -> ElfResult<u16> {
+        read_u16(self.data, off).ok_or(ElfError::Malformed("truncated"))
+    }
+
+    fn u32_at(&self, off: usize) -> ElfResult<u32> {
+        read_u32(self.data, off).ok_or(ElfError::Malformed("truncated"))
+    }
+
+    fn u64_at(&self, off: usize) -> ElfResult<u64> {
+        read_u64(self.data, off).ok_or(ElfError::Malformed("truncated"))
+    }
+}
+
+struct Reloc {
+    offset: u64,
+    ty: u32,
+    sym: u32,
+    addend: Option<i64>,
+    symtab: u32,
+}
+
+enum RelocKind {
+    /// Base relative, the value is the addend
+    Relative,
+    /// The value is the symbol's value plus the addend
+    Symbol,
+}
+
+fn reloc_kind(machine: u16, ty: u32) -> Option<RelocKind> {
+    Some(match (machine, ty) {
+        (EM_AARCH64, 1027) | (EM_X86_64, 8) | (EM_ARM, 23) | (EM_386, 8) | (EM_RISCV, 3) => {
+            RelocKind::Relative
+        }
+        (EM_AARCH64, 257 | 1025)
+        | (EM_X86_64, 1 | 6)
+        | (EM_ARM, 2 | 21)
+        | (EM_386, 1 | 6)
+        | (EM_RISCV, 2) => RelocKind::Symbol,
+        _ => return None,
+    })
+}
+
+/// Decode Android's packed relocation format (`APS2`)
+///
+/// Groups can describe any number of relocations in a few bytes, so the total
+/// is limited to `max_count` to keep a malformed file from using up all of
+/// the memory.
+fn decode_packed(
+    data: &[u8],
+    has_addend: bool,
+    is_64: bool,
+    max_count: u64,
+    symtab: u32,
+    into: &mut Vec<Reloc>,
+) -> ElfResult<()> {
+    if data.len() < 4 || &data[..4] != b"APS2" {
+        return Err(ElfError::Unsupported("unknown packed relocation format"));
+    }
+    let mut dec = Sleb128Decoder::new(&data[4..]);
+    let err = || ElfError::Malformed("truncated packed relocations");
+
+    let count = dec.next().ok_or_else(err)?;
+    if u64::try_from(count).map_or(true, |it| it > max_count) {
+        return Err(ElfError::Malformed("invalid packed relocation count"));
+    }
+    let mut offset = dec.next().ok_or_else(err)?;
+    let mut info = 0i64;
+    let mut addend = 0i64;
+    let mut done = 0i64;
+
+    while done < count {
+        let group_size = dec.next().ok_or_else(err)?;
+        if group_size <= 0 || group_size > count - done {
+            return Err(ElfError::Malformed("invalid packed relocation group size"));
+        }
+        let flags = dec.next().ok_or_else(err)?;
+        let by_offset_delta = flags & RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG != 0;
+        let by_info = flags & RELOCATION_GROUPED_BY_INFO_FLAG != 0;
+        let by_addend = flags & RELOCATION_GROUPED_BY_ADDEND_FLAG != 0;
+        let group_has_addend = flags & RELOCATION_GROUP_HAS_ADDEND_FLAG != 0;
+
+        let offset_delta = if by_offset_delta {
+            dec.next().ok_or_else(err)?
+        } else {
+            0
+        };
+        if by_info {
+            info = dec.next().ok_or_else(err)?;
+        }
+        if group_has_addend && by_addend {
+            addend = addend
+                .checked_add(dec.next().ok_or_else(err)?)
+                .ok_or_else(err)?;
+        } else if !group_has_addend {
+            addend = 0;
+        }
+
+        for _ in 0..group_size {
+            let delta = if by_offset_delta {
+                offset_delta
+            } else {
+                dec.next().ok_or_else(err)?
+            };
+            offset = offset.checked_add(delta).ok_or_else(err)?;
+            if !by_info {
+                info = dec.next().ok_or_else(err)?;
+            }
+            if group_has_addend && !by_addend {
+                addend = addend
+                    .checked_add(dec.next().ok_or_else(err)?)
+                    .ok_or_else(err)?;
+            }
+            let (ty, sym) = if is_64 {
+                ((info & 0xffff_ffff) as u32, (info as u64 >> 32) as u32)
+            } else {
+                (
+                    (info & 0xff) as u32,
+                    ((info as u64 & 0xffff_ffff) >> 8) as u32,
+                )
+            };
+            into.push(Reloc {
+                offset: offset as u64,
+                ty,
+                sym,
+                addend: has_addend.then_some(addend),
+                symtab,
+            });
+        }
+        done += group_size;
+    }
+    Ok(())
+}
+
+struct Sleb128Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Sleb128Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn next(&mut self) -> Option<i64> {
+        let mut value = 0i64;
+        let mut shift = 0u32;
+        loop {
+            let byte = *self.data.get(self.pos)?;
+            self.pos += 1;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1i64 << shift;
+                }
+                return Some(value);
+            }
+        }
+    }
+}
+
+fn read_cstr(data: &[u8], offset: u64) -> Option<&str> {
+    let start = usize::try_from(offset).ok()?;
+    let rest = data.get(start..)?;
+    let len = rest.iter().position(|it| *it == 0)?;
+    std::str::from_utf8(&rest[..len]).ok()
+}
+
+fn read_u16(data: &[u8], off: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(off..off + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], off: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(off..off + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], off: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(off..off + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sleb(mut value: i64, into: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+            if done {
+                into.push(byte);
+                return;
+            }
+            into.push(byte | 0x80);
+        }
+    }
+
+    #[test]
+    fn test_sleb128() {
+        for v in [0i64, 1, -1, 63, 64, -64, -65, 0x1234_5678, -0x1234_5678] {
+            let mut buf = Vec::new();
+            sleb(v, &mut buf);
+            assert_eq!(Sleb128Decoder::new(&buf).next(), Some(v), "value {}", v);
+        }
+    }
+
+    #[test]
+    fn test_decode_packed() {
+        let mut data = b"APS2".to_vec();
+        // 3 relocations starting at 0x1000
+        sleb(3, &mut data);
+        sleb(0x1000, &mut data);
+        // A group of 2 relative relocations 8 bytes apart with their own addends
+        sleb(2, &mut data);
+        sleb(
+            RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG
+                | RELOCATION_GROUPED_BY_INFO_FLAG
+                | RELOCATION_GROUP_HAS_ADDEND_FLAG,
+            &mut data,
+        );
+        sleb(8, &mut data);
+        sleb(1027, &mut data);
+        sleb(0x2000, &mut data);
+        sleb(0x10, &mut data);
+        // A single symbol relocation without an addend
+        sleb(1, &mut data);
+        sleb(0, &mut data);
+        sleb(0x100, &mut data);
+        sleb((5 << 32) | 257, &mut data);
+
+        let mut relocs = Vec::new();
+        decode_packed(&data, true, true, 100, 3, &mut relocs).expect("decode");
+
+        let got = relocs
+            .iter()
+            .map(|it| (it.offset, it.ty, it.sym, it.addend))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            vec![
+                (0x1008, 1027, 0, Some(0x2000)),
+                (0x1010, 1027, 0, Some(0x2010)),
+                (0x1110, 257, 5, Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_packed_malformed() {
+        let decode = |values: &[i64]| {
+            let mut data = b"APS2".to_vec();
+            for v in values {
+                sleb(*v, &mut data);
+            }
+            let mut relocs = Vec::new();
+            decode_packed(&data, true, true, 100, 3, &mut relocs).map(|_| relocs.len())
+        };
+        let grouped = RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG | RELOCATION_GROUPED_BY_INFO_FLAG;
+
+        // More relocations than could fit in the file
+        assert!(decode(&[i64::MAX, 0, i64::MAX, grouped, 8, 1027]).is_err());
+        // Groups larger than what is left or that don't make progress
+        assert!(decode(&[2, 0, 3, grouped, 8, 1027]).is_err());
+        assert!(decode(&[2, 0, 0, grouped, 8, 1027]).is_err());
+        assert!(decode(&[2, 0, -1, grouped, 8, 1027]).is_err());
+        // Offsets and addends that overflow
+        assert!(decode(&[2, i64::MAX - 8, 2, grouped, 8, 1027]).is_err());
+        assert!(decode(&[
+            2,
+            0,
+            2,
+            grouped | RELOCATION_GROUP_HAS_ADDEND_FLAG,
+            8,
+            1027,
+            i64::MAX,
+            1
+        ])
+        .is_err());
+        // Truncated
+        assert!(decode(&[2, 0, 2, grouped, 8]).is_err());
+
+        assert_eq!(decode(&[2, 0, 2, grouped, 8, 1027]).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_not_elf() {
+        assert!(matches!(
+            Elf::parse(b"not an elf file at all, but it is long enough to check"),
+            Err(ElfError::NotElf)
+        ));
+    }
+}
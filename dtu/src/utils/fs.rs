@@ -194,7 +194,18 @@ pub fn find_smali_file_for_class(
     class_name: &ClassName,
     apk: Option<&DevicePath>,
 ) -> Option<PathBuf> {
-    let mut base = ctx.get_smali_dir().ok()?;
+    let base = ctx.get_smali_dir().ok()?;
+    Some(smali_file_for_class_in(&base, class_name, apk))
+}
+
+/// Get the path the smali file for the class would have in the given smali
+/// directory, which is laid out like [Context::get_smali_dir]
+pub fn smali_file_for_class_in(
+    smali_dir: &Path,
+    class_name: &ClassName,
+    apk: Option<&DevicePath>,
+) -> PathBuf {
+    let mut base = PathBuf::from(smali_dir);
     match apk {
         Some(apk) => {
             base.push("apks");
@@ -206,7 +217,35 @@ pub fn find_smali_file_for_class(
     let to_path = as_java.replace('.', OS_PATH_SEP);
     base.push(Path::new(&to_path));
 
-    Some(base.with_extension("smali"))
+    base.with_extension("smali")
+}
+
+/// Find an existing smali file for the class in the given smali directory
+///
+/// The expected location is checked first, then the framework, and finally
+/// every APK in the directory. This is useful for smali from another device,
+/// where the class may have been moved.
+pub fn search_smali_file_for_class_in(
+    smali_dir: &Path,
+    class_name: &ClassName,
+    apk: Option<&DevicePath>,
+) -> Option<PathBuf> {
+    let expected = smali_file_for_class_in(smali_dir, class_name, apk);
+    if expected.exists() {
+        return Some(expected);
+    }
+    let framework = smali_file_for_class_in(smali_dir, class_name, None);
+    if framework.exists() {
+        return Some(framework);
+    }
+
+    let as_path =
+        PathBuf::from(class_name.get_java_name().replace('.', OS_PATH_SEP)).with_extension("smali");
+    read_dir(smali_dir.join("apks"))
+        .ok()?
+        .filter_map(|it| it.ok())
+        .map(|it| it.path().join(&as_path))
+        .find(|it| it.exists())
 }
 
 /// Check to see if the given pathlike type has the given extension
@@ -16,6 +16,14 @@ pub use smali::*;
 pub mod hex;
 pub use hex::*;
 
+pub mod elf;
+
+pub mod apk_signing;
+
+pub mod sepolicy;
+
+pub mod smali_diff;
+
 pub mod allowlist;
 
 pub use allowlist::*;
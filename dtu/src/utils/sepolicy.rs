@@ -0,0 +1,439 @@
+//! Minimal SELinux policy handling for answering reachability questions
+//!
+//! This reads the CIL policy files and `*service_contexts` files pulled by the
+//! SELinux pull task. It only understands type attributes and `allow` rules,
+//! which is enough to answer whether a domain is allowed to look up a service
+//! with one of the service managers.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::utils::path_has_ext;
+
+/// The service managers a binder service can be registered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServiceManagerKind {
+    /// `servicemanager`, used by framework services and AIDL HALs
+    Service,
+    /// `hwservicemanager`, used by HIDL HALs
+    HwService,
+    /// `vndservicemanager`, used by vendor services on `/dev/vndbinder`
+    VndService,
+}
+
+impl ServiceManagerKind {
+    /// The SELinux class checked when looking up a service
+    pub fn selinux_class(&self) -> &'static str {
+        match self {
+            Self::Service => "service_manager",
+            Self::HwService => "hwservice_manager",
+            Self::VndService => "vndservice_manager",
+        }
+    }
+
+    /// Get the kind of service manager a contexts file applies to
+    pub fn from_contexts_file(name: &str) -> Option<Self> {
+        if name.ends_with("hwservice_contexts") {
+            Some(Self::HwService)
+        } else if name.ends_with("vndservice_contexts") {
+            Some(Self::VndService)
+        } else if name.ends_with("service_contexts") {
+            Some(Self::Service)
+        } else {
+            None
+        }
+    }
+}
+
+/// Service name to type mappings from `*service_contexts` files
+#[derive(Default)]
+pub struct ServiceContexts {
+    entries: HashMap<String, String>,
+    default: Option<String>,
+}
+
+impl ServiceContexts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add all of the entries in the given contexts file content
+    pub fn parse(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut split = line.split_whitespace();
+            let (name, label) = match (split.next(), split.next()) {
+                (Some(name), Some(label)) => (name, label),
+                _ => continue,
+            };
+            let ty = match label_type(label) {
+                Some(v) => String::from(v),
+                None => continue,
+            };
+            if name == "*" {
+                self.default = Some(ty);
+            } else {
+                self.entries.insert(String::from(name), ty);
+            }
+        }
+    }
+
+    /// Get the type of the given service, falling back to the `*` entry
+    pub fn get_type(&self, name: &str) -> Option<&str> {
+        self.entries
+            .get(name)
+            .or(self.default.as_ref())
+            .map(|it| it.as_str())
+    }
+}
+
+/// Get the type out of a `user:role:type:level` label
+pub fn label_type(label: &str) -> Option<&str> {
+    label.split(':').nth(2)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    fn atom(&self) -> Option<&str> {
+        match self {
+            Self::Atom(v) => Some(v.as_str()),
+            Self::List(_) => None,
+        }
+    }
+}
+
+/// An `allow` rule from the policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowRule {
+    pub source: String,
+    pub target: String,
+    pub class: String,
+    pub perms: Vec<String>,
+}
+
+/// The attributes and `allow` rules of a CIL policy
+#[derive(Default)]
+pub struct CilPolicy {
+    attributes: HashMap<String, Sexp>,
+    allows: Vec<AllowRule>,
+}
+
+/// Attributes can refer to other attributes, don't chase them forever
+const MAX_ATTRIBUTE_DEPTH: usize = 16;
+
+impl CilPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the statements from the given CIL file content
+    pub fn parse(&mut self, content: &str) {
+        for stmt in parse_sexps(content) {
+            let items = match stmt {
+                Sexp::List(items) => items,
+                Sexp::Atom(_) => continue,
+            };
+            match items.first().and_then(|it| it.atom()) {
+                Some("typeattributeset") if items.len() == 3 => {
+                    if let Some(name) = items[1].atom() {
+                        self.add_attribute(name, items[2].clone());
+                    }
+                }
+                Some("allow") if items.len() == 4 => self.add_allow(&items),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_attribute(&mut self, name: &str, expr: Sexp) {
+        // Attributes can be set multiple times, the result is the union
+        match self.attributes.get_mut(name) {
+            Some(Sexp::List(existing)) => {
+                let merged = vec![
+                    Sexp::Atom(String::from("or")),
+                    Sexp::List(std::mem::take(existing)),
+                    expr,
+                ];
+                *existing = merged;
+            }
+            _ => {
+                self.attributes.insert(String::from(name), expr);
+            }
+        }
+    }
+
+    fn add_allow(&mut self, items: &[Sexp]) {
+        let (source, target) = match (items[1].atom(), items[2].atom()) {
+            (Some(s), Some(t)) => (s, t),
+            _ => return,
+        };
+        let (class, perms) = match &items[3] {
+            Sexp::List(cp) if cp.len() == 2 => match (&cp[0], &cp[1]) {
+                (Sexp::Atom(class), Sexp::List(perms)) => (class, perms),
+                _ => return,
+            },
+            _ => return,
+        };
+        self.allows.push(AllowRule {
+            source: String::from(source),
+            target: String::from(target),
+            class: class.clone(),
+            perms: perms
+                .iter()
+                .filter_map(|it| it.atom().map(String::from))
+                .collect(),
+        });
+    }
+
+    /// Whether `ty` is `name` or is a member of the attribute `name`
+    pub fn has_type(&self, name: &str, ty: &str) -> bool {
+        self.has_type_depth(name, ty, 0)
+    }
+
+    fn has_type_depth(&self, name: &str, ty: &str, depth: usize) -> bool {
+        if name == ty {
+            return true;
+        }
+        if depth > MAX_ATTRIBUTE_DEPTH {
+            return false;
+        }
+        match self.attributes.get(name) {
+            Some(expr) => self.expr_has_type(expr, ty, depth + 1),
+            None => false,
+        }
+    }
+
+    fn expr_has_type(&self, expr: &Sexp, ty: &str, depth: usize) -> bool {
+        let items = match expr {
+            Sexp::Atom(name) => return self.has_type_depth(name, ty, depth),
+            Sexp::List(items) => items,
+        };
+        let rest = items.get(1..).unwrap_or(&[]);
+        match items.first().and_then(|it| it.atom()) {
+            Some("and") => rest.iter().all(|it| self.expr_has_type(it, ty, depth)),
+            Some("or") => rest.iter().any(|it| self.expr_has_type(it, ty, depth)),
+            Some("not") => !rest.iter().any(|it| self.expr_has_type(it, ty, depth)),
+            Some("all") => true,
+            _ => items.iter().any(|it| self.expr_has_type(it, ty, depth)),
+        }
+    }
+
+    /// Whether `domain` is allowed `perm` on `target` for the given class
+    pub fn allows(&self, domain: &str, target: &str, class: &str, perm: &str) -> bool {
+        self.allows.iter().any(|rule| {
+            rule.class == class
+                && rule.perms.iter().any(|it| it == perm)
+                && self.has_type(&rule.source, domain)
+                && (self.has_type(&rule.target, target)
+                    || (rule.target == "self" && domain == target))
+        })
+    }
+}
+
+/// The policy and service contexts needed to determine whether a domain can
+/// look up a service
+pub struct ServiceAccess {
+    policy: CilPolicy,
+    contexts: HashMap<ServiceManagerKind, ServiceContexts>,
+}
+
+impl ServiceAccess {
+    /// Load all `.cil` files in `cils_dir` and all `*service_contexts` files in
+    /// `contexts_dir`
+    ///
+    /// Returns `None` if either directory doesn't exist.
+    pub fn load(cils_dir: &Path, contexts_dir: &Path) -> io::Result<Option<Self>> {
+        if !(cils_dir.is_dir() && contexts_dir.is_dir()) {
+            return Ok(None);
+        }
+
+        let mut policy = CilPolicy::new();
+        for ent in fs::read_dir(cils_dir)? {
+            let path = ent?.path();
+            if path_has_ext(&path, "cil") {
+                policy.parse(&fs::read_to_string(&path)?);
+            }
+        }
+
+        let mut contexts: HashMap<ServiceManagerKind, ServiceContexts> = HashMap::new();
+        for ent in fs::read_dir(contexts_dir)? {
+            let path = ent?.path();
+            let kind = match path
+                .file_name()
+                .and_then(|it| it.to_str())
+                .and_then(ServiceManagerKind::from_contexts_file)
+            {
+                Some(v) => v,
+                None => continue,
+            };
+            contexts
+                .entry(kind)
+                .or_default()
+                .parse(&fs::read_to_string(&path)?);
+        }
+
+        Ok(Some(Self { policy, contexts }))
+    }
+
+    /// Get the type of the given service name
+    pub fn service_type(&self, kind: ServiceManagerKind, name: &str) -> Option<&str> {
+        self.contexts.get(&kind)?.get_type(name)
+    }
+
+    /// Whether `domain` can find the service with the given name, `None` if
+    /// the service has no known type
+    pub fn can_find(&self, domain: &str, kind: ServiceManagerKind, name: &str) -> Option<bool> {
+        let ty = self.service_type(kind, name)?;
+        Some(self.policy.allows(domain, ty, kind.selinux_class(), "find"))
+    }
+}
+
+fn parse_sexps(content: &str) -> Vec<Sexp> {
+    let mut stack: Vec<Vec<Sexp>> = vec![Vec::new()];
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                while let Some(c) = chars.next() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => stack.push(Vec::new()),
+            ')' => {
+                if stack.len() > 1 {
+                    let list = stack.pop().unwrap();
+                    stack.last_mut().unwrap().push(Sexp::List(list));
+                }
+            }
+            '"' => {
+                let mut atom = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        break;
+                    }
+                    atom.push(c);
+                }
+                stack.last_mut().unwrap().push(Sexp::Atom(atom));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut atom = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == '(' || next == ')' || next == ';' {
+                        break;
+                    }
+                    atom.push(next);
+                    chars.next();
+                }
+                stack.last_mut().unwrap().push(Sexp::Atom(atom));
+            }
+        }
+    }
+
+    stack.swap_remove(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const POLICY: &'static str = r#"
+; Attributes
+(typeattribute appdomain)
+(typeattributeset untrusted_app_all (untrusted_app untrusted_app_30))
+(typeattributeset appdomain (untrusted_app_all platform_app))
+(typeattributeset hal_foo_server (hal_foo_default))
+(typeattributeset base_typeattr_1 (and (appdomain) (not (platform_app))))
+(allow appdomain hal_foo_hwservice (hwservice_manager (find)))
+(allow base_typeattr_1 vendor_thing_service (service_manager (find)))
+(allow platform_app vendor_secret_service (service_manager (find add)))
+(allow untrusted_app_all vendor_vnd_service (vndservice_manager (list)))
+(genfscon proc "/sys/kernel" (u object_r proc_kernel ((s0) (s0))))
+"#;
+
+    const HW_CONTEXTS: &'static str = r#"
+# comment
+android.hardware.foo::IFoo        u:object_r:hal_foo_hwservice:s0
+*                                 u:object_r:default_android_hwservice:s0
+"#;
+
+    #[test]
+    fn test_cil_policy() {
+        let mut policy = CilPolicy::new();
+        policy.parse(POLICY);
+
+        assert!(policy.has_type("appdomain", "untrusted_app"));
+        assert!(policy.has_type("base_typeattr_1", "untrusted_app_30"));
+        assert!(!policy.has_type("base_typeattr_1", "platform_app"));
+
+        assert!(policy.allows(
+            "untrusted_app",
+            "hal_foo_hwservice",
+            "hwservice_manager",
+            "find"
+        ));
+        assert!(policy.allows(
+            "untrusted_app",
+            "vendor_thing_service",
+            "service_manager",
+            "find"
+        ));
+        assert!(!policy.allows(
+            "platform_app",
+            "vendor_thing_service",
+            "service_manager",
+            "find"
+        ));
+        assert!(!policy.allows(
+            "untrusted_app",
+            "vendor_secret_service",
+            "service_manager",
+            "find"
+        ));
+        assert!(!policy.allows(
+            "untrusted_app",
+            "vendor_vnd_service",
+            "vndservice_manager",
+            "find"
+        ));
+    }
+
+    #[test]
+    fn test_service_contexts() {
+        let mut contexts = ServiceContexts::new();
+        contexts.parse(HW_CONTEXTS);
+        assert_eq!(
+            contexts.get_type("android.hardware.foo::IFoo"),
+            Some("hal_foo_hwservice")
+        );
+        assert_eq!(
+            contexts.get_type("android.hardware.bar::IBar"),
+            Some("default_android_hwservice")
+        );
+        assert_eq!(
+            ServiceManagerKind::from_contexts_file("vendor_hwservice_contexts"),
+            Some(ServiceManagerKind::HwService)
+        );
+        assert_eq!(
+            ServiceManagerKind::from_contexts_file("vndservice_contexts"),
+            Some(ServiceManagerKind::VndService)
+        );
+        assert_eq!(
+            ServiceManagerKind::from_contexts_file("plat_service_contexts"),
+            Some(ServiceManagerKind::Service)
+        );
+    }
+}
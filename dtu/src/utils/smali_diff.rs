@@ -0,0 +1,564 @@
+//! Textual diffs of smali method bodies
+//!
+//! Method bodies are normalized along the lines of the method hashes stored
+//! in the device database: only the method's name, arguments, and return
+//! type and the instructions are kept, registers are replaced with `v?` and
+//! labels with `:?`. Like the hashes, this ignores access flags, register
+//! renumbering, and debug info, so those changes don't add noise.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Placeholder used for every register in a normalized method
+pub const NORMALIZED_REGISTER: &str = "v?";
+
+/// Placeholder used for every label reference in a normalized method
+pub const NORMALIZED_LABEL: &str = ":?";
+
+/// Find the body of the method with the given name and smali argument string
+/// in the contents of a smali file. If `args` is None the first method with
+/// the name is used.
+///
+/// The returned lines include the `.method` and `.end method` lines.
+pub fn find_smali_method<'a>(
+    smali: &'a str,
+    name: &str,
+    args: Option<&str>,
+) -> Option<Vec<&'a str>> {
+    let decl = match args {
+        Some(args) => format!("{}({})", name, args),
+        None => format!("{}(", name),
+    };
+    let mut lines = smali.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if !trimmed.starts_with(".method ") {
+            continue;
+        }
+        let Some(sig) = trimmed.rsplit(' ').next() else {
+            continue;
+        };
+        if !sig.starts_with(&decl) {
+            continue;
+        }
+        let mut body = vec![line];
+        for line in lines.by_ref() {
+            body.push(line);
+            if line.trim() == ".end method" {
+                break;
+            }
+        }
+        return Some(body);
+    }
+    None
+}
+
+/// Normalize the lines of a smali method for diffing
+///
+/// The `.method` declaration is reduced to the name, arguments, and return
+/// type. Directives, labels, comments, and blank lines are removed, as are the
+/// payloads of switch and array data blocks. Registers and label references
+/// in the remaining instructions are replaced with [NORMALIZED_REGISTER] and
+/// [NORMALIZED_LABEL].
+pub fn normalize_smali_method<'a, I>(lines: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut normalized = Vec::new();
+    let mut in_payload = false;
+
+    for line in lines {
+        let line = line.trim();
+        if in_payload {
+            if line.starts_with(".end ") {
+                in_payload = false;
+            }
+            continue;
+        }
+
+        if line.starts_with(".method ") {
+            // Drop the access flags, the signature is always last
+            let sig = line.rsplit(' ').next().unwrap_or(line);
+            normalized.push(format!(".method {}", sig));
+            continue;
+        }
+
+        if line.starts_with(".packed-switch")
+            || line.starts_with(".sparse-switch")
+            || line.starts_with(".array-data")
+        {
+            in_payload = true;
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('.') || line.starts_with(':') {
+            continue;
+        }
+
+        let ins = normalize_instruction(line);
+        if !ins.is_empty() {
+            normalized.push(ins);
+        }
+    }
+
+    normalized
+}
+
+/// Replace registers and labels in a single instruction line, leaving string
+/// literals alone and dropping any trailing comment
+fn normalize_instruction(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let chars = line.chars().collect::<Vec<char>>();
+    let mut i = 0;
+    // Registers and labels are only ever found after one of these
+    let at_boundary = |i: usize| i == 0 || matches!(chars[i - 1], ' ' | '\t' | '{' | ',');
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' => {
+                out.push(c);
+                i += 1;
+                while i < chars.len() {
+                    let sc = chars[i];
+                    out.push(sc);
+                    i += 1;
+                    if sc == '\\' && i < chars.len() {
+                        out.push(chars[i]);
+                        i += 1;
+                    } else if sc == '"' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '#' => break,
+            'v' | 'p' if at_boundary(i) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|it| !it.is_ascii_digit())
+                    .map_or(chars.len(), |it| it + i + 1);
+                let ends_token = end == chars.len() || matches!(chars[end], ',' | '}' | ' ');
+                if end > i + 1 && ends_token {
+                    out.push_str(NORMALIZED_REGISTER);
+                    i = end;
+                    continue;
+                }
+            }
+            ':' if at_boundary(i) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|it| !(it.is_ascii_alphanumeric() || *it == '_'))
+                    .map_or(chars.len(), |it| it + i + 1);
+                out.push_str(NORMALIZED_LABEL);
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+
+    String::from(out.trim_end())
+}
+
+/// A single line in a [SmaliDiff]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    /// Only in the old version
+    Removed(String),
+    /// Only in the new version
+    Added(String),
+}
+
+impl DiffLine {
+    pub fn prefix(&self) -> char {
+        match self {
+            Self::Same(_) => ' ',
+            Self::Removed(_) => '-',
+            Self::Added(_) => '+',
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Same(s) | Self::Removed(s) | Self::Added(s) => s,
+        }
+    }
+
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Self::Same(_))
+    }
+}
+
+impl Display for DiffLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix(), self.text())
+    }
+}
+
+/// A hunk of a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk<'a> {
+    /// 1 based start line in the old version
+    pub old_start: usize,
+    pub old_len: usize,
+    /// 1 based start line in the new version
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: &'a [DiffLine],
+}
+
+impl<'a> Display for DiffHunk<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+}
+
+/// Line by line diff between two normalized smali methods
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmaliDiff {
+    pub lines: Vec<DiffLine>,
+}
+
+impl SmaliDiff {
+    /// Diff the `old` lines against the `new` lines
+    pub fn new(old: &[String], new: &[String]) -> Self {
+        // Only the lines between the common prefix and suffix need the LCS
+        // table, which keeps it small for generated methods with few changes
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut lines = Vec::with_capacity(old.len().max(new.len()));
+        lines.extend(old[..prefix].iter().map(|it| DiffLine::Same(it.clone())));
+        Self::diff_lcs(
+            &old[prefix..old.len() - suffix],
+            &new[prefix..new.len() - suffix],
+            &mut lines,
+        );
+        lines.extend(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|it| DiffLine::Same(it.clone())),
+        );
+
+        Self { lines }
+    }
+
+    fn diff_lcs(old: &[String], new: &[String], lines: &mut Vec<DiffLine>) {
+        let n = old.len();
+        let m = new.len();
+
+        // lcs[i][j] is the length of the longest common subsequence of
+        // old[i..] and new[j..]
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old[i] == new[j] {
+                lines.push(DiffLine::Same(old[i].clone()));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                lines.push(DiffLine::Removed(old[i].clone()));
+                i += 1;
+            } else {
+                lines.push(DiffLine::Added(new[j].clone()));
+                j += 1;
+            }
+        }
+        lines.extend(old[i..].iter().map(|it| DiffLine::Removed(it.clone())));
+        lines.extend(new[j..].iter().map(|it| DiffLine::Added(it.clone())));
+    }
+
+    /// Whether there are any differences at all
+    pub fn has_changes(&self) -> bool {
+        self.lines.iter().any(DiffLine::is_change)
+    }
+
+    /// Group the changes into unified diff hunks with `context` unchanged
+    /// lines around each change
+    pub fn hunks(&self, context: usize) -> Vec<DiffHunk<'_>> {
+        let mut hunks = Vec::new();
+        let changes = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| it.is_change())
+            .map(|(idx, _)| idx)
+            .collect::<Vec<usize>>();
+
+        let mut idx = 0;
+        while idx < changes.len() {
+            let start = changes[idx].saturating_sub(context);
+            let mut last = changes[idx];
+            idx += 1;
+            while idx < changes.len() && changes[idx] - last <= context * 2 + 1 {
+                last = changes[idx];
+                idx += 1;
+            }
+            let end = (last + context + 1).min(self.lines.len());
+            hunks.push(self.make_hunk(start, end));
+        }
+
+        hunks
+    }
+
+    fn make_hunk(&self, start: usize, end: usize) -> DiffHunk<'_> {
+        let before = &self.lines[..start];
+        let lines = &self.lines[start..end];
+        let old_before = before
+            .iter()
+            .filter(|it| !matches!(it, DiffLine::Added(_)))
+            .count();
+        let new_before = before
+            .iter()
+            .filter(|it| !matches!(it, DiffLine::Removed(_)))
+            .count();
+        let old_len = lines
+            .iter()
+            .filter(|it| !matches!(it, DiffLine::Added(_)))
+            .count();
+        let new_len = lines
+            .iter()
+            .filter(|it| !matches!(it, DiffLine::Removed(_)))
+            .count();
+        DiffHunk {
+            old_start: old_before + 1,
+            old_len,
+            new_start: new_before + 1,
+            new_len,
+            lines,
+        }
+    }
+
+    /// Render the diff in unified format
+    pub fn unified(&self, old_name: &str, new_name: &str, context: usize) -> String {
+        let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+        for hunk in self.hunks(context) {
+            out.push_str(&format!("{}\n", hunk));
+            for line in hunk.lines {
+                out.push_str(&format!("{}\n", line));
+            }
+        }
+        out
+    }
+
+    /// Render the diff with the old and new versions side by side, each
+    /// column `width` characters wide
+    pub fn side_by_side(&self, width: usize) -> String {
+        let mut out = String::new();
+        let mut idx = 0;
+        while idx < self.lines.len() {
+            match &self.lines[idx] {
+                DiffLine::Same(s) => {
+                    out.push_str(&side_by_side_row(s, ' ', s, width));
+                    idx += 1;
+                }
+                _ => {
+                    // Pair up a block of removals with the additions that
+                    // follow it
+                    let mut removed = Vec::new();
+                    let mut added = Vec::new();
+                    while let Some(line) = self.lines.get(idx) {
+                        match line {
+                            DiffLine::Removed(s) if added.is_empty() => removed.push(s.as_str()),
+                            DiffLine::Added(s) => added.push(s.as_str()),
+                            _ => break,
+                        }
+                        idx += 1;
+                    }
+                    for row in 0..removed.len().max(added.len()) {
+                        let (left, right) = (removed.get(row), added.get(row));
+                        let marker = match (left, right) {
+                            (Some(_), Some(_)) => '|',
+                            (Some(_), None) => '<',
+                            _ => '>',
+                        };
+                        out.push_str(&side_by_side_row(
+                            left.copied().unwrap_or(""),
+                            marker,
+                            right.copied().unwrap_or(""),
+                            width,
+                        ));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn side_by_side_row(left: &str, marker: char, right: &str, width: usize) -> String {
+    let left = left.chars().take(width).collect::<String>();
+    format!("{:<width$} {} {}\n", left, marker, right, width = width)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OLD: &'static str = r#"
+.method public isAllowed(Ljava/lang/String;I)Z
+    .locals 2
+    .param p1, "pkg"    # Ljava/lang/String;
+
+    .line 42
+    iget-object v0, p0, Lcom/android/server/Foo;->mLock:Ljava/lang/Object;
+
+    if-eqz p1, :cond_0
+
+    const-string v1, "a v1, :label # not a comment"
+
+    invoke-static {v1, p2}, Lcom/android/server/Foo;->check(Ljava/lang/String;I)Z
+
+    move-result v0
+
+    return v0
+
+    :cond_0
+    const/4 v0, 0x0
+
+    return v0
+.end method
+
+.method public other()V
+    .locals 0
+    return-void
+.end method
+"#;
+
+    const NEW: &'static str = r#"
+.method public isAllowed(Ljava/lang/String;I)Z
+    .registers 6
+
+    iget-object v2, p0, Lcom/android/server/Foo;->mLock:Ljava/lang/Object;
+
+    if-eqz p1, :cond_3
+
+    const-string v3, "a v1, :label # not a comment"
+
+    invoke-static {v3, p2}, Lcom/android/server/Foo;->check(Ljava/lang/String;I)Z
+
+    move-result v2
+
+    return v2
+
+    :cond_3
+    const/4 v2, 0x1
+
+    return v2
+.end method
+"#;
+
+    #[test]
+    fn test_find_and_normalize() {
+        let body = find_smali_method(OLD, "isAllowed", Some("Ljava/lang/String;I")).unwrap();
+        assert_eq!(body.last().map(|it| it.trim()), Some(".end method"));
+        assert!(find_smali_method(OLD, "isAllowed", Some("I")).is_none());
+        assert!(find_smali_method(OLD, "other", None).is_some());
+
+        let normalized = normalize_smali_method(body);
+        assert_eq!(
+            normalized,
+            vec![
+                ".method isAllowed(Ljava/lang/String;I)Z",
+                "iget-object v?, v?, Lcom/android/server/Foo;->mLock:Ljava/lang/Object;",
+                "if-eqz v?, :?",
+                "const-string v?, \"a v1, :label # not a comment\"",
+                "invoke-static {v?, v?}, Lcom/android/server/Foo;->check(Ljava/lang/String;I)Z",
+                "move-result v?",
+                "return v?",
+                "const/4 v?, 0x0",
+                "return v?",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_smali_diff() {
+        let old = normalize_smali_method(
+            find_smali_method(OLD, "isAllowed", Some("Ljava/lang/String;I")).unwrap(),
+        );
+        let new = normalize_smali_method(
+            find_smali_method(NEW, "isAllowed", Some("Ljava/lang/String;I")).unwrap(),
+        );
+
+        let diff = SmaliDiff::new(&old, &new);
+        assert!(diff.has_changes());
+        assert_eq!(
+            diff.lines
+                .iter()
+                .filter(|it| it.is_change())
+                .collect::<Vec<_>>(),
+            vec![
+                &DiffLine::Removed(String::from("const/4 v?, 0x0")),
+                &DiffLine::Added(String::from("const/4 v?, 0x1")),
+            ]
+        );
+
+        let hunks = diff.hunks(1);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].to_string(), "@@ -7,3 +7,3 @@");
+        assert_eq!(
+            diff.unified("a", "b", 1),
+            "--- a\n+++ b\n@@ -7,3 +7,3 @@\n return v?\n-const/4 v?, 0x0\n+const/4 v?, 0x1\n return v?\n"
+        );
+
+        assert!(diff
+            .side_by_side(16)
+            .contains("const/4 v?, 0x0  | const/4 v?, 0x1\n"));
+
+        assert!(!SmaliDiff::new(&old, &old).has_changes());
+    }
+
+    #[test]
+    fn test_access_flags_ignored() {
+        let old = normalize_smali_method(find_smali_method(OLD, "other", None).unwrap());
+        let changed = OLD.replace(".method public other()V", ".method private final other()V");
+        let new = normalize_smali_method(find_smali_method(&changed, "other", None).unwrap());
+        assert_eq!(new[0], ".method other()V");
+        assert!(!SmaliDiff::new(&old, &new).has_changes());
+    }
+
+    #[test]
+    fn test_smali_diff_trims_common_lines() {
+        let lines = |items: &[&str]| items.iter().map(|it| String::from(*it)).collect::<Vec<_>>();
+        let old = lines(&["a", "b", "c", "d", "e"]);
+        let new = lines(&["a", "b", "x", "d", "e", "f"]);
+        let diff = SmaliDiff::new(&old, &new);
+        assert_eq!(
+            diff.lines,
+            vec![
+                DiffLine::Same(String::from("a")),
+                DiffLine::Same(String::from("b")),
+                DiffLine::Removed(String::from("c")),
+                DiffLine::Added(String::from("x")),
+                DiffLine::Same(String::from("d")),
+                DiffLine::Same(String::from("e")),
+                DiffLine::Added(String::from("f")),
+            ]
+        );
+        assert!(!SmaliDiff::new(&old, &old).has_changes());
+        assert_eq!(SmaliDiff::new(&[], &new).lines.len(), new.len());
+    }
+}
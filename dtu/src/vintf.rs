@@ -0,0 +1,227 @@
+//! Parsing for VINTF manifests, which declare the HALs a device provides
+//!
+//! These are used to enumerate HAL services when the device can't be queried
+//! directly, such as with a filesystem dump.
+
+use serde::Deserialize;
+
+/// The HAL formats found in VINTF manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HalFormat {
+    Hidl,
+    Aidl,
+}
+
+impl HalFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hidl => "hidl",
+            Self::Aidl => "aidl",
+        }
+    }
+}
+
+/// A single HAL instance declared in a VINTF manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VintfHal {
+    pub format: HalFormat,
+    /// Fully qualified interface name, `package@version::IName` for HIDL and
+    /// `package.IName` for AIDL
+    pub interface: String,
+    pub instance: String,
+    /// `hwbinder` or `passthrough` for HIDL, `binder` for AIDL
+    pub transport: String,
+}
+
+#[derive(Deserialize)]
+struct ManifestXml {
+    #[serde(rename = "hal", default = "Vec::new")]
+    hals: Vec<HalXml>,
+}
+
+#[derive(Deserialize)]
+struct HalXml {
+    #[serde(rename = "@format")]
+    format: Option<String>,
+    name: String,
+    transport: Option<TransportXml>,
+    #[serde(rename = "version", default = "Vec::new")]
+    versions: Vec<String>,
+    #[serde(rename = "interface", default = "Vec::new")]
+    interfaces: Vec<InterfaceXml>,
+    #[serde(rename = "fqname", default = "Vec::new")]
+    fqnames: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TransportXml {
+    #[serde(rename = "$text")]
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct InterfaceXml {
+    name: String,
+    #[serde(rename = "instance", default = "Vec::new")]
+    instances: Vec<String>,
+}
+
+impl HalXml {
+    fn format(&self) -> Option<HalFormat> {
+        match self.format.as_deref() {
+            None | Some("hidl") => Some(HalFormat::Hidl),
+            Some("aidl") => Some(HalFormat::Aidl),
+            _ => None,
+        }
+    }
+
+    fn interface_name(&self, format: HalFormat, version: Option<&str>, iface: &str) -> String {
+        match (format, version) {
+            (HalFormat::Hidl, Some(version)) => format!("{}@{}::{}", self.name, version, iface),
+            (HalFormat::Hidl, None) => format!("{}::{}", self.name, iface),
+            (HalFormat::Aidl, _) => format!("{}.{}", self.name, iface),
+        }
+    }
+
+    fn into_hals(self, into: &mut Vec<VintfHal>) {
+        let format = match self.format() {
+            Some(v) => v,
+            None => return,
+        };
+        let transport = match (&self.transport, format) {
+            (Some(t), _) => String::from(t.value.trim()),
+            (None, HalFormat::Aidl) => String::from("binder"),
+            (None, HalFormat::Hidl) => String::from("hwbinder"),
+        };
+        let version = self.versions.first().map(|it| it.trim());
+
+        let mut push = |interface: String, instance: &str| {
+            let hal = VintfHal {
+                format,
+                interface,
+                instance: String::from(instance),
+                transport: transport.clone(),
+            };
+            if !into.contains(&hal) {
+                into.push(hal);
+            }
+        };
+
+        for iface in self.interfaces.iter() {
+            for instance in iface.instances.iter() {
+                push(
+                    self.interface_name(format, version, &iface.name),
+                    instance.trim(),
+                );
+            }
+        }
+
+        // HIDL: `@1.0::IName/instance`, AIDL: `IName/instance`
+        for fqname in self.fqnames.iter() {
+            let fqname = fqname.trim();
+            let (iface, instance) = match fqname.split_once('/') {
+                Some(v) => v,
+                None => continue,
+            };
+            let (fq_version, iface) = match iface.strip_prefix('@') {
+                Some(rest) => match rest.split_once("::") {
+                    Some((v, iface)) => (Some(v), iface),
+                    None => continue,
+                },
+                None => (version, iface),
+            };
+            push(self.interface_name(format, fq_version, iface), instance);
+        }
+    }
+}
+
+/// Get all of the HIDL and AIDL HAL instances declared in a VINTF manifest
+pub fn parse_vintf_manifest(content: &str) -> anyhow::Result<Vec<VintfHal>> {
+    let manifest: ManifestXml = quick_xml::de::from_str(content)?;
+    let mut hals = Vec::new();
+    for hal in manifest.hals {
+        hal.into_hals(&mut hals);
+    }
+    Ok(hals)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MANIFEST: &'static str = r#"<manifest version="1.0" type="device" target-level="5">
+    <hal format="hidl">
+        <name>android.hardware.audio</name>
+        <transport>hwbinder</transport>
+        <version>6.0</version>
+        <interface>
+            <name>IDevicesFactory</name>
+            <instance>default</instance>
+        </interface>
+        <fqname>@6.0::IDevicesFactory/default</fqname>
+    </hal>
+    <hal format="hidl">
+        <name>android.hardware.graphics.mapper</name>
+        <transport arch="32+64">passthrough</transport>
+        <fqname>@4.0::IMapper/default</fqname>
+    </hal>
+    <hal format="aidl">
+        <name>android.hardware.power</name>
+        <version>2</version>
+        <fqname>IPower/default</fqname>
+    </hal>
+    <hal format="aidl">
+        <name>vendor.thing</name>
+        <interface>
+            <name>IThing</name>
+            <instance>default</instance>
+            <instance>secondary</instance>
+        </interface>
+    </hal>
+    <hal format="native">
+        <name>mapper</name>
+        <version>5.0</version>
+    </hal>
+</manifest>"#;
+
+    #[test]
+    fn test_parse_vintf_manifest() {
+        let hals = parse_vintf_manifest(MANIFEST).unwrap();
+        let hal = |format, interface: &str, instance: &str, transport: &str| VintfHal {
+            format,
+            interface: String::from(interface),
+            instance: String::from(instance),
+            transport: String::from(transport),
+        };
+        assert_eq!(
+            hals,
+            vec![
+                hal(
+                    HalFormat::Hidl,
+                    "android.hardware.audio@6.0::IDevicesFactory",
+                    "default",
+                    "hwbinder"
+                ),
+                hal(
+                    HalFormat::Hidl,
+                    "android.hardware.graphics.mapper@4.0::IMapper",
+                    "default",
+                    "passthrough"
+                ),
+                hal(
+                    HalFormat::Aidl,
+                    "android.hardware.power.IPower",
+                    "default",
+                    "binder"
+                ),
+                hal(HalFormat::Aidl, "vendor.thing.IThing", "default", "binder"),
+                hal(
+                    HalFormat::Aidl,
+                    "vendor.thing.IThing",
+                    "secondary",
+                    "binder"
+                ),
+            ]
+        );
+    }
+}